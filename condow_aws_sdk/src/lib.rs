@@ -0,0 +1,763 @@
+//! # CONcurrent DOWnloads from AWS S3 with the AWS SDK for Rust
+//!
+//! Download speed from S3 can be significantly improved by
+//! downloading parts of the file concurrently. This crate
+//! does exactly that.
+//!
+//! The requests are made with the [Client](aws_sdk_s3::Client)
+//! of the official `aws-sdk-s3` crate.
+//!
+//! ```rust, noexec
+//!
+//! use condow_aws_sdk::*;
+//! use condow_aws_sdk::config::Config;
+//!
+//! # async {
+//! let client = S3ClientWrapper::from_env().await;
+//! let condow = client.condow(Config::default()).unwrap();
+//!
+//! let location = url::Url::parse("s3://my_bucket/my_object").expect("a valid s3 URL");
+//!
+//! let stream = condow.download(&location, 23..46).await.unwrap();
+//! let downloaded_bytes: Vec<u8> = stream.into_vec().await.unwrap();
+//! # };
+//! # ()
+//! ```
+use std::{
+    fmt,
+    ops::{Deref, DerefMut},
+    sync::Arc,
+};
+
+use anyhow::Error as AnyError;
+use aws_sdk_s3::{
+    config::retry::RetryConfig,
+    error::{DisplayErrorContext, ProvideErrorMetadata, SdkError},
+    operation::{
+        get_object::{GetObjectError, GetObjectOutput},
+        head_object::HeadObjectError,
+    },
+    primitives::ByteStream,
+};
+use futures::{future::BoxFuture, FutureExt, TryFutureExt};
+use percent_encoding::percent_decode_str;
+
+pub use aws_sdk_s3::Client as S3Client;
+
+use condow_core::{
+    condow_client::*,
+    config::Config,
+    errors::{CondowError, IoError},
+    streams::{BytesHint, BytesStream},
+};
+
+pub use condow_core::*;
+
+/// S3 bucket name
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Bucket(String);
+
+impl Bucket {
+    pub fn new<T: Into<String>>(bucket: T) -> Self {
+        Self(bucket.into())
+    }
+
+    pub fn object<O: Into<ObjectKey>>(self, key: O) -> S3Location {
+        S3Location(self, key.into())
+    }
+
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl fmt::Display for Bucket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<&str> for Bucket {
+    fn from(s: &str) -> Self {
+        Self::new(s)
+    }
+}
+
+impl Deref for Bucket {
+    type Target = String;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Bucket {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// S3 object key
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ObjectKey(String);
+
+impl ObjectKey {
+    pub fn new<T: Into<String>>(key: T) -> Self {
+        Self(key.into())
+    }
+
+    pub fn in_bucket<B: Into<Bucket>>(self, bucket: B) -> S3Location {
+        S3Location(bucket.into(), self)
+    }
+
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl fmt::Display for ObjectKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Deref for ObjectKey {
+    type Target = String;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for ObjectKey {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl From<&str> for ObjectKey {
+    fn from(s: &str) -> Self {
+        Self::new(s)
+    }
+}
+
+/// Full "path" to an S3 object
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct S3Location(Bucket, ObjectKey);
+
+impl S3Location {
+    pub fn new<B: Into<Bucket>, O: Into<ObjectKey>>(bucket: B, key: O) -> Self {
+        Self(bucket.into(), key.into())
+    }
+
+    pub fn bucket(&self) -> &Bucket {
+        &self.0
+    }
+
+    pub fn key(&self) -> &ObjectKey {
+        &self.1
+    }
+
+    /// Turn this into its two components
+    pub fn into_inner(self) -> (Bucket, ObjectKey) {
+        (self.0, self.1)
+    }
+}
+
+impl fmt::Display for S3Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "s3://{}/{}", self.0, self.1)
+    }
+}
+
+/// Extracts the [Bucket] and the [ObjectKey] from a location
+///
+/// The default expects URLs of the form `s3://bucket/key`
+/// with a percent-encoded key.
+#[derive(Clone)]
+pub struct LocationParser(
+    Arc<dyn Fn(&url::Url) -> Result<(Bucket, ObjectKey), CondowError> + Send + Sync>,
+);
+
+impl LocationParser {
+    /// Create a [LocationParser] from the given function
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(&url::Url) -> Result<(Bucket, ObjectKey), CondowError> + Send + Sync + 'static,
+    {
+        Self(Arc::new(f))
+    }
+
+    /// Extract the [Bucket] and the [ObjectKey] from the given location
+    pub fn parse(&self, location: &url::Url) -> Result<(Bucket, ObjectKey), CondowError> {
+        (self.0)(location)
+    }
+}
+
+impl Default for LocationParser {
+    fn default() -> Self {
+        Self::new(bucket_and_key)
+    }
+}
+
+impl<F> From<F> for LocationParser
+where
+    F: Fn(&url::Url) -> Result<(Bucket, ObjectKey), CondowError> + Send + Sync + 'static,
+{
+    fn from(f: F) -> Self {
+        Self::new(f)
+    }
+}
+
+/// Just a wrapper around an [S3Client](aws_sdk_s3::Client)
+/// to implement the trait [CondowClient](condow_client::CondowClient) on.
+///
+/// The query parameter `versionId` of a location selects the version of
+/// the object to download. Other query parameters are not passed on to S3.
+///
+/// Errors returned by the SDK are attached as the source of a [CondowError].
+/// Use [CondowError::downcast_ref] to get e.g. the `SdkError<GetObjectError>` back.
+#[derive(Clone)]
+pub struct S3ClientWrapper {
+    client: S3Client,
+    location_parser: LocationParser,
+}
+
+impl S3ClientWrapper {
+    /// Create a new wrapper wrapping an [S3Client](aws_sdk_s3::Client)
+    /// configured from the environment.
+    ///
+    /// The retries of the SDK are disabled since [Condow] already retries
+    /// requests as configured. Otherwise the attempts of both would multiply.
+    ///
+    /// See [aws_config::load_defaults].
+    pub async fn from_env() -> Self {
+        let config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+        let s3_config = aws_sdk_s3::config::Builder::from(&config)
+            .retry_config(RetryConfig::disabled())
+            .build();
+        Self::from_client(S3Client::from_conf(s3_config))
+    }
+
+    /// Create a new wrapper wrapping the given [S3Client](aws_sdk_s3::Client).
+    ///
+    /// Consider disabling the retries of the client with
+    /// [RetryConfig::disabled] if [Condow] is configured to retry.
+    pub fn from_client(client: S3Client) -> Self {
+        Self {
+            client,
+            location_parser: LocationParser::default(),
+        }
+    }
+
+    /// Set how the bucket and the object key are extracted from a location
+    ///
+    /// The default expects URLs of the form `s3://bucket/key`.
+    pub fn with_location_parser<P: Into<LocationParser>>(mut self, location_parser: P) -> Self {
+        self.location_parser = location_parser.into();
+        self
+    }
+
+    /// Create a concurrent downloader from this adapter and the given [Config]
+    pub fn condow(self, config: Config) -> Result<Condow<Self>, AnyError> {
+        Condow::new(self, config)
+    }
+}
+
+impl CondowClient for S3ClientWrapper {
+    fn get_size(&self, location: url::Url) -> BoxFuture<'static, Result<u64, CondowError>> {
+        let client = self.client.clone();
+        let location_parser = self.location_parser.clone();
+        let f = async move {
+            let (bucket, object_key) = location_parser.parse(&location)?;
+
+            let response = client
+                .head_object()
+                .bucket(bucket.into_inner())
+                .key(object_key.into_inner())
+                .set_version_id(version_id(&location))
+                .send()
+                .await
+                .map_err(head_obj_err_to_get_size_err)?;
+
+            content_length_to_size(response.content_length())
+        };
+
+        Box::pin(f)
+    }
+
+    fn download(
+        &self,
+        location: url::Url,
+        spec: DownloadSpec,
+    ) -> BoxFuture<'static, Result<(BytesStream, BytesHint), CondowError>> {
+        self.download_with_content_range(location, spec)
+            .map_ok(|(stream, bytes_hint, _content_range)| (stream, bytes_hint))
+            .boxed()
+    }
+
+    fn download_with_content_range(
+        &self,
+        location: url::Url,
+        spec: DownloadSpec,
+    ) -> BoxFuture<'static, Result<ContentRangeResponse, CondowError>> {
+        let client = self.client.clone();
+        let location_parser = self.location_parser.clone();
+        let f = async move {
+            let (bucket, object_key) = location_parser.parse(&location)?;
+
+            let response = client
+                .get_object()
+                .bucket(bucket.into_inner())
+                .key(object_key.into_inner())
+                .set_range(spec.http_range_value())
+                .set_version_id(version_id(&location))
+                .send()
+                .await
+                .map_err(get_obj_err_to_download_err)?;
+
+            check_range_honored(spec.http_range_value().as_deref(), &response)?;
+            let content_range = response.content_range().and_then(parse_content_range);
+
+            let bytes_hint = content_length_to_bytes_hint(response.content_length());
+            let stream = body_to_stream(response.body);
+
+            Ok((stream, bytes_hint, content_range))
+        };
+
+        Box::pin(f)
+    }
+
+    fn download_from(
+        &self,
+        location: url::Url,
+        start: u64,
+    ) -> BoxFuture<'static, Result<(BytesStream, BytesHint), CondowError>> {
+        let client = self.client.clone();
+        let location_parser = self.location_parser.clone();
+        let f = async move {
+            let (bucket, object_key) = location_parser.parse(&location)?;
+            let range = format!("bytes={}-", start);
+
+            let result = client
+                .get_object()
+                .bucket(bucket.into_inner())
+                .key(object_key.into_inner())
+                .range(range.clone())
+                .set_version_id(version_id(&location))
+                .send()
+                .await;
+
+            let response = match result {
+                Ok(response) => response,
+                // S3 answers with "416 Range Not Satisfiable" if `start`
+                // is not within the object
+                Err(err) if status_code(&err) == Some(416) => {
+                    let stream: BytesStream = Box::pin(futures::stream::empty());
+                    return Ok((stream, BytesHint::new_exact(0)));
+                }
+                Err(err) => return Err(get_obj_err_to_download_err(err)),
+            };
+
+            check_range_honored(Some(&range), &response)?;
+
+            let bytes_hint = content_length_to_bytes_hint(response.content_length());
+            let stream = body_to_stream(response.body);
+
+            Ok((stream, bytes_hint))
+        };
+
+        Box::pin(f)
+    }
+}
+
+/// Turns the body of a response into a [BytesStream]
+///
+/// The [ByteStream] of the SDK is not a [Stream](futures::Stream) itself.
+fn body_to_stream(body: ByteStream) -> BytesStream {
+    let stream = futures::stream::unfold(body, |mut body| async move {
+        let next = body.next().await?;
+        Some((next.map_err(|err| IoError(err.to_string())), body))
+    });
+
+    Box::pin(stream)
+}
+
+fn content_length_to_bytes_hint(content_length: Option<i64>) -> BytesHint {
+    content_length
+        .and_then(|len| u64::try_from(len).ok())
+        .map(BytesHint::new_exact)
+        .unwrap_or_else(BytesHint::new_no_hint)
+}
+
+/// Returns the size of an object from the content length of a HEAD request
+///
+/// A content length of 0 is an empty object. A missing content length is an error
+/// since the size of the object is unknown.
+fn content_length_to_size(content_length: Option<i64>) -> Result<u64, CondowError> {
+    match content_length {
+        Some(size) if size >= 0 => Ok(size as u64),
+        Some(size) => Err(CondowError::new_other(format!(
+            "response had a negative content length ({})",
+            size
+        ))),
+        None => Err(CondowError::new_other("response had no content length")),
+    }
+}
+
+/// Fails if a range was requested but the server sent the complete object
+///
+/// A server ignoring the `Range` header answers with a 200 instead of a 206
+/// and therefore without a `Content-Range` header.
+fn check_range_honored(range: Option<&str>, response: &GetObjectOutput) -> Result<(), CondowError> {
+    check_range_headers(range, response.accept_ranges(), response.content_range())
+}
+
+fn check_range_headers(
+    range: Option<&str>,
+    accept_ranges: Option<&str>,
+    content_range: Option<&str>,
+) -> Result<(), CondowError> {
+    let range = if let Some(range) = range {
+        range
+    } else {
+        return Ok(());
+    };
+
+    if accept_ranges == Some("none") {
+        return Err(CondowError::new_other(
+            "the server does not support range requests (Accept-Ranges: none)",
+        ));
+    }
+
+    if content_range.is_none() {
+        return Err(CondowError::new_other(format!(
+            "the server ignored the range '{}' and did not respond with a Content-Range \
+            - it probably does not support range requests",
+            range
+        )));
+    }
+
+    Ok(())
+}
+
+/// Parses the range of a `Content-Range` header like `bytes 0-99/100`
+///
+/// Returns `None` if the value is not a valid byte range.
+fn parse_content_range(value: &str) -> Option<InclusiveRange> {
+    let range = value.trim().strip_prefix("bytes ")?;
+    let (range, _total) = range.split_once('/')?;
+    let (start, end_incl) = range.split_once('-')?;
+    let start = start.trim().parse().ok()?;
+    let end_incl = end_incl.trim().parse().ok()?;
+
+    if start > end_incl {
+        return None;
+    }
+
+    Some(InclusiveRange(start, end_incl))
+}
+
+/// Extracts the bucket and the percent-decoded object key from an `s3://` location
+///
+/// The leading `/` of the path is not part of the key.
+/// Fails if the key is not properly percent-encoded or does not decode to UTF-8.
+fn bucket_and_key(location: &url::Url) -> Result<(Bucket, ObjectKey), CondowError> {
+    let bucket = location
+        .host_str()
+        .ok_or_else(|| CondowError::new_other(format!("no bucket in S3 URL '{}'", location)))?;
+
+    let path = location.path().trim_start_matches('/');
+    let object_key = decode_object_key(path).map_err(|msg| {
+        CondowError::new_other(format!(
+            "invalid object key '{}' in S3 URL '{}': {}",
+            path, location, msg
+        ))
+    })?;
+
+    Ok((Bucket::new(bucket), ObjectKey::new(object_key)))
+}
+
+/// Returns the decoded value of the query parameter `versionId`
+fn version_id(location: &url::Url) -> Option<String> {
+    location
+        .query_pairs()
+        .find(|(name, _)| name == "versionId")
+        .map(|(_, value)| value.into_owned())
+}
+
+/// Percent-decodes an object key
+///
+/// Unlike [percent_decode_str] a `%` which is not followed by two hex digits is an error.
+fn decode_object_key(encoded: &str) -> Result<String, String> {
+    let bytes = encoded.as_bytes();
+    for (idx, _) in encoded.match_indices('%') {
+        match bytes.get(idx + 1..idx + 3) {
+            None => return Err(format!("truncated percent-encoding at position {}", idx)),
+            Some(hex) if !hex.iter().all(u8::is_ascii_hexdigit) => {
+                return Err(format!("invalid percent-encoding at position {}", idx))
+            }
+            Some(_) => {}
+        }
+    }
+
+    percent_decode_str(encoded)
+        .decode_utf8()
+        .map(|key| key.into_owned())
+        .map_err(|_| "key is not valid UTF-8".to_string())
+}
+
+/// Returns the HTTP status code of the response an error was created from
+fn status_code<E>(err: &SdkError<E>) -> Option<u16> {
+    err.raw_response()
+        .map(|response| response.status().as_u16())
+}
+
+fn get_obj_err_to_download_err(err: SdkError<GetObjectError>) -> CondowError {
+    let condow_err = match &err {
+        SdkError::ServiceError(service_err) => match service_err.err() {
+            GetObjectError::NoSuchKey(no_such_key) => {
+                CondowError::new_not_found(no_such_key.message().unwrap_or("no such key"))
+            }
+            GetObjectError::InvalidObjectState(state) => {
+                CondowError::new_object_not_ready(format!(
+                    "object not restored from its storage class (get object request): {}",
+                    state.message().unwrap_or("invalid object state")
+                ))
+            }
+            other => response_to_condow_err(
+                "get object",
+                service_err.raw().status().as_u16(),
+                other.code(),
+                other.message(),
+            ),
+        },
+        other => sdk_err_to_condow_err("get object", other),
+    };
+
+    condow_err.with_source(err)
+}
+
+fn head_obj_err_to_get_size_err(err: SdkError<HeadObjectError>) -> CondowError {
+    let condow_err = match &err {
+        SdkError::ServiceError(service_err) => match service_err.err() {
+            HeadObjectError::NotFound(not_found) => {
+                CondowError::new_not_found(not_found.message().unwrap_or("not found"))
+            }
+            other => response_to_condow_err(
+                "head object",
+                service_err.raw().status().as_u16(),
+                other.code(),
+                other.message(),
+            ),
+        },
+        other => sdk_err_to_condow_err("head object", other),
+    };
+
+    condow_err.with_source(err)
+}
+
+/// Maps the errors which did not come with an error response of S3
+fn sdk_err_to_condow_err<E>(request: &str, err: &SdkError<E>) -> CondowError
+where
+    E: std::error::Error + 'static,
+{
+    match err {
+        SdkError::ConstructionFailure(_) => CondowError::new_other(format!(
+            "failed to construct the {} request: {}",
+            request,
+            DisplayErrorContext(err)
+        )),
+        SdkError::TimeoutError(_) => CondowError::new_timeout(format!(
+            "timeout ({} request): {}",
+            request,
+            DisplayErrorContext(err)
+        )),
+        // Connection resets and GOAWAYs end up here. They are worth a retry.
+        SdkError::DispatchFailure(_) => CondowError::new_io(format!(
+            "dispatch failure ({} request): {}",
+            request,
+            DisplayErrorContext(err)
+        )),
+        SdkError::ResponseError(response_err) => {
+            let status = response_err.raw().status().as_u16();
+            if (500..600).contains(&status) {
+                CondowError::new_remote(format!(
+                    "{} - invalid response ({} request): {}",
+                    status,
+                    request,
+                    DisplayErrorContext(err)
+                ))
+            } else {
+                CondowError::new_io(format!(
+                    "invalid response ({} request): {}",
+                    request,
+                    DisplayErrorContext(err)
+                ))
+            }
+        }
+        _ => CondowError::new_other(format!(
+            "{} request failed: {}",
+            request,
+            DisplayErrorContext(err)
+        )),
+    }
+}
+
+/// Maps an error response of S3 by its status and error code
+fn response_to_condow_err(
+    request: &str,
+    status: u16,
+    code: Option<&str>,
+    message: Option<&str>,
+) -> CondowError {
+    let message = format!(
+        "{} - {} ({} request): {}",
+        status,
+        code.unwrap_or("<no error code>"),
+        request,
+        message.unwrap_or("<no message>")
+    );
+
+    if code == Some("InvalidObjectState") {
+        return CondowError::new_object_not_ready(message);
+    }
+
+    match status {
+        404 => CondowError::new_not_found(message),
+        401 | 403 => CondowError::new_access_denied(message),
+        500..=599 => CondowError::new_remote(message),
+        _ => CondowError::new_other(message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use condow_core::{errors::CondowErrorKind, InclusiveRange};
+
+    use super::{
+        bucket_and_key, check_range_headers, content_length_to_bytes_hint, content_length_to_size,
+        parse_content_range, response_to_condow_err, version_id, LocationParser,
+    };
+
+    fn parse(url: &str) -> url::Url {
+        url::Url::parse(url).expect("a valid URL")
+    }
+
+    #[test]
+    fn plain_key() {
+        let (bucket, key) = bucket_and_key(&parse("s3://my_bucket/my_object")).unwrap();
+
+        assert_eq!(&*bucket, "my_bucket");
+        assert_eq!(&*key, "my_object");
+    }
+
+    #[test]
+    fn percent_encoded_key() {
+        let (_, key) = bucket_and_key(&parse("s3://my_bucket/my%20dir/%C3%A4.txt")).unwrap();
+
+        assert_eq!(&*key, "my dir/ä.txt");
+    }
+
+    #[test]
+    fn default_location_parser_parses_s3_urls() {
+        let (bucket, key) = LocationParser::default()
+            .parse(&parse("s3://my_bucket/my_object"))
+            .unwrap();
+
+        assert_eq!(&*bucket, "my_bucket");
+        assert_eq!(&*key, "my_object");
+    }
+
+    #[test]
+    fn invalid_percent_encoding() {
+        let err = bucket_and_key(&parse("s3://my_bucket/my%zzobject")).unwrap_err();
+
+        assert_eq!(err.kind(), CondowErrorKind::Other);
+        assert!(err.msg().contains("invalid percent-encoding"), "{}", err);
+    }
+
+    #[test]
+    fn truncated_percent_encoding() {
+        let err = bucket_and_key(&parse("s3://my_bucket/my_object%4")).unwrap_err();
+
+        assert!(err.msg().contains("truncated percent-encoding"), "{}", err);
+    }
+
+    #[test]
+    fn a_sign_is_not_a_hex_digit() {
+        let err = bucket_and_key(&parse("s3://my_bucket/my%+1object")).unwrap_err();
+
+        assert!(err.msg().contains("invalid percent-encoding"), "{}", err);
+    }
+
+    #[test]
+    fn version_id_from_the_query() {
+        assert_eq!(version_id(&parse("s3://bucket/key")), None);
+        assert_eq!(
+            version_id(&parse("s3://bucket/key?other=1&versionId=abc")),
+            Some("abc".to_string())
+        );
+    }
+
+    #[test]
+    fn the_query_is_not_part_of_the_key() {
+        let (_, key) = bucket_and_key(&parse("s3://bucket/key?versionId=abc")).unwrap();
+
+        assert_eq!(&*key, "key");
+    }
+
+    #[test]
+    fn sizes_from_content_lengths() {
+        assert_eq!(content_length_to_size(Some(0)).unwrap(), 0);
+        assert_eq!(content_length_to_size(Some(42)).unwrap(), 42);
+        assert!(content_length_to_size(Some(-1)).is_err());
+        assert!(content_length_to_size(None).is_err());
+
+        assert_eq!(content_length_to_bytes_hint(Some(42)).exact(), Some(42));
+        assert_eq!(content_length_to_bytes_hint(Some(-1)).exact(), None);
+        assert_eq!(content_length_to_bytes_hint(None).exact(), None);
+    }
+
+    #[test]
+    fn content_ranges_are_parsed() {
+        assert_eq!(
+            parse_content_range("bytes 0-99/100"),
+            Some(InclusiveRange(0, 99))
+        );
+        assert_eq!(
+            parse_content_range("bytes 10-19/*"),
+            Some(InclusiveRange(10, 19))
+        );
+        assert_eq!(parse_content_range("bytes */100"), None);
+        assert_eq!(parse_content_range("bytes 20-10/100"), None);
+    }
+
+    #[test]
+    fn an_ignored_range_is_an_error() {
+        let range = Some("bytes=0-9");
+
+        assert!(check_range_headers(None, None, None).is_ok());
+        assert!(check_range_headers(range, Some("bytes"), Some("bytes 0-9/100")).is_ok());
+        assert!(check_range_headers(range, Some("none"), Some("bytes 0-9/100")).is_err());
+        assert!(check_range_headers(range, Some("bytes"), None).is_err());
+    }
+
+    #[test]
+    fn error_responses_are_mapped_by_status_and_code() {
+        let kind = |status, code| response_to_condow_err("get object", status, code, None).kind();
+
+        assert_eq!(kind(404, None), CondowErrorKind::NotFound);
+        assert_eq!(
+            kind(403, Some("AccessDenied")),
+            CondowErrorKind::AccessDenied
+        );
+        assert_eq!(kind(401, None), CondowErrorKind::AccessDenied);
+        assert_eq!(kind(503, Some("SlowDown")), CondowErrorKind::Remote);
+        assert_eq!(
+            kind(403, Some("InvalidObjectState")),
+            CondowErrorKind::ObjectNotReady
+        );
+        assert_eq!(kind(400, Some("InvalidRequest")), CondowErrorKind::Other);
+    }
+}
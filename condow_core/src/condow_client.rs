@@ -1,13 +1,17 @@
 //! Adapter for [crate::Condow] to access BLOBs to be downloaded
 //!
+//! [InstrumentedClient] wraps any [CondowClient] to observe the requests made.
+//!
+//! A [ClientFactory] creates a new [CondowClient] for each download.
+//!
 //! There are also implementation of a client mostly for testing
 //!
 //! * [InMemoryClient]: A client which keeps data in memory and never fails
 //! * [failing_client_simulator]: A module containing a client with data kept in memory
 //! which can fail and cause panics.
-use std::ops::RangeInclusive;
+use std::{fmt, future::Future, ops::RangeInclusive, sync::Arc};
 
-use futures::future::BoxFuture;
+use futures::{future::BoxFuture, FutureExt};
 
 use crate::{
     errors::CondowError,
@@ -16,6 +20,7 @@ use crate::{
 };
 
 pub use in_memory::InMemoryClient;
+pub use instrumented::InstrumentedClient;
 
 /// Specifies whether a whole BLOB or part of it should be downloaded
 #[derive(Debug, Copy, Clone)]
@@ -57,6 +62,12 @@ impl From<RangeInclusive<u64>> for DownloadSpec {
     }
 }
 
+/// The byte stream and [BytesHint] of a download together with
+/// the range of the BLOB the response claims to contain
+///
+/// See [CondowClient::download_with_content_range]
+pub type ContentRangeResponse = (BytesStream, BytesHint, Option<InclusiveRange>);
+
 /// A client to some service or other resource which supports
 /// partial downloads
 ///
@@ -75,6 +86,119 @@ pub trait CondowClient: Clone + Send + Sync + 'static {
         location: url::Url,
         spec: DownloadSpec,
     ) -> BoxFuture<'static, Result<(BytesStream, BytesHint), CondowError>>;
+
+    /// Download like [CondowClient::download] but also return the range of the BLOB
+    /// the response claims to contain, e.g. from a `Content-Range` header
+    ///
+    /// This is used to verify that a request made to resume a broken stream
+    /// really starts at the requested offset. Otherwise bytes would be placed
+    /// at the wrong offset without noticing.
+    ///
+    /// The default implementation calls [CondowClient::download] and returns `None`
+    /// which means that the range can not be verified.
+    fn download_with_content_range(
+        &self,
+        location: url::Url,
+        spec: DownloadSpec,
+    ) -> BoxFuture<'static, Result<ContentRangeResponse, CondowError>> {
+        self.download(location, spec)
+            .map(|result| result.map(|(stream, bytes_hint)| (stream, bytes_hint, None)))
+            .boxed()
+    }
+
+    /// Download the BLOB from the given location starting at `start` up to its end
+    ///
+    /// Backends supporting open ranges (e.g. `Range: bytes=start-` with HTTP)
+    /// should implement this with a single request. [crate::Condow] then
+    /// streams the tail of a BLOB without requesting its size first
+    /// if nothing is downloaded concurrently.
+    ///
+    /// An empty stream must be returned if `start` is not within the BLOB.
+    ///
+    /// The default implementation requests the size with [CondowClient::get_size]
+    /// and downloads the remaining range with [CondowClient::download].
+    /// This size request is not bounded by
+    /// [Config::size_request_timeout_ms](crate::config::Config::size_request_timeout_ms)
+    /// since the client does not know the [Config](crate::config::Config).
+    /// Implement this method with an open range to avoid the size request.
+    fn download_from(
+        &self,
+        location: url::Url,
+        start: u64,
+    ) -> BoxFuture<'static, Result<(BytesStream, BytesHint), CondowError>> {
+        let client = self.clone();
+        async move {
+            let size = client.get_size(location.clone()).await?;
+            if start >= size {
+                return Ok((
+                    Box::pin(futures::stream::empty()) as BytesStream,
+                    BytesHint::new_exact(0),
+                ));
+            }
+
+            client
+                .download(
+                    location,
+                    DownloadSpec::Range(InclusiveRange(start, size - 1)),
+                )
+                .await
+        }
+        .boxed()
+    }
+}
+
+/// Creates a new [CondowClient] for each download
+///
+/// Used with [Condow::from_client_factory](crate::Condow::from_client_factory)
+/// if a single client can not be shared by all downloads, e.g.
+/// because each download needs fresh short lived credentials.
+///
+/// # Examples
+///
+/// ```rust
+/// # use condow_core::condow_client::{ClientFactory, InMemoryClient};
+/// let factory = ClientFactory::new(|| async { Ok(InMemoryClient::new_static(&[1, 2, 3])) });
+/// ```
+pub struct ClientFactory<C>(Arc<ClientFactoryFn<C>>);
+
+type ClientFactoryFn<C> = dyn Fn() -> BoxFuture<'static, Result<C, CondowError>> + Send + Sync;
+
+impl<C: 'static> ClientFactory<C> {
+    /// Create a [ClientFactory] from the given function
+    pub fn new<F, Fut>(f: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<C, CondowError>> + Send + 'static,
+    {
+        Self(Arc::new(move || f().boxed()))
+    }
+
+    /// Create a new client
+    pub fn create(&self) -> BoxFuture<'static, Result<C, CondowError>> {
+        (self.0)()
+    }
+}
+
+impl<C: 'static, F, Fut> From<F> for ClientFactory<C>
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<C, CondowError>> + Send + 'static,
+{
+    fn from(f: F) -> Self {
+        Self::new(f)
+    }
+}
+
+impl<C> Clone for ClientFactory<C> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<C> fmt::Debug for ClientFactory<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ClientFactory")
+    }
 }
 
 /// A location usable for testing.
@@ -87,6 +211,339 @@ impl std::fmt::Display for NoLocation {
     }
 }
 
+mod instrumented {
+    use std::{
+        sync::Arc,
+        time::{Duration, Instant},
+    };
+
+    use futures::future::BoxFuture;
+
+    use crate::{
+        errors::CondowError,
+        streams::{BytesHint, BytesStream},
+    };
+
+    use super::{CondowClient, ContentRangeResponse, DownloadSpec};
+
+    type GetSizeCallback = dyn Fn(&url::Url, Result<u64, &CondowError>, Duration) + Send + Sync;
+    type DownloadCallback =
+        dyn Fn(&url::Url, DownloadSpec, Result<&BytesHint, &CondowError>, Duration) + Send + Sync;
+    type DownloadFromCallback =
+        dyn Fn(&url::Url, u64, Result<&BytesHint, &CondowError>, Duration) + Send + Sync;
+
+    /// Wraps a [CondowClient] and invokes callbacks on each request
+    /// made to the wrapped client.
+    ///
+    /// Since [Condow](crate::Condow) retries on top of its client, every
+    /// attempt is observed, not only the final outcome. This is the
+    /// request layer below the [Reporter](crate::reporter::Reporter)s.
+    ///
+    /// The [Duration] passed to a download callback is the time until the
+    /// response arrived, not the time it took to stream the bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use condow_core::condow_client::{InMemoryClient, InstrumentedClient};
+    /// let client = InstrumentedClient::new(InMemoryClient::new_static(b"a BLOB"))
+    ///     .on_get_size(|location, outcome, elapsed| {
+    ///         println!("get_size {} took {:?}: {:?}", location, elapsed, outcome)
+    ///     })
+    ///     .on_download(|location, spec, outcome, elapsed| {
+    ///         println!("download {} {:?} took {:?}: {:?}", location, spec, elapsed, outcome)
+    ///     });
+    /// ```
+    pub struct InstrumentedClient<C> {
+        client: C,
+        on_get_size: Option<Arc<GetSizeCallback>>,
+        on_download: Option<Arc<DownloadCallback>>,
+        on_download_from: Option<Arc<DownloadFromCallback>>,
+    }
+
+    impl<C: CondowClient> InstrumentedClient<C> {
+        /// Wrap the given client without any callbacks
+        pub fn new(client: C) -> Self {
+            Self {
+                client,
+                on_get_size: None,
+                on_download: None,
+                on_download_from: None,
+            }
+        }
+
+        /// Set the callback invoked after each call to
+        /// [CondowClient::get_size] on the wrapped client
+        pub fn on_get_size<F>(mut self, f: F) -> Self
+        where
+            F: Fn(&url::Url, Result<u64, &CondowError>, Duration) + Send + Sync + 'static,
+        {
+            self.on_get_size = Some(Arc::new(f));
+            self
+        }
+
+        /// Set the callback invoked after each call to
+        /// [CondowClient::download] on the wrapped client
+        pub fn on_download<F>(mut self, f: F) -> Self
+        where
+            F: Fn(&url::Url, DownloadSpec, Result<&BytesHint, &CondowError>, Duration)
+                + Send
+                + Sync
+                + 'static,
+        {
+            self.on_download = Some(Arc::new(f));
+            self
+        }
+
+        /// Set the callback invoked after each call to
+        /// [CondowClient::download_from] on the wrapped client
+        ///
+        /// The `u64` is the requested start. If the wrapped client uses
+        /// the default implementation of [CondowClient::download_from]
+        /// its size request and download are not observed separately.
+        pub fn on_download_from<F>(mut self, f: F) -> Self
+        where
+            F: Fn(&url::Url, u64, Result<&BytesHint, &CondowError>, Duration)
+                + Send
+                + Sync
+                + 'static,
+        {
+            self.on_download_from = Some(Arc::new(f));
+            self
+        }
+
+        /// Returns the wrapped client
+        pub fn into_inner(self) -> C {
+            self.client
+        }
+    }
+
+    impl<C: CondowClient> Clone for InstrumentedClient<C> {
+        fn clone(&self) -> Self {
+            Self {
+                client: self.client.clone(),
+                on_get_size: self.on_get_size.clone(),
+                on_download: self.on_download.clone(),
+                on_download_from: self.on_download_from.clone(),
+            }
+        }
+    }
+
+    impl<C: CondowClient> CondowClient for InstrumentedClient<C> {
+        fn get_size(&self, location: url::Url) -> BoxFuture<'static, Result<u64, CondowError>> {
+            let on_get_size = if let Some(on_get_size) = self.on_get_size.clone() {
+                on_get_size
+            } else {
+                return self.client.get_size(location);
+            };
+
+            let request = self.client.get_size(location.clone());
+            Box::pin(async move {
+                let started_at = Instant::now();
+                let result = request.await;
+                on_get_size(&location, result.as_ref().copied(), started_at.elapsed());
+                result
+            })
+        }
+
+        fn download(
+            &self,
+            location: url::Url,
+            spec: DownloadSpec,
+        ) -> BoxFuture<'static, Result<(BytesStream, BytesHint), CondowError>> {
+            let on_download = if let Some(on_download) = self.on_download.clone() {
+                on_download
+            } else {
+                return self.client.download(location, spec);
+            };
+
+            let request = self.client.download(location.clone(), spec);
+            Box::pin(async move {
+                let started_at = Instant::now();
+                let result = request.await;
+                on_download(
+                    &location,
+                    spec,
+                    result.as_ref().map(|(_, bytes_hint)| bytes_hint),
+                    started_at.elapsed(),
+                );
+                result
+            })
+        }
+
+        fn download_with_content_range(
+            &self,
+            location: url::Url,
+            spec: DownloadSpec,
+        ) -> BoxFuture<'static, Result<ContentRangeResponse, CondowError>> {
+            let on_download = if let Some(on_download) = self.on_download.clone() {
+                on_download
+            } else {
+                return self.client.download_with_content_range(location, spec);
+            };
+
+            let request = self
+                .client
+                .download_with_content_range(location.clone(), spec);
+            Box::pin(async move {
+                let started_at = Instant::now();
+                let result = request.await;
+                on_download(
+                    &location,
+                    spec,
+                    result.as_ref().map(|(_, bytes_hint, _)| bytes_hint),
+                    started_at.elapsed(),
+                );
+                result
+            })
+        }
+
+        fn download_from(
+            &self,
+            location: url::Url,
+            start: u64,
+        ) -> BoxFuture<'static, Result<(BytesStream, BytesHint), CondowError>> {
+            let on_download_from = if let Some(on_download_from) = self.on_download_from.clone() {
+                on_download_from
+            } else {
+                return self.client.download_from(location, start);
+            };
+
+            let request = self.client.download_from(location.clone(), start);
+            Box::pin(async move {
+                let started_at = Instant::now();
+                let result = request.await;
+                on_download_from(
+                    &location,
+                    start,
+                    result.as_ref().map(|(_, bytes_hint)| bytes_hint),
+                    started_at.elapsed(),
+                );
+                result
+            })
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use std::sync::{Arc, Mutex};
+
+        use crate::{
+            condow_client::{
+                failing_client_simulator::FailingClientSimulatorBuilder, InMemoryClient,
+            },
+            config::{Config, RetryConfig},
+            errors::{CondowError, CondowErrorKind},
+            Condow,
+        };
+
+        use super::InstrumentedClient;
+
+        const BLOB: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+
+        #[tokio::test]
+        async fn observes_get_size() {
+            let observed = Arc::new(Mutex::new(Vec::new()));
+            let observed_in_callback = Arc::clone(&observed);
+            let client = InstrumentedClient::new(InMemoryClient::new_static(BLOB)).on_get_size(
+                move |location, outcome, _elapsed| {
+                    observed_in_callback
+                        .lock()
+                        .unwrap()
+                        .push((location.to_string(), outcome.map_err(|err| err.kind())));
+                },
+            );
+            let condow = Condow::new(client, Config::default()).unwrap();
+            let location = url::Url::parse("noscheme://blob").expect("a valid URL");
+
+            let size = condow.get_size(&location).await.unwrap();
+
+            assert_eq!(size, BLOB.len() as u64);
+            assert_eq!(
+                *observed.lock().unwrap(),
+                vec![(location.to_string(), Ok(BLOB.len() as u64))]
+            );
+        }
+
+        #[tokio::test]
+        async fn observes_every_download_attempt() {
+            let observed = Arc::new(Mutex::new(Vec::new()));
+            let observed_in_callback = Arc::clone(&observed);
+            let failing_client = FailingClientSimulatorBuilder::default()
+                .blob_static(BLOB)
+                .responses()
+                .failure(CondowError::new_remote("boom"))
+                .success()
+                .finish();
+            let client = InstrumentedClient::new(failing_client).on_download(
+                move |_location, spec, outcome, _elapsed| {
+                    observed_in_callback.lock().unwrap().push((
+                        spec.start(),
+                        outcome
+                            .map(|bytes_hint| bytes_hint.exact())
+                            .map_err(|err| err.kind()),
+                    ));
+                },
+            );
+            let config = Config::default()
+                .buffers_full_delay_ms(0)
+                .retries(RetryConfig::default().max_attempts(1).initial_delay_ms(0));
+            let condow = Condow::new(client, config).unwrap();
+            let location = url::Url::parse("noscheme://blob").expect("a valid URL");
+
+            let result = condow
+                .download(&location, 2..=12)
+                .await
+                .unwrap()
+                .into_vec()
+                .await
+                .unwrap();
+
+            assert_eq!(result, BLOB[2..=12]);
+            assert_eq!(
+                *observed.lock().unwrap(),
+                vec![(2, Err(CondowErrorKind::Remote)), (2, Ok(Some(11)))]
+            );
+        }
+
+        #[tokio::test]
+        async fn observes_download_from() {
+            let observed = Arc::new(Mutex::new(Vec::new()));
+            let observed_in_callback = Arc::clone(&observed);
+            let client = InstrumentedClient::new(InMemoryClient::new_static(BLOB))
+                .on_download_from(move |_location, start, outcome, _elapsed| {
+                    observed_in_callback
+                        .lock()
+                        .unwrap()
+                        .push((start, outcome.map(|hint| hint.exact()).ok().flatten()));
+                });
+            let condow = Condow::new(client, Config::default()).unwrap();
+            let location = url::Url::parse("noscheme://blob").expect("a valid URL");
+
+            let _ = condow.download_raw(&location, 20..).await.unwrap();
+
+            assert_eq!(*observed.lock().unwrap(), vec![(20, Some(6))]);
+        }
+
+        #[tokio::test]
+        async fn without_callbacks_the_client_is_used_as_is() {
+            let client = InstrumentedClient::new(InMemoryClient::new_static(BLOB));
+            let condow = Condow::new(client, Config::default()).unwrap();
+            let location = url::Url::parse("noscheme://blob").expect("a valid URL");
+
+            let result = condow
+                .download(&location, ..)
+                .await
+                .unwrap()
+                .into_vec()
+                .await
+                .unwrap();
+
+            assert_eq!(result, BLOB);
+        }
+    }
+}
+
 mod in_memory {
     use std::sync::Arc;
 
@@ -1000,7 +1457,7 @@ pub mod failing_client_simulator {
             let range = DownloadSpec::Complete;
 
             let result = download(&client, range).await.unwrap().unwrap_err();
-            assert_eq!(result, &[], "err");
+            assert_eq!(result, &[] as &[u8], "err");
             let result = download(&client, range).await.unwrap().unwrap();
             assert_eq!(result, BLOB, "ok");
         }
@@ -1015,9 +1472,9 @@ pub mod failing_client_simulator {
             let range = DownloadSpec::Complete;
 
             let result = download(&client, range).await.unwrap().unwrap_err();
-            assert_eq!(result, &[], "err");
+            assert_eq!(result, &[] as &[u8], "err");
             let result = download(&client, range).await.unwrap().unwrap_err();
-            assert_eq!(result, &[], "err");
+            assert_eq!(result, &[] as &[u8], "err");
             let result = download(&client, range).await.unwrap().unwrap();
             assert_eq!(result, BLOB, "ok");
         }
@@ -1119,7 +1576,7 @@ pub mod failing_client_simulator {
             let result = download(&client, range).await.unwrap_err();
             assert_eq!(result.kind(), CondowErrorKind::Io, "1");
             let result = download(&client, range).await.unwrap().unwrap_err();
-            assert_eq!(result, &[], "2");
+            assert_eq!(result, &[] as &[u8], "2");
             let result = download(&client, range).await.unwrap_err();
             assert_eq!(result.kind(), CondowErrorKind::Remote, "3");
             let result = download(&client, range).await.unwrap_err();
@@ -1150,7 +1607,7 @@ pub mod failing_client_simulator {
                 .await
                 .unwrap()
                 .unwrap_err();
-            assert_eq!(result, &[], "2");
+            assert_eq!(result, &[] as &[u8], "2");
             let result = download(&client, DownloadSpec::Complete).await.unwrap_err();
             assert_eq!(result.kind(), CondowErrorKind::Remote, "3");
             let result = download(&client, DownloadSpec::Complete).await.unwrap_err();
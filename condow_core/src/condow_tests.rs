@@ -35,7 +35,7 @@ mod blob {
                         condow.downloader_with_reporting(SimpleReporterFactory::default());
 
                     let result_stream = downloader
-                        .download_rep(url::Url::parse("noscheme://").expect("a valid URL"), ..)
+                        .download_rep(&url::Url::parse("noscheme://").expect("a valid URL"), ..)
                         .await
                         .unwrap();
 
@@ -74,7 +74,7 @@ mod blob {
                         condow.downloader_with_reporting(SimpleReporterFactory::default());
 
                     let result_stream = downloader
-                        .download_rep(url::Url::parse("noscheme://").expect("a valid URL"), ..)
+                        .download_rep(&url::Url::parse("noscheme://").expect("a valid URL"), ..)
                         .await
                         .unwrap();
 
@@ -84,6 +84,50 @@ mod blob {
             }
         }
     }
+
+    #[tokio::test]
+    async fn download_with_bytes_counter() {
+        use std::sync::atomic::{AtomicU64, Ordering};
+
+        let data = Arc::new(create_test_data());
+
+        let client = TestCondowClient {
+            data: Arc::clone(&data),
+            max_jitter_ms: 0,
+            include_size_hint: true,
+            max_chunk_size: 3,
+        };
+
+        let config = Config::default()
+            .buffers_full_delay_ms(0)
+            .part_size_bytes(10)
+            .max_concurrency(4);
+        let condow = Condow::new(client, config).unwrap();
+
+        let counter = Arc::new(AtomicU64::new(0));
+        let downloader = condow.downloader().with_bytes_counter(Arc::clone(&counter));
+
+        let result = downloader
+            .download(&url::Url::parse("noscheme://").expect("a valid URL"), ..)
+            .await
+            .unwrap()
+            .into_vec()
+            .await
+            .unwrap();
+        assert_eq!(counter.load(Ordering::SeqCst), data.len() as u64);
+
+        let _ = downloader
+            .download_rep(&url::Url::parse("noscheme://").expect("a valid URL"), 0..10)
+            .await
+            .unwrap()
+            .into_stream()
+            .into_vec()
+            .await
+            .unwrap();
+        assert_eq!(counter.load(Ordering::SeqCst), data.len() as u64 + 10);
+
+        assert_eq!(&result, data.as_ref());
+    }
 }
 
 mod range {
@@ -512,3 +556,3407 @@ mod range {
         }
     }
 }
+
+mod bytes {
+    use std::sync::Arc;
+
+    use futures::TryStreamExt;
+
+    use crate::condow_client::NoLocation;
+    use crate::{config::Config, test_utils::create_test_data, test_utils::*, Condow};
+
+    #[tokio::test]
+    async fn download_bytes_ordered() {
+        let data = Arc::new(create_test_data());
+
+        let client = TestCondowClient {
+            data: Arc::clone(&data),
+            max_jitter_ms: 5,
+            include_size_hint: true,
+            max_chunk_size: 3,
+        };
+
+        let config = Config::default()
+            .buffers_full_delay_ms(0)
+            .part_size_bytes(10)
+            .max_concurrency(10)
+            .default_ordered(true);
+        let condow = Condow::new(client, config).unwrap();
+
+        let result = condow
+            .download_bytes(&url::Url::parse("noscheme://").expect("a valid URL"), ..)
+            .await
+            .unwrap()
+            .try_fold(Vec::new(), |mut acc, bytes| async move {
+                acc.extend_from_slice(&bytes);
+                Ok(acc)
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(result, data.as_ref()[..]);
+    }
+
+    #[tokio::test]
+    async fn download_bytes_unordered() {
+        let data = Arc::new(create_test_data());
+
+        let client = TestCondowClient {
+            data: Arc::clone(&data),
+            max_jitter_ms: 5,
+            include_size_hint: true,
+            max_chunk_size: 3,
+        };
+
+        let config = Config::default()
+            .buffers_full_delay_ms(0)
+            .part_size_bytes(10)
+            .max_concurrency(10)
+            .default_ordered(false);
+        let condow = Condow::new(client, config).unwrap();
+
+        let mut result = condow
+            .download_bytes(&url::Url::parse("noscheme://").expect("a valid URL"), ..)
+            .await
+            .unwrap()
+            .try_fold(Vec::new(), |mut acc, bytes| async move {
+                acc.extend_from_slice(&bytes);
+                Ok(acc)
+            })
+            .await
+            .unwrap();
+
+        let mut expected = data.as_ref().clone();
+        result.sort_unstable();
+        expected.sort_unstable();
+        assert_eq!(result, expected);
+    }
+}
+
+mod downloads_defaults {
+    use futures::{future::BoxFuture, AsyncReadExt, TryStreamExt};
+
+    use crate::{
+        condow_client::InMemoryClient,
+        config::Config,
+        errors::CondowError,
+        reader::RandomAccessReader,
+        streams::{ChunkStream, PartStream},
+        Condow, DownloadRange, Downloads,
+    };
+
+    const BLOB: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+
+    /// Only implements the required methods of [Downloads]
+    #[derive(Clone)]
+    struct Minimal(Condow<InMemoryClient>);
+
+    impl Downloads for Minimal {
+        fn download<'a, R: Into<DownloadRange> + Send + Sync + 'static>(
+            &'a self,
+            location: &'a url::Url,
+            range: R,
+        ) -> BoxFuture<'a, Result<PartStream<ChunkStream>, CondowError>> {
+            Downloads::download(&self.0, location, range)
+        }
+
+        fn download_chunks<'a, R: Into<DownloadRange> + Send + Sync + 'static>(
+            &'a self,
+            location: &'a url::Url,
+            range: R,
+        ) -> BoxFuture<'a, Result<ChunkStream, CondowError>> {
+            Downloads::download_chunks(&self.0, location, range)
+        }
+
+        fn get_size<'a>(
+            &'a self,
+            location: &'a url::Url,
+        ) -> BoxFuture<'a, Result<u64, CondowError>> {
+            Downloads::get_size(&self.0, location)
+        }
+
+        fn reader_with_length(&self, location: &url::Url, length: u64) -> RandomAccessReader<Self> {
+            RandomAccessReader::new_with_length(self.clone(), location.clone(), length)
+        }
+    }
+
+    #[tokio::test]
+    async fn download_from_sequential() {
+        let client = InMemoryClient::new_static(BLOB).chunk_size(2);
+        let config = Config::default()
+            .buffers_full_delay_ms(0)
+            .part_size_bytes(3)
+            .max_concurrency(4);
+        let downloads = Minimal(Condow::new(client, config).unwrap());
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let (stream, bytes_hint) = downloads
+            .download_from_sequential(&location, 5)
+            .await
+            .unwrap();
+        let received: Vec<_> = stream.try_collect().await.unwrap();
+
+        assert_eq!(bytes_hint.exact(), Some(BLOB.len() as u64 - 5));
+        assert_eq!(received.concat(), BLOB[5..]);
+    }
+
+    #[tokio::test]
+    async fn reader_streaming() {
+        let client = InMemoryClient::new_static(BLOB).chunk_size(2);
+        let config = Config::default()
+            .buffers_full_delay_ms(0)
+            .part_size_bytes(3)
+            .max_concurrency(4);
+        let downloads = Minimal(Condow::new(client, config).unwrap());
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let mut reader = downloads.reader_streaming(&location);
+        let mut received = Vec::new();
+        reader.read_to_end(&mut received).await.unwrap();
+
+        assert_eq!(reader.length(), Some(BLOB.len() as u64));
+        assert_eq!(received, BLOB);
+    }
+}
+
+mod download_from_sequential {
+    use futures::{StreamExt, TryStreamExt};
+
+    use crate::{
+        condow_client::failing_client_simulator::{
+            FailingClientSimulator, FailingClientSimulatorBuilder,
+        },
+        config::Config,
+        errors::CondowErrorKind,
+        reporter::{KillReason, RecordingReporter, ReporterEvent},
+        Condow, Downloads,
+    };
+
+    fn condow(builder: FailingClientSimulatorBuilder) -> Condow<FailingClientSimulator> {
+        let client = builder.blob((0u8..100).collect()).chunk_size(10).finish();
+        let config = Config::default().buffers_full_delay_ms(0).disable_retries();
+        Condow::new(client, config).unwrap()
+    }
+
+    #[tokio::test]
+    async fn reports_start_and_completion() {
+        let condow = condow(FailingClientSimulatorBuilder::default());
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+        let reporter = RecordingReporter::new();
+
+        let (stream, _bytes_hint) = condow
+            .download_session(reporter.clone())
+            .download_from_sequential(&location, 0)
+            .await
+            .unwrap();
+        assert_eq!(reporter.event_names(), vec!["download_started"]);
+
+        let received: Vec<_> = stream.try_collect().await.unwrap();
+
+        assert_eq!(received.concat().len(), 100);
+        assert_eq!(
+            reporter.event_names(),
+            vec!["download_started", "download_completed"]
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_a_failed_request() {
+        let condow = condow(
+            FailingClientSimulatorBuilder::default()
+                .responses()
+                .failure(CondowErrorKind::NotFound)
+                .done(),
+        );
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+        let reporter = RecordingReporter::new();
+
+        let result = condow
+            .download_session(reporter.clone())
+            .download_from_sequential(&location, 0)
+            .await;
+
+        assert!(result.is_err());
+        let events = reporter.events();
+        assert_eq!(events[0], ReporterEvent::DownloadStarted);
+        assert!(matches!(
+            events[1..],
+            [ReporterEvent::DownloadFailed {
+                time: Some(_),
+                reason: KillReason::ClientError
+            }]
+        ));
+    }
+
+    #[tokio::test]
+    async fn reports_a_failed_stream() {
+        let condow = condow(
+            FailingClientSimulatorBuilder::default()
+                .responses()
+                .success_with_stream_failure(15)
+                .done(),
+        );
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+        let reporter = RecordingReporter::new();
+
+        let (stream, _bytes_hint) = condow
+            .download_session(reporter.clone())
+            .download_from_sequential(&location, 0)
+            .await
+            .unwrap();
+        let _ = stream.collect::<Vec<_>>().await;
+
+        let events = reporter.events();
+        assert_eq!(events[0], ReporterEvent::DownloadStarted);
+        assert!(matches!(
+            events[1..],
+            [ReporterEvent::DownloadFailed {
+                time: Some(_),
+                reason: KillReason::StreamError
+            }]
+        ));
+    }
+
+    #[tokio::test]
+    async fn reports_a_dropped_stream_as_cancelled() {
+        let condow = condow(FailingClientSimulatorBuilder::default());
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+        let reporter = RecordingReporter::new();
+
+        let (mut stream, _bytes_hint) = condow
+            .download_session(reporter.clone())
+            .download_from_sequential(&location, 0)
+            .await
+            .unwrap();
+        let _ = stream.next().await;
+        drop(stream);
+
+        assert_eq!(
+            reporter.event_names(),
+            vec!["download_started", "download_cancelled"]
+        );
+    }
+}
+
+mod chunks_ordered {
+    use futures::TryStreamExt;
+
+    use crate::{condow_client::InMemoryClient, config::Config, Condow, Downloads};
+
+    const BLOB: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+
+    #[tokio::test]
+    async fn streams_the_range_in_order() {
+        let client = InMemoryClient::new_static(BLOB).chunk_size(2);
+        let config = Config::default()
+            .buffers_full_delay_ms(0)
+            .part_size_bytes(3)
+            .max_concurrency(4);
+        let condow = Condow::new(client, config).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let stream = condow
+            .download_chunks_ordered(&location, 2..=20)
+            .await
+            .unwrap();
+        let received: Vec<_> = stream.try_collect().await.unwrap();
+
+        assert_eq!(received.concat(), BLOB[2..=20]);
+    }
+
+    #[tokio::test]
+    async fn via_the_downloads_trait() {
+        let client = InMemoryClient::new_static(BLOB).chunk_size(2);
+        let config = Config::default()
+            .buffers_full_delay_ms(0)
+            .part_size_bytes(3)
+            .max_concurrency(4);
+        let downloader = Condow::new(client, config).unwrap().downloader();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let stream = Downloads::download_chunks_ordered(&downloader, &location, ..)
+            .await
+            .unwrap();
+        let received: Vec<_> = stream.try_collect().await.unwrap();
+
+        assert_eq!(received.concat(), BLOB);
+    }
+}
+
+mod download_with {
+    use futures::TryStreamExt;
+
+    use crate::{
+        condow_client::InMemoryClient,
+        config::Config,
+        errors::CondowError,
+        streams::{ChunkStream, Ordered, OrderingStrategy, PartAligned, Unordered},
+        Condow,
+    };
+
+    const BLOB: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+
+    fn condow() -> Condow<InMemoryClient> {
+        let client = InMemoryClient::new_static(BLOB).chunk_size(2);
+        let config = Config::default()
+            .buffers_full_delay_ms(0)
+            .part_size_bytes(3)
+            .max_concurrency(4);
+        Condow::new(client, config).unwrap()
+    }
+
+    fn location() -> url::Url {
+        url::Url::parse("noscheme://").expect("a valid URL")
+    }
+
+    #[tokio::test]
+    async fn unordered() {
+        let stream = condow()
+            .download_with::<Unordered, _>(&location(), 2..=20)
+            .await
+            .unwrap();
+
+        let mut received = vec![0; 19];
+        for chunk in stream.try_collect::<Vec<_>>().await.unwrap() {
+            let start = chunk.range_offset as usize;
+            received[start..start + chunk.len()].copy_from_slice(&chunk.bytes);
+        }
+
+        assert_eq!(received, BLOB[2..=20]);
+    }
+
+    #[tokio::test]
+    async fn ordered() {
+        let stream = condow()
+            .download_with::<Ordered, _>(&location(), 2..=20)
+            .await
+            .unwrap();
+        let received: Vec<_> = stream.try_collect().await.unwrap();
+
+        assert_eq!(received.concat(), BLOB[2..=20]);
+    }
+
+    #[tokio::test]
+    async fn part_aligned() {
+        let stream = condow()
+            .download_with::<PartAligned, _>(&location(), 2..=20)
+            .await
+            .unwrap();
+        let parts: Vec<_> = stream.try_collect().await.unwrap();
+
+        assert_eq!(parts.len(), 7);
+        for (idx, part) in parts.iter().enumerate() {
+            assert_eq!(part.part_index, idx as u64);
+        }
+        let received: Vec<u8> = parts.into_iter().flat_map(|p| p.chunks).flatten().collect();
+        assert_eq!(received, BLOB[2..=20]);
+    }
+
+    /// Counts the bytes instead of handing them out
+    struct CountBytes;
+
+    impl OrderingStrategy for CountBytes {
+        type Stream =
+            futures::stream::Once<futures::future::BoxFuture<'static, Result<u64, CondowError>>>;
+
+        fn order(chunk_stream: ChunkStream) -> Result<Self::Stream, CondowError> {
+            Ok(futures::stream::once(Box::pin(
+                chunk_stream.try_fold(0, |n, chunk| async move { Ok(n + chunk.len() as u64) }),
+            )))
+        }
+    }
+
+    #[tokio::test]
+    async fn custom() {
+        let stream = condow()
+            .download_with::<CountBytes, _>(&location(), 2..=20)
+            .await
+            .unwrap();
+        let counted: Vec<_> = stream.try_collect().await.unwrap();
+
+        assert_eq!(counted, vec![19]);
+    }
+}
+
+mod raw {
+    use std::sync::Arc;
+
+    use futures::TryStreamExt;
+
+    use crate::condow_client::NoLocation;
+    use crate::{config::Config, test_utils::create_test_data, test_utils::*, Condow};
+
+    #[tokio::test]
+    async fn download_raw() {
+        let data = Arc::new(create_test_data());
+
+        let client = TestCondowClient {
+            data: Arc::clone(&data),
+            max_jitter_ms: 0,
+            include_size_hint: true,
+            max_chunk_size: 3,
+        };
+
+        let config = Config::default()
+            .buffers_full_delay_ms(0)
+            .part_size_bytes(2)
+            .max_concurrency(10);
+        let condow = Condow::new(client, config).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let result = collect(condow.download_raw(&location, ..).await.unwrap()).await;
+        assert_eq!(result, data.as_ref()[..]);
+
+        let result = collect(condow.download_raw(&location, 10..).await.unwrap()).await;
+        assert_eq!(result, data.as_ref()[10..]);
+
+        let result = collect(condow.download_raw(&location, 3..=17).await.unwrap()).await;
+        assert_eq!(result, data.as_ref()[3..=17]);
+
+        let result = collect(condow.download_raw(&location, 5..5).await.unwrap()).await;
+        assert!(result.is_empty());
+    }
+
+    async fn collect(stream: crate::streams::BytesStream) -> Vec<u8> {
+        stream
+            .try_fold(Vec::new(), |mut acc, bytes| async move {
+                acc.extend_from_slice(&bytes);
+                Ok(acc)
+            })
+            .await
+            .unwrap()
+    }
+}
+
+mod reporter_factory {
+    use std::{
+        fmt,
+        sync::{Arc, Mutex},
+    };
+
+    use crate::{
+        condow_client::NoLocation,
+        config::Config,
+        reporter::{NoReporting, ReporterFactory},
+        test_utils::*,
+        ClosedRange, Condow, DownloadRange, OpenRange,
+    };
+
+    #[derive(Default)]
+    struct RangeRecordingFactory {
+        ranges: Mutex<Vec<DownloadRange>>,
+    }
+
+    impl ReporterFactory for RangeRecordingFactory {
+        type ReporterType = NoReporting;
+
+        fn make(&self, _location: &dyn fmt::Display) -> Self::ReporterType {
+            panic!("make_for should be called instead")
+        }
+
+        fn make_for(
+            &self,
+            _location: &dyn fmt::Display,
+            range: &DownloadRange,
+        ) -> Self::ReporterType {
+            self.ranges.lock().unwrap().push(*range);
+            NoReporting
+        }
+    }
+
+    #[tokio::test]
+    async fn factory_sees_the_requested_range() {
+        let config = Config::default().buffers_full_delay_ms(0);
+        let condow = Condow::new(TestCondowClient::new(), config).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+        let factory = Arc::new(RangeRecordingFactory::default());
+
+        let downloader = condow.downloader_with_reporting_arc(Arc::clone(&factory));
+        let _ = downloader.download_rep(&location, 3..10).await.unwrap();
+
+        let session = condow.download_session_arc(Arc::clone(&factory));
+        let _ = session.download(&location, 5..).await.unwrap();
+
+        let ranges = factory.ranges.lock().unwrap().clone();
+        assert_eq!(
+            ranges,
+            vec![
+                DownloadRange::Closed(ClosedRange::FromTo(3, 10)),
+                DownloadRange::Open(OpenRange::From(5)),
+            ]
+        );
+    }
+}
+
+mod max_concurrent_downloads {
+    use std::time::Duration;
+
+    use crate::{
+        condow_client::NoLocation, config::Config, reporter::NoReporting, test_utils::*, Condow,
+    };
+
+    #[tokio::test]
+    async fn download_waits_for_an_active_download() {
+        let client = TestCondowClient::new();
+        let data = client.data();
+        let config = Config::default().buffers_full_delay_ms(0);
+        let condow = Condow::new(client, config).unwrap();
+        let session = condow
+            .download_session(NoReporting)
+            .with_max_concurrent_downloads(1);
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let first = session.download(&location, ..).await.unwrap();
+
+        let waiting =
+            tokio::time::timeout(Duration::from_millis(50), session.download(&location, ..)).await;
+        assert!(waiting.is_err(), "download did not wait");
+
+        assert_eq!(first.into_vec().await.unwrap(), data.as_ref()[..]);
+
+        let second = session.download(&location, ..).await.unwrap();
+        assert_eq!(second.into_vec().await.unwrap(), data.as_ref()[..]);
+    }
+
+    #[tokio::test]
+    async fn dropping_a_stream_releases_the_permit() {
+        let config = Config::default().buffers_full_delay_ms(0);
+        let condow = Condow::new(TestCondowClient::new(), config).unwrap();
+        let session = condow
+            .download_session(NoReporting)
+            .with_max_concurrent_downloads(1);
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let first = session.download_chunks(&location, 0..10).await.unwrap();
+        drop(first);
+
+        let second =
+            tokio::time::timeout(Duration::from_millis(50), session.download(&location, ..)).await;
+        assert!(second.is_ok(), "permit was not released");
+    }
+}
+
+mod out_of_bounds_policy {
+    use crate::{
+        condow_client::{InMemoryClient, NoLocation},
+        config::{Config, OutOfBoundsPolicy},
+        errors::CondowErrorKind,
+        test_utils::*,
+        Condow,
+    };
+
+    #[tokio::test]
+    async fn clamp_adjusts_the_upper_bound() {
+        let client = TestCondowClient::new();
+        let data = client.data();
+        let config = Config::default()
+            .buffers_full_delay_ms(0)
+            .always_get_size(true)
+            .out_of_bounds_policy(OutOfBoundsPolicy::Clamp);
+        let condow = Condow::new(client, config).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+        let len = data.len() as u64;
+
+        let result = condow
+            .download(&location, 10..len + 10)
+            .await
+            .unwrap()
+            .into_vec()
+            .await
+            .unwrap();
+
+        assert_eq!(result, data[10..]);
+    }
+
+    #[tokio::test]
+    async fn error_fails_on_a_range_past_the_end() {
+        let client = TestCondowClient::new();
+        let data = client.data();
+        let config = Config::default()
+            .buffers_full_delay_ms(0)
+            .always_get_size(true)
+            .out_of_bounds_policy(OutOfBoundsPolicy::Error);
+        let condow = Condow::new(client, config).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+        let len = data.len() as u64;
+
+        match condow.download(&location, 10..len + 10).await {
+            Err(err) => assert_eq!(err.kind(), CondowErrorKind::InvalidRange),
+            Ok(_) => panic!("range should be out of bounds"),
+        }
+
+        match condow.download(&location, 10..=len).await {
+            Err(err) => assert_eq!(err.kind(), CondowErrorKind::InvalidRange),
+            Ok(_) => panic!("range should be out of bounds"),
+        }
+
+        let result = condow
+            .download(&location, 10..len)
+            .await
+            .unwrap()
+            .into_vec()
+            .await
+            .unwrap();
+        assert_eq!(result, data[10..]);
+    }
+
+    fn condow_with_policy(policy: OutOfBoundsPolicy) -> (Condow<TestCondowClient>, u64) {
+        let client = TestCondowClient::new();
+        let len = client.data().len() as u64;
+        let config = Config::default()
+            .buffers_full_delay_ms(0)
+            .out_of_bounds_policy(policy);
+        (Condow::new(client, config).unwrap(), len)
+    }
+
+    #[tokio::test]
+    async fn clamp_open_range_starting_at_the_end_is_empty() {
+        let (condow, len) = condow_with_policy(OutOfBoundsPolicy::Clamp);
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let result = condow
+            .download(&location, len..)
+            .await
+            .unwrap()
+            .into_vec()
+            .await
+            .unwrap();
+
+        assert!(result.is_empty());
+    }
+
+    #[tokio::test]
+    async fn clamp_open_range_starting_past_the_end_is_empty() {
+        let (condow, len) = condow_with_policy(OutOfBoundsPolicy::Clamp);
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let result = condow
+            .download(&location, len + 10..)
+            .await
+            .unwrap()
+            .into_vec()
+            .await
+            .unwrap();
+
+        assert!(result.is_empty());
+    }
+
+    #[tokio::test]
+    async fn clamp_open_range_starting_at_the_last_byte() {
+        let (condow, len) = condow_with_policy(OutOfBoundsPolicy::Clamp);
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let result = condow
+            .download(&location, len - 1..)
+            .await
+            .unwrap()
+            .into_vec()
+            .await
+            .unwrap();
+
+        assert_eq!(result.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn error_open_range_starting_at_the_end_fails() {
+        let (condow, len) = condow_with_policy(OutOfBoundsPolicy::Error);
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        match condow.download(&location, len..).await {
+            Err(err) => assert_eq!(err.kind(), CondowErrorKind::InvalidRange),
+            Ok(_) => panic!("range should be out of bounds"),
+        }
+    }
+
+    #[tokio::test]
+    async fn error_open_range_starting_past_the_end_fails() {
+        let (condow, len) = condow_with_policy(OutOfBoundsPolicy::Error);
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        match condow.download(&location, len + 10..).await {
+            Err(err) => assert_eq!(err.kind(), CondowErrorKind::InvalidRange),
+            Ok(_) => panic!("range should be out of bounds"),
+        }
+    }
+
+    #[tokio::test]
+    async fn error_open_range_starting_at_the_last_byte() {
+        let (condow, len) = condow_with_policy(OutOfBoundsPolicy::Error);
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let result = condow
+            .download(&location, len - 1..)
+            .await
+            .unwrap()
+            .into_vec()
+            .await
+            .unwrap();
+
+        assert_eq!(result.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn error_complete_empty_blob_does_not_fail() {
+        let client = InMemoryClient::new(Vec::new());
+        let config = Config::default().out_of_bounds_policy(OutOfBoundsPolicy::Error);
+        let condow = Condow::new(client, config).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let result = condow
+            .download(&location, 0..)
+            .await
+            .unwrap()
+            .into_vec()
+            .await
+            .unwrap();
+
+        assert!(result.is_empty());
+    }
+}
+
+mod try_new {
+    use crate::{config::Config, errors::CondowErrorKind, test_utils::*, Condow};
+
+    #[test]
+    fn valid_config() {
+        assert!(Condow::try_new(TestCondowClient::new(), Config::default()).is_ok());
+    }
+
+    #[test]
+    fn invalid_config() {
+        let config = Config::default().max_concurrency(0);
+
+        match Condow::try_new(TestCondowClient::new(), config) {
+            Err(err) => {
+                assert_eq!(err.kind(), CondowErrorKind::Other);
+                assert!(err.msg().starts_with("invalid config"));
+            }
+            Ok(_) => panic!("config should be invalid"),
+        }
+    }
+}
+
+mod default_range {
+    use crate::{
+        config::{Config, OutOfBoundsPolicy},
+        errors::CondowErrorKind,
+        test_utils::*,
+        Condow,
+    };
+
+    #[tokio::test]
+    async fn downloads_the_complete_blob_by_default() {
+        let client = TestCondowClient::new();
+        let data = client.data();
+        let config = Config::default().buffers_full_delay_ms(0);
+        let condow = Condow::new(client, config).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let result = condow
+            .download_default(&location)
+            .await
+            .unwrap()
+            .into_vec()
+            .await
+            .unwrap();
+
+        assert_eq!(result, data[..]);
+    }
+
+    #[tokio::test]
+    async fn downloads_the_configured_range() {
+        let client = TestCondowClient::new();
+        let data = client.data();
+        let config = Config::default()
+            .buffers_full_delay_ms(0)
+            .default_range(..10);
+        let condow = Condow::new(client, config).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let result = condow
+            .download_default(&location)
+            .await
+            .unwrap()
+            .into_vec()
+            .await
+            .unwrap();
+
+        assert_eq!(result, data[..10]);
+    }
+
+    #[tokio::test]
+    async fn clamps_the_configured_range_even_without_always_get_size() {
+        let client = TestCondowClient::new();
+        let data = client.data();
+        let len = data.len() as u64;
+        let config = Config::default()
+            .buffers_full_delay_ms(0)
+            .always_get_size(false)
+            .default_range(5..len + 100);
+        let condow = Condow::new(client, config).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let result = condow
+            .download_default(&location)
+            .await
+            .unwrap()
+            .into_vec()
+            .await
+            .unwrap();
+
+        assert_eq!(result, data[5..]);
+    }
+
+    #[tokio::test]
+    async fn respects_the_out_of_bounds_policy() {
+        let client = TestCondowClient::new();
+        let len = client.data().len() as u64;
+        let config = Config::default()
+            .buffers_full_delay_ms(0)
+            .out_of_bounds_policy(OutOfBoundsPolicy::Error)
+            .default_range(5..len + 100);
+        let condow = Condow::new(client, config).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        match condow.download_default(&location).await {
+            Err(err) => assert_eq!(err.kind(), CondowErrorKind::InvalidRange),
+            Ok(_) => panic!("range should be out of bounds"),
+        }
+    }
+
+    #[test]
+    fn an_invalid_default_range_is_rejected() {
+        let config = Config::default().default_range(10..5);
+
+        assert!(Condow::new(TestCondowClient::new(), config).is_err());
+    }
+}
+
+mod part_priority {
+    use futures::TryStreamExt;
+
+    use crate::{
+        condow_client::InMemoryClient, config::Config, Condow, InclusiveRange, RangeRequest,
+    };
+
+    const BLOB: &[u8] = b"abcdefghij";
+
+    async fn part_indexes_received(config: Config) -> Vec<u64> {
+        // A single download task receives the parts in the order they were dispatched
+        let config = config
+            .buffers_full_delay_ms(0)
+            .part_size_bytes(1)
+            .max_concurrency(1);
+        let condow = Condow::new(InMemoryClient::new_static(BLOB), config).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        condow
+            .download_chunks(&location, ..)
+            .await
+            .unwrap()
+            .map_ok(|chunk| chunk.part_index)
+            .try_collect()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn ascending_by_default() {
+        let received = part_indexes_received(Config::default()).await;
+
+        assert_eq!(received, (0..10).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn highest_priority_first() {
+        let config = Config::default().part_priority(|req: &RangeRequest| req.part_index() as i64);
+
+        let received = part_indexes_received(config).await;
+
+        assert_eq!(received, (0..10).rev().collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn same_priority_ascending() {
+        // Parts in the second half first
+        let config =
+            Config::default().part_priority(|req: &RangeRequest| (req.part_index() >= 5) as i64);
+
+        let received = part_indexes_received(config).await;
+
+        assert_eq!(received, vec![5, 6, 7, 8, 9, 0, 1, 2, 3, 4]);
+    }
+
+    #[tokio::test]
+    async fn the_priority_can_inspect_the_requested_range() {
+        // Parts overlapping the bytes 3 to 6 first
+        let config = Config::default().part_priority(|req: &RangeRequest| {
+            let InclusiveRange(start, end_incl) = req.blob_range();
+            (start <= 6 && end_incl >= 3) as i64
+        });
+
+        let received = part_indexes_received(config).await;
+
+        assert_eq!(received, vec![3, 4, 5, 6, 0, 1, 2, 7, 8, 9]);
+    }
+}
+
+mod warmup {
+    use crate::{condow_client::failing_client_simulator::FailingClientSimulatorBuilder, Condow};
+
+    #[tokio::test]
+    async fn returns_the_size_without_downloading() {
+        // Any download request would fail
+        let client = FailingClientSimulatorBuilder::default()
+            .blob_static(b"abcdefghij")
+            .responses()
+            .never()
+            .finish();
+        let condow = Condow::new(client, Default::default()).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let size = condow.warmup(&location).await.unwrap();
+
+        assert_eq!(size, 10);
+    }
+}
+
+mod with_config {
+    use crate::{config::Config, test_utils::*, Condow};
+
+    #[tokio::test]
+    async fn applies_the_new_config() {
+        let client = TestCondowClient::new();
+        let data = client.data();
+        let len = data.len() as u64;
+        let condow = Condow::new(client, Config::default().part_size_bytes(len)).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let derived = condow
+            .with_config(Config::default().part_size_bytes(len / 2 + 1))
+            .unwrap();
+
+        let stream = condow.download(&location, ..).await.unwrap();
+        assert_eq!(stream.n_parts(), Some(1));
+
+        let stream = derived.download(&location, ..).await.unwrap();
+        assert_eq!(stream.n_parts(), Some(2));
+        assert_eq!(stream.into_vec().await.unwrap(), data[..]);
+    }
+
+    #[test]
+    fn exposes_the_effective_config() {
+        let condow = Condow::new(
+            TestCondowClient::new(),
+            Config::default().part_size_bytes(1_000),
+        )
+        .unwrap();
+
+        let derived = condow
+            .with_config(Config::default().part_size_bytes(500).max_concurrency(3))
+            .unwrap();
+
+        assert_eq!(u64::from(condow.config().part_size_bytes), 1_000);
+        assert_eq!(u64::from(derived.config().part_size_bytes), 500);
+        assert_eq!(derived.config().max_concurrency.into_inner(), 3);
+    }
+
+    #[test]
+    fn fails_on_an_invalid_config() {
+        let condow = Condow::new(TestCondowClient::new(), Config::default()).unwrap();
+
+        assert!(condow
+            .with_config(Config::default().max_concurrency(0))
+            .is_err());
+    }
+}
+
+mod download_with_known_size {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use crate::{
+        condow_client::{InMemoryClient, InstrumentedClient},
+        config::{Config, OutOfBoundsPolicy},
+        errors::CondowErrorKind,
+        Condow,
+    };
+
+    fn condow(
+        out_of_bounds_policy: OutOfBoundsPolicy,
+    ) -> (Condow<InstrumentedClient<InMemoryClient>>, Arc<AtomicUsize>) {
+        let n_get_size = Arc::new(AtomicUsize::new(0));
+        let n_get_size_in_callback = Arc::clone(&n_get_size);
+        let client = InstrumentedClient::new(InMemoryClient::new((0u8..100).collect()))
+            .on_get_size(move |_location, _result, _elapsed| {
+                n_get_size_in_callback.fetch_add(1, Ordering::SeqCst);
+            });
+        let config = Config::default()
+            .part_size_bytes(10)
+            .buffers_full_delay_ms(0)
+            .always_get_size(true)
+            .out_of_bounds_policy(out_of_bounds_policy);
+
+        (Condow::new(client, config).unwrap(), n_get_size)
+    }
+
+    #[tokio::test]
+    async fn an_open_range_is_resolved_without_a_size_request() {
+        let (condow, n_get_size) = condow(OutOfBoundsPolicy::Clamp);
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let stream = condow
+            .download_with_known_size(&location, 50.., 100)
+            .await
+            .unwrap();
+
+        assert_eq!(stream.n_parts(), Some(5));
+        assert_eq!(
+            stream.into_vec().await.unwrap(),
+            (50u8..100).collect::<Vec<_>>()
+        );
+        assert_eq!(n_get_size.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn a_closed_range_is_clamped() {
+        let (condow, n_get_size) = condow(OutOfBoundsPolicy::Clamp);
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let bytes = condow
+            .download_with_known_size(&location, 90..200, 100)
+            .await
+            .unwrap()
+            .into_vec()
+            .await
+            .unwrap();
+
+        assert_eq!(bytes, (90u8..100).collect::<Vec<_>>());
+        assert_eq!(n_get_size.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn a_range_beyond_the_size_fails_with_the_error_policy() {
+        let (condow, _n_get_size) = condow(OutOfBoundsPolicy::Error);
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let result = condow
+            .download_with_known_size(&location, 90..200, 100)
+            .await;
+
+        assert_eq!(
+            result.err().map(|err| err.kind()),
+            Some(CondowErrorKind::InvalidRange)
+        );
+    }
+
+    #[tokio::test]
+    async fn a_range_starting_beyond_the_size_is_empty() {
+        let (condow, _n_get_size) = condow(OutOfBoundsPolicy::Clamp);
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let bytes = condow
+            .download_with_known_size(&location, 150.., 100)
+            .await
+            .unwrap()
+            .into_vec()
+            .await
+            .unwrap();
+
+        assert!(bytes.is_empty());
+    }
+}
+
+mod download_sized {
+    use crate::{config::Config, test_utils::*, Condow};
+
+    #[tokio::test]
+    async fn closed_range() {
+        let client = TestCondowClient::new();
+        let data = client.data();
+        let condow = Condow::new(client, Config::default().part_size_bytes(7)).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let (len, stream) = condow.download_sized(&location, 10..30).await.unwrap();
+
+        assert_eq!(len, 20);
+        assert_eq!(stream.into_vec().await.unwrap(), data[10..30]);
+    }
+
+    #[tokio::test]
+    async fn full_range() {
+        let client = TestCondowClient::new();
+        let data = client.data();
+        let condow = Condow::new(client, Config::default().part_size_bytes(7)).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let (len, stream) = condow.download_sized(&location, ..).await.unwrap();
+
+        assert_eq!(len, data.len() as u64);
+        assert_eq!(stream.into_vec().await.unwrap(), data[..]);
+    }
+
+    #[tokio::test]
+    async fn open_range_from() {
+        let client = TestCondowClient::new();
+        let data = client.data();
+        let condow = Condow::new(client, Config::default().part_size_bytes(7)).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let (len, stream) = condow.download_sized(&location, 5..).await.unwrap();
+
+        assert_eq!(len, data.len() as u64 - 5);
+        assert_eq!(stream.into_vec().await.unwrap(), data[5..]);
+    }
+
+    #[tokio::test]
+    async fn empty_range() {
+        let condow = Condow::new(TestCondowClient::new(), Config::default()).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let (len, stream) = condow.download_sized(&location, 10..10).await.unwrap();
+
+        assert_eq!(len, 0);
+        assert!(stream.into_vec().await.unwrap().is_empty());
+    }
+}
+
+mod kill_reason {
+    use std::{
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc, Mutex,
+        },
+        time::Duration,
+    };
+
+    use futures::StreamExt;
+
+    use crate::{
+        condow_client::failing_client_simulator::FailingClientSimulatorBuilder,
+        config::Config,
+        errors::CondowErrorKind,
+        reporter::{KillReason, Reporter},
+        test_utils::TestCondowClient,
+        Condow,
+    };
+
+    #[derive(Clone, Default)]
+    struct ReasonRecorder {
+        reasons: Arc<Mutex<Vec<KillReason>>>,
+        cancelled: Arc<AtomicBool>,
+    }
+
+    impl Reporter for ReasonRecorder {
+        fn download_failed(&self, _time: Option<Duration>, reason: KillReason) {
+            self.reasons.lock().unwrap().push(reason);
+        }
+
+        fn download_cancelled(&self, _time: Duration) {
+            self.cancelled.store(true, Ordering::SeqCst);
+        }
+    }
+
+    async fn reasons_for(builder: FailingClientSimulatorBuilder) -> Vec<KillReason> {
+        let client = builder.blob((0u8..100).collect()).chunk_size(10).finish();
+        let config = Config::default()
+            .part_size_bytes(50)
+            .max_concurrency(1)
+            .buffers_full_delay_ms(0)
+            .disable_retries();
+        let condow = Condow::new(client, config).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+        let recorder = ReasonRecorder::default();
+
+        if let Ok(stream) = condow
+            .downloader()
+            .download_chunks_wrep(&location, 0..100, recorder.clone())
+            .await
+        {
+            let _ = stream.into_stream().collect::<Vec<_>>().await;
+        }
+
+        let reasons = recorder.reasons.lock().unwrap().clone();
+        reasons
+    }
+
+    #[tokio::test]
+    async fn client_error() {
+        let builder = FailingClientSimulatorBuilder::default()
+            .responses()
+            .success()
+            .failure(CondowErrorKind::NotFound)
+            .done();
+
+        assert_eq!(reasons_for(builder).await, vec![KillReason::ClientError]);
+    }
+
+    #[tokio::test]
+    async fn stream_error() {
+        let builder = FailingClientSimulatorBuilder::default()
+            .responses()
+            .success_with_stream_failure(15)
+            .done();
+
+        assert_eq!(reasons_for(builder).await, vec![KillReason::StreamError]);
+    }
+
+    #[tokio::test]
+    async fn panic() {
+        let builder = FailingClientSimulatorBuilder::default()
+            .responses()
+            .success_with_stream_panic(15)
+            .done();
+
+        assert_eq!(reasons_for(builder).await, vec![KillReason::Panic]);
+    }
+
+    #[tokio::test]
+    async fn not_started() {
+        let client = FailingClientSimulatorBuilder::default()
+            .blob((0u8..100).collect())
+            .finish();
+        let condow = Condow::new(client, Config::default()).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+        let recorder = ReasonRecorder::default();
+
+        let result = condow
+            .downloader()
+            .download_chunks_wrep(&location, 10..5, recorder.clone())
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(
+            *recorder.reasons.lock().unwrap(),
+            vec![KillReason::NotStarted]
+        );
+    }
+
+    #[tokio::test]
+    async fn dropping_the_stream_midway_is_a_cancellation() {
+        let client = TestCondowClient::new().max_chunk_size(1).max_jitter_ms(2);
+        let config = Config::default()
+            .part_size_bytes(50)
+            .max_concurrency(2)
+            .buffers_full_delay_ms(0);
+        let condow = Condow::new(client, config).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+        let recorder = ReasonRecorder::default();
+
+        let mut stream = condow
+            .downloader()
+            .download_chunks_wrep(&location, 0..200, recorder.clone())
+            .await
+            .unwrap()
+            .into_stream();
+        assert!(stream.next().await.unwrap().is_ok());
+        drop(stream);
+
+        for _ in 0..500 {
+            if recorder.cancelled.load(Ordering::SeqCst) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        assert!(recorder.cancelled.load(Ordering::SeqCst));
+        assert!(recorder.reasons.lock().unwrap().is_empty());
+    }
+}
+
+mod min_parts {
+    use crate::{condow_client::InMemoryClient, config::Config, Condow};
+
+    fn condow(config: Config) -> Condow<InMemoryClient> {
+        let client = InMemoryClient::new((0u8..100).collect());
+        Condow::new(client, config.buffers_full_delay_ms(0)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn reduces_the_part_size() {
+        let condow = condow(Config::default().part_size_bytes(1_000u64).min_parts(7));
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let stream = condow.download(&location, ..).await.unwrap();
+
+        assert_eq!(stream.n_parts(), Some(8));
+        assert_eq!(
+            stream.into_vec().await.unwrap(),
+            (0u8..100).collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn does_not_increase_the_part_size() {
+        let condow = condow(Config::default().part_size_bytes(10u64).min_parts(2));
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let stream = condow.download(&location, ..).await.unwrap();
+
+        assert_eq!(stream.n_parts(), Some(10));
+    }
+
+    #[tokio::test]
+    async fn clamps_the_part_size_to_a_single_byte() {
+        let condow = condow(Config::default().min_parts(1_000));
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let stream = condow.download(&location, 10..15).await.unwrap();
+
+        assert_eq!(stream.n_parts(), Some(5));
+        assert_eq!(stream.into_vec().await.unwrap(), vec![10, 11, 12, 13, 14]);
+    }
+
+    #[tokio::test]
+    async fn none_uses_the_configured_part_size() {
+        let condow = condow(Config::default().part_size_bytes(50u64).min_parts(None));
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let stream = condow.download(&location, ..).await.unwrap();
+
+        assert_eq!(stream.n_parts(), Some(2));
+    }
+
+    #[test]
+    fn zero_is_invalid() {
+        assert!(Config::default().min_parts(0).validated().is_err());
+    }
+}
+
+mod request_coalescing {
+    use std::sync::{Arc, Mutex};
+
+    use futures::TryStreamExt;
+
+    use crate::{
+        condow_client::{DownloadSpec, InMemoryClient, InstrumentedClient},
+        config::Config,
+        Condow, InclusiveRange,
+    };
+
+    type Requested = Arc<Mutex<Vec<InclusiveRange>>>;
+
+    fn condow(
+        request_coalescing: usize,
+        chunk_size: usize,
+    ) -> (Condow<InstrumentedClient<InMemoryClient>>, Requested) {
+        let requested = Arc::new(Mutex::new(Vec::new()));
+        let requested_in_callback = Arc::clone(&requested);
+        let client = InstrumentedClient::new(
+            InMemoryClient::new((0u8..100).collect()).chunk_size(chunk_size),
+        )
+        .on_download(move |_location, spec, _outcome, _elapsed| {
+            if let DownloadSpec::Range(range) = spec {
+                requested_in_callback.lock().unwrap().push(range);
+            }
+        });
+        let config = Config::default()
+            .part_size_bytes(10)
+            .max_concurrency(1)
+            .buffers_full_delay_ms(0)
+            .request_coalescing(request_coalescing);
+
+        (Condow::new(client, config).unwrap(), requested)
+    }
+
+    #[tokio::test]
+    async fn a_request_covers_multiple_parts() {
+        let (condow, requested) = condow(3, 100);
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let stream = condow.download(&location, ..).await.unwrap();
+
+        assert_eq!(stream.n_parts(), Some(10));
+        assert_eq!(
+            stream.into_vec().await.unwrap(),
+            (0u8..100).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            *requested.lock().unwrap(),
+            vec![
+                InclusiveRange(0, 29),
+                InclusiveRange(30, 59),
+                InclusiveRange(60, 89),
+                InclusiveRange(90, 99)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn chunks_are_split_into_parts() {
+        let (condow, _requested) = condow(4, 7);
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let chunks = condow
+            .download_chunks(&location, 5..100)
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+
+        let mut next_range_offset = 0;
+        let mut next_chunk_index = 0;
+        for chunk in chunks {
+            let part_index = chunk.range_offset / 10;
+            let part_end = ((part_index + 1) * 10).min(95);
+
+            assert_eq!(chunk.range_offset, next_range_offset);
+            assert_eq!(chunk.blob_offset, chunk.range_offset + 5);
+            assert_eq!(chunk.part_index, part_index);
+            assert_eq!(chunk.chunk_index, next_chunk_index);
+            assert_eq!(
+                chunk.bytes_left,
+                part_end - chunk.range_offset - chunk.len() as u64
+            );
+
+            next_range_offset += chunk.len() as u64;
+            next_chunk_index = if chunk.is_last() {
+                0
+            } else {
+                next_chunk_index + 1
+            };
+        }
+        assert_eq!(next_range_offset, 95);
+    }
+
+    #[tokio::test]
+    async fn parts_keep_the_configured_size() {
+        let (condow, requested) = condow(5, 3);
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let parts = condow
+            .download(&location, ..)
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+
+        assert_eq!(parts.len(), 10);
+        for (part_index, part) in parts.iter().enumerate() {
+            assert_eq!(part.part_index, part_index as u64);
+            assert_eq!(part.blob_offset, part_index as u64 * 10);
+            assert_eq!(part.len(), 10);
+        }
+        assert_eq!(requested.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn zero_is_invalid() {
+        assert!(Config::default().request_coalescing(0).validated().is_err());
+    }
+}
+
+mod concurrency_min_bytes {
+    use std::sync::{Arc, Mutex};
+
+    use futures::TryStreamExt;
+
+    use crate::{
+        condow_client::{
+            failing_client_simulator::FailingClientSimulatorBuilder, DownloadSpec, InMemoryClient,
+            InstrumentedClient,
+        },
+        config::Config,
+        errors::CondowErrorKind,
+        reporter::{KillReason, RecordingReporter, ReporterEvent},
+        Condow, InclusiveRange,
+    };
+
+    type Requested = Arc<Mutex<Vec<InclusiveRange>>>;
+
+    fn condow(config: Config) -> (Condow<InstrumentedClient<InMemoryClient>>, Requested) {
+        let requested = Arc::new(Mutex::new(Vec::new()));
+        let requested_in_callback = Arc::clone(&requested);
+        let client = InstrumentedClient::new(InMemoryClient::new((0u8..100).collect()))
+            .on_download(move |_location, spec, _outcome, _elapsed| {
+                if let DownloadSpec::Range(range) = spec {
+                    requested_in_callback.lock().unwrap().push(range);
+                }
+            });
+        let config = config.part_size_bytes(10).max_concurrency(4);
+        (Condow::new(client, config).unwrap(), requested)
+    }
+
+    #[tokio::test]
+    async fn a_small_blob_is_downloaded_with_a_single_request() {
+        let (condow, requested) = condow(Config::default().concurrency_min_bytes(101));
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+        let reporter = RecordingReporter::new();
+
+        let chunks = condow
+            .downloader()
+            .download_chunks_wrep(&location, 5.., reporter.clone())
+            .await
+            .unwrap()
+            .into_stream()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+
+        assert_eq!(*requested.lock().unwrap(), vec![InclusiveRange(5, 99)]);
+        assert!(chunks.iter().all(|chunk| chunk.part_index == 0));
+        let bytes: Vec<u8> = chunks
+            .iter()
+            .flat_map(|chunk| chunk.bytes.iter().copied())
+            .collect();
+        assert_eq!(bytes, (5u8..100).collect::<Vec<_>>());
+
+        let events = reporter.events();
+        assert!(events.iter().any(|event| matches!(
+            event,
+            ReporterEvent::PartsPlanned(parts) if parts.to_vec() == vec![InclusiveRange(5, 99)]
+        )));
+        assert_eq!(reporter.event_names().last(), Some(&"download_completed"));
+    }
+
+    #[tokio::test]
+    async fn a_blob_of_at_least_the_minimum_is_split_into_parts() {
+        let (condow, requested) = condow(Config::default().concurrency_min_bytes(100));
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let bytes = condow
+            .download(&location, ..)
+            .await
+            .unwrap()
+            .into_vec()
+            .await
+            .unwrap();
+
+        assert_eq!(bytes, (0u8..100).collect::<Vec<_>>());
+        assert_eq!(requested.lock().unwrap().len(), 10);
+    }
+
+    #[tokio::test]
+    async fn without_a_size_request_the_range_is_split_into_parts() {
+        let (condow, requested) = condow(
+            Config::default()
+                .concurrency_min_bytes(1_000)
+                .always_get_size(false),
+        );
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let bytes = condow
+            .download(&location, 0..30)
+            .await
+            .unwrap()
+            .into_vec()
+            .await
+            .unwrap();
+
+        assert_eq!(bytes, (0u8..30).collect::<Vec<_>>());
+        assert_eq!(requested.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn a_failed_single_request_fails_the_download() {
+        let client = FailingClientSimulatorBuilder::default()
+            .blob((0u8..100).collect())
+            .responses()
+            .failure(CondowErrorKind::NotFound)
+            .finish();
+        let config = Config::default().concurrency_min_bytes(1_000);
+        let condow = Condow::new(client, config).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+        let reporter = RecordingReporter::new();
+
+        let result = condow
+            .downloader()
+            .download_chunks_wrep(&location, .., reporter.clone())
+            .await
+            .unwrap()
+            .into_stream()
+            .try_collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(
+            result.err().map(|err| err.kind()),
+            Some(CondowErrorKind::NotFound)
+        );
+        assert!(matches!(
+            reporter.events().last(),
+            Some(ReporterEvent::DownloadFailed {
+                reason: KillReason::ClientError,
+                ..
+            })
+        ));
+    }
+}
+
+mod parts_planned {
+    use std::sync::{Arc, Mutex};
+
+    use futures::TryStreamExt;
+
+    use crate::{
+        condow_client::{DownloadSpec, InMemoryClient, InstrumentedClient},
+        config::Config,
+        reporter::{RecordingReporter, ReporterEvent},
+        Condow, InclusiveRange, PartBoundaries,
+    };
+
+    /// Downloads `range` and returns the planned parts, the ranges passed
+    /// to `part_started` by part index and the ranges requested from the client
+    async fn download(
+        range: std::ops::Range<u64>,
+        request_coalescing: usize,
+        max_concurrency: usize,
+    ) -> (PartBoundaries, Vec<InclusiveRange>, Vec<InclusiveRange>) {
+        let requested = Arc::new(Mutex::new(Vec::new()));
+        let requested_in_callback = Arc::clone(&requested);
+        let client = InstrumentedClient::new(InMemoryClient::new((0u8..100).collect()))
+            .on_download(move |_location, spec, _outcome, _elapsed| {
+                if let DownloadSpec::Range(range) = spec {
+                    requested_in_callback.lock().unwrap().push(range);
+                }
+            });
+        let config = Config::default()
+            .part_size_bytes(7)
+            .request_coalescing(request_coalescing)
+            .max_concurrency(max_concurrency)
+            .buffers_full_delay_ms(0);
+        let condow = Condow::new(client, config).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+        let reporter = RecordingReporter::new();
+
+        let _ = condow
+            .downloader()
+            .download_chunks_wrep(&location, range, reporter.clone())
+            .await
+            .unwrap()
+            .into_stream()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+
+        let events = reporter.events();
+        let planned = events
+            .iter()
+            .find_map(|event| match event {
+                ReporterEvent::PartsPlanned(parts) => Some(*parts),
+                _ => None,
+            })
+            .unwrap();
+        let mut started: Vec<_> = events
+            .iter()
+            .filter_map(|event| match event {
+                ReporterEvent::PartStarted { part_index, range } => Some((*part_index, *range)),
+                _ => None,
+            })
+            .collect();
+        started.sort_by_key(|(part_index, _)| *part_index);
+
+        let mut requested = requested.lock().unwrap().clone();
+        requested.sort_by_key(|range| range.start());
+
+        (
+            planned,
+            started.into_iter().map(|(_, range)| range).collect(),
+            requested,
+        )
+    }
+
+    #[tokio::test]
+    async fn the_planned_parts_are_the_ranges_requested() {
+        for request_coalescing in [1, 3] {
+            for max_concurrency in [1, 4] {
+                let (planned, started, requested) =
+                    download(5..96, request_coalescing, max_concurrency).await;
+
+                assert_eq!(planned.range(), InclusiveRange(5, 95));
+                assert_eq!(planned.part_size_bytes(), 7 * request_coalescing as u64);
+                assert_eq!(
+                    planned.to_vec(),
+                    started,
+                    "coalescing={}, concurrency={}",
+                    request_coalescing,
+                    max_concurrency
+                );
+                assert_eq!(
+                    planned.to_vec(),
+                    requested,
+                    "coalescing={}, concurrency={}",
+                    request_coalescing,
+                    max_concurrency
+                );
+            }
+        }
+    }
+}
+
+mod download_and_parse {
+    use crate::{
+        condow_client::InMemoryClient, config::Config, errors::CondowErrorKind, Condow, Downloads,
+    };
+
+    fn condow(blob: &'static [u8]) -> Condow<InMemoryClient> {
+        let client = InMemoryClient::new_static(blob);
+        let config = Config::default()
+            .part_size_bytes(3u64)
+            .buffers_full_delay_ms(0);
+        Condow::new(client, config).unwrap()
+    }
+
+    #[tokio::test]
+    async fn parses_the_complete_blob() {
+        let condow = condow(b"condow parses");
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let text = condow
+            .download_and_parse(&location, |bytes: &[u8]| {
+                std::str::from_utf8(bytes).map(str::to_string)
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(text, "condow parses");
+    }
+
+    #[tokio::test]
+    async fn parse_errors_are_deserialize_errors() {
+        let condow = condow(&[0xff, 0xfe, 0xfd, 0xfc]);
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let err = condow
+            .download_and_parse(&location, |bytes: &[u8]| {
+                std::str::from_utf8(bytes).map(str::to_string)
+            })
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.kind(), CondowErrorKind::Deserialize);
+        assert!(!err.is_retryable());
+    }
+
+    #[cfg(feature = "serde")]
+    #[tokio::test]
+    async fn deserializes_json() {
+        let condow = condow(br#"{"a": [1, 2, 3], "b": []}"#);
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let value: std::collections::HashMap<String, Vec<u32>> =
+            condow.download_and_deserialize(&location).await.unwrap();
+
+        assert_eq!(value["a"], vec![1, 2, 3]);
+        assert!(value["b"].is_empty());
+    }
+
+    #[cfg(feature = "serde")]
+    #[tokio::test]
+    async fn invalid_json_is_a_deserialize_error() {
+        let condow = condow(b"{\"a\": ");
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let err = condow
+            .download_and_deserialize::<Vec<u32>>(&location)
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.kind(), CondowErrorKind::Deserialize);
+    }
+}
+
+mod config_defaults {
+    use crate::config::Config;
+
+    #[test]
+    fn default_uses_the_constants() {
+        let config = Config::default();
+
+        assert_eq!(
+            u64::from(config.part_size_bytes),
+            Config::DEFAULT_PART_SIZE_BYTES
+        );
+        assert_eq!(
+            config.max_concurrency.into_inner(),
+            Config::DEFAULT_MAX_CONCURRENCY
+        );
+        assert_eq!(config.buffer_size.into_inner(), Config::DEFAULT_BUFFER_SIZE);
+        assert_eq!(
+            config.buffers_full_delay_ms.into_inner(),
+            Config::DEFAULT_BUFFERS_FULL_DELAY_MS
+        );
+    }
+
+    #[test]
+    fn auto_buffer_size_with_the_defaults_is_the_default() {
+        let config = Config::default().auto_buffer_size().validated().unwrap();
+
+        assert_eq!(config.buffer_size.into_inner(), Config::DEFAULT_BUFFER_SIZE);
+    }
+
+    #[test]
+    fn auto_buffer_size_grows_with_smaller_parts() {
+        // 10ms at 100 KiB/ms are 1000 KiB
+        let config = Config::default()
+            .part_size_bytes(100 * 1024u64)
+            .auto_buffer_size()
+            .validated()
+            .unwrap();
+        assert_eq!(config.buffer_size.into_inner(), 11);
+
+        let config = Config::default()
+            .part_size_bytes(300 * 1024u64)
+            .auto_buffer_size()
+            .validated()
+            .unwrap();
+        assert_eq!(config.buffer_size.into_inner(), 5);
+    }
+
+    #[test]
+    fn auto_buffer_size_without_a_delay() {
+        let config = Config::default()
+            .buffers_full_delay_ms(0)
+            .part_size_bytes(1u64)
+            .auto_buffer_size()
+            .validated()
+            .unwrap();
+
+        assert_eq!(config.buffer_size.into_inner(), 2);
+    }
+
+    #[test]
+    fn auto_buffer_size_respects_a_part_size_set_afterwards() {
+        let config = Config::default()
+            .auto_buffer_size()
+            .part_size_bytes(100 * 1024u64)
+            .validated()
+            .unwrap();
+
+        assert_eq!(config.buffer_size.into_inner(), 11);
+    }
+
+    #[test]
+    fn explicit_buffer_size_wins_if_set_afterwards() {
+        let config = Config::default()
+            .part_size_bytes(1u64)
+            .auto_buffer_size()
+            .buffer_size(3)
+            .validated()
+            .unwrap();
+
+        assert_eq!(config.buffer_size.into_inner(), 3);
+    }
+
+    #[test]
+    fn explicit_buffer_size_wins_if_set_before() {
+        let config = Config::default()
+            .part_size_bytes(1u64)
+            .buffer_size(3)
+            .auto_buffer_size()
+            .validated()
+            .unwrap();
+
+        assert_eq!(config.buffer_size.into_inner(), 3);
+    }
+}
+
+mod overlaid_with_env {
+    use crate::config::{Config, OnPartFailure, RetryConfig};
+
+    // Each test uses its own prefix since the environment is shared by all tests
+
+    #[test]
+    fn only_variables_which_are_set_override() {
+        std::env::set_var("OVERLAY_TEST_1_MAX_CONCURRENCY", "7");
+        std::env::set_var("OVERLAY_TEST_1_ON_PART_FAILURE", "skip");
+
+        let config = Config::default()
+            .part_size_bytes(1_000u64)
+            .max_concurrency(3)
+            .min_parts(4)
+            .overlaid_with_env("OVERLAY_TEST_1")
+            .unwrap();
+
+        assert_eq!(config.max_concurrency.into_inner(), 7);
+        assert_eq!(config.on_part_failure, OnPartFailure::Skip);
+        assert_eq!(u64::from(config.part_size_bytes), 1_000);
+        assert_eq!(config.min_parts, Some(4));
+    }
+
+    #[test]
+    fn without_variables_the_config_is_unchanged() {
+        let config = Config::default().part_size_bytes(1_000u64).buffer_size(5);
+
+        let overlaid = config.clone().overlaid_with_env("OVERLAY_TEST_2").unwrap();
+
+        assert_eq!(overlaid, config);
+    }
+
+    #[test]
+    fn retries_keep_the_fields_without_variables() {
+        std::env::set_var("OVERLAY_TEST_3_RETRY_MAX_ATTEMPTS", "5");
+
+        let config = Config::default()
+            .retries(RetryConfig::default().max_attempts(1).max_delay_ms(123))
+            .overlaid_with_env("OVERLAY_TEST_3")
+            .unwrap();
+
+        let retries = config.retries.unwrap();
+        assert_eq!(retries.max_attempts.into_inner(), 5);
+        assert_eq!(retries.max_delay_ms.into_inner(), 123);
+    }
+
+    #[test]
+    fn disabled_retries_are_enabled_by_a_variable() {
+        std::env::set_var("OVERLAY_TEST_4_RETRY_MAX_ATTEMPTS", "5");
+
+        let config = Config::default()
+            .disable_retries()
+            .overlaid_with_env("OVERLAY_TEST_4")
+            .unwrap();
+
+        assert_eq!(config.retries, Some(RetryConfig::default().max_attempts(5)));
+    }
+
+    #[test]
+    fn disabled_retries_stay_disabled_without_variables() {
+        std::env::set_var("OVERLAY_TEST_5_BUFFER_SIZE", "5");
+
+        let config = Config::default()
+            .disable_retries()
+            .overlaid_with_env("OVERLAY_TEST_5")
+            .unwrap();
+
+        assert_eq!(config.buffer_size.into_inner(), 5);
+        assert_eq!(config.retries, None);
+    }
+
+    #[test]
+    fn an_invalid_variable_fails() {
+        std::env::set_var("OVERLAY_TEST_6_BUFFER_SIZE", "many");
+
+        assert!(Config::default()
+            .overlaid_with_env("OVERLAY_TEST_6")
+            .is_err());
+    }
+}
+
+#[cfg(feature = "memmap2")]
+mod download_to_mmap {
+    use crate::{
+        test_utils::{condow, temp_path},
+        Downloads,
+    };
+
+    #[tokio::test]
+    async fn complete_blob() {
+        let path = temp_path("condow_mmap", "complete_blob");
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let file = condow(7, 100)
+            .download_to_mmap(&location, .., &path)
+            .await
+            .unwrap();
+
+        assert_eq!(file.metadata().unwrap().len(), 100);
+        drop(file);
+        let written = std::fs::read(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(written, (0u8..100).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn range_is_written_from_the_start_of_the_file() {
+        let path = temp_path("condow_mmap", "range");
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        condow(7, 100)
+            .download_to_mmap(&location, 10..60, &path)
+            .await
+            .unwrap();
+
+        let written = std::fs::read(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(written, (10u8..60).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn existing_file_is_truncated() {
+        let path = temp_path("condow_mmap", "truncated");
+        std::fs::write(&path, vec![255u8; 200]).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        condow(7, 100)
+            .download_to_mmap(&location, 0..10, &path)
+            .await
+            .unwrap();
+
+        let written = std::fs::read(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(written, (0u8..10).collect::<Vec<_>>());
+    }
+}
+
+mod download_parts_to_dir {
+    use futures::TryStreamExt;
+
+    use crate::{
+        condow_client::failing_client_simulator::FailingClientSimulatorBuilder,
+        config::Config,
+        errors::CondowErrorKind,
+        test_utils::{condow, temp_path},
+        Condow, Downloads, InclusiveRange,
+    };
+
+    #[tokio::test]
+    async fn each_part_is_written_to_its_own_file() {
+        let dir = temp_path("condow_parts", "each_part");
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let mut part_files = condow(30, 100)
+            .download_parts_to_dir(&location, 10..90, &dir)
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        part_files.sort_by_key(|part_file| part_file.part_index);
+
+        let written = part_files
+            .iter()
+            .map(|part_file| std::fs::read(&part_file.path).unwrap())
+            .collect::<Vec<_>>();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let ranges = part_files.iter().map(|pf| pf.range).collect::<Vec<_>>();
+        assert_eq!(
+            ranges,
+            vec![
+                InclusiveRange(10, 39),
+                InclusiveRange(40, 69),
+                InclusiveRange(70, 89)
+            ]
+        );
+        assert_eq!(part_files[1].path, dir.join("part_001"));
+        assert_eq!(written[0], (10u8..40).collect::<Vec<_>>());
+        assert_eq!(written[1], (40u8..70).collect::<Vec<_>>());
+        assert_eq!(written[2], (70u8..90).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn an_empty_range_writes_no_files() {
+        let dir = temp_path("condow_parts", "empty");
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let part_files = condow(30, 100)
+            .download_parts_to_dir(&location, 10..10, &dir)
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(part_files.is_empty());
+    }
+
+    #[tokio::test]
+    async fn a_failed_part_ends_the_stream_with_an_error() {
+        let dir = temp_path("condow_parts", "failed");
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+        let client = FailingClientSimulatorBuilder::default()
+            .blob((0u8..100).collect())
+            .responses()
+            .failure(CondowErrorKind::NotFound)
+            .finish();
+        let condow = Condow::new(client, Config::default().disable_retries()).unwrap();
+
+        let result = condow
+            .download_parts_to_dir(&location, .., &dir)
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await;
+
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(result.unwrap_err().kind(), CondowErrorKind::NotFound);
+    }
+}
+
+mod download_ranges {
+    use futures::{StreamExt, TryStreamExt};
+
+    use crate::{
+        condow_client::{failing_client_simulator::FailingClientSimulatorBuilder, InMemoryClient},
+        config::Config,
+        errors::CondowErrorKind,
+        test_utils::condow,
+        Condow,
+    };
+
+    #[tokio::test]
+    async fn each_chunk_carries_its_range_index() {
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let items = condow(10, 3)
+            .download_ranges(&location, vec![0..25, 50..60, 90..100])
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+
+        let mut ranges = vec![vec![0u8; 25], vec![0u8; 10], vec![0u8; 10]];
+        for item in items {
+            let chunk = item.chunk;
+            let offset = chunk.range_offset as usize;
+            ranges[item.range_index][offset..offset + chunk.len()].copy_from_slice(&chunk.bytes);
+        }
+
+        assert_eq!(ranges[0], (0u8..25).collect::<Vec<_>>());
+        assert_eq!(ranges[1], (50u8..60).collect::<Vec<_>>());
+        assert_eq!(ranges[2], (90u8..100).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn no_ranges() {
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let items = condow(10, 3)
+            .download_ranges(&location, Vec::<std::ops::Range<u64>>::new())
+            .await
+            .unwrap()
+            .collect::<Vec<_>>()
+            .await;
+
+        assert!(items.is_empty());
+    }
+
+    #[tokio::test]
+    async fn fails_if_a_range_is_invalid() {
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let result = condow(10, 3)
+            .download_ranges(&location, vec![0..10, 20..10])
+            .await;
+
+        assert_eq!(result.err().unwrap().kind(), CondowErrorKind::InvalidRange);
+    }
+
+    #[tokio::test]
+    async fn ends_after_the_first_error() {
+        let client = FailingClientSimulatorBuilder::default()
+            .blob((0u8..100).collect())
+            .chunk_size(3)
+            .responses()
+            .success_with_stream_failure(5)
+            .success()
+            .done()
+            .finish();
+        let config = Config::default()
+            .part_size_bytes(100)
+            .disable_retries()
+            .buffers_full_delay_ms(0);
+        let condow = Condow::new(client, config).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let items = condow
+            .download_ranges(&location, vec![0..10, 10..20])
+            .await
+            .unwrap()
+            .collect::<Vec<_>>()
+            .await;
+
+        assert!(items.last().unwrap().is_err());
+        assert_eq!(items.iter().filter(|item| item.is_err()).count(), 1);
+    }
+
+    #[tokio::test]
+    async fn max_concurrency_bounds_all_ranges() {
+        use std::{
+            sync::{
+                atomic::{AtomicUsize, Ordering},
+                Arc,
+            },
+            time::Duration,
+        };
+
+        use futures::future::BoxFuture;
+
+        use crate::{
+            condow_client::{CondowClient, DownloadSpec},
+            streams::{BytesHint, BytesStream},
+        };
+
+        /// Counts the responses whose streams were not dropped yet
+        #[derive(Clone)]
+        struct CountingClient {
+            client: InMemoryClient,
+            open: Arc<AtomicUsize>,
+            peak: Arc<AtomicUsize>,
+        }
+
+        struct Open(Arc<AtomicUsize>);
+
+        impl Drop for Open {
+            fn drop(&mut self) {
+                self.0.fetch_sub(1, Ordering::SeqCst);
+            }
+        }
+
+        impl CondowClient for CountingClient {
+            fn get_size(
+                &self,
+                location: url::Url,
+            ) -> BoxFuture<'static, Result<u64, crate::errors::CondowError>> {
+                self.client.get_size(location)
+            }
+
+            fn download(
+                &self,
+                location: url::Url,
+                spec: DownloadSpec,
+            ) -> BoxFuture<'static, Result<(BytesStream, BytesHint), crate::errors::CondowError>>
+            {
+                let open = Arc::clone(&self.open);
+                let peak = Arc::clone(&self.peak);
+                let download = self.client.download(location, spec);
+                Box::pin(async move {
+                    let now_open = open.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now_open, Ordering::SeqCst);
+                    let guard = Open(open);
+                    tokio::time::sleep(Duration::from_millis(5)).await;
+                    let (stream, bytes_hint) = download.await?;
+                    let stream = stream.map(move |item| {
+                        let _ = &guard;
+                        item
+                    });
+                    Ok((Box::pin(stream) as BytesStream, bytes_hint))
+                })
+            }
+        }
+
+        let peak = Arc::new(AtomicUsize::new(0));
+        let client = CountingClient {
+            client: InMemoryClient::new((0u8..100).collect()).chunk_size(3),
+            open: Default::default(),
+            peak: Arc::clone(&peak),
+        };
+        let config = Config::default()
+            .part_size_bytes(10)
+            .max_concurrency(4)
+            .buffers_full_delay_ms(0);
+        let condow = Condow::new(client, config).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let items = condow
+            .download_ranges(&location, vec![0..40, 40..80, 80..100, 0..30, 50..90])
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+
+        let n_bytes: usize = items.iter().map(|item| item.chunk.len()).sum();
+        assert_eq!(n_bytes, 40 + 40 + 20 + 30 + 40);
+        let peak = peak.load(Ordering::SeqCst);
+        assert!(peak > 1, "peak: {}", peak);
+        assert!(peak <= 4, "peak: {}", peak);
+    }
+}
+
+mod probe {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{
+        condow_client::{
+            failing_client_simulator::FailingClientSimulatorBuilder, DownloadSpec, InMemoryClient,
+            InstrumentedClient,
+        },
+        config::Config,
+        errors::CondowErrorKind,
+        Condow, Downloads, InclusiveRange,
+    };
+
+    #[tokio::test]
+    async fn downloads_the_first_byte() {
+        let requested = Arc::new(Mutex::new(Vec::new()));
+        let requested_in_callback = Arc::clone(&requested);
+        let client = InstrumentedClient::new(InMemoryClient::new((0u8..100).collect()))
+            .on_download(move |_location, spec, _outcome, _elapsed| {
+                if let DownloadSpec::Range(range) = spec {
+                    requested_in_callback.lock().unwrap().push(range);
+                }
+            });
+        let condow = Condow::new(client, Config::default()).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        condow.probe(&location).await.unwrap();
+
+        assert_eq!(*requested.lock().unwrap(), vec![InclusiveRange(0, 0)]);
+    }
+
+    #[tokio::test]
+    async fn a_failed_download_is_classified() {
+        let client = FailingClientSimulatorBuilder::default()
+            .blob((0u8..100).collect())
+            .responses()
+            .failure(CondowErrorKind::AccessDenied)
+            .finish();
+        let condow = Condow::new(client, Config::default().disable_retries()).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let err = condow.probe(&location).await.unwrap_err();
+
+        assert_eq!(err.kind(), CondowErrorKind::AccessDenied);
+    }
+}
+
+mod seekable_reader {
+    use futures::{AsyncReadExt, AsyncSeekExt};
+
+    use crate::{reader::FetchAheadMode, test_utils::condow, Downloads};
+
+    #[tokio::test]
+    async fn fetches_to_the_end() {
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let reader = condow(7, 3).seekable_reader(&location).await.unwrap();
+
+        assert_eq!(reader.fetch_ahead_mode(), FetchAheadMode::ToEnd);
+        assert_eq!(reader.length(), Some(100));
+    }
+
+    #[tokio::test]
+    async fn reads_forward_and_after_seeking() {
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+        let mut reader = condow(7, 3).seekable_reader(&location).await.unwrap();
+
+        let mut buf = vec![0; 10];
+        reader.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf, (0u8..10).collect::<Vec<_>>());
+
+        reader.seek(std::io::SeekFrom::Start(50)).await.unwrap();
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest).await.unwrap();
+        assert_eq!(rest, (50u8..100).collect::<Vec<_>>());
+
+        reader.seek(std::io::SeekFrom::Start(5)).await.unwrap();
+        reader.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf, (5u8..15).collect::<Vec<_>>());
+    }
+}
+
+mod copy_to {
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    use futures::AsyncWrite;
+
+    use crate::{errors::CondowErrorKind, test_utils::condow, Downloads};
+
+    #[tokio::test]
+    async fn copies_the_range_in_order() {
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+        let mut written = Vec::new();
+
+        let n_bytes = condow(7, 3)
+            .copy_to(&location, 10..90, &mut written)
+            .await
+            .unwrap();
+
+        assert_eq!(n_bytes, 80);
+        assert_eq!(written, (10u8..90).collect::<Vec<_>>());
+    }
+
+    struct FailingWriter;
+
+    impl AsyncWrite for FailingWriter {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            _buf: &[u8],
+        ) -> Poll<std::io::Result<usize>> {
+            Poll::Ready(Err(std::io::Error::new(
+                std::io::ErrorKind::BrokenPipe,
+                "broken",
+            )))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[tokio::test]
+    async fn fails_if_the_writer_fails() {
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let result = condow(7, 3).copy_to(&location, .., FailingWriter).await;
+
+        assert_eq!(result.unwrap_err().kind(), CondowErrorKind::Io);
+    }
+}
+
+mod client_factory {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use crate::{
+        condow_client::{ClientFactory, InMemoryClient},
+        config::Config,
+        errors::{CondowError, CondowErrorKind},
+        Condow,
+    };
+
+    const BLOB: &[u8] = &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+
+    fn counting_factory(n_created: Arc<AtomicUsize>) -> ClientFactory<InMemoryClient> {
+        ClientFactory::new(move || {
+            n_created.fetch_add(1, Ordering::SeqCst);
+            async { Ok(InMemoryClient::new_static(BLOB)) }
+        })
+    }
+
+    fn config() -> Config {
+        Config::default().part_size_bytes(3).max_concurrency(4)
+    }
+
+    #[tokio::test]
+    async fn each_download_creates_one_client() {
+        let n_created = Arc::new(AtomicUsize::new(0));
+        let condow =
+            Condow::from_client_factory(counting_factory(Arc::clone(&n_created)), config())
+                .unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        // The size request and all parts are made with the same client
+        let bytes = condow
+            .download(&location, 2..)
+            .await
+            .unwrap()
+            .into_vec()
+            .await
+            .unwrap();
+        assert_eq!(bytes, BLOB[2..].to_vec());
+        assert_eq!(n_created.load(Ordering::SeqCst), 1);
+
+        let bytes = condow
+            .download(&location, 0..=4)
+            .await
+            .unwrap()
+            .into_vec()
+            .await
+            .unwrap();
+        assert_eq!(bytes, BLOB[0..=4].to_vec());
+        assert_eq!(n_created.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn get_size_creates_a_client() {
+        let n_created = Arc::new(AtomicUsize::new(0));
+        let condow =
+            Condow::from_client_factory(counting_factory(Arc::clone(&n_created)), config())
+                .unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let size = condow.get_size(&location).await.unwrap();
+
+        assert_eq!(size, BLOB.len() as u64);
+        assert_eq!(n_created.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn with_config_keeps_the_factory() {
+        let n_created = Arc::new(AtomicUsize::new(0));
+        let condow =
+            Condow::from_client_factory(counting_factory(Arc::clone(&n_created)), config())
+                .unwrap()
+                .with_config(Config::default().part_size_bytes(5))
+                .unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let bytes = condow
+            .download(&location, ..)
+            .await
+            .unwrap()
+            .into_vec()
+            .await
+            .unwrap();
+
+        assert_eq!(bytes, BLOB.to_vec());
+        assert_eq!(n_created.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn a_failing_factory_fails_the_download() {
+        let factory = ClientFactory::<InMemoryClient>::new(|| async {
+            Err(CondowError::new_remote("no credentials"))
+        });
+        let condow = Condow::from_client_factory(factory, config()).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let result = condow.download(&location, 0..=4).await;
+
+        assert_eq!(
+            result.err().map(|err| err.kind()),
+            Some(CondowErrorKind::Remote)
+        );
+    }
+}
+
+mod download_plan {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use crate::{
+        condow_client::{
+            failing_client_simulator::FailingClientSimulatorBuilder, InMemoryClient,
+            InstrumentedClient,
+        },
+        config::Config,
+        errors::CondowErrorKind,
+        test_utils::temp_path,
+        Condow, DownloadPlan, InclusiveRange,
+    };
+
+    fn blob() -> Vec<u8> {
+        (0u8..100).collect()
+    }
+
+    fn config() -> Config {
+        Config::default()
+            .max_concurrency(1)
+            .buffers_full_delay_ms(0)
+            .disable_retries()
+    }
+
+    #[tokio::test]
+    async fn all_parts_are_written_at_their_offsets() {
+        let path = temp_path("condow_plan", "all_parts");
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+        let condow = Condow::new(InMemoryClient::new(blob()), config()).unwrap();
+        let mut plan = DownloadPlan::new(InclusiveRange(10, 59), 15);
+
+        condow
+            .download_plan(&location, &mut plan, &path)
+            .await
+            .unwrap();
+
+        let written = std::fs::read(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert!(plan.is_complete());
+        assert_eq!(written, blob()[10..=59].to_vec());
+    }
+
+    #[tokio::test]
+    async fn only_incomplete_parts_are_downloaded_on_resume() {
+        let path = temp_path("condow_plan", "resume");
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+        let mut plan = DownloadPlan::new(InclusiveRange(0, 99), 20);
+
+        let failing_client = FailingClientSimulatorBuilder::default()
+            .blob(blob())
+            .responses()
+            .successes(2)
+            .failure(CondowErrorKind::NotFound)
+            .never()
+            .finish();
+        let condow = Condow::new(failing_client, config()).unwrap();
+        let result = condow.download_plan(&location, &mut plan, &path).await;
+
+        assert!(result.is_err());
+        let completed = plan
+            .parts()
+            .iter()
+            .filter(|part| part.is_completed())
+            .count();
+        assert_eq!(completed, 2);
+
+        let n_downloads = Arc::new(AtomicUsize::new(0));
+        let client = InstrumentedClient::new(InMemoryClient::new(blob())).on_download({
+            let n_downloads = Arc::clone(&n_downloads);
+            move |_, _, _, _| {
+                n_downloads.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+        let condow = Condow::new(client, config()).unwrap();
+        condow
+            .download_plan(&location, &mut plan, &path)
+            .await
+            .unwrap();
+
+        let written = std::fs::read(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert!(plan.is_complete());
+        assert_eq!(n_downloads.load(Ordering::SeqCst), 3);
+        assert_eq!(written, blob());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn a_plan_can_be_persisted() {
+        let plan = DownloadPlan::new(InclusiveRange(5, 30), 10);
+
+        let json = serde_json::to_string(&plan).unwrap();
+        let restored: DownloadPlan = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored, plan);
+    }
+}
+
+mod part_queue_time {
+    use std::time::Duration;
+
+    use futures::TryStreamExt;
+
+    use crate::{condow_client::InMemoryClient, config::Config, reporter::SimpleReporter, Condow};
+
+    #[tokio::test]
+    async fn simple_reporter_measures_the_queue_time_of_all_parts() {
+        let client = InMemoryClient::new((0u8..50).collect());
+        let config = Config::default()
+            .part_size_bytes(10)
+            .max_concurrency(1)
+            .buffer_size(1)
+            .buffers_full_delay_ms(1);
+        let condow = Condow::new(client, config).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+        let reporter = SimpleReporter::default();
+
+        let _ = condow
+            .downloader()
+            .download_chunks_wrep(&location, .., reporter.clone())
+            .await
+            .unwrap()
+            .into_stream()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+
+        let report = reporter.report();
+        assert_eq!(report.n_parts_received, 5);
+        assert_ne!(report.min_part_queue_time, Duration::from_micros(u64::MAX));
+        assert!(report.min_part_queue_time <= report.max_part_queue_time);
+        // With a single task buffering one part the last parts had to wait
+        assert!(report.max_part_queue_time > Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn a_part_is_started_before_it_is_requested() {
+        use crate::{
+            condow_client::failing_client_simulator::FailingClientSimulatorBuilder,
+            errors::CondowError,
+            reporter::{RecordingReporter, ReporterEvent},
+        };
+
+        let client = FailingClientSimulatorBuilder::default()
+            .blob((0u8..50).collect())
+            .responses()
+            .failure(CondowError::new_remote("boom"))
+            .finish();
+        let config = Config::default()
+            .part_size_bytes(50)
+            .disable_retries()
+            .always_get_size(false);
+        let condow = Condow::new(client, config).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+        let reporter = RecordingReporter::new();
+
+        let result = condow
+            .downloader()
+            .download_chunks_wrep(&location, 0..50, reporter.clone())
+            .await
+            .unwrap()
+            .into_stream()
+            .try_collect::<Vec<_>>()
+            .await;
+
+        assert!(result.is_err());
+        assert!(reporter
+            .events()
+            .iter()
+            .any(|event| matches!(event, ReporterEvent::PartStarted { part_index: 0, .. })));
+    }
+}
+
+mod download_with_percent {
+    use futures::StreamExt;
+
+    use crate::{condow_client::InMemoryClient, config::Config, Condow};
+
+    #[tokio::test]
+    async fn yields_increasing_percentages_up_to_100() {
+        let blob = (0u8..=255).cycle().take(1_000).collect::<Vec<_>>();
+        let client = InMemoryClient::new(blob.clone()).chunk_size(7);
+        let config = Config::default()
+            .part_size_bytes(100)
+            .max_concurrency(4)
+            .buffers_full_delay_ms(0);
+        let condow = Condow::new(client, config).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let (stream, percentages) = condow
+            .downloader()
+            .download_with_percent(&location, ..)
+            .await
+            .unwrap();
+
+        assert_eq!(stream.into_vec().await.unwrap(), blob);
+        let percentages = percentages.collect::<Vec<_>>().await;
+        assert!(
+            percentages.windows(2).all(|w| w[0] < w[1]),
+            "{:?}",
+            percentages
+        );
+        assert_eq!(percentages.last(), Some(&100));
+    }
+
+    #[tokio::test]
+    async fn is_relative_to_the_downloaded_range() {
+        let blob = (0u8..100).collect::<Vec<_>>();
+        let client = InMemoryClient::new(blob).chunk_size(5);
+        let config = Config::default().part_size_bytes(10).max_concurrency(1);
+        let condow = Condow::new(client, config).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let (stream, percentages) = condow
+            .downloader()
+            .download_with_percent(&location, 50..70)
+            .await
+            .unwrap();
+
+        assert_eq!(stream.into_vec().await.unwrap().len(), 20);
+        let percentages = percentages.collect::<Vec<_>>().await;
+        assert_eq!(percentages, vec![25, 50, 75, 100]);
+    }
+}
+
+mod on_part_failure {
+    use futures::StreamExt;
+
+    use crate::{
+        condow_client::failing_client_simulator::FailingClientSimulatorBuilder,
+        config::{Config, OnPartFailure},
+        errors::{CondowError, CondowErrorKind},
+        Condow, InclusiveRange,
+    };
+
+    async fn download(
+        builder: FailingClientSimulatorBuilder,
+        on_part_failure: OnPartFailure,
+    ) -> Result<(Vec<u8>, Vec<InclusiveRange>), CondowError> {
+        let client = builder.blob((0u8..100).collect()).chunk_size(10).finish();
+        let config = Config::default()
+            .part_size_bytes(25)
+            .max_concurrency(1)
+            .buffers_full_delay_ms(0)
+            .disable_retries()
+            .on_part_failure(on_part_failure);
+        let condow = Condow::new(client, config).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let mut stream = condow.download(&location, 0..100).await?;
+        let mut bytes = Vec::new();
+        while let Some(part) = stream.next().await {
+            part?
+                .chunks
+                .iter()
+                .for_each(|chunk| bytes.extend_from_slice(chunk));
+        }
+
+        Ok((bytes, stream.missing_ranges()))
+    }
+
+    fn expected_with_zeros(zeroed: &[InclusiveRange]) -> Vec<u8> {
+        (0u8..100)
+            .map(|b| {
+                if zeroed
+                    .iter()
+                    .any(|r| r.start() <= b as u64 && b as u64 <= r.end_incl())
+                {
+                    0
+                } else {
+                    b
+                }
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn a_failed_request_aborts_by_default() {
+        let builder = FailingClientSimulatorBuilder::default()
+            .responses()
+            .success()
+            .failure(CondowErrorKind::Remote)
+            .done();
+
+        let err = download(builder, OnPartFailure::default())
+            .await
+            .err()
+            .unwrap();
+
+        assert_eq!(err.kind(), CondowErrorKind::Remote);
+    }
+
+    #[tokio::test]
+    async fn a_failed_request_is_skipped() {
+        let builder = FailingClientSimulatorBuilder::default()
+            .responses()
+            .success()
+            .failure(CondowErrorKind::Remote)
+            .done();
+
+        let (bytes, missing_ranges) = download(builder, OnPartFailure::Skip).await.unwrap();
+
+        assert_eq!(missing_ranges, vec![InclusiveRange(25, 49)]);
+        assert_eq!(bytes, expected_with_zeros(&missing_ranges));
+    }
+
+    #[tokio::test]
+    async fn the_rest_of_a_broken_stream_is_skipped() {
+        let builder = FailingClientSimulatorBuilder::default()
+            .responses()
+            .success()
+            .success()
+            .success_with_stream_failure(15)
+            .done();
+
+        let (bytes, missing_ranges) = download(builder, OnPartFailure::Skip).await.unwrap();
+
+        assert_eq!(missing_ranges, vec![InclusiveRange(65, 74)]);
+        assert_eq!(bytes, expected_with_zeros(&missing_ranges));
+    }
+
+    #[tokio::test]
+    async fn multiple_failed_parts_are_skipped() {
+        let builder = FailingClientSimulatorBuilder::default()
+            .responses()
+            .failure(CondowErrorKind::Remote)
+            .success()
+            .success()
+            .failure(CondowErrorKind::NotFound)
+            .done();
+
+        let (bytes, missing_ranges) = download(builder, OnPartFailure::Skip).await.unwrap();
+
+        assert_eq!(
+            missing_ranges,
+            vec![InclusiveRange(0, 24), InclusiveRange(75, 99)]
+        );
+        assert_eq!(bytes, expected_with_zeros(&missing_ranges));
+    }
+
+    #[tokio::test]
+    async fn nothing_is_missing_without_failures() {
+        let (bytes, missing_ranges) = download(
+            FailingClientSimulatorBuilder::default(),
+            OnPartFailure::Skip,
+        )
+        .await
+        .unwrap();
+
+        assert!(missing_ranges.is_empty());
+        assert_eq!(bytes, (0u8..100).collect::<Vec<_>>());
+    }
+}
+
+mod retry_budget {
+    use crate::{
+        condow_client::{failing_client_simulator::FailingClientSimulatorBuilder, CondowClient},
+        config::{Config, RetryBudget, RetryConfig},
+        errors::{CondowError, CondowErrorKind},
+        reporter::NoReporting,
+        Condow, DownloadSession,
+    };
+
+    #[tokio::test]
+    async fn the_budget_is_shared_by_the_downloads_of_a_session() {
+        let client = FailingClientSimulatorBuilder::default()
+            .blob((0u8..10).collect())
+            .responses()
+            .failures([CondowErrorKind::Remote; 3])
+            .success()
+            .never()
+            .finish();
+        let config = Config::default()
+            .buffers_full_delay_ms(0)
+            .retries(RetryConfig::default().max_attempts(2).max_delay_ms(0));
+        let condow = Condow::new(client, config).unwrap();
+        let budget = RetryBudget::new(1, 0.0);
+        let session = condow
+            .download_session(NoReporting)
+            .with_retry_budget(budget.clone());
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        // The first retry takes the only token
+        let err = download(&session, &location).await.unwrap_err();
+        assert_eq!(err.kind(), CondowErrorKind::Remote);
+        assert_eq!(budget.available(), 0);
+
+        // Without a budget the second request would have been retried successfully
+        let err = download(&session.clone(), &location).await.unwrap_err();
+        assert_eq!(err.kind(), CondowErrorKind::Remote);
+    }
+
+    async fn download<C: CondowClient>(
+        session: &DownloadSession<C>,
+        location: &url::Url,
+    ) -> Result<Vec<u8>, CondowError> {
+        session.download(location, ..).await?.into_vec().await
+    }
+}
+
+mod retry_report {
+    use futures::StreamExt;
+
+    use crate::{
+        condow_client::failing_client_simulator::FailingClientSimulatorBuilder,
+        config::{Config, RetryConfig},
+        errors::CondowErrorKind,
+        streams::{ChunkStream, PartStream},
+        Condow, InclusiveRange,
+    };
+
+    async fn download(
+        builder: FailingClientSimulatorBuilder,
+    ) -> (Vec<u8>, PartStream<ChunkStream>, bool) {
+        let client = builder.blob((0u8..100).collect()).chunk_size(10).finish();
+        let config = Config::default()
+            .part_size_bytes(25)
+            .max_concurrency(1)
+            .buffers_full_delay_ms(0)
+            .retries(RetryConfig::default().max_attempts(1).max_delay_ms(0));
+        let condow = Condow::new(client, config).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let mut stream = condow.download(&location, 0..100).await.unwrap();
+        let mut bytes = Vec::new();
+        let mut failed = false;
+        while let Some(part) = stream.next().await {
+            match part {
+                Ok(part) => part
+                    .chunks
+                    .iter()
+                    .for_each(|chunk| bytes.extend_from_slice(chunk)),
+                Err(_) => failed = true,
+            }
+        }
+
+        (bytes, stream, failed)
+    }
+
+    #[tokio::test]
+    async fn a_download_without_retries_has_an_empty_report() {
+        let (bytes, stream, failed) = download(FailingClientSimulatorBuilder::default()).await;
+
+        assert!(!failed);
+        assert_eq!(bytes, (0u8..100).collect::<Vec<_>>());
+        assert!(stream.retry_report().is_empty());
+    }
+
+    #[tokio::test]
+    async fn retries_and_resumes_are_reported_by_part() {
+        let builder = FailingClientSimulatorBuilder::default()
+            .responses()
+            .success()
+            .failure(CondowErrorKind::Remote)
+            .success()
+            .success_with_stream_failure(5)
+            .success()
+            .success()
+            .done();
+
+        let (bytes, stream, failed) = download(builder).await;
+
+        assert!(!failed);
+        assert_eq!(bytes, (0u8..100).collect::<Vec<_>>());
+        let report = stream.retry_report();
+        assert_eq!(report.parts.keys().copied().collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(report.n_retries(), 2);
+
+        let part = &report.parts[&1];
+        assert_eq!(part.range, InclusiveRange(25, 49));
+        assert_eq!(part.attempts, 2);
+        assert_eq!(part.errors.len(), 1);
+        assert_eq!(part.errors[0].kind, CondowErrorKind::Remote);
+        assert!(!part.failed);
+
+        let part = &report.parts[&2];
+        assert_eq!(part.range, InclusiveRange(50, 74));
+        assert_eq!(part.attempts, 2);
+        assert_eq!(part.errors[0].kind, CondowErrorKind::Io);
+        assert!(!part.failed);
+    }
+
+    #[tokio::test]
+    async fn a_failed_part_is_reported() {
+        let builder = FailingClientSimulatorBuilder::default()
+            .responses()
+            .success()
+            .failures([CondowErrorKind::Remote, CondowErrorKind::Io])
+            .done();
+
+        let (_bytes, stream, failed) = download(builder).await;
+
+        assert!(failed);
+        let report = stream.retry_report();
+        let failed_parts = report.failed_parts().collect::<Vec<_>>();
+        assert_eq!(failed_parts.len(), 1);
+
+        let (part_index, part) = failed_parts[0];
+        assert_eq!(part_index, 1);
+        assert_eq!(part.attempts, 2);
+        assert_eq!(
+            part.errors.iter().map(|err| err.kind).collect::<Vec<_>>(),
+            vec![CondowErrorKind::Remote, CondowErrorKind::Io]
+        );
+    }
+}
+
+mod size_request_timeout {
+    use futures::future::BoxFuture;
+
+    use crate::{
+        condow_client::{CondowClient, DownloadSpec, InMemoryClient},
+        config::Config,
+        errors::{CondowError, CondowErrorKind},
+        streams::{BytesHint, BytesStream},
+        Condow,
+    };
+
+    /// A client which never answers a size request
+    #[derive(Clone)]
+    struct HangingSizeClient(InMemoryClient);
+
+    impl CondowClient for HangingSizeClient {
+        fn get_size(&self, _location: url::Url) -> BoxFuture<'static, Result<u64, CondowError>> {
+            Box::pin(futures::future::pending())
+        }
+
+        fn download(
+            &self,
+            location: url::Url,
+            spec: DownloadSpec,
+        ) -> BoxFuture<'static, Result<(BytesStream, BytesHint), CondowError>> {
+            self.0.download(location, spec)
+        }
+    }
+
+    fn condow(config: Config) -> Condow<HangingSizeClient> {
+        let client = HangingSizeClient(InMemoryClient::new((0u8..100).collect()));
+        Condow::new(client, config).unwrap()
+    }
+
+    #[tokio::test]
+    async fn a_hanging_size_request_times_out() {
+        let condow = condow(
+            Config::default()
+                .size_request_timeout_ms(10)
+                .configure_retries(|retries| retries.max_attempts(2).max_delay_ms(0)),
+        );
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let err = condow.get_size(&location).await.unwrap_err();
+        assert_eq!(err.kind(), CondowErrorKind::Timeout);
+
+        let result = condow.download(&location, ..).await;
+        assert_eq!(
+            result.err().map(|err| err.kind()),
+            Some(CondowErrorKind::Timeout)
+        );
+    }
+
+    #[tokio::test]
+    async fn byte_requests_are_not_affected() {
+        let condow = condow(
+            Config::default()
+                .size_request_timeout_ms(10)
+                .always_get_size(false),
+        );
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let bytes = condow
+            .download(&location, 10..20)
+            .await
+            .unwrap()
+            .into_vec()
+            .await
+            .unwrap();
+
+        assert_eq!(bytes, (10u8..20).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn none_disables_the_timeout() {
+        let config = Config::default()
+            .size_request_timeout_ms(10)
+            .size_request_timeout_ms(None);
+
+        assert_eq!(config.size_request_timeout_ms, None);
+    }
+}
+
+mod download_from {
+    use std::sync::{Arc, Mutex};
+
+    use futures::{future::BoxFuture, AsyncReadExt, TryStreamExt};
+
+    use crate::{
+        condow_client::{
+            failing_client_simulator::FailingClientSimulatorBuilder, CondowClient, DownloadSpec,
+            InMemoryClient, InstrumentedClient,
+        },
+        config::{Config, RetryConfig},
+        errors::{CondowError, CondowErrorKind},
+        streams::{BytesHint, BytesStream},
+        Condow,
+    };
+
+    /// A client which supports open ranges but fails on size requests
+    #[derive(Clone)]
+    struct OpenRangeClient(InMemoryClient);
+
+    impl CondowClient for OpenRangeClient {
+        fn get_size(&self, _location: url::Url) -> BoxFuture<'static, Result<u64, CondowError>> {
+            Box::pin(futures::future::ready(Err(CondowError::new_other(
+                "no size requests",
+            ))))
+        }
+
+        fn download(
+            &self,
+            location: url::Url,
+            spec: DownloadSpec,
+        ) -> BoxFuture<'static, Result<(BytesStream, BytesHint), CondowError>> {
+            self.0.download(location, spec)
+        }
+
+        fn download_from(
+            &self,
+            location: url::Url,
+            start: u64,
+        ) -> BoxFuture<'static, Result<(BytesStream, BytesHint), CondowError>> {
+            self.0.download_from(location, start)
+        }
+    }
+
+    async fn collect(stream: BytesStream) -> Vec<u8> {
+        stream
+            .try_fold(Vec::new(), |mut acc, bytes| async move {
+                acc.extend_from_slice(&bytes);
+                Ok(acc)
+            })
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn the_tail_is_downloaded_without_a_size_request() {
+        let client = OpenRangeClient(InMemoryClient::new((0u8..100).collect()));
+        let condow = Condow::new(client, Config::default()).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let bytes = collect(condow.download_raw(&location, 90..).await.unwrap()).await;
+        assert_eq!(bytes, (90u8..100).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn the_default_implementation_downloads_the_remaining_range() {
+        let client = InMemoryClient::new((0u8..100).collect());
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let (stream, bytes_hint) = client.download_from(location.clone(), 95).await.unwrap();
+        assert_eq!(bytes_hint, BytesHint::new_exact(5));
+        assert_eq!(collect(stream).await, (95u8..100).collect::<Vec<_>>());
+
+        for start in [100, 1_000] {
+            let (stream, bytes_hint) = client.download_from(location.clone(), start).await.unwrap();
+            assert_eq!(bytes_hint, BytesHint::new_exact(0));
+            assert!(collect(stream).await.is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn a_start_beyond_the_end_is_empty() {
+        let condow =
+            Condow::new(InMemoryClient::new((0u8..100).collect()), Config::default()).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let bytes = collect(condow.download_raw(&location, 100..).await.unwrap()).await;
+        assert!(bytes.is_empty());
+    }
+
+    #[tokio::test]
+    async fn a_streaming_reader_downloads_from_its_position() {
+        let specs = Arc::new(Mutex::new(Vec::new()));
+        let specs_in_callback = Arc::clone(&specs);
+        let starts = Arc::new(Mutex::new(Vec::new()));
+        let starts_in_callback = Arc::clone(&starts);
+        let client =
+            InstrumentedClient::new(OpenRangeClient(InMemoryClient::new((0u8..100).collect())))
+                .on_download(move |_location, spec, _outcome, _elapsed| {
+                    specs_in_callback.lock().unwrap().push(spec);
+                })
+                .on_download_from(move |_location, start, _outcome, _elapsed| {
+                    starts_in_callback.lock().unwrap().push(start);
+                });
+        let condow = Condow::new(client, Config::default()).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let mut reader = condow.reader_streaming(&location);
+        reader.seek_to(90).unwrap();
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await.unwrap();
+
+        assert_eq!(bytes, (90u8..100).collect::<Vec<_>>());
+        assert_eq!(reader.length(), Some(100));
+        assert!(specs.lock().unwrap().is_empty());
+        assert_eq!(*starts.lock().unwrap(), vec![90]);
+    }
+
+    #[tokio::test]
+    async fn failed_requests_and_broken_streams_are_retried() {
+        let blob: Vec<u8> = (0u8..100).collect();
+        let client = FailingClientSimulatorBuilder::default()
+            .blob(blob.clone())
+            .chunk_size(7)
+            .responses()
+            .failure(CondowErrorKind::Remote)
+            .success_with_stream_failure(20)
+            .success()
+            .finish();
+        let config = Config::default().retries(RetryConfig::default().max_delay_ms(0));
+        let condow = Condow::new(client, config).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+
+        let bytes = collect(condow.download_raw(&location, 10..).await.unwrap()).await;
+        assert_eq!(bytes, blob[10..]);
+    }
+}
+
+mod get_size_mode {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use futures::future::BoxFuture;
+
+    use crate::{
+        condow_client::{CondowClient, DownloadSpec},
+        config::Config,
+        errors::CondowError,
+        streams::{BytesHint, BytesStream},
+        test_utils::*,
+        Condow, GetSizeMode,
+    };
+
+    #[derive(Clone)]
+    struct CountingClient {
+        inner: TestCondowClient,
+        n_get_size: Arc<AtomicUsize>,
+    }
+
+    impl CondowClient for CountingClient {
+        fn get_size(&self, location: url::Url) -> BoxFuture<'static, Result<u64, CondowError>> {
+            self.n_get_size.fetch_add(1, Ordering::SeqCst);
+            self.inner.get_size(location)
+        }
+
+        fn download(
+            &self,
+            location: url::Url,
+            spec: DownloadSpec,
+        ) -> BoxFuture<'static, Result<(BytesStream, BytesHint), CondowError>> {
+            self.inner.download(location, spec)
+        }
+    }
+
+    fn create_condow() -> (Condow<CountingClient>, Arc<AtomicUsize>, Arc<Vec<u8>>) {
+        let n_get_size = Arc::new(AtomicUsize::new(0));
+        let inner = TestCondowClient::new();
+        let data = inner.data();
+        let client = CountingClient {
+            inner,
+            n_get_size: Arc::clone(&n_get_size),
+        };
+        let config = Config::default()
+            .buffers_full_delay_ms(0)
+            .part_size_bytes(5)
+            .always_get_size(true);
+        (Condow::new(client, config).unwrap(), n_get_size, data)
+    }
+
+    #[tokio::test]
+    async fn required_never_queries_size_for_closed_ranges() {
+        let (condow, n_get_size, data) = create_condow();
+
+        let downloader = condow.downloader().get_size_mode(GetSizeMode::Required);
+
+        let result = downloader
+            .download(
+                &url::Url::parse("noscheme://").expect("a valid URL"),
+                23..46,
+            )
+            .await
+            .unwrap()
+            .into_vec()
+            .await
+            .unwrap();
+        assert_eq!(result, data[23..46]);
+
+        let result = downloader
+            .download(&url::Url::parse("noscheme://").expect("a valid URL"), ..=9)
+            .await
+            .unwrap()
+            .into_vec()
+            .await
+            .unwrap();
+        assert_eq!(result, data[..=9]);
+
+        assert_eq!(n_get_size.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn no_size_query_does_not_clamp_upper_bound() {
+        let (condow, n_get_size, data) = create_condow();
+        let size = data.len() as u64;
+
+        let downloader = condow.downloader().no_size_query();
+
+        let result = downloader
+            .download(
+                &url::Url::parse("noscheme://").expect("a valid URL"),
+                size - 2..size + 10,
+            )
+            .await
+            .unwrap()
+            .into_vec()
+            .await;
+        assert!(result.is_err());
+
+        assert_eq!(n_get_size.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn default_queries_size_for_closed_ranges_if_configured() {
+        let (condow, n_get_size, _data) = create_condow();
+
+        let _ = condow
+            .downloader()
+            .download(
+                &url::Url::parse("noscheme://").expect("a valid URL"),
+                23..46,
+            )
+            .await
+            .unwrap()
+            .into_vec()
+            .await
+            .unwrap();
+
+        assert_eq!(n_get_size.load(Ordering::SeqCst), 1);
+    }
+}
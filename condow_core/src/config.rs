@@ -5,12 +5,16 @@
 //! This is mainly to allow them to be
 //! initialized from the environment.
 use std::{
+    fmt,
     str::{from_utf8, FromStr},
+    sync::Arc,
     time::Duration,
 };
 
 use anyhow::{bail, Error as AnyError};
 
+use crate::{DownloadRange, OpenRange, RangeRequest};
+
 pub use crate::retry::*;
 
 /// A configuration for [Condow](super::Condow).
@@ -29,7 +33,9 @@ pub struct Config {
     pub max_concurrency: MaxConcurrency,
     /// Size of the buffer for each download task.
     ///
-    /// If set to 0 (not advised) there will be now buffer at all.
+    /// The minimum is 0 (not advised). In that case a download task
+    /// takes exactly one request at a time and at most one further request
+    /// can be handed over while it is still working on the previous one.
     /// This should be configured so that none of the tasks runs ever empty
     ///
     /// Default is 2
@@ -45,15 +51,119 @@ pub struct Config {
     ///
     /// The default is `true`.
     pub always_get_size: AlwaysGetSize,
+    /// If `true` the bytes of a `download_bytes` call are streamed
+    /// in the same order as within the BLOB/range. Otherwise they
+    /// are streamed as received from the concurrently downloaded parts.
+    ///
+    /// The default is `true`.
+    pub default_ordered: DefaultOrdered,
+    /// The maximum number of bytes to preallocate when collecting a
+    /// download into a `Vec`.
+    ///
+    /// The size hint of a download is only trusted up to this limit.
+    /// Beyond it the `Vec` grows as bytes arrive.
+    ///
+    /// Default is 16 MiB
+    pub max_preallocation_bytes: MaxPreallocationBytes,
+    /// What to do if the upper bound of a closed range exceeds the
+    /// size of a BLOB or an open range starts beyond its end after the size was requested.
+    ///
+    /// The default is [OutOfBoundsPolicy::Clamp].
+    pub out_of_bounds_policy: OutOfBoundsPolicy,
+    /// If `true` empty chunks received after all bytes of a part
+    /// are ignored. Otherwise they fail the download.
+    ///
+    /// Some sources send a trailing empty chunk after the
+    /// expected bytes.
+    ///
+    /// The default is `false`.
+    pub tolerate_trailing_empty: TolerateTrailingEmpty,
+    /// What to do if a part of a download fails permanently,
+    /// i.e. after all retries.
+    ///
+    /// The default is [OnPartFailure::Abort].
+    pub on_part_failure: OnPartFailure,
+    /// The range downloaded by `download_default` where no range is given.
+    ///
+    /// The range is clamped to the size of the BLOB.
+    ///
+    /// Can not be initialized from the environment.
+    ///
+    /// The default is the complete BLOB.
+    pub default_range: DownloadRange,
+    /// Decides which of the pending parts is dispatched next
+    /// to the download tasks.
+    ///
+    /// Can not be initialized from the environment.
+    ///
+    /// The default is `None` which dispatches the parts in
+    /// ascending order.
+    pub part_priority: Option<PartPriority>,
+    /// The minimum number of parts a download is split into.
+    ///
+    /// If set, the part size is reduced for a download so that the range is split
+    /// into at least this many parts. The part size never drops below 1 byte so
+    /// a range with less bytes than `min_parts` is split into single bytes.
+    ///
+    /// This is mostly useful to exercise concurrent downloads of small BLOBs
+    /// e.g. for benchmarks.
+    ///
+    /// Can not be initialized from the environment.
+    ///
+    /// The default is `None`.
+    pub min_parts: Option<usize>,
+    /// The number of parts covered by a single request to the client.
+    ///
+    /// With a factor greater than 1 fewer but larger requests are made
+    /// while the bytes are still streamed as [Chunk](crate::streams::Chunk)s
+    /// of parts of `part_size_bytes`. Download tasks, part priorities and the
+    /// part indexes reported to a [Reporter](crate::reporter::Reporter) refer
+    /// to the requests.
+    ///
+    /// The default is 1.
+    pub request_coalescing: RequestCoalescing,
+    /// BLOBs smaller than this are downloaded with a single request.
+    ///
+    /// This only applies if the size of the BLOB was requested, e.g. for an
+    /// open range. Instead of splitting the range into parts and spawning
+    /// download tasks, the range is downloaded as a single part right after
+    /// the size request.
+    ///
+    /// The default is 0 which always downloads the parts concurrently.
+    pub concurrency_min_bytes: ConcurrencyMinBytes,
+    /// The time a single size request may take.
+    ///
+    /// A size request which takes longer fails with
+    /// [CondowErrorKind::Timeout](crate::errors::CondowErrorKind::Timeout)
+    /// and is retried like any other failed size request.
+    /// Requests for the bytes are not affected.
+    ///
+    /// The size request made by the default implementation of
+    /// [CondowClient::download_from](crate::condow_client::CondowClient::download_from)
+    /// is not affected either since it is made by the client itself.
+    ///
+    /// The default is `None` which waits forever.
+    pub size_request_timeout_ms: Option<SizeRequestTimeoutMs>,
     /// Configures retries if there.
     ///
     /// Otherwise there won't be any retry attempts made
     ///
     /// Retries are turned on by default
     pub retries: Option<RetryConfig>,
+    /// Whether `buffer_size` is derived on validation
+    buffer_size_mode: BufferSizeMode,
 }
 
 impl Config {
+    /// The default size of the parts in bytes (4 MiB)
+    pub const DEFAULT_PART_SIZE_BYTES: u64 = Mebi(4).value();
+    /// The default maximum concurrency of a single download
+    pub const DEFAULT_MAX_CONCURRENCY: usize = 64;
+    /// The default size of the buffer of each download task
+    pub const DEFAULT_BUFFER_SIZE: usize = 2;
+    /// The default delay in milliseconds if all buffers are full
+    pub const DEFAULT_BUFFERS_FULL_DELAY_MS: u64 = 10;
+
     env_ctors!(no_fill);
 
     /// Set the size of the parts the download is split into in bytes
@@ -69,11 +179,50 @@ impl Config {
     }
 
     /// Set the size of the buffer for each download task.
+    ///
+    /// This takes precedence over [Config::auto_buffer_size].
     pub fn buffer_size<T: Into<BufferSize>>(mut self, buffer_size: T) -> Self {
         self.buffer_size = buffer_size.into();
+        self.buffer_size_mode = BufferSizeMode::Explicit;
+        self
+    }
+
+    /// Derive the size of the buffer for each download task from the part size
+    /// and the delay if all buffers are full.
+    ///
+    /// If all buffers are full no new parts are enqueued for `buffers_full_delay_ms`.
+    /// The buffer of a download task should hold enough parts to not run empty during
+    /// this delay. Assuming a single download task receives roughly 100 MiB/s, the
+    /// buffer size is the number of parts received during the delay plus one
+    /// (at least 2). The concurrency does not matter since each task has its own buffer.
+    ///
+    /// With the defaults this results in the default buffer size of 2.
+    ///
+    /// The buffer size is derived when the [Config] is validated, so the part size
+    /// and the delay may be set before or after calling this method.
+    /// A buffer size set with [Config::buffer_size] or from the environment
+    /// always takes precedence.
+    pub fn auto_buffer_size(mut self) -> Self {
+        if self.buffer_size_mode != BufferSizeMode::Explicit {
+            self.buffer_size_mode = BufferSizeMode::Auto;
+        }
         self
     }
 
+    /// The buffer size for [Config::auto_buffer_size]
+    fn derived_buffer_size(&self) -> BufferSize {
+        let bytes_during_delay = self
+            .buffers_full_delay_ms
+            .into_inner()
+            .saturating_mul(AUTO_BUFFER_SIZE_BYTES_PER_MS);
+        let part_size_bytes = self.part_size_bytes.0.max(1);
+        let parts_during_delay =
+            bytes_during_delay / part_size_bytes + (bytes_during_delay % part_size_bytes).min(1);
+        let buffer_size = parts_during_delay.saturating_add(1).max(2);
+
+        BufferSize(buffer_size.min(usize::MAX as u64) as usize)
+    }
+
     /// Set the delay in case all task buffers are full before a retry
     /// to enqueue the next downlod part is made.
     pub fn buffers_full_delay_ms<T: Into<BuffersFullDelayMs>>(
@@ -90,6 +239,93 @@ impl Config {
         self
     }
 
+    /// Set whether the bytes of a download should be ordered by default
+    pub fn default_ordered<T: Into<DefaultOrdered>>(mut self, default_ordered: T) -> Self {
+        self.default_ordered = default_ordered.into();
+        self
+    }
+
+    /// Set the maximum number of bytes to preallocate when collecting
+    /// a download into a `Vec`
+    pub fn max_preallocation_bytes<T: Into<MaxPreallocationBytes>>(
+        mut self,
+        max_preallocation_bytes: T,
+    ) -> Self {
+        self.max_preallocation_bytes = max_preallocation_bytes.into();
+        self
+    }
+
+    /// Set what to do if the upper bound of a closed range exceeds the size of a BLOB
+    /// or an open range starts beyond its end
+    pub fn out_of_bounds_policy<T: Into<OutOfBoundsPolicy>>(
+        mut self,
+        out_of_bounds_policy: T,
+    ) -> Self {
+        self.out_of_bounds_policy = out_of_bounds_policy.into();
+        self
+    }
+
+    /// Set whether empty chunks received after all bytes of a part are ignored
+    pub fn tolerate_trailing_empty<T: Into<TolerateTrailingEmpty>>(
+        mut self,
+        tolerate_trailing_empty: T,
+    ) -> Self {
+        self.tolerate_trailing_empty = tolerate_trailing_empty.into();
+        self
+    }
+
+    /// Set what to do if a part of a download fails permanently
+    pub fn on_part_failure<T: Into<OnPartFailure>>(mut self, on_part_failure: T) -> Self {
+        self.on_part_failure = on_part_failure.into();
+        self
+    }
+
+    /// Set the range to be downloaded if none is given
+    pub fn default_range<T: Into<DownloadRange>>(mut self, default_range: T) -> Self {
+        self.default_range = default_range.into();
+        self
+    }
+
+    /// Set the priority by which pending parts are dispatched
+    pub fn part_priority<T: Into<PartPriority>>(mut self, part_priority: T) -> Self {
+        self.part_priority = Some(part_priority.into());
+        self
+    }
+
+    /// Set the minimum number of parts a download is split into
+    ///
+    /// `None` disables the minimum.
+    pub fn min_parts<T: Into<Option<usize>>>(mut self, min_parts: T) -> Self {
+        self.min_parts = min_parts.into();
+        self
+    }
+
+    /// Set the number of parts covered by a single request to the client
+    pub fn request_coalescing<T: Into<RequestCoalescing>>(mut self, request_coalescing: T) -> Self {
+        self.request_coalescing = request_coalescing.into();
+        self
+    }
+
+    /// Set the size below which a BLOB is downloaded with a single request
+    pub fn concurrency_min_bytes<T: Into<ConcurrencyMinBytes>>(
+        mut self,
+        concurrency_min_bytes: T,
+    ) -> Self {
+        self.concurrency_min_bytes = concurrency_min_bytes.into();
+        self
+    }
+
+    /// Set the time a single size request may take
+    ///
+    /// `None` disables the timeout.
+    pub fn size_request_timeout_ms<T: Into<Option<u64>>>(
+        mut self,
+        size_request_timeout_ms: T,
+    ) -> Self {
+        self.size_request_timeout_ms = size_request_timeout_ms.into().map(SizeRequestTimeoutMs);
+        self
+    }
+
     /// Enables retries with the given configuration
     pub fn retries(mut self, config: RetryConfig) -> Self {
         self.retries = Some(config);
@@ -125,7 +361,13 @@ impl Config {
     }
 
     /// Validate this [Config]
-    pub fn validated(self) -> Result<Self, AnyError> {
+    ///
+    /// `max_concurrency` and `part_size_bytes` must be at least 1.
+    /// `buffer_size` may be 0. `default_range` must be a valid range.
+    /// `min_parts` must be at least 1 if set. `request_coalescing` must be at least 1.
+    ///
+    /// The buffer size is derived here if [Config::auto_buffer_size] was called.
+    pub fn validated(mut self) -> Result<Self, AnyError> {
         if self.max_concurrency.0 == 0 {
             bail!("'max_concurrency' must not be 0");
         }
@@ -134,10 +376,64 @@ impl Config {
             bail!("'part_size_bytes' must not be 0");
         }
 
+        if self.min_parts == Some(0) {
+            bail!("'min_parts' must not be 0");
+        }
+
+        if self.request_coalescing.0 == 0 {
+            bail!("'request_coalescing' must not be 0");
+        }
+
+        if let Err(err) = self.default_range.validate() {
+            bail!("'default_range' is invalid: {}", err);
+        }
+
         if let Some(retries) = &self.retries {
             retries.validate()?;
         }
 
+        if self.buffer_size_mode == BufferSizeMode::Auto {
+            self.buffer_size = self.derived_buffer_size();
+        }
+
+        Ok(self)
+    }
+
+    /// The timeout of a single size request if configured
+    pub(crate) fn size_request_timeout(&self) -> Option<Duration> {
+        self.size_request_timeout_ms.map(Duration::from)
+    }
+
+    /// The part size used to download a range of `range_len` bytes
+    ///
+    /// This is `part_size_bytes` unless it has to be reduced to
+    /// split the range into at least `min_parts` parts.
+    pub(crate) fn effective_part_size_bytes(&self, range_len: u64) -> u64 {
+        let part_size_bytes = self.part_size_bytes.0;
+
+        match self.min_parts {
+            Some(min_parts) if min_parts > 1 => {
+                let max_part_size_bytes = (range_len / min_parts as u64).max(1);
+                part_size_bytes.min(max_part_size_bytes)
+            }
+            _ => part_size_bytes,
+        }
+    }
+
+    /// Returns this [Config] overlaid with environment variables prefixed with "[prefix]_"
+    ///
+    /// The underscore is omitted if `prefix` is empty.
+    ///
+    /// A value found in the environment takes precedence over the value of this [Config].
+    /// Fields without an environment variable keep their value instead of being
+    /// reset to their defaults like with [Config::from_env_prefixed]. This also applies
+    /// to the fields of the [RetryConfig]. If retries are disabled they are enabled with
+    /// the defaults overlaid by the environment once one of their variables is set.
+    ///
+    /// Fields which can not be initialized from the environment are never changed.
+    /// The result is not validated.
+    pub fn overlaid_with_env<T: AsRef<str>>(mut self, prefix: T) -> Result<Self, AnyError> {
+        self.fill_from_env_prefixed_internal(prefix)?;
         Ok(self)
     }
 
@@ -158,6 +454,7 @@ impl Config {
         if let Some(buffer_size) = BufferSize::try_from_env_prefixed(prefix.as_ref())? {
             found_any = true;
             self.buffer_size = buffer_size;
+            self.buffer_size_mode = BufferSizeMode::Explicit;
         }
         if let Some(buffers_full_delay_ms) =
             BuffersFullDelayMs::try_from_env_prefixed(prefix.as_ref())?
@@ -169,8 +466,52 @@ impl Config {
             found_any = true;
             self.always_get_size = always_get_size;
         }
+        if let Some(default_ordered) = DefaultOrdered::try_from_env_prefixed(prefix.as_ref())? {
+            found_any = true;
+            self.default_ordered = default_ordered;
+        }
+        if let Some(max_preallocation_bytes) =
+            MaxPreallocationBytes::try_from_env_prefixed(prefix.as_ref())?
+        {
+            found_any = true;
+            self.max_preallocation_bytes = max_preallocation_bytes;
+        }
+        if let Some(out_of_bounds_policy) =
+            OutOfBoundsPolicy::try_from_env_prefixed(prefix.as_ref())?
+        {
+            found_any = true;
+            self.out_of_bounds_policy = out_of_bounds_policy;
+        }
+        if let Some(tolerate_trailing_empty) =
+            TolerateTrailingEmpty::try_from_env_prefixed(prefix.as_ref())?
+        {
+            found_any = true;
+            self.tolerate_trailing_empty = tolerate_trailing_empty;
+        }
+        if let Some(on_part_failure) = OnPartFailure::try_from_env_prefixed(prefix.as_ref())? {
+            found_any = true;
+            self.on_part_failure = on_part_failure;
+        }
+        if let Some(request_coalescing) = RequestCoalescing::try_from_env_prefixed(prefix.as_ref())?
+        {
+            found_any = true;
+            self.request_coalescing = request_coalescing;
+        }
+        if let Some(concurrency_min_bytes) =
+            ConcurrencyMinBytes::try_from_env_prefixed(prefix.as_ref())?
+        {
+            found_any = true;
+            self.concurrency_min_bytes = concurrency_min_bytes;
+        }
+        if let Some(size_request_timeout_ms) =
+            SizeRequestTimeoutMs::try_from_env_prefixed(prefix.as_ref())?
+        {
+            found_any = true;
+            self.size_request_timeout_ms = Some(size_request_timeout_ms);
+        }
 
-        if let Some(retries) = RetryConfig::from_env_prefixed(prefix.as_ref())? {
+        let mut retries = self.retries.clone().unwrap_or_default();
+        if retries.fill_from_env_prefixed_internal(prefix.as_ref())? {
             found_any = true;
             self.retries = Some(retries);
         }
@@ -187,11 +528,37 @@ impl Default for Config {
             buffer_size: Default::default(),
             buffers_full_delay_ms: Default::default(),
             always_get_size: Default::default(),
+            default_ordered: Default::default(),
+            max_preallocation_bytes: Default::default(),
+            out_of_bounds_policy: Default::default(),
+            tolerate_trailing_empty: Default::default(),
+            on_part_failure: Default::default(),
+            default_range: DownloadRange::Open(OpenRange::Full),
+            part_priority: None,
+            min_parts: None,
+            request_coalescing: Default::default(),
+            concurrency_min_bytes: Default::default(),
+            size_request_timeout_ms: None,
             retries: Some(Default::default()),
+            buffer_size_mode: BufferSizeMode::Default,
         }
     }
 }
 
+/// How the buffer size of the download tasks is determined
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BufferSizeMode {
+    /// `buffer_size` is used as it is
+    Default,
+    /// `buffer_size` was set with [Config::buffer_size] or from the environment
+    Explicit,
+    /// `buffer_size` is derived on validation, see [Config::auto_buffer_size]
+    Auto,
+}
+
+/// The assumed throughput of a single download task for [Config::auto_buffer_size]
+const AUTO_BUFFER_SIZE_BYTES_PER_MS: u64 = Kibi(100).value();
+
 /// Size of the parts in bytes a download is split into
 ///
 /// # Examples
@@ -247,7 +614,7 @@ impl PartSizeBytes {
 
 impl Default for PartSizeBytes {
     fn default() -> Self {
-        PartSizeBytes::new(Mebi(4))
+        PartSizeBytes::new(Config::DEFAULT_PART_SIZE_BYTES)
     }
 }
 
@@ -337,7 +704,7 @@ new_type! {
 
 impl Default for MaxConcurrency {
     fn default() -> Self {
-        MaxConcurrency(64)
+        MaxConcurrency(Config::DEFAULT_MAX_CONCURRENCY)
     }
 }
 
@@ -349,7 +716,7 @@ new_type! {
 
 impl Default for BufferSize {
     fn default() -> Self {
-        BufferSize(2)
+        BufferSize(Config::DEFAULT_BUFFER_SIZE)
     }
 }
 
@@ -365,6 +732,216 @@ impl Default for AlwaysGetSize {
     }
 }
 
+new_type! {
+    #[doc="Whether the bytes of a download are ordered by default"]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub copy struct DefaultOrdered(bool, env="DEFAULT_ORDERED");
+}
+
+impl Default for DefaultOrdered {
+    fn default() -> Self {
+        DefaultOrdered(true)
+    }
+}
+
+new_type! {
+    #[doc="Whether empty chunks received after all bytes of a part are ignored"]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub copy struct TolerateTrailingEmpty(bool, env="TOLERATE_TRAILING_EMPTY");
+}
+
+new_type! {
+    #[doc="Number of parts covered by a single request"]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub copy struct RequestCoalescing(usize, env="REQUEST_COALESCING");
+}
+
+impl Default for RequestCoalescing {
+    fn default() -> Self {
+        RequestCoalescing(1)
+    }
+}
+
+new_type! {
+    #[doc="Size in bytes below which a BLOB is downloaded with a single request"]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub copy struct ConcurrencyMinBytes(u64, env="CONCURRENCY_MIN_BYTES");
+}
+
+new_type! {
+    #[doc="Maximum number of bytes to preallocate when collecting a download"]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub copy struct MaxPreallocationBytes(usize, env="MAX_PREALLOCATION_BYTES");
+}
+
+impl Default for MaxPreallocationBytes {
+    fn default() -> Self {
+        MaxPreallocationBytes(16 * 1024 * 1024)
+    }
+}
+
+/// What to do if a range exceeds the size of a BLOB
+///
+/// A closed range exceeds the BLOB if its upper bound is beyond the last byte.
+/// This only applies if the size of the BLOB was requested
+/// (see [GetSizeMode](crate::GetSizeMode)).
+///
+/// An open range like `1000..` exceeds the BLOB if it starts at or beyond
+/// its end (e.g. on a BLOB of 500 bytes). Clamping results in an empty download.
+/// An open range starting at 0 is the complete BLOB and never exceeds it.
+///
+/// # Examples
+///
+/// ```rust
+/// # use condow_core::config::OutOfBoundsPolicy;
+/// let policy: OutOfBoundsPolicy = "clamp".parse().unwrap();
+/// assert_eq!(policy, OutOfBoundsPolicy::Clamp);
+///
+/// let policy: OutOfBoundsPolicy = "error".parse().unwrap();
+/// assert_eq!(policy, OutOfBoundsPolicy::Error);
+///
+/// let res = "x".parse::<OutOfBoundsPolicy>();
+/// assert!(res.is_err());
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum OutOfBoundsPolicy {
+    /// Adjust the range to the end of the BLOB
+    #[default]
+    Clamp,
+    /// Fail with [CondowErrorKind::InvalidRange](crate::errors::CondowErrorKind::InvalidRange)
+    Error,
+}
+
+impl OutOfBoundsPolicy {
+    env_funs!("OUT_OF_BOUNDS_POLICY");
+}
+
+impl FromStr for OutOfBoundsPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "clamp" => Ok(OutOfBoundsPolicy::Clamp),
+            "error" => Ok(OutOfBoundsPolicy::Error),
+            _ => bail!("'{}' is not a valid out of bounds policy", s),
+        }
+    }
+}
+
+/// What to do if a part of a download fails permanently
+///
+/// # Examples
+///
+/// ```rust
+/// # use condow_core::config::OnPartFailure;
+/// let on_failure: OnPartFailure = "abort".parse().unwrap();
+/// assert_eq!(on_failure, OnPartFailure::Abort);
+///
+/// let on_failure: OnPartFailure = "skip".parse().unwrap();
+/// assert_eq!(on_failure, OnPartFailure::Skip);
+///
+/// let res = "x".parse::<OnPartFailure>();
+/// assert!(res.is_err());
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum OnPartFailure {
+    /// Fail the whole download with the error of the part
+    #[default]
+    Abort,
+    /// Continue with the other parts
+    ///
+    /// The bytes of the failed part which were not received are
+    /// filled with zeros and their range is recorded as missing.
+    /// See [PartStream::missing_ranges](crate::streams::PartStream::missing_ranges).
+    /// A cancelled download is never continued.
+    Skip,
+}
+
+impl OnPartFailure {
+    env_funs!("ON_PART_FAILURE");
+}
+
+impl FromStr for OnPartFailure {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "abort" => Ok(OnPartFailure::Abort),
+            "skip" => Ok(OnPartFailure::Skip),
+            _ => bail!("'{}' is not a valid action on a failed part", s),
+        }
+    }
+}
+
+/// Returns the priority of a part given its pending [RangeRequest]
+///
+/// Whenever a part is to be dispatched to a download task, the
+/// pending part with the highest priority is chosen. Parts with
+/// the same priority are dispatched in ascending order.
+///
+/// The function is evaluated again for all pending parts on each dispatch,
+/// so the priorities may change while a download is running
+/// (e.g. to follow the playback position of a video). Parts already
+/// handed over to the buffers of the download tasks
+/// (see [Config::buffer_size]) are not reordered.
+///
+/// Since all parts have to be considered, all requests for the parts are
+/// created upfront. Without a [PartPriority] they are created lazily.
+///
+/// # Examples
+///
+/// ```rust
+/// # use condow_core::{config::{Config, PartPriority}, RangeRequest};
+/// // Download the parts in descending order
+/// let priority = PartPriority::new(|request: &RangeRequest| request.part_index() as i64);
+///
+/// let config = Config::default().part_priority(priority);
+/// ```
+#[derive(Clone)]
+pub struct PartPriority(Arc<dyn Fn(&RangeRequest) -> i64 + Send + Sync>);
+
+impl PartPriority {
+    /// Create a [PartPriority] from the given function
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(&RangeRequest) -> i64 + Send + Sync + 'static,
+    {
+        Self(Arc::new(f))
+    }
+
+    /// Returns the priority of the part requested by `request`
+    pub fn priority(&self, request: &RangeRequest) -> i64 {
+        (self.0)(request)
+    }
+}
+
+impl From<Arc<dyn Fn(&RangeRequest) -> i64 + Send + Sync>> for PartPriority {
+    fn from(f: Arc<dyn Fn(&RangeRequest) -> i64 + Send + Sync>) -> Self {
+        Self(f)
+    }
+}
+
+impl<F> From<F> for PartPriority
+where
+    F: Fn(&RangeRequest) -> i64 + Send + Sync + 'static,
+{
+    fn from(f: F) -> Self {
+        Self::new(f)
+    }
+}
+
+impl fmt::Debug for PartPriority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PartPriority")
+    }
+}
+
+impl PartialEq for PartPriority {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
 new_type! {
     #[doc="Time to wait for download buffers when all were full in ms"]
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -373,7 +950,7 @@ new_type! {
 
 impl Default for BuffersFullDelayMs {
     fn default() -> Self {
-        Self(10)
+        Self(Config::DEFAULT_BUFFERS_FULL_DELAY_MS)
     }
 }
 
@@ -383,6 +960,18 @@ impl From<BuffersFullDelayMs> for Duration {
     }
 }
 
+new_type! {
+    #[doc="Time a single size request may take in ms"]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub copy struct SizeRequestTimeoutMs(u64, env="SIZE_REQUEST_TIMEOUT_MS");
+}
+
+impl From<SizeRequestTimeoutMs> for Duration {
+    fn from(m: SizeRequestTimeoutMs) -> Self {
+        Duration::from_millis(m.0)
+    }
+}
+
 /// Multiplies by 1_000 when converted to a u64
 ///
 // # Examples
@@ -0,0 +1,327 @@
+//! Plans of downloads which can be resumed later
+use std::{
+    fs::{File, OpenOptions},
+    io::{Seek, SeekFrom, Write},
+    path::Path,
+    sync::Arc,
+};
+
+use futures::{StreamExt, TryStreamExt};
+
+use crate::{
+    condow_client::CondowClient, errors::CondowError, helpers::spawn_blocking, Condow,
+    InclusiveRange,
+};
+
+/// A download split into parts which remembers the completed parts
+///
+/// A plan can be persisted (with the feature `serde`) to resume a very large
+/// download later and only fetch the parts which are still missing.
+/// See [Condow::download_plan].
+///
+/// # Examples
+///
+/// ```rust
+/// # use condow_core::{DownloadPlan, InclusiveRange};
+/// let plan = DownloadPlan::new(InclusiveRange(0, 24), 10);
+///
+/// assert_eq!(plan.parts().len(), 3);
+/// assert_eq!(plan.parts()[2].range(), InclusiveRange(20, 24));
+/// assert!(!plan.is_complete());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DownloadPlan {
+    parts: Vec<PlannedPart>,
+}
+
+impl DownloadPlan {
+    /// Create a plan for `range` split into parts of `part_size_bytes`
+    ///
+    /// The last part may be smaller. No part is completed.
+    ///
+    /// # Panics
+    ///
+    /// If `part_size_bytes` is 0.
+    pub fn new(range: InclusiveRange, part_size_bytes: u64) -> Self {
+        if part_size_bytes == 0 {
+            panic!("'part_size_bytes' must not be 0");
+        }
+
+        let parts = range
+            .split_into_parts(part_size_bytes)
+            .map(|range| PlannedPart {
+                range,
+                completed: false,
+            })
+            .collect();
+
+        Self { parts }
+    }
+
+    /// The parts of the plan in ascending order
+    pub fn parts(&self) -> &[PlannedPart] {
+        &self.parts
+    }
+
+    /// The range covered by all parts
+    ///
+    /// Returns `None` if the plan has no parts.
+    pub fn range(&self) -> Option<InclusiveRange> {
+        let first = self.parts.first()?;
+        let last = self.parts.last()?;
+        Some(InclusiveRange(first.range.start(), last.range.end_incl()))
+    }
+
+    /// Returns `true` if all parts were completed
+    pub fn is_complete(&self) -> bool {
+        self.parts.iter().all(|part| part.completed)
+    }
+
+    /// Validate this [DownloadPlan]
+    ///
+    /// Succeeds if
+    /// * no part is empty
+    /// * each part starts right after the previous one ended
+    ///
+    /// This means the parts are sorted, do not overlap and cover
+    /// [DownloadPlan::range] without any gaps. A plan created with
+    /// [DownloadPlan::new] is always valid but a deserialized one might not be.
+    ///
+    /// Fails with [CondowErrorKind::InvalidRange](crate::errors::CondowErrorKind::InvalidRange)
+    /// naming the first offending part.
+    pub fn validate(&self) -> Result<(), CondowError> {
+        let mut previous: Option<InclusiveRange> = None;
+        for (part_idx, part) in self.parts.iter().enumerate() {
+            if part.range.len() == 0 {
+                return Err(CondowError::new_invalid_range(format!(
+                    "part {} with range {} is empty",
+                    part_idx, part.range
+                )));
+            }
+
+            if let Some(previous) = previous {
+                let expected_start = previous.end_incl() + 1;
+                if part.range.start() < expected_start {
+                    return Err(CondowError::new_invalid_range(format!(
+                        "part {} starts at {} which overlaps part {} ending at {}",
+                        part_idx,
+                        part.range.start(),
+                        part_idx - 1,
+                        previous.end_incl()
+                    )));
+                }
+                if part.range.start() > expected_start {
+                    return Err(CondowError::new_invalid_range(format!(
+                        "part {} starts at {} which leaves a gap after part {} ending at {}",
+                        part_idx,
+                        part.range.start(),
+                        part_idx - 1,
+                        previous.end_incl()
+                    )));
+                }
+            }
+
+            previous = Some(part.range);
+        }
+
+        Ok(())
+    }
+}
+
+/// A part of a [DownloadPlan]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlannedPart {
+    range: InclusiveRange,
+    completed: bool,
+}
+
+impl PlannedPart {
+    /// The range of the part within the BLOB
+    pub fn range(&self) -> InclusiveRange {
+        self.range
+    }
+
+    /// Returns `true` if the part was downloaded and written
+    pub fn is_completed(&self) -> bool {
+        self.completed
+    }
+}
+
+/// Download the incomplete parts of `plan` and write them to the file at `path`
+///
+/// See [Condow::download_plan]
+pub(crate) async fn download_plan<C: CondowClient>(
+    condow: &Condow<C>,
+    location: &url::Url,
+    plan: &mut DownloadPlan,
+    path: &Path,
+) -> Result<(), CondowError> {
+    plan.validate()?;
+
+    let plan_range = if let Some(plan_range) = plan.range() {
+        plan_range
+    } else {
+        return Ok(());
+    };
+
+    let file = spawn_blocking({
+        let path = path.to_path_buf();
+        move || -> Result<File, CondowError> {
+            let file = OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(path)?;
+            if file.metadata()?.len() < plan_range.len() {
+                file.set_len(plan_range.len())?;
+            }
+            Ok(file)
+        }
+    })
+    .await??;
+    let file = Arc::new(file);
+
+    // Each part is downloaded with a single request so that no more than
+    // `max_concurrency` requests are made in total
+    let downloads = plan
+        .parts
+        .iter()
+        .enumerate()
+        .filter(|(_, part)| !part.completed)
+        .map(|(part_idx, part)| {
+            let range = part.range;
+            async move {
+                let bytes = condow
+                    .download_raw(location, range)
+                    .await?
+                    .try_fold(Vec::new(), |mut bytes, chunk| async move {
+                        bytes.extend_from_slice(&chunk);
+                        Ok(bytes)
+                    })
+                    .await?;
+                Ok::<_, CondowError>((part_idx, bytes))
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let mut downloads = futures::stream::iter(downloads)
+        .buffer_unordered(condow.config.max_concurrency.into_inner());
+
+    while let Some(result) = downloads.next().await {
+        let (part_idx, bytes) = result?;
+        let part = &mut plan.parts[part_idx];
+        let offset = part.range.start() - plan_range.start();
+
+        let file = Arc::clone(&file);
+        spawn_blocking(move || -> Result<(), CondowError> {
+            let mut file = &*file;
+            file.seek(SeekFrom::Start(offset))?;
+            file.write_all(&bytes)?;
+            // Only a part which is on disk may be marked as completed
+            file.sync_data()?;
+            Ok(())
+        })
+        .await??;
+
+        part.completed = true;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::CondowErrorKind;
+
+    use super::*;
+
+    fn plan(ranges: &[(u64, u64)]) -> DownloadPlan {
+        DownloadPlan {
+            parts: ranges
+                .iter()
+                .map(|&(start, end_incl)| PlannedPart {
+                    range: InclusiveRange(start, end_incl),
+                    completed: false,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn new_plans_are_valid() {
+        for part_size in 1..12 {
+            let plan = DownloadPlan::new(InclusiveRange(3, 13), part_size);
+            assert!(plan.validate().is_ok(), "part_size={}", part_size);
+        }
+    }
+
+    #[test]
+    fn an_empty_plan_is_valid() {
+        assert!(plan(&[]).validate().is_ok());
+    }
+
+    #[test]
+    fn contiguous_parts_are_valid() {
+        assert!(plan(&[(0, 4), (5, 5), (6, 9)]).validate().is_ok());
+    }
+
+    #[test]
+    fn an_empty_part_is_invalid() {
+        let err = plan(&[(0, 4), (6, 5)]).validate().unwrap_err();
+
+        assert_eq!(err.kind(), CondowErrorKind::InvalidRange);
+        assert_eq!(err.msg(), "part 1 with range [6,5] is empty");
+    }
+
+    #[test]
+    fn overlapping_parts_are_invalid() {
+        let err = plan(&[(0, 4), (5, 9), (9, 12)]).validate().unwrap_err();
+
+        assert_eq!(err.kind(), CondowErrorKind::InvalidRange);
+        assert_eq!(
+            err.msg(),
+            "part 2 starts at 9 which overlaps part 1 ending at 9"
+        );
+    }
+
+    #[test]
+    fn unsorted_parts_are_invalid() {
+        let err = plan(&[(5, 9), (0, 4)]).validate().unwrap_err();
+
+        assert_eq!(
+            err.msg(),
+            "part 1 starts at 0 which overlaps part 0 ending at 9"
+        );
+    }
+
+    #[test]
+    fn a_gap_is_invalid() {
+        let err = plan(&[(0, 4), (6, 9)]).validate().unwrap_err();
+
+        assert_eq!(err.kind(), CondowErrorKind::InvalidRange);
+        assert_eq!(
+            err.msg(),
+            "part 1 starts at 6 which leaves a gap after part 0 ending at 4"
+        );
+    }
+
+    #[tokio::test]
+    async fn an_invalid_plan_is_not_downloaded() {
+        let mut path = std::env::temp_dir();
+        path.push(format!("condow_invalid_plan_{}", std::process::id()));
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+        let client = crate::condow_client::InMemoryClient::new((0u8..20).collect());
+        let condow = Condow::new(client, Default::default()).unwrap();
+        let mut plan = plan(&[(0, 4), (6, 9)]);
+
+        let err = condow
+            .download_plan(&location, &mut plan, &path)
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.kind(), CondowErrorKind::InvalidRange);
+        assert!(!path.exists());
+    }
+}
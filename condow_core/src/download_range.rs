@@ -10,6 +10,7 @@ use crate::errors::CondowError;
 ///
 /// A replacement for [RangeInclusive] with some sugar.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InclusiveRange(pub u64, pub u64);
 
 impl InclusiveRange {
@@ -47,6 +48,47 @@ impl InclusiveRange {
     pub fn http_range_value(&self) -> String {
         format!("bytes={}-{}", self.0, self.1)
     }
+
+    /// Splits this range into consecutive parts of `part_size` bytes
+    ///
+    /// The last part may be smaller than `part_size`. These are exactly the parts
+    /// [Condow](crate::Condow) downloads for this range when configured with
+    /// the same part size.
+    ///
+    /// # Panics
+    ///
+    /// If `part_size` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use condow_core::InclusiveRange;
+    /// let parts: Vec<_> = InclusiveRange(2, 8).split_into_parts(3).collect();
+    /// assert_eq!(
+    ///     parts,
+    ///     vec![InclusiveRange(2, 4), InclusiveRange(5, 7), InclusiveRange(8, 8)]
+    /// );
+    /// ```
+    pub fn split_into_parts(self, part_size: u64) -> impl Iterator<Item = InclusiveRange> {
+        if part_size == 0 {
+            panic!("part_size must not be 0");
+        }
+
+        let end_incl = self.end_incl();
+        let mut next_start = Some(self.start()).filter(|&start| start <= end_incl);
+        std::iter::from_fn(move || {
+            let start = next_start?;
+
+            let current_end_incl = start.saturating_add(part_size - 1).min(end_incl);
+            next_start = if current_end_incl == end_incl {
+                None
+            } else {
+                Some(current_end_incl + 1)
+            };
+
+            Some(InclusiveRange(start, current_end_incl))
+        })
+    }
 }
 
 impl fmt::Display for InclusiveRange {
@@ -61,6 +103,99 @@ impl From<RangeInclusive<u64>> for InclusiveRange {
     }
 }
 
+/// The ranges of the parts of a download
+///
+/// The requests of a download are created from these boundaries, so
+/// [PartBoundaries::to_vec] returns exactly the ranges requested from the
+/// client (not counting retries). The index of a range is the `part_index`
+/// passed to the [Reporter](crate::reporter::Reporter).
+///
+/// See [Reporter::parts_planned](crate::reporter::Reporter::parts_planned).
+///
+/// # Examples
+///
+/// ```rust
+/// # use condow_core::{InclusiveRange, PartBoundaries};
+/// let boundaries = PartBoundaries::new(InclusiveRange(10, 34), 10);
+///
+/// assert_eq!(boundaries.n_parts(), 3);
+/// assert_eq!(boundaries.part(2), Some(InclusiveRange(30, 34)));
+/// assert_eq!(
+///     boundaries.to_vec(),
+///     vec![InclusiveRange(10, 19), InclusiveRange(20, 29), InclusiveRange(30, 34)]
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PartBoundaries {
+    range: InclusiveRange,
+    part_size_bytes: u64,
+}
+
+impl PartBoundaries {
+    /// Boundaries of `range` split into parts of `part_size_bytes`
+    ///
+    /// The last part may be smaller.
+    ///
+    /// # Panics
+    ///
+    /// If `part_size_bytes` is 0.
+    pub fn new(range: InclusiveRange, part_size_bytes: u64) -> Self {
+        if part_size_bytes == 0 {
+            panic!("'part_size_bytes' must not be 0");
+        }
+
+        Self {
+            range,
+            part_size_bytes,
+        }
+    }
+
+    /// The range covered by all parts
+    pub fn range(&self) -> InclusiveRange {
+        self.range
+    }
+
+    /// The size of all parts but the last one
+    pub fn part_size_bytes(&self) -> u64 {
+        self.part_size_bytes
+    }
+
+    /// The number of parts
+    pub fn n_parts(&self) -> u64 {
+        let mut n_parts = self.range.len() / self.part_size_bytes;
+        if self.range.len() % self.part_size_bytes != 0 {
+            n_parts += 1;
+        }
+
+        n_parts
+    }
+
+    /// The range of the part with the given index
+    ///
+    /// Returns `None` if there is no such part.
+    pub fn part(&self, part_index: u64) -> Option<InclusiveRange> {
+        if part_index >= self.n_parts() {
+            return None;
+        }
+
+        let start = self.range.start() + part_index * self.part_size_bytes;
+        let end_incl = start
+            .saturating_add(self.part_size_bytes - 1)
+            .min(self.range.end_incl());
+        Some(InclusiveRange(start, end_incl))
+    }
+
+    /// The ranges of the parts in ascending order
+    pub fn iter(&self) -> impl Iterator<Item = InclusiveRange> {
+        self.range.split_into_parts(self.part_size_bytes)
+    }
+
+    /// Collects the ranges of the parts in ascending order
+    pub fn to_vec(&self) -> Vec<InclusiveRange> {
+        self.iter().collect()
+    }
+}
+
 impl From<InclusiveRange> for RangeInclusive<u64> {
     fn from(ir: InclusiveRange) -> Self {
         ir.to_std_range()
@@ -414,6 +549,70 @@ impl From<OffsetRange> for DownloadRange {
     }
 }
 
+#[test]
+fn split_into_parts() {
+    let parts: Vec<_> = InclusiveRange(0, 0).split_into_parts(1).collect();
+    assert_eq!(parts, vec![InclusiveRange(0, 0)]);
+
+    let parts: Vec<_> = InclusiveRange(0, 9).split_into_parts(5).collect();
+    assert_eq!(parts, vec![InclusiveRange(0, 4), InclusiveRange(5, 9)]);
+
+    let parts: Vec<_> = InclusiveRange(3, 9).split_into_parts(3).collect();
+    assert_eq!(
+        parts,
+        vec![
+            InclusiveRange(3, 5),
+            InclusiveRange(6, 8),
+            InclusiveRange(9, 9)
+        ]
+    );
+
+    let parts: Vec<_> = InclusiveRange(3, 9).split_into_parts(100).collect();
+    assert_eq!(parts, vec![InclusiveRange(3, 9)]);
+}
+
+#[test]
+fn split_into_parts_up_to_u64_max() {
+    let parts: Vec<_> = InclusiveRange(u64::MAX - 9, u64::MAX)
+        .split_into_parts(4)
+        .collect();
+    assert_eq!(
+        parts,
+        vec![
+            InclusiveRange(u64::MAX - 9, u64::MAX - 6),
+            InclusiveRange(u64::MAX - 5, u64::MAX - 2),
+            InclusiveRange(u64::MAX - 1, u64::MAX)
+        ]
+    );
+
+    let parts: Vec<_> = InclusiveRange(10, u64::MAX)
+        .split_into_parts(u64::MAX)
+        .collect();
+    assert_eq!(parts, vec![InclusiveRange(10, u64::MAX)]);
+
+    let parts: Vec<_> = InclusiveRange(u64::MAX, u64::MAX)
+        .split_into_parts(1)
+        .collect();
+    assert_eq!(parts, vec![InclusiveRange(u64::MAX, u64::MAX)]);
+
+    let boundaries = PartBoundaries::new(InclusiveRange(10, u64::MAX - 1), u64::MAX);
+    assert_eq!(boundaries.part(0), Some(InclusiveRange(10, u64::MAX - 1)));
+}
+
+#[test]
+fn part_boundaries_match_split_into_parts() {
+    for part_size in 1..12 {
+        let boundaries = PartBoundaries::new(InclusiveRange(3, 13), part_size);
+        let parts = boundaries.to_vec();
+
+        assert_eq!(parts.len() as u64, boundaries.n_parts());
+        for (part_index, part) in parts.iter().enumerate() {
+            assert_eq!(boundaries.part(part_index as u64), Some(*part));
+        }
+        assert_eq!(boundaries.part(boundaries.n_parts()), None);
+    }
+}
+
 #[test]
 fn range_full() {
     let result: DownloadRange = (..).into();
@@ -1,16 +1,19 @@
 /// Downloading API with shared request instrumentation
 use std::sync::Arc;
 
-use futures::future::BoxFuture;
+use bytes::Bytes;
+use futures::{future::BoxFuture, stream::BoxStream};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 
 use crate::{
     condow_client::CondowClient,
+    config::RetryBudget,
     errors::CondowError,
     machinery,
     reader::RandomAccessReader,
     reporter::{CompositeReporter, NoReporting, Reporter, ReporterFactory},
-    streams::{ChunkStream, PartStream},
-    Condow, DownloadRange, Downloads, GetSizeMode, StreamWithReport,
+    streams::{BytesHint, BytesStream, ChunkStream, PartStream},
+    Condow, DownloadRange, Downloads, GetSizeMode, OpenRange, StreamWithReport,
 };
 
 /// A downloading API for instrumented downloading.
@@ -30,6 +33,8 @@ pub struct DownloadSession<C: CondowClient, RF: ReporterFactory = NoReporting> {
     get_size_mode: GetSizeMode,
     condow: Condow<C>,
     reporter_factory: Arc<RF>,
+    /// Limits the number of active downloads if set
+    download_permits: Option<Arc<Semaphore>>,
 }
 
 impl<C: CondowClient, RF: ReporterFactory> DownloadSession<C, RF> {
@@ -38,6 +43,7 @@ impl<C: CondowClient, RF: ReporterFactory> DownloadSession<C, RF> {
             condow,
             get_size_mode: GetSizeMode::default(),
             reporter_factory: rep_fac,
+            download_permits: None,
         }
     }
 
@@ -47,6 +53,48 @@ impl<C: CondowClient, RF: ReporterFactory> DownloadSession<C, RF> {
         self
     }
 
+    /// Limit the number of downloads which are active at the same time.
+    ///
+    /// Each download acquires a permit before it starts and holds it until
+    /// its stream ended, failed or was dropped. Further downloads wait until
+    /// a permit becomes available. The limit is shared with all clones of
+    /// this [DownloadSession].
+    ///
+    /// This is independent of
+    /// [Config::max_concurrency](crate::config::Config::max_concurrency) which
+    /// limits the number of concurrent requests of a single download. At most
+    /// `max_concurrent_downloads` times `max_concurrency` requests are made
+    /// concurrently.
+    ///
+    /// # Panics
+    ///
+    /// If `max_concurrent_downloads` is 0.
+    pub fn with_max_concurrent_downloads(mut self, max_concurrent_downloads: usize) -> Self {
+        assert!(
+            max_concurrent_downloads > 0,
+            "'max_concurrent_downloads' must not be 0"
+        );
+        self.download_permits = Some(Arc::new(Semaphore::new(max_concurrent_downloads)));
+        self
+    }
+
+    /// Limit the retries of all downloads of this session with a [RetryBudget].
+    ///
+    /// Once the budget is exhausted, requests which failed are not retried
+    /// and broken streams are not resumed until tokens were refilled. The
+    /// budget is shared with all clones of this [DownloadSession] and with
+    /// anyone else holding a clone of the [RetryBudget].
+    ///
+    /// This has no effect if retries are disabled in the
+    /// [Config](crate::config::Config) of the [Condow].
+    pub fn with_retry_budget(mut self, budget: RetryBudget) -> Self {
+        if let Some(retries) = self.condow.config.retries.as_mut() {
+            retries.budget = Some(budget);
+            self.condow.client = self.condow.client.with_retries(Some(retries.clone()));
+        }
+        self
+    }
+
     /// Returns a reference to the [ReporterFactory].
     pub fn reporter_factory(&self) -> &RF {
         self.reporter_factory.as_ref()
@@ -60,7 +108,7 @@ impl<C: CondowClient, RF: ReporterFactory> DownloadSession<C, RF> {
     /// within the BLOB/range downloaded.
     pub async fn download<R: Into<DownloadRange>>(
         &self,
-        location: url::Url,
+        location: &url::Url,
         range: R,
     ) -> Result<PartStream<ChunkStream>, CondowError> {
         self.download_chunks(location, range)
@@ -78,13 +126,39 @@ impl<C: CondowClient, RF: ReporterFactory> DownloadSession<C, RF> {
     /// downloaded sequentially.
     pub async fn download_chunks<R: Into<DownloadRange>>(
         &self,
-        location: url::Url,
+        location: &url::Url,
         range: R,
     ) -> Result<ChunkStream, CondowError> {
-        let reporter = self.reporter_factory.make(&location);
-        machinery::download(&self.condow, location, range, self.get_size_mode, reporter)
-            .await
-            .map(|o| o.stream)
+        let range = range.into();
+        let reporter = self.reporter_factory.make_for(location, &range);
+        let permit = self.acquire_download_permit().await?;
+        let mut stream = machinery::download(
+            &self.condow,
+            location.clone(),
+            range,
+            self.get_size_mode,
+            reporter,
+        )
+        .await?
+        .stream;
+        if let Some(permit) = permit {
+            stream.set_download_permit(permit);
+        }
+        Ok(stream)
+    }
+
+    /// Download the BLOB/range as a stream of [Bytes].
+    ///
+    /// Whether the bytes are ordered depends on
+    /// [Config::default_ordered](crate::config::Config::default_ordered).
+    pub async fn download_bytes<R: Into<DownloadRange>>(
+        &self,
+        location: &url::Url,
+        range: R,
+    ) -> Result<BoxStream<'static, Result<Bytes, CondowError>>, CondowError> {
+        self.download_chunks(location, range)
+            .await?
+            .try_into_bytes_stream(self.condow.config.default_ordered.into_inner())
     }
 
     /// Download the BLOB/range and report events.
@@ -95,10 +169,11 @@ impl<C: CondowClient, RF: ReporterFactory> DownloadSession<C, RF> {
     /// within the BLOB/range downloaded.
     pub async fn download_rep<R: Into<DownloadRange>>(
         &self,
-        location: url::Url,
+        location: &url::Url,
         range: R,
     ) -> Result<StreamWithReport<PartStream<ChunkStream>, RF::ReporterType>, CondowError> {
-        let reporter = self.reporter_factory.make(&location);
+        let range = range.into();
+        let reporter = self.reporter_factory.make_for(location, &range);
         self.download_wrep(location, range, reporter).await
     }
 
@@ -112,10 +187,11 @@ impl<C: CondowClient, RF: ReporterFactory> DownloadSession<C, RF> {
     /// downloaded sequentially.
     pub async fn download_chunks_rep<R: Into<DownloadRange>>(
         &self,
-        location: url::Url,
+        location: &url::Url,
         range: R,
     ) -> Result<StreamWithReport<ChunkStream, RF::ReporterType>, CondowError> {
-        let reporter = self.reporter_factory.make(&location);
+        let range = range.into();
+        let reporter = self.reporter_factory.make_for(location, &range);
         self.download_chunks_wrep(location, range, reporter).await
     }
 
@@ -129,11 +205,13 @@ impl<C: CondowClient, RF: ReporterFactory> DownloadSession<C, RF> {
     /// within the BLOB/range downloaded.
     pub async fn download_wrep<R: Into<DownloadRange>, RRP: Reporter>(
         &self,
-        location: url::Url,
+        location: &url::Url,
         range: R,
         reporter: RRP,
     ) -> Result<StreamWithReport<PartStream<ChunkStream>, RRP>, CondowError> {
-        let composite = CompositeReporter(self.reporter_factory.make(&location), reporter);
+        let range = range.into();
+        let composite =
+            CompositeReporter(self.reporter_factory.make_for(location, &range), reporter);
         self.download_chunks_wrep(location, range, composite)
             .await?
             .part_stream()
@@ -158,24 +236,53 @@ impl<C: CondowClient, RF: ReporterFactory> DownloadSession<C, RF> {
     /// downloaded sequentially.
     pub async fn download_chunks_wrep<R: Into<DownloadRange>, RPP: Reporter>(
         &self,
-        location: url::Url,
+        location: &url::Url,
         range: R,
         reporter: RPP,
     ) -> Result<StreamWithReport<ChunkStream, RPP>, CondowError> {
-        let composite = CompositeReporter(self.reporter_factory.make(&location), reporter);
-        machinery::download(&self.condow, location, range, self.get_size_mode, composite)
-            .await
-            .map(|sr| {
-                let StreamWithReport { stream, reporter } = sr;
-                StreamWithReport {
-                    stream,
-                    reporter: reporter.1,
-                }
-            })
+        let range = range.into();
+        let composite =
+            CompositeReporter(self.reporter_factory.make_for(location, &range), reporter);
+        let permit = self.acquire_download_permit().await?;
+        machinery::download(
+            &self.condow,
+            location.clone(),
+            range,
+            self.get_size_mode,
+            composite,
+        )
+        .await
+        .map(|sr| {
+            let StreamWithReport {
+                mut stream,
+                reporter,
+            } = sr;
+            if let Some(permit) = permit {
+                stream.set_download_permit(permit);
+            }
+            StreamWithReport {
+                stream,
+                reporter: reporter.1,
+            }
+        })
+    }
+
+    async fn acquire_download_permit(&self) -> Result<Option<OwnedSemaphorePermit>, CondowError> {
+        if let Some(permits) = self.download_permits.as_ref() {
+            Arc::clone(permits)
+                .acquire_owned()
+                .await
+                .map(Some)
+                .map_err(|err| {
+                    CondowError::new_other("failed to acquire a download permit").with_source(err)
+                })
+        } else {
+            Ok(None)
+        }
     }
 
     /// Get the size of a file at the BLOB at location
-    pub async fn get_size(&self, location: url::Url) -> Result<u64, CondowError> {
+    pub async fn get_size(&self, location: &url::Url) -> Result<u64, CondowError> {
         self.condow.get_size(location).await
     }
 
@@ -184,12 +291,12 @@ impl<C: CondowClient, RF: ReporterFactory> DownloadSession<C, RF> {
     /// The reader will use the configured [ReporterFactory].
     pub async fn reader(
         &self,
-        location: url::Url,
+        location: &url::Url,
     ) -> Result<RandomAccessReader<Self>, CondowError> {
-        let length = self.get_size(location.clone()).await?;
+        let length = self.get_size(location).await?;
         Ok(RandomAccessReader::new_with_length(
             self.clone(),
-            location,
+            location.clone(),
             length,
         ))
     }
@@ -197,10 +304,19 @@ impl<C: CondowClient, RF: ReporterFactory> DownloadSession<C, RF> {
     /// Creates a [RandomAccessReader] for the given location
     ///
     /// The reader will use the configured [ReporterFactory].
-    pub fn reader_with_length(&self, location: url::Url, length: u64) -> RandomAccessReader<Self> {
+    pub fn reader_with_length(&self, location: &url::Url, length: u64) -> RandomAccessReader<Self> {
         let mut me = self.clone();
         me.get_size_mode = GetSizeMode::Required;
-        RandomAccessReader::new_with_length(me, location, length)
+        RandomAccessReader::new_with_length(me, location.clone(), length)
+    }
+
+    /// Creates a [RandomAccessReader] for the given location
+    ///
+    /// The size of the BLOB will not be queried. It will be discovered
+    /// on the first read which downloads the BLOB from the reading position
+    /// to its end sequentially.
+    pub fn reader_streaming(&self, location: &url::Url) -> RandomAccessReader<Self> {
+        RandomAccessReader::new_streaming(self.clone(), location.clone())
     }
 }
 
@@ -210,6 +326,7 @@ impl<C: CondowClient, RF: ReporterFactory> Clone for DownloadSession<C, RF> {
             condow: self.condow.clone(),
             reporter_factory: Arc::clone(&self.reporter_factory),
             get_size_mode: self.get_size_mode,
+            download_permits: self.download_permits.clone(),
         }
     }
 }
@@ -221,7 +338,7 @@ where
 {
     fn download<'a, R: Into<DownloadRange> + Send + Sync + 'static>(
         &'a self,
-        location: url::Url,
+        location: &'a url::Url,
         range: R,
     ) -> BoxFuture<'a, Result<PartStream<ChunkStream>, CondowError>> {
         Box::pin(self.download(location, range))
@@ -229,17 +346,37 @@ where
 
     fn download_chunks<'a, R: Into<DownloadRange> + Send + Sync + 'static>(
         &'a self,
-        location: url::Url,
+        location: &'a url::Url,
         range: R,
     ) -> BoxFuture<'a, Result<ChunkStream, CondowError>> {
         Box::pin(self.download_chunks(location, range))
     }
 
-    fn get_size<'a>(&'a self, location: url::Url) -> BoxFuture<'a, Result<u64, CondowError>> {
+    fn get_size<'a>(&'a self, location: &'a url::Url) -> BoxFuture<'a, Result<u64, CondowError>> {
         Box::pin(self.get_size(location))
     }
 
-    fn reader_with_length(&self, location: url::Url, length: u64) -> RandomAccessReader<Self> {
+    fn download_from_sequential<'a>(
+        &'a self,
+        location: &'a url::Url,
+        start: u64,
+    ) -> BoxFuture<'a, Result<(BytesStream, BytesHint), CondowError>> {
+        let reporter = self
+            .reporter_factory
+            .make_for(location, &DownloadRange::Open(OpenRange::From(start)));
+        Box::pin(machinery::download_from_sequential(
+            &self.condow,
+            location.clone(),
+            start,
+            reporter,
+        ))
+    }
+
+    fn reader_with_length(&self, location: &url::Url, length: u64) -> RandomAccessReader<Self> {
         DownloadSession::reader_with_length(self, location, length)
     }
+
+    fn reader_streaming(&self, location: &url::Url) -> RandomAccessReader<Self> {
+        DownloadSession::reader_streaming(self, location)
+    }
 }
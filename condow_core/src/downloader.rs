@@ -1,16 +1,28 @@
 /// Downloading API with optional per request instrumentation
-use std::sync::Arc;
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
 
-use futures::future::BoxFuture;
+use bytes::Bytes;
+use futures::{
+    channel::mpsc::{self, UnboundedSender},
+    future::BoxFuture,
+    stream::BoxStream,
+    FutureExt, Stream, StreamExt, TryFutureExt,
+};
 
 use crate::{
     condow_client::CondowClient,
     errors::CondowError,
     machinery,
     reader::RandomAccessReader,
-    reporter::{NoReporting, Reporter, ReporterFactory},
-    streams::{ChunkStream, PartStream},
-    Condow, DownloadRange, Downloads, GetSizeMode, StreamWithReport,
+    reporter::{CompositeReporter, NoReporting, Reporter, ReporterFactory},
+    streams::{BytesHint, BytesStream, ChunkStream, PartStream},
+    Condow, DownloadRange, Downloads, GetSizeMode, InclusiveRange, StreamWithReport,
 };
 
 /// A downloading API.
@@ -36,6 +48,7 @@ pub struct Downloader<C: CondowClient, RF: ReporterFactory = NoReporting> {
     get_size_mode: GetSizeMode,
     condow: Condow<C>,
     reporter_factory: Arc<RF>,
+    bytes_counter: BytesCounter,
 }
 
 impl<C: CondowClient> Downloader<C, NoReporting> {
@@ -54,6 +67,7 @@ impl<C: CondowClient, RF: ReporterFactory> Downloader<C, RF> {
             condow,
             get_size_mode: GetSizeMode::default(),
             reporter_factory: rep_fac,
+            bytes_counter: BytesCounter::default(),
         }
     }
 
@@ -63,6 +77,24 @@ impl<C: CondowClient, RF: ReporterFactory> Downloader<C, RF> {
         self
     }
 
+    /// Never query the size of a BLOB for closed ranges
+    ///
+    /// This is a shortcut for setting [GetSizeMode::Required]. The upper bound
+    /// of a closed range will not be adjusted to the size of the BLOB.
+    pub fn no_size_query(self) -> Self {
+        self.get_size_mode(GetSizeMode::Required)
+    }
+
+    /// Set a counter which is incremented by the number of bytes received
+    ///
+    /// The counter is incremented for all downloads made with this [Downloader]
+    /// and its clones as soon as the bytes arrive. This does not require
+    /// a [Reporter].
+    pub fn with_bytes_counter(mut self, counter: Arc<AtomicU64>) -> Self {
+        self.bytes_counter = BytesCounter(Some(counter));
+        self
+    }
+
     /// Set or replace the [ReporterFactory] in a builder style
     pub fn with_reporting<RRF: ReporterFactory>(self, rep_fac: RRF) -> Downloader<C, RRF> {
         self.with_reporting_arc(Arc::new(rep_fac))
@@ -73,6 +105,7 @@ impl<C: CondowClient, RF: ReporterFactory> Downloader<C, RF> {
         let Downloader {
             get_size_mode,
             condow,
+            bytes_counter,
             ..
         } = self;
 
@@ -80,6 +113,7 @@ impl<C: CondowClient, RF: ReporterFactory> Downloader<C, RF> {
             condow,
             get_size_mode,
             reporter_factory: rep_fac,
+            bytes_counter,
         }
     }
 
@@ -89,7 +123,7 @@ impl<C: CondowClient, RF: ReporterFactory> Downloader<C, RF> {
     /// within the BLOB/range downloaded.
     pub async fn download<R: Into<DownloadRange>>(
         &self,
-        location: url::Url,
+        location: &url::Url,
         range: R,
     ) -> Result<PartStream<ChunkStream>, CondowError> {
         self.download_chunks(location, range)
@@ -105,20 +139,34 @@ impl<C: CondowClient, RF: ReporterFactory> Downloader<C, RF> {
     /// downloaded sequentially.
     pub async fn download_chunks<R: Into<DownloadRange>>(
         &self,
-        location: url::Url,
+        location: &url::Url,
         range: R,
     ) -> Result<ChunkStream, CondowError> {
         machinery::download(
             &self.condow,
-            location,
+            location.clone(),
             range,
             self.get_size_mode,
-            NoReporting,
+            self.bytes_counter.clone(),
         )
         .await
         .map(|o| o.stream)
     }
 
+    /// Download the BLOB/range as a stream of [Bytes].
+    ///
+    /// Whether the bytes are ordered depends on
+    /// [Config::default_ordered](crate::config::Config::default_ordered).
+    pub async fn download_bytes<R: Into<DownloadRange>>(
+        &self,
+        location: &url::Url,
+        range: R,
+    ) -> Result<BoxStream<'static, Result<Bytes, CondowError>>, CondowError> {
+        self.download_chunks(location, range)
+            .await?
+            .try_into_bytes_stream(self.condow.config.default_ordered.into_inner())
+    }
+
     /// Download the BLOB/range and report events.
     ///
     /// The returned [Reporter] is created by the [ReporterFactory] when constructed.
@@ -127,10 +175,11 @@ impl<C: CondowClient, RF: ReporterFactory> Downloader<C, RF> {
     /// within the BLOB/range downloaded.
     pub async fn download_rep<R: Into<DownloadRange>>(
         &self,
-        location: url::Url,
+        location: &url::Url,
         range: R,
     ) -> Result<StreamWithReport<PartStream<ChunkStream>, RF::ReporterType>, CondowError> {
-        let reporter = self.reporter_factory.make(&location);
+        let range = range.into();
+        let reporter = self.reporter_factory.make_for(location, &range);
         self.download_wrep(location, range, reporter).await
     }
 
@@ -144,10 +193,11 @@ impl<C: CondowClient, RF: ReporterFactory> Downloader<C, RF> {
     /// downloaded sequentially.
     pub async fn download_chunks_rep<R: Into<DownloadRange>>(
         &self,
-        location: url::Url,
+        location: &url::Url,
         range: R,
     ) -> Result<StreamWithReport<ChunkStream, RF::ReporterType>, CondowError> {
-        let reporter = self.reporter_factory.make(&location);
+        let range = range.into();
+        let reporter = self.reporter_factory.make_for(location, &range);
         self.download_chunks_wrep(location, range, reporter).await
     }
 
@@ -159,7 +209,7 @@ impl<C: CondowClient, RF: ReporterFactory> Downloader<C, RF> {
     /// within the BLOB/range downloaded.
     pub async fn download_wrep<R: Into<DownloadRange>, RP: Reporter>(
         &self,
-        location: url::Url,
+        location: &url::Url,
         range: R,
         reporter: RP,
     ) -> Result<StreamWithReport<PartStream<ChunkStream>, RP>, CondowError> {
@@ -178,15 +228,54 @@ impl<C: CondowClient, RF: ReporterFactory> Downloader<C, RF> {
     /// downloaded sequentially.
     pub async fn download_chunks_wrep<R: Into<DownloadRange>, RP: Reporter>(
         &self,
-        location: url::Url,
+        location: &url::Url,
         range: R,
         reporter: RP,
     ) -> Result<StreamWithReport<ChunkStream, RP>, CondowError> {
-        machinery::download(&self.condow, location, range, self.get_size_mode, reporter).await
+        let composite = CompositeReporter(reporter, self.bytes_counter.clone());
+        machinery::download(
+            &self.condow,
+            location.clone(),
+            range,
+            self.get_size_mode,
+            composite,
+        )
+        .await
+        .map(|sr| {
+            let StreamWithReport { stream, reporter } = sr;
+            StreamWithReport {
+                stream,
+                reporter: reporter.0,
+            }
+        })
+    }
+
+    /// Download the BLOB/range along with a stream of its progress in percent
+    ///
+    /// The progress is calculated from the bytes received so far and the
+    /// length of the range actually downloaded. A percentage is only yielded
+    /// if it changed, so the values are strictly increasing. The stream yields
+    /// 100 once all bytes were received and ends with the download.
+    ///
+    /// The parts and the chunks streamed have the same ordering as
+    /// within the BLOB/range downloaded.
+    pub async fn download_with_percent<R: Into<DownloadRange>>(
+        &self,
+        location: &url::Url,
+        range: R,
+    ) -> Result<(PartStream<ChunkStream>, impl Stream<Item = u8>), CondowError> {
+        let (sender, receiver) = mpsc::unbounded();
+        let reporter = PercentReporter::new(sender);
+        let stream = self
+            .download_wrep(location, range, reporter)
+            .await?
+            .into_stream();
+
+        Ok((stream, receiver))
     }
 
     /// Get the size of a BLOB at location
-    pub async fn get_size(&self, location: url::Url) -> Result<u64, CondowError> {
+    pub async fn get_size(&self, location: &url::Url) -> Result<u64, CondowError> {
         self.condow.get_size(location).await
     }
 
@@ -195,12 +284,12 @@ impl<C: CondowClient, RF: ReporterFactory> Downloader<C, RF> {
     /// The reader will use the configured [ReporterFactory].
     pub async fn reader(
         &self,
-        location: url::Url,
+        location: &url::Url,
     ) -> Result<RandomAccessReader<Self>, CondowError> {
-        let length = self.get_size(location.clone()).await?;
+        let length = self.get_size(location).await?;
         Ok(RandomAccessReader::new_with_length(
             self.clone(),
-            location,
+            location.clone(),
             length,
         ))
     }
@@ -208,10 +297,19 @@ impl<C: CondowClient, RF: ReporterFactory> Downloader<C, RF> {
     /// Creates a [RandomAccessReader] for the given location
     ///
     /// The reader will use the configured [ReporterFactory].
-    pub fn reader_with_length(&self, location: url::Url, length: u64) -> RandomAccessReader<Self> {
+    pub fn reader_with_length(&self, location: &url::Url, length: u64) -> RandomAccessReader<Self> {
         let mut me = self.clone();
         me.get_size_mode = GetSizeMode::Required;
-        RandomAccessReader::new_with_length(me, location, length)
+        RandomAccessReader::new_with_length(me, location.clone(), length)
+    }
+
+    /// Creates a [RandomAccessReader] for the given location
+    ///
+    /// The size of the BLOB will not be queried. It will be discovered
+    /// on the first read which downloads the BLOB from the reading position
+    /// to its end sequentially.
+    pub fn reader_streaming(&self, location: &url::Url) -> RandomAccessReader<Self> {
+        RandomAccessReader::new_streaming(self.clone(), location.clone())
     }
 }
 
@@ -221,6 +319,7 @@ impl<C: CondowClient, RF: ReporterFactory> Clone for Downloader<C, RF> {
             condow: self.condow.clone(),
             reporter_factory: Arc::clone(&self.reporter_factory),
             get_size_mode: self.get_size_mode,
+            bytes_counter: self.bytes_counter.clone(),
         }
     }
 }
@@ -232,7 +331,7 @@ where
 {
     fn download<'a, R: Into<DownloadRange> + Send + Sync + 'static>(
         &'a self,
-        location: url::Url,
+        location: &'a url::Url,
         range: R,
     ) -> BoxFuture<'a, Result<PartStream<ChunkStream>, CondowError>> {
         Box::pin(self.download(location, range))
@@ -240,17 +339,114 @@ where
 
     fn download_chunks<'a, R: Into<DownloadRange> + Send + Sync + 'static>(
         &'a self,
-        location: url::Url,
+        location: &'a url::Url,
         range: R,
     ) -> BoxFuture<'a, Result<ChunkStream, CondowError>> {
         Box::pin(self.download_chunks(location, range))
     }
 
-    fn get_size<'a>(&'a self, location: url::Url) -> BoxFuture<'a, Result<u64, CondowError>> {
+    fn get_size<'a>(&'a self, location: &'a url::Url) -> BoxFuture<'a, Result<u64, CondowError>> {
         Box::pin(self.get_size(location))
     }
 
-    fn reader_with_length(&self, location: url::Url, length: u64) -> RandomAccessReader<Self> {
+    fn download_from_sequential<'a>(
+        &'a self,
+        location: &'a url::Url,
+        start: u64,
+    ) -> BoxFuture<'a, Result<(BytesStream, BytesHint), CondowError>> {
+        let bytes_counter = self.bytes_counter.clone();
+        machinery::download_from_sequential(&self.condow, location.clone(), start, NoReporting)
+            .map_ok(move |(stream, bytes_hint)| {
+                let stream = stream
+                    .inspect(move |next| {
+                        if let Ok(bytes) = next {
+                            bytes_counter.add(bytes.len() as u64);
+                        }
+                    })
+                    .boxed();
+                (stream as BytesStream, bytes_hint)
+            })
+            .boxed()
+    }
+
+    fn reader_with_length(&self, location: &url::Url, length: u64) -> RandomAccessReader<Self> {
         Downloader::reader_with_length(self, location, length)
     }
+
+    fn reader_streaming(&self, location: &url::Url) -> RandomAccessReader<Self> {
+        Downloader::reader_streaming(self, location)
+    }
+}
+
+/// Counts the bytes received if a counter was set
+#[derive(Default, Clone)]
+struct BytesCounter(Option<Arc<AtomicU64>>);
+
+impl BytesCounter {
+    fn add(&self, n_bytes: u64) {
+        if let Some(counter) = &self.0 {
+            counter.fetch_add(n_bytes, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Reporter for BytesCounter {
+    fn chunk_completed(
+        &self,
+        _part_index: u64,
+        _chunk_index: usize,
+        n_bytes: usize,
+        _time: Duration,
+    ) {
+        self.add(n_bytes as u64);
+    }
+}
+
+/// Sends the progress of a download in percent whenever it changes
+#[derive(Clone)]
+struct PercentReporter(Arc<Mutex<PercentState>>);
+
+struct PercentState {
+    total_bytes: u64,
+    bytes_received: u64,
+    percent: u8,
+    sender: UnboundedSender<u8>,
+}
+
+impl PercentReporter {
+    fn new(sender: UnboundedSender<u8>) -> Self {
+        Self(Arc::new(Mutex::new(PercentState {
+            total_bytes: 0,
+            bytes_received: 0,
+            percent: 0,
+            sender,
+        })))
+    }
+}
+
+impl Reporter for PercentReporter {
+    fn effective_range(&self, range: InclusiveRange) {
+        self.0.lock().unwrap().total_bytes = range.len();
+    }
+
+    fn chunk_completed(
+        &self,
+        _part_index: u64,
+        _chunk_index: usize,
+        n_bytes: usize,
+        _time: Duration,
+    ) {
+        let mut state = self.0.lock().unwrap();
+        if state.total_bytes == 0 {
+            return;
+        }
+
+        state.bytes_received += n_bytes as u64;
+        let percent = (state.bytes_received.min(state.total_bytes) * 100 / state.total_bytes) as u8;
+        if percent > state.percent {
+            state.percent = percent;
+            // The consumer is not interested in the progress anymore
+            let _ = state.sender.unbounded_send(percent);
+        }
+    }
 }
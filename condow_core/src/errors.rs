@@ -42,6 +42,22 @@ impl CondowError {
         Self::new(msg, CondowErrorKind::Io)
     }
 
+    pub fn new_object_not_ready<T: Into<String>>(msg: T) -> Self {
+        Self::new(msg, CondowErrorKind::ObjectNotReady)
+    }
+
+    pub fn new_deserialize<T: Into<String>>(msg: T) -> Self {
+        Self::new(msg, CondowErrorKind::Deserialize)
+    }
+
+    pub fn new_timeout<T: Into<String>>(msg: T) -> Self {
+        Self::new(msg, CondowErrorKind::Timeout)
+    }
+
+    pub fn new_cancelled<T: Into<String>>(msg: T) -> Self {
+        Self::new(msg, CondowErrorKind::Cancelled)
+    }
+
     pub fn new_other<T: Into<String>>(msg: T) -> Self {
         Self::new(msg, CondowErrorKind::Other)
     }
@@ -55,6 +71,35 @@ impl CondowError {
         &self.msg
     }
 
+    /// Returns the first error of type `T` in the chain of sources
+    ///
+    /// This gives access to the native error of a client
+    /// if the client attached it as the source.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use condow_core::errors::CondowError;
+    /// let io_err = std::io::Error::new(std::io::ErrorKind::Other, "native");
+    /// let err = CondowError::new_io("failed").with_source(io_err);
+    ///
+    /// let native = err.downcast_ref::<std::io::Error>().unwrap();
+    /// assert_eq!(native.to_string(), "native");
+    /// assert!(err.downcast_ref::<std::fmt::Error>().is_none());
+    ///
+    /// let wrapped = CondowError::new_other("wrapped").with_source(err);
+    /// assert!(wrapped.downcast_ref::<std::io::Error>().is_some());
+    /// ```
+    pub fn downcast_ref<T>(&self) -> Option<&T>
+    where
+        T: std::error::Error + Send + Sync + 'static,
+    {
+        self.source
+            .as_ref()?
+            .chain()
+            .find_map(|err| err.downcast_ref::<T>())
+    }
+
     pub fn kind(&self) -> CondowErrorKind {
         self.kind
     }
@@ -71,7 +116,10 @@ impl fmt::Display for CondowError {
 }
 
 /// Specifies the kind of a [CondowError]
+///
+/// More kinds might be added in the future.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum CondowErrorKind {
     /// An inavlid range was encountered.
     ///
@@ -96,6 +144,28 @@ pub enum CondowErrorKind {
     ///
     /// Errors with this kind are **retryable**
     Io,
+    /// The BLOB exists but can not be downloaded in its current state
+    ///
+    /// E.g. an archived object which has to be restored first.
+    ///
+    /// Errors with this kind are **not retryable**
+    ObjectNotReady,
+    /// The downloaded bytes could not be deserialized
+    ///
+    /// Errors with this kind are **not retryable**
+    Deserialize,
+    /// A request did not complete in time
+    ///
+    /// Errors with this kind are **retryable**
+    Timeout,
+    /// The download was cancelled on purpose
+    ///
+    /// E.g. because the consumer dropped the stream. Such an error
+    /// is usually not seen by anyone but tells a cancellation apart
+    /// from a failure.
+    ///
+    /// Errors with this kind are **not retryable**
+    Cancelled,
     /// Anything else which does not fall under one of the other categories
     ///
     /// Errors with this kind are **not retryable**
@@ -112,6 +182,10 @@ impl CondowErrorKind {
             AccessDenied => false,
             Remote => true,
             Io => true,
+            ObjectNotReady => false,
+            Deserialize => false,
+            Timeout => true,
+            Cancelled => false,
             Other => false,
         }
     }
@@ -135,6 +209,29 @@ impl From<IoError> for CondowError {
     }
 }
 
+/// Keeps the [CondowError] as the inner error of the [std::io::Error]
+impl From<CondowError> for std::io::Error {
+    fn from(err: CondowError) -> Self {
+        use std::io::ErrorKind;
+
+        let kind = match err.kind() {
+            CondowErrorKind::InvalidRange => ErrorKind::InvalidInput,
+            CondowErrorKind::NotFound => ErrorKind::NotFound,
+            CondowErrorKind::AccessDenied => ErrorKind::PermissionDenied,
+            CondowErrorKind::Deserialize => ErrorKind::InvalidData,
+            CondowErrorKind::Timeout => ErrorKind::TimedOut,
+            // Not `Interrupted` since readers retry on it
+            CondowErrorKind::Cancelled => ErrorKind::ConnectionAborted,
+            CondowErrorKind::Remote
+            | CondowErrorKind::Io
+            | CondowErrorKind::ObjectNotReady
+            | CondowErrorKind::Other => ErrorKind::Other,
+        };
+
+        std::io::Error::new(kind, err)
+    }
+}
+
 #[derive(Error, Debug)]
 #[error("io error: {0}")]
 pub struct IoError(pub String);
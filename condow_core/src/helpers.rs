@@ -1,5 +1,73 @@
+use std::future::Future;
+
+use tokio::task::JoinHandle;
+
+use crate::errors::CondowError;
+
 pub(crate) const CONDOW_PREFIX: &str = "CONDOW";
 
+/// Spawns a new task on the runtime
+///
+/// With the `tracing` feature enabled the task is instrumented with
+/// the span which is current when spawning. Otherwise the task is
+/// simply spawned.
+pub(crate) fn spawn<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    #[cfg(feature = "tracing")]
+    {
+        use tracing::Instrument;
+        tokio::spawn(future.instrument(tracing::Span::current()))
+    }
+    #[cfg(not(feature = "tracing"))]
+    {
+        tokio::spawn(future)
+    }
+}
+
+/// Runs blocking code (e.g. file IO) on the blocking thread pool of the runtime
+///
+/// With the `tracing` feature enabled `f` runs within the span which
+/// is current when spawning. Fails if `f` panicked.
+pub(crate) async fn spawn_blocking<F, T>(f: F) -> Result<T, CondowError>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    #[cfg(feature = "tracing")]
+    let f = {
+        let span = tracing::Span::current();
+        move || span.in_scope(f)
+    };
+
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|err| CondowError::new_other("blocking task failed").with_source(err))
+}
+
+/// Casts a `u64` to a `usize` without truncating
+///
+/// Fails with [CondowErrorKind::Other](crate::errors::CondowErrorKind::Other)
+/// if the value does not fit. On 32 bit targets this is the case for anything
+/// of 4 GiB or more, e.g. the length of a 5 GiB BLOB.
+pub(crate) fn u64_to_usize(value: u64) -> Result<usize, CondowError> {
+    usize::try_from(value)
+        .map_err(|_| CondowError::new_other("usize overflow while casting from u64"))
+}
+
+/// Returns the start and the exclusive end of `len` bytes at `offset` within a buffer
+///
+/// Fails like [u64_to_usize] if the start or the end do not fit into a `usize`.
+pub(crate) fn buffer_range(offset: u64, len: usize) -> Result<(usize, usize), CondowError> {
+    let start = u64_to_usize(offset)?;
+    let end_excl = start.checked_add(len).ok_or_else(|| {
+        CondowError::new_other("usize overflow while calculating the end of a buffer range")
+    })?;
+    Ok((start, end_excl))
+}
+
 macro_rules! env_funs {
     ($var:expr) => {
         #[doc="The default name of the environment variable for this type.\n\n"]
@@ -420,3 +488,46 @@ macro_rules! env_ctors {
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{config::Gibi, errors::CondowErrorKind};
+
+    use super::{buffer_range, u64_to_usize};
+
+    #[test]
+    fn usize_max_can_be_cast() {
+        assert_eq!(u64_to_usize(usize::MAX as u64).unwrap(), usize::MAX);
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn five_gib_can_be_cast_on_64_bit() {
+        let five_gib = Gibi(5).value();
+
+        assert_eq!(u64_to_usize(five_gib).unwrap() as u64, five_gib);
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "32")]
+    fn five_gib_can_not_be_cast_on_32_bit() {
+        let err = u64_to_usize(Gibi(5).value()).unwrap_err();
+
+        assert_eq!(err.kind(), CondowErrorKind::Other);
+        assert_eq!(err.msg(), "usize overflow while casting from u64");
+    }
+
+    #[test]
+    fn buffer_range_returns_start_and_end_excl() {
+        assert_eq!(buffer_range(10, 5).unwrap(), (10, 15));
+        assert_eq!(buffer_range(10, 0).unwrap(), (10, 10));
+    }
+
+    #[test]
+    fn buffer_range_fails_if_the_end_overflows() {
+        let err = buffer_range(usize::MAX as u64, 1).unwrap_err();
+
+        assert_eq!(err.kind(), CondowErrorKind::Other);
+        assert!(err.msg().starts_with("usize overflow"), "{}", err);
+    }
+}
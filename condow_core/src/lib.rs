@@ -14,6 +14,7 @@
 //! use it, use one of the implementation crates:
 //!
 //! * [condow_rusoto] for downloading AWS S3 via the rusoto
+//! * [condow_aws_sdk] for downloading AWS S3 via the AWS SDK for Rust
 //! * [condow_fs] for using async file access via [tokio]
 //!
 //! All that is required to add more "services" is to implement
@@ -32,37 +33,58 @@
 //! their underlying implementation. In this case you should disable retries for either the
 //! client or ConDow itself.
 //!
+//! ## Features
+//!
+//! * `tracing`: Tasks spawned by ConDow are instrumented with the
+//!   [tracing](https://docs.rs/tracing) span which was current when the download
+//!   was started.
+//! * `serde`: Enables `Downloads::download_and_deserialize` and makes a `DownloadPlan`
+//!   serializable to persist it.
+//! * `memmap2`: Enables `Downloads::download_to_mmap` to download into a memory mapped file.
+//! * `futures-io`: Makes `streams::AsyncReadBytes` also implement `futures::AsyncRead`.
+//! * `test-support`: Enables `reporter::RecordingReporter` which records all events of a download.
+//!
 //! [condow_rusoto]:https://docs.rs/condow_rusoto
+//! [condow_aws_sdk]:https://docs.rs/condow_aws_sdk
 //! [condow_fs]:https://docs.rs/condow_fs
 use std::sync::Arc;
 
-use futures::{future::BoxFuture, FutureExt, Stream};
+use bytes::Bytes;
+use futures::{future::BoxFuture, stream::BoxStream, FutureExt, Stream, StreamExt, TryStreamExt};
 
-use condow_client::CondowClient;
+use condow_client::{ClientFactory, CondowClient};
 use config::{AlwaysGetSize, ClientRetryWrapper, Config};
 use errors::CondowError;
 use reader::RandomAccessReader;
 use reporter::{NoReporting, Reporter, ReporterFactory};
-use streams::{ChunkStream, ChunkStreamItem, PartStream};
+use streams::{
+    BytesHint, BytesStream, ChunkStream, ChunkStreamItem, MultiRangeItem, OrderedBytesStream,
+    OrderingStrategy, PartStream,
+};
 
 #[macro_use]
 pub(crate) mod helpers;
 pub mod condow_client;
 pub mod config;
+mod download_plan;
 mod download_range;
 mod download_session;
 mod downloader;
 pub mod errors;
 pub mod logging;
 mod machinery;
+mod part_files;
 pub mod reader;
 pub mod reporter;
 mod retry;
 pub mod streams;
 
+pub use download_plan::{DownloadPlan, PlannedPart};
 pub use download_range::*;
 pub use download_session::*;
 pub use downloader::*;
+pub use machinery::RangeRequest;
+pub use part_files::PartFile;
 
 #[cfg(test)]
 pub mod test_utils;
@@ -74,7 +96,7 @@ pub trait Downloads {
     /// Returns a stream of [Chunk](streams::Chunk)s.
     fn download<'a, R: Into<DownloadRange> + Send + Sync + 'static>(
         &'a self,
-        location: url::Url,
+        location: &'a url::Url,
         range: R,
     ) -> BoxFuture<'a, Result<PartStream<ChunkStream>, CondowError>>;
 
@@ -83,12 +105,240 @@ pub trait Downloads {
     /// Returns a stream of [Parts](streams::Part)s.
     fn download_chunks<'a, R: Into<DownloadRange> + Send + Sync + 'static>(
         &'a self,
-        location: url::Url,
+        location: &'a url::Url,
         range: R,
     ) -> BoxFuture<'a, Result<ChunkStream, CondowError>>;
 
+    /// Download a BLOB range concurrently
+    ///
+    /// Returns an [OrderedBytesStream] which streams the bytes in the same
+    /// order as within the BLOB/range without reassembling complete parts.
+    fn download_chunks_ordered<'a, R: Into<DownloadRange> + Send + Sync + 'static>(
+        &'a self,
+        location: &'a url::Url,
+        range: R,
+    ) -> BoxFuture<'a, Result<OrderedBytesStream, CondowError>> {
+        let chunk_stream = self.download_chunks(location, range);
+        async move {
+            chunk_stream
+                .await
+                .and_then(OrderedBytesStream::from_chunk_stream)
+        }
+        .boxed()
+    }
+
+    /// Download the complete BLOB and parse it with `parse`
+    ///
+    /// The BLOB is collected into a single buffer which is passed to `parse`.
+    /// An error returned by `parse` fails with
+    /// [CondowErrorKind::Deserialize](errors::CondowErrorKind::Deserialize).
+    fn download_and_parse<'a, T, E, F>(
+        &'a self,
+        location: &'a url::Url,
+        parse: F,
+    ) -> BoxFuture<'a, Result<T, CondowError>>
+    where
+        T: Send + 'a,
+        E: std::error::Error + Send + Sync + 'static,
+        F: FnOnce(&[u8]) -> Result<T, E> + Send + 'a,
+    {
+        let stream = self.download(location, ..);
+        async move {
+            let bytes = stream.await?.into_vec().await?;
+            parse(&bytes).map_err(|err| {
+                CondowError::new_deserialize(format!(
+                    "failed to deserialize the BLOB at '{}': {}",
+                    location, err
+                ))
+                .with_source(err)
+            })
+        }
+        .boxed()
+    }
+
+    /// Download the complete BLOB and deserialize it from JSON
+    ///
+    /// An error while deserializing fails with
+    /// [CondowErrorKind::Deserialize](errors::CondowErrorKind::Deserialize).
+    ///
+    /// Requires the feature `serde`. Use [Downloads::download_and_parse]
+    /// for other formats.
+    #[cfg(feature = "serde")]
+    fn download_and_deserialize<'a, T>(
+        &'a self,
+        location: &'a url::Url,
+    ) -> BoxFuture<'a, Result<T, CondowError>>
+    where
+        T: serde::de::DeserializeOwned + Send + 'a,
+    {
+        self.download_and_parse(location, |bytes: &[u8]| serde_json::from_slice(bytes))
+    }
+
+    /// Download a BLOB range concurrently into a memory mapped file at `path`
+    ///
+    /// The file is created or truncated and resized to the size of the range.
+    /// Each chunk is copied to its offset within the range as soon as it is
+    /// received without any intermediate buffering.
+    ///
+    /// Returns the file after the memory map was flushed and unmapped.
+    /// Opening, mapping and flushing the file run on the blocking thread pool
+    /// of the runtime.
+    ///
+    /// Fails if the size of the range is not known upfront since the file
+    /// must be mapped with its final size. On failure the file is left
+    /// in an incomplete state.
+    ///
+    /// Requires the feature `memmap2`.
+    #[cfg(feature = "memmap2")]
+    fn download_to_mmap<'a, R: Into<DownloadRange> + Send + Sync + 'static>(
+        &'a self,
+        location: &'a url::Url,
+        range: R,
+        path: &'a std::path::Path,
+    ) -> BoxFuture<'a, Result<std::fs::File, CondowError>> {
+        let chunk_stream = self.download_chunks(location, range);
+        async move {
+            let chunk_stream = chunk_stream.await?;
+            let len = chunk_stream.bytes_hint().exact().ok_or_else(|| {
+                CondowError::new_other(
+                    "the size of the range must be known to download into a memory mapped file",
+                )
+            })?;
+
+            let path = path.to_path_buf();
+            let (file, mmap) = helpers::spawn_blocking(move || -> Result<_, CondowError> {
+                let file = std::fs::OpenOptions::new()
+                    .read(true)
+                    .write(true)
+                    .create(true)
+                    .truncate(true)
+                    .open(path)?;
+                file.set_len(len)?;
+
+                if len == 0 {
+                    return Ok((file, None));
+                }
+
+                // SAFETY: The file was just created by us and is only modified via this map.
+                // Other processes modifying the file concurrently is undefined behaviour.
+                let mmap = unsafe { memmap2::MmapMut::map_mut(&file)? };
+                Ok((file, Some(mmap)))
+            })
+            .await??;
+
+            let mut mmap = if let Some(mmap) = mmap {
+                mmap
+            } else {
+                return Ok(file);
+            };
+
+            chunk_stream.write_buffer(&mut mmap[..]).await?;
+
+            let file = helpers::spawn_blocking(move || -> Result<_, CondowError> {
+                mmap.flush()?;
+                drop(mmap);
+                Ok(file)
+            })
+            .await??;
+
+            Ok(file)
+        }
+        .boxed()
+    }
+
+    /// Download a BLOB range concurrently and write it to `writer`
+    ///
+    /// The bytes are written in order as soon as they are received.
+    /// Only the parts which are ahead of the next byte to write are
+    /// buffered (see [OrderedBytesStream]). The writer is flushed at the end.
+    ///
+    /// Returns the number of bytes written.
+    fn copy_to<'a, R, W>(
+        &'a self,
+        location: &'a url::Url,
+        range: R,
+        mut writer: W,
+    ) -> BoxFuture<'a, Result<u64, CondowError>>
+    where
+        R: Into<DownloadRange> + Send + Sync + 'static,
+        W: futures::AsyncWrite + Unpin + Send + 'a,
+    {
+        use futures::{AsyncWriteExt, TryStreamExt};
+
+        let bytes_stream = self.download_chunks_ordered(location, range);
+        async move {
+            let mut bytes_stream = bytes_stream.await?;
+            let mut bytes_written = 0;
+            while let Some(bytes) = bytes_stream.try_next().await? {
+                writer.write_all(&bytes).await?;
+                bytes_written += bytes.len() as u64;
+            }
+            writer.flush().await?;
+
+            Ok(bytes_written)
+        }
+        .boxed()
+    }
+
+    /// Download a BLOB range concurrently and write each part to its own file in `dir`
+    ///
+    /// The files are named `part_000`, `part_001`, ... after the index of
+    /// their part. The directory is created if it does not exist and
+    /// existing files are truncated.
+    ///
+    /// Returns a stream which yields a [PartFile] as soon as a part was
+    /// written completely. Parts complete in no specific order.
+    fn download_parts_to_dir<'a, R: Into<DownloadRange> + Send + Sync + 'static>(
+        &'a self,
+        location: &'a url::Url,
+        range: R,
+        dir: &'a std::path::Path,
+    ) -> BoxFuture<'a, Result<BoxStream<'static, Result<PartFile, CondowError>>, CondowError>> {
+        let chunk_stream = self.download_chunks(location, range);
+        async move { part_files::write_parts_to_dir(chunk_stream.await?, dir).await }.boxed()
+    }
+
+    /// Check that the BLOB at `location` can be downloaded
+    ///
+    /// Downloads the first byte of the BLOB and discards it. Unlike a size
+    /// request this also fails if only downloading is not permitted.
+    /// The error is classified like the error of any other download.
+    fn probe<'a>(&'a self, location: &'a url::Url) -> BoxFuture<'a, Result<(), CondowError>> {
+        let stream = self.download_chunks(location, 0..1);
+        async move {
+            stream.await?.into_vec().await?;
+            Ok(())
+        }
+        .boxed()
+    }
+
     /// Get the size of a file at the BLOB location
-    fn get_size<'a>(&'a self, location: url::Url) -> BoxFuture<'a, Result<u64, CondowError>>;
+    fn get_size<'a>(&'a self, location: &'a url::Url) -> BoxFuture<'a, Result<u64, CondowError>>;
+
+    /// Download the BLOB from `start` to its end with a single request
+    /// without querying its size
+    ///
+    /// Returns the raw byte stream and the [BytesHint](streams::BytesHint)
+    /// returned by the client. The stream is empty if `start` is not
+    /// within the BLOB.
+    ///
+    /// The default implementation falls back to [Downloads::download_chunks_ordered]
+    /// for the range `start..` which queries its size and may download concurrently.
+    fn download_from_sequential<'a>(
+        &'a self,
+        location: &'a url::Url,
+        start: u64,
+    ) -> BoxFuture<'a, Result<(BytesStream, BytesHint), CondowError>> {
+        let ordered = self.download_chunks_ordered(location, start..);
+        async move {
+            let ordered = ordered.await?;
+            let bytes_hint = ordered.bytes_hint();
+            let stream: BytesStream =
+                Box::pin(ordered.map_err(|err| errors::IoError(err.to_string())));
+            Ok((stream, bytes_hint))
+        }
+        .boxed()
+    }
 
     /// Creates a [RandomAccessReader] for the given location
     ///
@@ -96,25 +346,61 @@ pub trait Downloads {
     /// call [Downloads::reader_with_length]
     fn reader<'a>(
         &'a self,
-        location: url::Url,
+        location: &'a url::Url,
     ) -> BoxFuture<'a, Result<RandomAccessReader<Self>, CondowError>>
     where
         Self: Sized + Sync,
     {
         let me = self;
         async move {
-            let length = me.get_size(location.clone()).await?;
+            let length = me.get_size(location).await?;
             Ok(me.reader_with_length(location, length))
         }
         .boxed()
     }
 
+    /// Creates a [RandomAccessReader] for the given location which streams to the end
+    ///
+    /// The reader fetches all bytes from the current position to the end
+    /// of the BLOB with a single concurrent download (see
+    /// [FetchAheadMode::ToEnd](reader::FetchAheadMode::ToEnd)). Reading forward
+    /// is as efficient as consuming a [PartStream] while seeking to another
+    /// position restarts the download at the new position on the next read.
+    ///
+    /// This function will query the size of the BLOB.
+    fn seekable_reader<'a>(
+        &'a self,
+        location: &'a url::Url,
+    ) -> BoxFuture<'a, Result<RandomAccessReader<Self>, CondowError>>
+    where
+        Self: Sized + Clone + Send + Sync + 'static,
+    {
+        let reader = self.reader(location);
+        async move {
+            let mut reader = reader.await?;
+            reader.set_fetch_ahead_mode(reader::FetchAheadMode::ToEnd);
+            Ok(reader)
+        }
+        .boxed()
+    }
+
     /// Creates a [RandomAccessReader] for the given location
     ///
     /// This function will create a new reader immediately
-    fn reader_with_length(&self, location: url::Url, length: u64) -> RandomAccessReader<Self>
+    fn reader_with_length(&self, location: &url::Url, length: u64) -> RandomAccessReader<Self>
     where
         Self: Sized;
+
+    /// Creates a [RandomAccessReader] for the given location
+    ///
+    /// The size of the BLOB will not be queried. It will be discovered
+    /// on the first read.
+    fn reader_streaming(&self, location: &url::Url) -> RandomAccessReader<Self>
+    where
+        Self: Sized + Clone + Send + Sync + 'static,
+    {
+        RandomAccessReader::new_streaming(self.clone(), location.clone())
+    }
 }
 
 /// The CONcurrent DOWnloader
@@ -149,6 +435,9 @@ impl<C: CondowClient> Condow<C> {
     /// Create a new CONcurrent DOWnloader.
     ///
     /// Fails if the [Config] is not valid.
+    ///
+    /// Prefer [Condow::try_new] which fails with a [CondowError]
+    /// like the rest of the API.
     pub fn new(client: C, config: Config) -> Result<Self, anyhow::Error> {
         let config = config.validated()?;
         Ok(Self {
@@ -157,6 +446,55 @@ impl<C: CondowClient> Condow<C> {
         })
     }
 
+    /// Create a new CONcurrent DOWnloader which creates a new client for each download
+    ///
+    /// The client is created with the [ClientFactory] when a download is started
+    /// and used for all requests of that download including retries.
+    /// A [Condow::get_size] also creates a new client. This allows e.g. short
+    /// lived credentials or serving multiple tenants with a single [Condow].
+    ///
+    /// Fails if the [Config] is not valid.
+    pub fn from_client_factory<F: Into<ClientFactory<C>>>(
+        factory: F,
+        config: Config,
+    ) -> Result<Self, anyhow::Error> {
+        let config = config.validated()?;
+        Ok(Self {
+            client: ClientRetryWrapper::from_factory(factory.into(), config.retries.clone()),
+            config,
+        })
+    }
+
+    /// Create a new CONcurrent DOWnloader.
+    ///
+    /// Fails with a [CondowError] of kind
+    /// [CondowErrorKind::Other](errors::CondowErrorKind::Other)
+    /// if the [Config] is not valid.
+    pub fn try_new(client: C, config: Config) -> Result<Self, CondowError> {
+        Self::new(client, config).map_err(|err| {
+            CondowError::new_other(format!("invalid config: {}", err)).with_source(err)
+        })
+    }
+
+    /// Create a new CONcurrent DOWnloader with the given [Config]
+    /// which shares the client (or [ClientFactory]) of this one.
+    ///
+    /// Fails if the [Config] is not valid.
+    pub fn with_config(&self, config: Config) -> Result<Self, anyhow::Error> {
+        let config = config.validated()?;
+        Ok(Self {
+            client: self.client.with_retries(config.retries.clone()),
+            config,
+        })
+    }
+
+    /// The validated [Config] used by this [Condow]
+    ///
+    /// E.g. to align buffers with the part size of the downloads.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
     /// Create a reusable [Downloader] which has a richer API.
     pub fn downloader(&self) -> Downloader<C, NoReporting> {
         Downloader::new(self.clone())
@@ -193,12 +531,18 @@ impl<C: CondowClient> Condow<C> {
     /// Returns a stream of [Chunk](streams::Chunk)s.
     pub async fn download_chunks<R: Into<DownloadRange>>(
         &self,
-        location: url::Url,
+        location: &url::Url,
         range: R,
     ) -> Result<ChunkStream, CondowError> {
-        machinery::download(self, location, range, GetSizeMode::Default, NoReporting)
-            .await
-            .map(|o| o.into_stream())
+        machinery::download(
+            self,
+            location.clone(),
+            range,
+            GetSizeMode::Default,
+            NoReporting,
+        )
+        .await
+        .map(|o| o.into_stream())
     }
 
     /// Download a BLOB range (potentially) concurrently
@@ -206,32 +550,277 @@ impl<C: CondowClient> Condow<C> {
     /// Returns a stream of [Parts](streams::Part)s.
     pub async fn download<R: Into<DownloadRange>>(
         &self,
-        location: url::Url,
+        location: &url::Url,
         range: R,
     ) -> Result<PartStream<ChunkStream>, CondowError> {
-        let chunk_stream =
-            machinery::download(self, location, range, GetSizeMode::Default, NoReporting)
-                .await
-                .map(|o| o.into_stream())?;
-        PartStream::from_chunk_stream(chunk_stream)
+        self.download_chunks(location, range)
+            .await
+            .and_then(PartStream::from_chunk_stream)
+    }
+
+    /// Download a BLOB range (potentially) concurrently with a known size of the BLOB
+    ///
+    /// No size request is made. `size` is used to resolve open ranges and to clamp
+    /// the upper bound of closed ranges as configured with
+    /// [Config::out_of_bounds_policy]. This saves a request if the size is already
+    /// known from elsewhere, e.g. from a catalog of the BLOBs.
+    ///
+    /// Returns a stream of [Parts](streams::Part)s.
+    pub async fn download_with_known_size<R: Into<DownloadRange>>(
+        &self,
+        location: &url::Url,
+        range: R,
+        size: u64,
+    ) -> Result<PartStream<ChunkStream>, CondowError> {
+        machinery::download_with_known_size(self, location.clone(), range, size, NoReporting)
+            .await
+            .map(|o| o.into_stream())
+            .and_then(PartStream::from_chunk_stream)
+    }
+
+    /// Download a BLOB range (potentially) concurrently and return its length
+    ///
+    /// Returns the number of bytes of the resolved range together with
+    /// a stream of [Parts](streams::Part)s. For a closed range this is the
+    /// length of the range. For an open range it is derived from the size of
+    /// the BLOB which has to be queried for the download anyways, so no
+    /// additional request is made.
+    pub async fn download_sized<R: Into<DownloadRange>>(
+        &self,
+        location: &url::Url,
+        range: R,
+    ) -> Result<(u64, PartStream<ChunkStream>), CondowError> {
+        let stream = self.download(location, range).await?;
+        let len = stream.bytes_hint().upper_bound().ok_or_else(|| {
+            CondowError::new_other("the length of the resolved range is unknown. This is a bug.")
+        })?;
+        Ok((len, stream))
+    }
+
+    /// Download a BLOB range (potentially) concurrently
+    ///
+    /// Returns a stream of [Bytes](bytes::Bytes) in the same order as within the
+    /// BLOB/range. Unlike [Condow::download] complete parts are not reassembled.
+    /// See [OrderedBytesStream] for how much is buffered.
+    pub async fn download_chunks_ordered<R: Into<DownloadRange>>(
+        &self,
+        location: &url::Url,
+        range: R,
+    ) -> Result<OrderedBytesStream, CondowError> {
+        self.download_chunks(location, range)
+            .await
+            .and_then(OrderedBytesStream::from_chunk_stream)
+    }
+
+    /// Download a BLOB range (potentially) concurrently
+    ///
+    /// Returns the stream of the [OrderingStrategy] `O` which decides
+    /// how the downloaded chunks are ordered and buffered. The built in
+    /// strategies are [Unordered](streams::Unordered), [Ordered](streams::Ordered)
+    /// and [PartAligned](streams::PartAligned).
+    ///
+    /// ```rust,no_run
+    /// # use condow_core::{Condow, condow_client::InMemoryClient, config::Config};
+    /// # use condow_core::streams::Ordered;
+    /// # async fn run(condow: Condow<InMemoryClient>, location: url::Url) {
+    /// let bytes = condow.download_with::<Ordered, _>(&location, 10..20).await.unwrap();
+    /// # }
+    /// ```
+    pub async fn download_with<O: OrderingStrategy, R: Into<DownloadRange>>(
+        &self,
+        location: &url::Url,
+        range: R,
+    ) -> Result<O::Stream, CondowError> {
+        self.download_chunks(location, range)
+            .await
+            .and_then(O::order)
+    }
+
+    /// Download multiple ranges of a BLOB (potentially) concurrently
+    ///
+    /// Each range is downloaded like with [Condow::download_chunks]. The chunks
+    /// of all ranges are merged into a single stream in the order they are
+    /// received, so chunks of different ranges can interleave. Each
+    /// [MultiRangeItem] carries the index of the range within `ranges` its chunk
+    /// belongs to.
+    ///
+    /// [Config::max_concurrency] bounds the requests of all ranges together.
+    /// It is split evenly among the ranges downloaded at the same time. If there
+    /// are more ranges than [Config::max_concurrency] each range is downloaded
+    /// with a single task and a range is only started once another one finished.
+    ///
+    /// Fails if one of the ranges is invalid. Other errors, e.g. of a range
+    /// which could not be started, are yielded by the stream.
+    /// The stream ends after the first error.
+    pub async fn download_ranges<R, I>(
+        &self,
+        location: &url::Url,
+        ranges: I,
+    ) -> Result<impl Stream<Item = Result<MultiRangeItem, CondowError>> + Send + 'static, CondowError>
+    where
+        R: Into<DownloadRange>,
+        I: IntoIterator<Item = R>,
+    {
+        let ranges = ranges
+            .into_iter()
+            .map(Into::into)
+            .collect::<Vec<DownloadRange>>();
+        for range in &ranges {
+            range.validate()?;
+        }
+
+        let max_concurrency = self.config.max_concurrency.into_inner();
+        let concurrent_ranges = ranges.len().clamp(1, max_concurrency);
+        let condow = Condow {
+            client: self.client.clone(),
+            config: self
+                .config
+                .clone()
+                .max_concurrency(max_concurrency / concurrent_ranges),
+        };
+        let location = location.clone();
+
+        let merged = futures::stream::iter(ranges.into_iter().enumerate())
+            .then(move |(range_index, range)| {
+                let condow = condow.clone();
+                let location = location.clone();
+                async move {
+                    match condow.download_chunks(&location, range).await {
+                        Ok(chunk_stream) => chunk_stream
+                            .map_ok(move |chunk| MultiRangeItem { range_index, chunk })
+                            .left_stream(),
+                        Err(err) => {
+                            futures::stream::once(futures::future::ready(Err(err))).right_stream()
+                        }
+                    }
+                }
+            })
+            .flatten_unordered(concurrent_ranges);
+
+        Ok(merged.scan(false, |failed, item| {
+            if *failed {
+                return futures::future::ready(None);
+            }
+            *failed = item.is_err();
+            futures::future::ready(Some(item))
+        }))
+    }
+
+    /// Download the incomplete parts of a [DownloadPlan] into the file at `path`
+    ///
+    /// Each part is written at its offset from the start of the planned range.
+    /// A part is marked as completed once it was written and synced to disk,
+    /// so `plan` can be persisted to resume the download later even if this
+    /// function failed. Completed parts are not downloaded again.
+    ///
+    /// The file is created if it does not exist but never truncated.
+    /// Each part is downloaded with a single request. Up to [Config::max_concurrency]
+    /// parts are downloaded at the same time and each of them is kept in memory
+    /// until it is written. Writing to the file does not block the runtime.
+    pub async fn download_plan(
+        &self,
+        location: &url::Url,
+        plan: &mut DownloadPlan,
+        path: &std::path::Path,
+    ) -> Result<(), CondowError> {
+        download_plan::download_plan(self, location, plan, path).await
+    }
+
+    /// Download a BLOB range (potentially) concurrently
+    ///
+    /// Returns a stream of [Bytes](bytes::Bytes). Whether the bytes are ordered
+    /// depends on [Config::default_ordered].
+    ///
+    /// The returned stream is `Send` but not `Sync`. Use [Condow::download]
+    /// or [Condow::download_chunks] if a `Sync` stream is required.
+    pub async fn download_bytes<R: Into<DownloadRange>>(
+        &self,
+        location: &url::Url,
+        range: R,
+    ) -> Result<BoxStream<'static, Result<Bytes, CondowError>>, CondowError> {
+        self.download_chunks(location, range)
+            .await?
+            .try_into_bytes_stream(self.config.default_ordered.into_inner())
+    }
+
+    /// Download the [Config::default_range] of a BLOB (potentially) concurrently
+    ///
+    /// The size of the BLOB is always requested so that the range
+    /// can be clamped to it according to [Config::out_of_bounds_policy].
+    ///
+    /// Returns a stream of [Parts](streams::Part)s.
+    pub async fn download_default(
+        &self,
+        location: &url::Url,
+    ) -> Result<PartStream<ChunkStream>, CondowError> {
+        machinery::download(
+            self,
+            location.clone(),
+            self.config.default_range,
+            GetSizeMode::Always,
+            NoReporting,
+        )
+        .await
+        .and_then(|o| PartStream::from_chunk_stream(o.into_stream()))
+    }
+
+    /// Download a BLOB range with a single request to the client
+    ///
+    /// The range is not split into parts and nothing is downloaded concurrently.
+    /// Retries are applied as configured. This is useful for tiny BLOBs or for
+    /// testing a [CondowClient] in isolation.
+    ///
+    /// A range like `start..` is requested with [CondowClient::download_from]
+    /// so that no size request is made if the client supports open ranges.
+    pub async fn download_raw<R: Into<DownloadRange>>(
+        &self,
+        location: &url::Url,
+        range: R,
+    ) -> Result<BytesStream, CondowError> {
+        machinery::download_raw(self, location.clone(), range, NoReporting).await
     }
 
     /// Get the size of a file at the given location
-    pub async fn get_size(&self, location: url::Url) -> Result<u64, CondowError> {
-        self.client.get_size(location, &NoReporting).await
+    pub async fn get_size(&self, location: &url::Url) -> Result<u64, CondowError> {
+        self.client
+            .get_size(
+                location.clone(),
+                self.config.size_request_timeout(),
+                &NoReporting,
+            )
+            .await
+    }
+
+    /// Make a minimal request to the given location and return the size of the BLOB
+    ///
+    /// This is a size request which primes credentials and the connection
+    /// pool of the client before a burst of downloads to the same endpoint.
+    /// Retries are applied as configured.
+    pub async fn warmup(&self, location: &url::Url) -> Result<u64, CondowError> {
+        self.get_size(location).await
     }
 
     /// Creates a [RandomAccessReader] for the given location
     pub async fn reader(
         &self,
-        location: url::Url,
+        location: &url::Url,
     ) -> Result<RandomAccessReader<Self>, CondowError> {
-        RandomAccessReader::new(self.clone(), location).await
+        RandomAccessReader::new(self.clone(), location.clone()).await
+    }
+
+    /// Creates a [RandomAccessReader] for the given location
+    pub fn reader_with_length(&self, location: &url::Url, length: u64) -> RandomAccessReader<Self> {
+        RandomAccessReader::new_with_length(self.clone(), location.clone(), length)
     }
 
     /// Creates a [RandomAccessReader] for the given location
-    pub fn reader_with_length(&self, location: url::Url, length: u64) -> RandomAccessReader<Self> {
-        RandomAccessReader::new_with_length(self.clone(), location, length)
+    ///
+    /// The size of the BLOB will not be queried. It will be discovered
+    /// on the first read which downloads the BLOB from the reading position
+    /// to its end sequentially.
+    /// Use this if the client can not efficiently query the size.
+    pub fn reader_streaming(&self, location: &url::Url) -> RandomAccessReader<Self> {
+        RandomAccessReader::new_streaming(self.clone(), location.clone())
     }
 }
 
@@ -241,7 +830,7 @@ where
 {
     fn download<'a, R: Into<DownloadRange> + Send + Sync + 'static>(
         &'a self,
-        location: url::Url,
+        location: &'a url::Url,
         range: R,
     ) -> BoxFuture<'a, Result<PartStream<ChunkStream>, CondowError>> {
         Box::pin(self.download(location, range))
@@ -249,19 +838,36 @@ where
 
     fn download_chunks<'a, R: Into<DownloadRange> + Send + Sync + 'static>(
         &'a self,
-        location: url::Url,
+        location: &'a url::Url,
         range: R,
     ) -> BoxFuture<'a, Result<ChunkStream, CondowError>> {
         Box::pin(self.download_chunks(location, range))
     }
 
-    fn get_size<'a>(&'a self, location: url::Url) -> BoxFuture<'a, Result<u64, CondowError>> {
+    fn get_size<'a>(&'a self, location: &'a url::Url) -> BoxFuture<'a, Result<u64, CondowError>> {
         Box::pin(self.get_size(location))
     }
 
-    fn reader_with_length(&self, location: url::Url, length: u64) -> RandomAccessReader<Self> {
+    fn download_from_sequential<'a>(
+        &'a self,
+        location: &'a url::Url,
+        start: u64,
+    ) -> BoxFuture<'a, Result<(BytesStream, BytesHint), CondowError>> {
+        Box::pin(machinery::download_from_sequential(
+            self,
+            location.clone(),
+            start,
+            NoReporting,
+        ))
+    }
+
+    fn reader_with_length(&self, location: &url::Url, length: u64) -> RandomAccessReader<Self> {
         Condow::reader_with_length(self, location, length)
     }
+
+    fn reader_streaming(&self, location: &url::Url) -> RandomAccessReader<Self> {
+        Condow::reader_streaming(self, location)
+    }
 }
 
 /// A composite struct of a stream and a [Reporter]
@@ -332,6 +938,11 @@ pub enum GetSizeMode {
     Always,
     /// Only request the size of a BLOB when required. This is when an open
     /// range (e.g. complete BLOB or from x to end)
+    ///
+    /// Closed ranges (e.g. `23..46`) never cause a size request in this mode
+    /// regardless of the configuration of [Condow]. The upper bound of a closed
+    /// range is also not adjusted to the size of the BLOB. It is up to the caller
+    /// to supply a range which is within the BLOB.
     Required,
     /// As configured with [Condow] itself.
     Default,
@@ -4,7 +4,7 @@ use std::{fmt, sync::Arc};
 
 use crate::{errors::CondowError, InclusiveRange};
 
-use super::{Reporter, ReporterFactory};
+use crate::reporter::{KillReason, Reporter, ReporterFactory};
 
 /// A logger logging on events send to a [Reporter]
 ///
@@ -94,6 +94,15 @@ impl Logger {
 impl Reporter for Logger {
     fn effective_range(&self, _range: crate::InclusiveRange) {}
 
+    fn parts_planned(&self, parts: crate::PartBoundaries) {
+        self.debug(format_args!(
+            "Range {} split into {} parts of up to {} bytes",
+            parts.range(),
+            parts.n_parts(),
+            parts.part_size_bytes()
+        ));
+    }
+
     fn download_started(&self) {
         self.info(format_args!("Download started"));
     }
@@ -102,14 +111,21 @@ impl Reporter for Logger {
         self.info(format_args!("Download completed"));
     }
 
-    fn download_failed(&self, time: Option<std::time::Duration>) {
+    fn download_failed(&self, time: Option<std::time::Duration>, reason: KillReason) {
         if let Some(time) = time {
-            self.error(format_args!("Download failed after {:?}", time));
+            self.error(format_args!(
+                "Download failed after {:?} ({})",
+                time, reason
+            ));
         } else {
-            self.error(format_args!("Download failed"));
+            self.error(format_args!("Download failed ({})", reason));
         }
     }
 
+    fn download_cancelled(&self, time: std::time::Duration) {
+        self.info(format_args!("Download cancelled after {:?}", time));
+    }
+
     fn retry_attempt(
         &self,
         _location: &dyn fmt::Display,
@@ -148,6 +164,10 @@ impl Reporter for Logger {
     ) {
     }
 
+    fn part_queued(&self, part_index: u64) {
+        self.debug(format_args!("Part {} queued", part_index));
+    }
+
     fn part_started(&self, part_index: u64, range: crate::InclusiveRange) {
         self.debug(format_args!(
             "Download of part {} ({}) started",
@@ -155,6 +175,13 @@ impl Reporter for Logger {
         ));
     }
 
+    fn part_first_byte(&self, part_index: u64, elapsed: std::time::Duration) {
+        self.debug(format_args!(
+            "First byte of part {} received after {:?}",
+            part_index, elapsed
+        ));
+    }
+
     fn part_completed(
         &self,
         part_index: u64,
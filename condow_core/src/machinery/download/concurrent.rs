@@ -9,10 +9,10 @@ use futures::{channel::mpsc::UnboundedSender, Stream, StreamExt};
 
 use crate::{
     condow_client::CondowClient,
-    config::{ClientRetryWrapper, Config},
+    config::{ClientRetryWrapper, Config, PartPriority},
     machinery::range_stream::RangeRequest,
-    reporter::Reporter,
-    streams::ChunkStreamItem,
+    reporter::{KillReason, Reporter},
+    streams::{ChunkStreamItem, MissingRanges, RetryRecords},
 };
 
 use super::{
@@ -29,6 +29,7 @@ pub(crate) struct ConcurrentDownloader<R: Reporter> {
 }
 
 impl<R: Reporter> ConcurrentDownloader<R> {
+    #[allow(clippy::too_many_arguments)]
     pub fn new<C: CondowClient>(
         n_concurrent: usize,
         results_sender: UnboundedSender<ChunkStreamItem>,
@@ -36,6 +37,9 @@ impl<R: Reporter> ConcurrentDownloader<R> {
         config: Config,
         location: url::Url,
         reporter: R,
+        missing_ranges: Option<MissingRanges>,
+        split_part_size_bytes: Option<u64>,
+        retry_records: RetryRecords,
     ) -> Self {
         let started_at = Instant::now();
         let kill_switch = KillSwitch::new();
@@ -46,13 +50,17 @@ impl<R: Reporter> ConcurrentDownloader<R> {
                     client.clone(),
                     location.clone(),
                     config.buffer_size.into(),
+                    config.tolerate_trailing_empty.into_inner(),
                     DownloaderContext::new(
                         results_sender.clone(),
                         Arc::clone(&counter),
                         kill_switch.clone(),
                         reporter.clone(),
                         started_at,
-                    ),
+                    )
+                    .skip_failed_parts(missing_ranges.clone())
+                    .split_into_parts(split_part_size_bytes)
+                    .record_retries(retry_records.clone()),
                 )
             })
             .collect();
@@ -71,37 +79,205 @@ impl<R: Reporter> ConcurrentDownloader<R> {
         ranges_stream: impl Stream<Item = RangeRequest>,
     ) -> Result<(), ()> {
         self.reporter.download_started();
-        let mut ranges_stream = Box::pin(ranges_stream);
-        while let Some(mut range_request) = ranges_stream.next().await {
-            let mut attempt = 1;
 
-            let buffers_full_delay = self.config.buffers_full_delay_ms.into();
-            let n_downloaders = self.downloaders.len();
+        if let Some(part_priority) = self.config.part_priority.clone() {
+            let pending: Vec<RangeRequest> = ranges_stream.collect().await;
+            for range_request in prioritized(pending, &part_priority) {
+                self.dispatch(range_request).await?;
+            }
+        } else {
+            let mut ranges_stream = Box::pin(ranges_stream);
+            while let Some(range_request) = ranges_stream.next().await {
+                self.dispatch(range_request).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Hand the [RangeRequest] over to the next download task with a free buffer
+    async fn dispatch(&mut self, mut range_request: RangeRequest) -> Result<(), ()> {
+        let mut attempt = 1;
 
-            loop {
-                if attempt % self.downloaders.len() == 0 {
-                    self.reporter.queue_full();
-                    tokio::time::sleep(buffers_full_delay).await;
+        let buffers_full_delay = self.config.buffers_full_delay_ms.into();
+        let n_downloaders = self.downloaders.len();
+
+        // Reported before the first attempt so that the delays
+        // on full buffers count as waiting in the queue
+        self.reporter.part_queued(range_request.part_index);
+
+        loop {
+            if attempt % self.downloaders.len() == 0 {
+                self.reporter.queue_full();
+                tokio::time::sleep(buffers_full_delay).await;
+            }
+            let idx = next_downloader_idx(self.counter, attempt, n_downloaders);
+            let downloader = &mut self.downloaders[idx];
+
+            match downloader.enqueue(range_request) {
+                Ok(None) => break,
+                Ok(Some(msg)) => {
+                    range_request = msg;
                 }
-                let idx = self.counter + attempt;
-                let downloader = &mut self.downloaders[idx % n_downloaders];
-
-                match downloader.enqueue(range_request) {
-                    Ok(None) => break,
-                    Ok(Some(msg)) => {
-                        range_request = msg;
-                    }
-                    Err(()) => {
-                        self.kill_switch.push_the_button();
-                        return Err(());
-                    }
+                Err(()) => {
+                    // A download task only ends early after pushing the button itself
+                    // or while unwinding from a panic
+                    self.kill_switch.push_the_button(KillReason::Panic);
+                    return Err(());
                 }
-
-                attempt += 1;
             }
 
-            self.counter += 1;
+            attempt = attempt.wrapping_add(1);
         }
+
+        self.counter = self.counter.wrapping_add(1);
         Ok(())
     }
 }
+
+/// Returns the index of the download task to try next
+///
+/// `counter` and `attempt` wrap on overflow (e.g. for very long downloads
+/// on 32 bit targets). This only disturbs the round robin once.
+fn next_downloader_idx(counter: usize, attempt: usize, n_downloaders: usize) -> usize {
+    counter.wrapping_add(attempt) % n_downloaders
+}
+
+/// Sorts the pending [RangeRequest]s by descending priority
+///
+/// On equal priorities the one with the lowest part index comes first.
+/// The priority of each part is only computed once.
+fn prioritized(mut pending: Vec<RangeRequest>, priority: &PartPriority) -> Vec<RangeRequest> {
+    pending.sort_by_cached_key(|req| (std::cmp::Reverse(priority.priority(req)), req.part_index));
+    pending
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use futures::{
+        channel::mpsc,
+        future::{self, BoxFuture},
+        FutureExt, StreamExt,
+    };
+
+    use crate::{
+        condow_client::{CondowClient, DownloadSpec},
+        config::{ClientRetryWrapper, Config, PartPriority},
+        errors::CondowError,
+        machinery::range_stream::{RangeRequest, RangeStream},
+        reporter::NoReporting,
+        streams::{BytesHint, BytesStream},
+        InclusiveRange,
+    };
+
+    use super::{next_downloader_idx, prioritized, ConcurrentDownloader};
+
+    /// A client whose requests never complete
+    #[derive(Clone)]
+    struct StalledClient;
+
+    impl CondowClient for StalledClient {
+        fn get_size(&self, _location: url::Url) -> BoxFuture<'static, Result<u64, CondowError>> {
+            future::pending().boxed()
+        }
+
+        fn download(
+            &self,
+            _location: url::Url,
+            _spec: DownloadSpec,
+        ) -> BoxFuture<'static, Result<(BytesStream, BytesHint), CondowError>> {
+            future::pending().boxed()
+        }
+    }
+
+    #[tokio::test]
+    async fn range_requests_are_pulled_only_when_they_can_be_enqueued() {
+        let n_concurrent = 3;
+        let buffer_size = 2;
+        let config = Config::default()
+            .buffer_size(buffer_size)
+            .buffers_full_delay_ms(1);
+
+        let (n_parts, ranges_stream) = RangeStream::create(InclusiveRange(0, u64::MAX - 1), 1);
+        assert_eq!(n_parts, u64::MAX);
+
+        let pulled = Arc::new(AtomicUsize::new(0));
+        let pulled_by_downloader = Arc::clone(&pulled);
+        let ranges_stream = ranges_stream.inspect(move |_| {
+            pulled_by_downloader.fetch_add(1, Ordering::SeqCst);
+        });
+
+        let (results_sender, _results_receiver) = mpsc::unbounded();
+        let mut downloader = ConcurrentDownloader::new(
+            n_concurrent,
+            results_sender,
+            ClientRetryWrapper::new(StalledClient, None),
+            config,
+            url::Url::parse("noscheme://").expect("a valid URL"),
+            NoReporting,
+            None,
+            None,
+            Default::default(),
+        );
+
+        let download = downloader.download(ranges_stream);
+        let result = tokio::time::timeout(Duration::from_millis(100), download).await;
+        assert!(result.is_err(), "download should still be running");
+
+        // Per task: 1 in progress and `buffer_size + 1` in the channel.
+        // Plus the one waiting to be enqueued.
+        let max_outstanding = n_concurrent * (buffer_size + 2) + 1;
+        let pulled = pulled.load(Ordering::SeqCst);
+        assert!(
+            pulled <= max_outstanding,
+            "pulled {} range requests but at most {} can be outstanding",
+            pulled,
+            max_outstanding
+        );
+    }
+
+    #[test]
+    fn next_downloader_idx_round_robin() {
+        let indexes: Vec<_> = (0..6)
+            .map(|counter| next_downloader_idx(counter, 1, 3))
+            .collect();
+
+        assert_eq!(indexes, vec![1, 2, 0, 1, 2, 0]);
+    }
+
+    #[test]
+    fn parts_are_prioritized_with_ties_by_part_index() {
+        let pending = (0..6)
+            .map(|part_index| RangeRequest {
+                part_index,
+                blob_range: InclusiveRange(part_index, part_index),
+                range_offset: part_index,
+            })
+            .collect();
+        let priority = PartPriority::new(|req: &RangeRequest| (req.part_index() % 3) as i64);
+
+        let order: Vec<_> = prioritized(pending, &priority)
+            .into_iter()
+            .map(|req| req.part_index)
+            .collect();
+
+        assert_eq!(order, vec![2, 5, 1, 4, 0, 3]);
+    }
+
+    #[test]
+    fn next_downloader_idx_wraps_on_overflow() {
+        assert_eq!(next_downloader_idx(usize::MAX, 1, 3), 0);
+        assert_eq!(
+            next_downloader_idx(usize::MAX, usize::MAX, 3),
+            (usize::MAX - 1) % 3
+        );
+    }
+}
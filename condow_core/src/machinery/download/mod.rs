@@ -1,8 +1,11 @@
 //! Perform the actual download
 
-use std::sync::{
-    atomic::{AtomicBool, Ordering},
-    Arc,
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Instant,
 };
 
 use futures::{channel::mpsc::UnboundedSender, Stream};
@@ -10,11 +13,12 @@ use futures::{channel::mpsc::UnboundedSender, Stream};
 use crate::{
     condow_client::CondowClient,
     config::{ClientRetryWrapper, Config},
-    reporter::Reporter,
-    streams::ChunkStreamItem,
+    reporter::{KillReason, Reporter},
+    streams::{ChunkStreamItem, MissingRanges, RetryRecords},
+    InclusiveRange,
 };
 
-use self::concurrent::ConcurrentDownloader;
+use self::{concurrent::ConcurrentDownloader, sequential::DownloaderContext};
 
 use super::range_stream::RangeRequest;
 
@@ -22,6 +26,7 @@ mod concurrent;
 mod sequential;
 
 /// Download the parst of a BLOB concurrently
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn download_concurrently<C: CondowClient, R: Reporter>(
     ranges_stream: impl Stream<Item = RangeRequest>,
     n_concurrent: usize,
@@ -30,6 +35,9 @@ pub(crate) async fn download_concurrently<C: CondowClient, R: Reporter>(
     config: Config,
     location: url::Url,
     reporter: R,
+    missing_ranges: Option<MissingRanges>,
+    split_part_size_bytes: Option<u64>,
+    retry_records: RetryRecords,
 ) -> Result<(), ()> {
     let mut downloader = ConcurrentDownloader::new(
         n_concurrent,
@@ -38,21 +46,68 @@ pub(crate) async fn download_concurrently<C: CondowClient, R: Reporter>(
         config.clone(),
         location,
         reporter,
+        missing_ranges,
+        split_part_size_bytes,
+        retry_records,
     );
 
     downloader.download(ranges_stream).await
 }
 
+/// Download `range` as a single part with a single request
+///
+/// No download tasks are spawned. The request is made by the calling task.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn download_single_request<C: CondowClient, R: Reporter>(
+    range: InclusiveRange,
+    results_sender: UnboundedSender<ChunkStreamItem>,
+    client: ClientRetryWrapper<C>,
+    config: Config,
+    location: url::Url,
+    reporter: R,
+    missing_ranges: Option<MissingRanges>,
+    retry_records: RetryRecords,
+) {
+    let context = DownloaderContext::new(
+        results_sender,
+        Arc::new(AtomicUsize::new(0)),
+        KillSwitch::new(),
+        reporter.clone(),
+        Instant::now(),
+    )
+    .skip_failed_parts(missing_ranges)
+    .record_retries(retry_records);
+
+    reporter.download_started();
+    reporter.part_queued(0);
+
+    let range_request = RangeRequest {
+        part_index: 0,
+        blob_range: range,
+        range_offset: 0,
+    };
+    sequential::download_single(
+        client,
+        location,
+        range_request,
+        config.tolerate_trailing_empty.into_inner(),
+        context,
+    )
+    .await
+}
+
 /// Shared state to control cancellation of a download
 #[derive(Clone)]
 pub(crate) struct KillSwitch {
     is_pushed: Arc<AtomicBool>,
+    reason: Arc<Mutex<Option<KillReason>>>,
 }
 
 impl KillSwitch {
     pub fn new() -> Self {
         Self {
             is_pushed: Arc::new(AtomicBool::new(false)),
+            reason: Arc::new(Mutex::new(None)),
         }
     }
 
@@ -62,7 +117,15 @@ impl KillSwitch {
     }
 
     /// Request cancellation of the download
-    pub fn push_the_button(&self) {
+    ///
+    /// Only the reason of the first push is kept.
+    pub fn push_the_button(&self, reason: KillReason) {
+        self.reason.lock().unwrap().get_or_insert(reason);
         self.is_pushed.store(true, Ordering::SeqCst)
     }
+
+    /// The reason the button was pushed for first
+    pub fn reason(&self) -> Option<KillReason> {
+        *self.reason.lock().unwrap()
+    }
 }
@@ -8,6 +8,7 @@ use std::{
     time::Instant,
 };
 
+use bytes::Bytes;
 use futures::{
     channel::mpsc::{self, Sender, UnboundedSender},
     StreamExt,
@@ -16,10 +17,15 @@ use futures::{
 use crate::{
     condow_client::{CondowClient, DownloadSpec},
     config::ClientRetryWrapper,
-    errors::{CondowError, IoError},
+    errors::{CondowError, CondowErrorKind, IoError},
+    helpers::u64_to_usize,
     machinery::range_stream::RangeRequest,
-    reporter::Reporter,
-    streams::{BytesStream, Chunk, ChunkStreamItem},
+    reporter::{CompositeReporter, KillReason, Reporter},
+    streams::{
+        BytesStream, Chunk, ChunkStreamItem, MissingRanges, PartError, PartRetryRecorder,
+        RetryRecords,
+    },
+    InclusiveRange,
 };
 
 use super::KillSwitch;
@@ -42,48 +48,39 @@ impl SequentialDownloader {
         client: ClientRetryWrapper<C>,
         location: url::Url,
         buffer_size: usize,
+        tolerate_trailing_empty: bool,
         mut context: DownloaderContext<R>,
     ) -> Self {
         let (request_sender, request_receiver) = mpsc::channel::<RangeRequest>(buffer_size);
 
-        tokio::spawn(async move {
+        crate::helpers::spawn(async move {
             let mut request_receiver = Box::pin(request_receiver);
             while let Some(range_request) = request_receiver.next().await {
                 if context.kill_switch.is_pushed() {
                     // That failed task should have already sent an error...
                     // ...but we do not want to prove that...
-                    context.send_err(CondowError::new_other(
-                        "another download task already failed",
-                    ));
+                    // The reason was already set by the failed task
+                    let err = if context.kill_switch.reason() == Some(KillReason::ConsumerDropped) {
+                        CondowError::new_cancelled("the consumer dropped the download")
+                    } else {
+                        CondowError::new_other("another download task already failed")
+                    };
+                    context.send_err(err, KillReason::Aborted);
                     return;
                 }
 
-                match client
-                    .download(
-                        location.clone(),
-                        DownloadSpec::Range(range_request.blob_range),
-                        &context.reporter,
-                    )
-                    .await
+                if download_range_request(
+                    &client,
+                    &location,
+                    range_request,
+                    tolerate_trailing_empty,
+                    &mut context,
+                )
+                .await
+                .is_err()
                 {
-                    Ok((bytes_stream, _total_bytes)) => {
-                        if consume_and_dispatch_bytes(bytes_stream, &mut context, range_request)
-                            .await
-                            .is_err()
-                        {
-                            return;
-                        }
-                    }
-                    Err(err) => {
-                        context.reporter.part_failed(
-                            &err,
-                            range_request.part_index,
-                            &range_request.blob_range,
-                        );
-                        context.send_err(err);
-                        return;
-                    }
-                };
+                    return;
+                }
             }
             context.mark_successful();
             drop(context);
@@ -106,6 +103,73 @@ impl SequentialDownloader {
     }
 }
 
+/// Download a single [RangeRequest] without spawning a task
+///
+/// This is what a [SequentialDownloader] does for each enqueued part.
+/// It is used directly if a download consists of a single request.
+pub(crate) async fn download_single<C: CondowClient, R: Reporter>(
+    client: ClientRetryWrapper<C>,
+    location: url::Url,
+    range_request: RangeRequest,
+    tolerate_trailing_empty: bool,
+    mut context: DownloaderContext<R>,
+) {
+    if download_range_request(
+        &client,
+        &location,
+        range_request,
+        tolerate_trailing_empty,
+        &mut context,
+    )
+    .await
+    .is_ok()
+    {
+        context.mark_successful();
+    }
+}
+
+/// Request the range of a [RangeRequest] and dispatch its bytes
+/// via the [DownloaderContext]
+///
+/// Returns `Err(())` if the download must not continue.
+async fn download_range_request<C: CondowClient, R: Reporter>(
+    client: &ClientRetryWrapper<C>,
+    location: &url::Url,
+    range_request: RangeRequest,
+    tolerate_trailing_empty: bool,
+    context: &mut DownloaderContext<R>,
+) -> Result<(), ()> {
+    let reporter = CompositeReporter(
+        context.reporter.clone(),
+        context.retry_recorder(&range_request),
+    );
+    let part_start = Instant::now();
+    context
+        .reporter
+        .part_started(range_request.part_index, range_request.blob_range);
+
+    match client
+        .download(
+            location.clone(),
+            DownloadSpec::Range(range_request.blob_range),
+            &reporter,
+        )
+        .await
+    {
+        Ok((bytes_stream, _total_bytes)) => {
+            consume_and_dispatch_bytes(
+                bytes_stream,
+                context,
+                range_request,
+                part_start,
+                tolerate_trailing_empty,
+            )
+            .await
+        }
+        Err(err) => context.fail_part(err, KillReason::ClientError, &range_request, 0, 0),
+    }
+}
+
 /// A context to control a [SequentialDownloader]
 pub(crate) struct DownloaderContext<R: Reporter> {
     started_at: Instant,
@@ -114,6 +178,12 @@ pub(crate) struct DownloaderContext<R: Reporter> {
     reporter: R,
     results_sender: UnboundedSender<ChunkStreamItem>,
     completed: bool,
+    missing_ranges: Option<MissingRanges>,
+    split_part_size_bytes: Option<u64>,
+    retry_records: RetryRecords,
+    /// The part of the last chunk sent and the index of its next chunk
+    /// if requests are split into parts
+    current_part: Option<(u64, usize)>,
 }
 
 impl<R: Reporter> DownloaderContext<R> {
@@ -132,24 +202,175 @@ impl<R: Reporter> DownloaderContext<R> {
             started_at,
             results_sender,
             completed: false,
+            missing_ranges: None,
+            split_part_size_bytes: None,
+            retry_records: RetryRecords::default(),
+            current_part: None,
         }
     }
 
-    pub fn send_chunk(&self, chunk: Chunk) -> Result<(), ()> {
+    /// Skip failed parts and record their missing ranges if `missing_ranges` is given
+    ///
+    /// Otherwise a failed part fails the download.
+    pub fn skip_failed_parts(mut self, missing_ranges: Option<MissingRanges>) -> Self {
+        self.missing_ranges = missing_ranges;
+        self
+    }
+
+    /// Record the retries and failures of the parts in `retry_records`
+    pub fn record_retries(mut self, retry_records: RetryRecords) -> Self {
+        self.retry_records = retry_records;
+        self
+    }
+
+    /// A [Reporter] recording the retries of the requests for `range_request`
+    fn retry_recorder(&self, range_request: &RangeRequest) -> PartRetryRecorder {
+        PartRetryRecorder {
+            part_index: range_request.part_index,
+            range: range_request.blob_range,
+            records: self.retry_records.clone(),
+        }
+    }
+
+    /// Split the chunks of a request into parts of `part_size_bytes` if given
+    ///
+    /// This is used if a request covers multiple parts. The chunks sent get
+    /// the index of the part they belong to and are numbered within that part.
+    /// Otherwise chunks are sent as they are.
+    pub fn split_into_parts(mut self, part_size_bytes: Option<u64>) -> Self {
+        self.split_part_size_bytes = part_size_bytes;
+        self
+    }
+
+    pub fn send_chunk(&mut self, chunk: Chunk) -> Result<(), ()> {
+        let part_size_bytes = if let Some(part_size_bytes) = self.split_part_size_bytes {
+            part_size_bytes
+        } else {
+            return self.send_unsplit_chunk(chunk);
+        };
+
+        let request_end = chunk.range_offset + chunk.len() as u64 + chunk.bytes_left;
+        let mut chunk = chunk;
+        loop {
+            let part_index = chunk.range_offset / part_size_bytes;
+            let part_end = (part_index + 1)
+                .saturating_mul(part_size_bytes)
+                .min(request_end);
+            let n_bytes_in_part = part_end - chunk.range_offset;
+
+            let (mut head, tail) = if chunk.len() as u64 > n_bytes_in_part {
+                let (head, tail) = chunk.split_at(n_bytes_in_part as usize);
+                (head, Some(tail))
+            } else {
+                (chunk, None)
+            };
+
+            let chunk_index = match self.current_part {
+                Some((current_part, next_chunk_index)) if current_part == part_index => {
+                    next_chunk_index
+                }
+                _ => 0,
+            };
+            self.current_part = Some((part_index, chunk_index + 1));
+
+            head.part_index = part_index;
+            head.chunk_index = chunk_index;
+            head.bytes_left = part_end - head.range_offset - head.len() as u64;
+            self.send_unsplit_chunk(head)?;
+
+            if let Some(tail) = tail {
+                chunk = tail;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn send_unsplit_chunk(&self, chunk: Chunk) -> Result<(), ()> {
         if self.results_sender.unbounded_send(Ok(chunk)).is_ok() {
             return Ok(());
         }
 
-        self.kill_switch.push_the_button();
+        self.kill_switch
+            .push_the_button(KillReason::ConsumerDropped);
 
         return Err(());
     }
 
     /// Send an error and mark as completed
-    pub fn send_err(&mut self, err: CondowError) {
+    ///
+    /// An error of kind [CondowErrorKind::Cancelled] always counts as
+    /// [KillReason::ConsumerDropped] so that the download is reported
+    /// as cancelled instead of failed.
+    pub fn send_err(&mut self, err: CondowError, reason: KillReason) {
+        let reason = if err.kind() == CondowErrorKind::Cancelled {
+            KillReason::ConsumerDropped
+        } else {
+            reason
+        };
         let _ = self.results_sender.unbounded_send(Err(err));
         self.completed = true;
-        self.kill_switch.push_the_button();
+        self.kill_switch.push_the_button(reason);
+    }
+
+    /// Handle a part which failed permanently
+    ///
+    /// The failure is reported. If failed parts are not skipped or the download
+    /// was cancelled the error is sent and `Err(())` is returned to end the
+    /// download task.
+    ///
+    /// Otherwise the bytes of the part from `offset_in_range` on are sent as zeros,
+    /// their range is recorded as missing and the download task can continue.
+    pub fn fail_part(
+        &mut self,
+        err: CondowError,
+        reason: KillReason,
+        range_request: &RangeRequest,
+        chunk_index: usize,
+        offset_in_range: u64,
+    ) -> Result<(), ()> {
+        self.retry_records.failure(
+            range_request.part_index,
+            range_request.blob_range,
+            PartError::from(&err),
+        );
+        self.reporter
+            .part_failed(&err, range_request.part_index, &range_request.blob_range);
+
+        let missing_ranges = match self.missing_ranges.as_ref() {
+            Some(missing_ranges) if err.kind() != CondowErrorKind::Cancelled => {
+                missing_ranges.clone()
+            }
+            _ => {
+                self.send_err(err, reason);
+                return Err(());
+            }
+        };
+
+        let blob_range = range_request.blob_range;
+        if offset_in_range >= blob_range.len() {
+            // All bytes were already sent
+            return Ok(());
+        }
+
+        let n_missing = match u64_to_usize(blob_range.len() - offset_in_range) {
+            Ok(n_missing) => n_missing,
+            Err(err) => {
+                self.send_err(err, reason);
+                return Err(());
+            }
+        };
+        let missing = InclusiveRange(blob_range.start() + offset_in_range, blob_range.end_incl());
+        missing_ranges.push(missing);
+
+        self.send_chunk(Chunk {
+            part_index: range_request.part_index,
+            chunk_index,
+            blob_offset: missing.start(),
+            range_offset: range_request.range_offset + offset_in_range,
+            bytes: Bytes::from(vec![0; n_missing]),
+            bytes_left: 0,
+        })
     }
 
     /// Mark the download as complete if successful
@@ -166,12 +387,14 @@ impl<R: Reporter> DownloaderContext<R> {
 impl<R: Reporter> Drop for DownloaderContext<R> {
     fn drop(&mut self) {
         if !self.completed {
-            self.kill_switch.push_the_button();
-
             let err = if std::thread::panicking() {
+                self.kill_switch.push_the_button(KillReason::Panic);
                 self.reporter.panic_detected("panic detected in downloader");
                 CondowError::new_other("download ended unexpectedly due to a panic")
+            } else if self.kill_switch.reason() == Some(KillReason::ConsumerDropped) {
+                CondowError::new_cancelled("the consumer dropped the download")
             } else {
+                self.kill_switch.push_the_button(KillReason::Aborted);
                 CondowError::new_other("download ended unexpectetly")
             };
             let _ = self.results_sender.unbounded_send(Err(err));
@@ -179,9 +402,12 @@ impl<R: Reporter> Drop for DownloaderContext<R> {
 
         self.counter.fetch_sub(1, Ordering::SeqCst);
         if self.counter.load(Ordering::SeqCst) == 0 {
-            if self.kill_switch.is_pushed() {
+            if self.kill_switch.reason() == Some(KillReason::ConsumerDropped) {
+                self.reporter.download_cancelled(self.started_at.elapsed())
+            } else if self.kill_switch.is_pushed() {
+                let reason = self.kill_switch.reason().unwrap_or(KillReason::Aborted);
                 self.reporter
-                    .download_failed(Some(self.started_at.elapsed()))
+                    .download_failed(Some(self.started_at.elapsed()), reason)
             } else {
                 self.reporter.download_completed(self.started_at.elapsed())
             }
@@ -193,32 +419,58 @@ impl<R: Reporter> Drop for DownloaderContext<R> {
 /// as [Chunk]s via the [DownloaderContext].
 ///
 /// The [RangeRequest] is only passed for reporting purposes.
+/// `part_start` is when the part was requested from the client.
 ///
 /// This function marks the [DownloaderContext] as complete via
 /// sending an error only.
 ///
+/// Empty chunks received after all bytes of the part are ignored
+/// if `tolerate_trailing_empty` is `true`. Otherwise they are an error.
+///
 /// [Bytes]: bytes::bytes
 async fn consume_and_dispatch_bytes<R: Reporter>(
     mut bytes_stream: BytesStream,
     context: &mut DownloaderContext<R>,
     range_request: RangeRequest,
+    part_start: Instant,
+    tolerate_trailing_empty: bool,
 ) -> Result<(), ()> {
     let mut chunk_index = 0;
     let mut offset_in_range = 0;
     let mut bytes_received = 0;
     let bytes_expected = range_request.blob_range.len();
-    let part_start = Instant::now();
     let mut chunk_start = Instant::now();
 
-    context
-        .reporter
-        .part_started(range_request.part_index, range_request.blob_range);
-
     while let Some(bytes_res) = bytes_stream.next().await {
         match bytes_res {
             Ok(bytes) => {
+                if bytes.is_empty() && bytes_received == bytes_expected {
+                    if tolerate_trailing_empty {
+                        continue;
+                    }
+
+                    let err = CondowError::new_other(format!(
+                        "received an empty chunk after all bytes of part {} ({}..={})",
+                        range_request.part_index,
+                        range_request.blob_range.start(),
+                        range_request.blob_range.end_incl(),
+                    ));
+                    return context.fail_part(
+                        err,
+                        KillReason::StreamError,
+                        &range_request,
+                        chunk_index,
+                        offset_in_range,
+                    );
+                }
+
                 let t_chunk = chunk_start.elapsed();
                 chunk_start = Instant::now();
+                if chunk_index == 0 {
+                    context
+                        .reporter
+                        .part_first_byte(range_request.part_index, part_start.elapsed());
+                }
                 let n_bytes = bytes.len();
                 bytes_received += bytes.len() as u64;
 
@@ -231,13 +483,13 @@ async fn consume_and_dispatch_bytes<R: Reporter>(
                         range_request.blob_range.len(),
                         bytes_received
                     ));
-                    context.reporter.part_failed(
-                        &err,
-                        range_request.part_index,
-                        &range_request.blob_range,
+                    return context.fail_part(
+                        err,
+                        KillReason::StreamError,
+                        &range_request,
+                        chunk_index,
+                        offset_in_range,
                     );
-                    context.send_err(err);
-                    return Err(());
                 }
 
                 context.reporter.chunk_completed(
@@ -259,13 +511,13 @@ async fn consume_and_dispatch_bytes<R: Reporter>(
                 offset_in_range += n_bytes as u64;
             }
             Err(IoError(msg)) => {
-                context.reporter.part_failed(
-                    &CondowError::new_io(msg.clone()),
-                    range_request.part_index,
-                    &range_request.blob_range,
+                return context.fail_part(
+                    CondowError::new_io(msg),
+                    KillReason::StreamError,
+                    &range_request,
+                    chunk_index,
+                    offset_in_range,
                 );
-                context.send_err(CondowError::new_io(msg));
-                return Err(());
             }
         }
     }
@@ -286,11 +538,13 @@ async fn consume_and_dispatch_bytes<R: Reporter>(
             range_request.blob_range.len(),
             bytes_received
         ));
-        context
-            .reporter
-            .part_failed(&err, range_request.part_index, &range_request.blob_range);
-        let _ = context.send_err(err);
-        Err(())
+        context.fail_part(
+            err,
+            KillReason::StreamError,
+            &range_request,
+            chunk_index,
+            offset_in_range,
+        )
     } else {
         Ok(())
     }
@@ -303,6 +557,7 @@ mod tests {
         time::Instant,
     };
 
+    use bytes::Bytes;
     use futures::StreamExt;
 
     use crate::{
@@ -313,13 +568,13 @@ mod tests {
         errors::{CondowError, CondowErrorKind},
         machinery::{
             download::{
-                sequential::{DownloaderContext, SequentialDownloader},
+                sequential::{consume_and_dispatch_bytes, DownloaderContext, SequentialDownloader},
                 KillSwitch,
             },
-            range_stream::RangeStream,
+            range_stream::{RangeRequest, RangeStream},
         },
-        reporter::NoReporting,
-        streams::{BytesHint, Chunk, ChunkStream},
+        reporter::{KillReason, NoReporting},
+        streams::{BytesHint, BytesStream, Chunk, ChunkStream},
         test_utils::*,
         InclusiveRange,
     };
@@ -350,45 +605,236 @@ mod tests {
         assert!(check(InclusiveRange(0, 99), client, 100).await.is_err());
     }
 
-    async fn check<C: CondowClient>(
-        range: InclusiveRange,
-        client: C,
-        part_size_bytes: u64,
-    ) -> Result<(), CondowError> {
-        let config = Config::default()
-            .buffer_size(10)
-            .buffers_full_delay_ms(0)
-            .part_size_bytes(part_size_bytes)
-            .max_concurrency(1); // Won't work otherwise
+    #[test]
+    fn a_dropped_consumer_is_the_kill_reason() {
+        let (result_stream, results_sender) = ChunkStream::new(BytesHint::new_no_hint());
+        let kill_switch = KillSwitch::new();
+        let mut context = DownloaderContext::new(
+            results_sender,
+            Arc::new(AtomicUsize::new(0)),
+            kill_switch.clone(),
+            NoReporting,
+            Instant::now(),
+        );
+        drop(result_stream);
+
+        let chunk = Chunk {
+            part_index: 0,
+            chunk_index: 0,
+            blob_offset: 0,
+            range_offset: 0,
+            bytes: vec![0].into(),
+            bytes_left: 0,
+        };
+        assert!(context.send_chunk(chunk).is_err());
+        context.send_err(CondowError::new_other("later"), KillReason::StreamError);
+
+        assert!(kill_switch.is_pushed());
+        assert_eq!(kill_switch.reason(), Some(KillReason::ConsumerDropped));
+    }
 
-        let bytes_hint = BytesHint::new(range.len(), Some(range.len()));
+    #[tokio::test]
+    async fn a_cancelled_error_is_reported_as_a_dropped_consumer() {
+        let (result_stream, results_sender) = ChunkStream::new(BytesHint::new_no_hint());
+        let kill_switch = KillSwitch::new();
+        let mut context = DownloaderContext::new(
+            results_sender,
+            Arc::new(AtomicUsize::new(0)),
+            kill_switch.clone(),
+            NoReporting,
+            Instant::now(),
+        );
 
-        let (_n_parts, mut ranges_stream) =
-            RangeStream::create(range, config.part_size_bytes.into());
+        context.send_err(
+            CondowError::new_cancelled("cancelled"),
+            KillReason::StreamError,
+        );
+        drop(context);
 
-        let (result_stream, results_sender) = ChunkStream::new(bytes_hint);
+        assert_eq!(kill_switch.reason(), Some(KillReason::ConsumerDropped));
+        let items = result_stream.collect::<Vec<_>>().await;
+        let err = items.into_iter().next().unwrap().unwrap_err();
+        assert_eq!(err.kind(), CondowErrorKind::Cancelled);
+    }
 
-        let mut downloader = SequentialDownloader::new(
-            client.into(),
-            url::Url::parse("noscheme://").expect("a valid url"),
-            config.buffer_size.into(),
-            DownloaderContext::new(
-                results_sender,
-                Arc::new(AtomicUsize::new(0)),
-                KillSwitch::new(),
-                NoReporting,
-                Instant::now(),
-            ),
+    #[tokio::test]
+    async fn an_incomplete_download_after_a_dropped_consumer_is_cancelled() {
+        let (result_stream, results_sender) = ChunkStream::new(BytesHint::new_no_hint());
+        let kill_switch = KillSwitch::new();
+        let context = DownloaderContext::new(
+            results_sender,
+            Arc::new(AtomicUsize::new(0)),
+            kill_switch.clone(),
+            NoReporting,
+            Instant::now(),
         );
 
-        while let Some(next) = ranges_stream.next().await {
-            let _ = downloader.enqueue(next).unwrap();
+        kill_switch.push_the_button(KillReason::ConsumerDropped);
+        drop(context);
+
+        assert_eq!(kill_switch.reason(), Some(KillReason::ConsumerDropped));
+        let items = result_stream.collect::<Vec<_>>().await;
+        let err = items.into_iter().next().unwrap().unwrap_err();
+        assert_eq!(err.kind(), CondowErrorKind::Cancelled);
+        assert!(!err.is_retryable());
+    }
+
+    #[tokio::test]
+    async fn trailing_empty_chunk_is_ignored_if_tolerated() {
+        let (result, chunks) = consume_with_trailing_empty_chunk(true).await;
+
+        assert!(result.is_ok());
+        let chunks = chunks.into_iter().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].bytes.as_ref(), &[0, 1, 2, 3, 4]);
+        assert!(chunks[0].is_last());
+    }
+
+    #[tokio::test]
+    async fn trailing_empty_chunk_fails_if_not_tolerated() {
+        let (result, chunks) = consume_with_trailing_empty_chunk(false).await;
+
+        assert!(result.is_err());
+        assert!(chunks[0].is_ok());
+        assert!(chunks[1].is_err());
+    }
+
+    #[tokio::test]
+    async fn the_chunks_of_a_final_part_with_a_remainder_are_exact() {
+        // The last part of 0..=10 in parts of 3
+        let range_request = RangeRequest {
+            part_index: 3,
+            blob_range: InclusiveRange(9, 10),
+            range_offset: 9,
+        };
+
+        let (result, chunks) = consume(range_request.clone(), vec![vec![9], vec![10]]).await;
+        assert!(result.is_ok());
+        let chunks = chunks.into_iter().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(
+            chunks
+                .iter()
+                .map(|c| (c.part_index, c.chunk_index, c.blob_offset, c.range_offset))
+                .collect::<Vec<_>>(),
+            vec![(3, 0, 9, 9), (3, 1, 10, 10)]
+        );
+        assert_eq!(chunks[0].bytes_left, 1);
+        assert!(!chunks[0].is_last());
+        assert_eq!(chunks[1].bytes_left, 0);
+        assert!(chunks[1].is_last());
+
+        let (result, chunks) = consume(range_request.clone(), vec![vec![9, 10]]).await;
+        assert!(result.is_ok());
+        let chunks = chunks.into_iter().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].len(), 2);
+        assert!(chunks[0].is_last());
+
+        // A full sized part is too long for the final part
+        let (result, _chunks) = consume(range_request, vec![vec![9, 10, 11]]).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn bytes_left_are_exact_for_all_parts_with_a_remainder() {
+        for max_chunk_size in 1..5 {
+            let client = TestCondowClient::new().max_chunk_size(max_chunk_size);
+            let range = InclusiveRange(0, 10);
+            let chunks = download_chunks(range, client, 3).await.unwrap();
+
+            let mut lens_by_part = vec![0u64; 4];
+            for chunk in &chunks {
+                let part_len = if chunk.part_index == 3 { 2 } else { 3 };
+                let offset_in_part = chunk.range_offset - chunk.part_index * 3;
+                assert_eq!(
+                    chunk.bytes_left,
+                    part_len - offset_in_part - chunk.len() as u64,
+                    "max_chunk_size={}, chunk={:?}",
+                    max_chunk_size,
+                    chunk
+                );
+                lens_by_part[chunk.part_index as usize] += chunk.len() as u64;
+            }
+            assert_eq!(lens_by_part, vec![3, 3, 3, 2]);
+            assert_eq!(chunks.iter().filter(|c| c.is_last()).count(), 4);
         }
+    }
 
-        drop(downloader); // Ends the stream
+    /// Dispatch `chunks` as the bytes of `range_request` and return the sent chunks
+    async fn consume(
+        range_request: RangeRequest,
+        chunks: Vec<Vec<u8>>,
+    ) -> (Result<(), ()>, Vec<Result<Chunk, CondowError>>) {
+        let (result_stream, results_sender) =
+            ChunkStream::new(BytesHint::new_exact(range_request.blob_range.len()));
+        let mut context = DownloaderContext::new(
+            results_sender,
+            Arc::new(AtomicUsize::new(0)),
+            KillSwitch::new(),
+            NoReporting,
+            Instant::now(),
+        );
+        let bytes_stream: BytesStream =
+            futures::stream::iter(chunks.into_iter().map(|c| Ok(Bytes::from(c)))).boxed();
+
+        let result = consume_and_dispatch_bytes(
+            bytes_stream,
+            &mut context,
+            range_request,
+            Instant::now(),
+            false,
+        )
+        .await;
+        if result.is_ok() {
+            context.mark_successful();
+        }
+        drop(context);
 
-        let result = result_stream.collect::<Vec<_>>().await;
-        let result = result.into_iter().collect::<Result<Vec<_>, _>>()?;
+        (result, result_stream.collect().await)
+    }
+
+    async fn consume_with_trailing_empty_chunk(
+        tolerate_trailing_empty: bool,
+    ) -> (Result<(), ()>, Vec<Result<Chunk, CondowError>>) {
+        let (result_stream, results_sender) = ChunkStream::new(BytesHint::new_exact(5));
+        let mut context = DownloaderContext::new(
+            results_sender,
+            Arc::new(AtomicUsize::new(0)),
+            KillSwitch::new(),
+            NoReporting,
+            Instant::now(),
+        );
+        let bytes_stream: BytesStream =
+            futures::stream::iter(vec![Ok(Bytes::from(vec![0, 1, 2, 3, 4])), Ok(Bytes::new())])
+                .boxed();
+        let range_request = RangeRequest {
+            part_index: 0,
+            blob_range: InclusiveRange(0, 4),
+            range_offset: 0,
+        };
+
+        let result = consume_and_dispatch_bytes(
+            bytes_stream,
+            &mut context,
+            range_request,
+            Instant::now(),
+            tolerate_trailing_empty,
+        )
+        .await;
+        if result.is_ok() {
+            context.mark_successful();
+        }
+        drop(context);
+
+        (result, result_stream.collect().await)
+    }
+
+    async fn check<C: CondowClient>(
+        range: InclusiveRange,
+        client: C,
+        part_size_bytes: u64,
+    ) -> Result<(), CondowError> {
+        let result = download_chunks(range, client, part_size_bytes).await?;
 
         let total_bytes: u64 = result.iter().map(|c| c.bytes.len() as u64).sum();
         assert_eq!(total_bytes, range.len(), "total_bytes");
@@ -420,4 +866,47 @@ mod tests {
 
         Ok(())
     }
+
+    /// Download `range` in parts of `part_size_bytes` with a single [SequentialDownloader]
+    async fn download_chunks<C: CondowClient>(
+        range: InclusiveRange,
+        client: C,
+        part_size_bytes: u64,
+    ) -> Result<Vec<Chunk>, CondowError> {
+        let config = Config::default()
+            .buffer_size(10)
+            .buffers_full_delay_ms(0)
+            .part_size_bytes(part_size_bytes)
+            .max_concurrency(1); // Won't work otherwise
+
+        let bytes_hint = BytesHint::new(range.len(), Some(range.len()));
+
+        let (_n_parts, mut ranges_stream) =
+            RangeStream::create(range, config.part_size_bytes.into());
+
+        let (result_stream, results_sender) = ChunkStream::new(bytes_hint);
+
+        let mut downloader = SequentialDownloader::new(
+            client.into(),
+            url::Url::parse("noscheme://").expect("a valid url"),
+            config.buffer_size.into(),
+            config.tolerate_trailing_empty.into_inner(),
+            DownloaderContext::new(
+                results_sender,
+                Arc::new(AtomicUsize::new(0)),
+                KillSwitch::new(),
+                NoReporting,
+                Instant::now(),
+            ),
+        );
+
+        while let Some(next) = ranges_stream.next().await {
+            let _ = downloader.enqueue(next).unwrap();
+        }
+
+        drop(downloader); // Ends the stream
+
+        let result = result_stream.collect::<Vec<_>>().await;
+        result.into_iter().collect()
+    }
 }
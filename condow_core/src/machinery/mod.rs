@@ -1,17 +1,32 @@
 //! Streams for handling downloads
 
-use crate::condow_client::CondowClient;
-use crate::config::{ClientRetryWrapper, Config};
-use crate::errors::CondowError;
-use crate::streams::{BytesHint, ChunkStream};
-use crate::Reporter;
-use crate::{Condow, DownloadRange, GetSizeMode, InclusiveRange, StreamWithReport};
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use bytes::Bytes;
+use futures::{ready, stream, Stream, StreamExt};
+use pin_project_lite::pin_project;
+
+use crate::condow_client::{CondowClient, DownloadSpec};
+use crate::config::{ClientRetryWrapper, Config, OnPartFailure, OutOfBoundsPolicy};
+use crate::errors::{CondowError, IoError};
+use crate::reporter::{KillReason, Reporter};
+use crate::streams::{BytesHint, BytesStream, ChunkStream, DownloadMode};
+use crate::{
+    ClosedRange, Condow, DownloadRange, GetSizeMode, InclusiveRange, OpenRange, PartBoundaries,
+    StreamWithReport,
+};
 
 use self::range_stream::RangeStream;
 
 mod download;
 mod range_stream;
 
+pub use range_stream::RangeRequest;
+
 pub async fn download<C: CondowClient, DR: Into<DownloadRange>, R: Reporter>(
     condow: &Condow<C>,
     location: url::Url,
@@ -22,7 +37,7 @@ pub async fn download<C: CondowClient, DR: Into<DownloadRange>, R: Reporter>(
     download_range(condow, location, range, get_size_mode, reporter.clone())
         .await
         .map_err(|err| {
-            reporter.download_failed(None);
+            reporter.download_failed(None, KillReason::NotStarted);
             err
         })
 }
@@ -42,10 +57,24 @@ pub async fn download_range<C: CondowClient, DR: Into<DownloadRange>, R: Reporte
         return Ok(StreamWithReport::new(ChunkStream::empty(), reporter));
     };
 
+    // All requests of a download are made with the same client
+    let client = condow.client.for_download().await?;
+
+    // The size of the BLOB if it was requested
+    let mut blob_size = None;
+
     let (inclusive_range, bytes_hint) = match range {
         DownloadRange::Open(or) => {
-            let size = condow.client.get_size(location.clone(), &reporter).await?;
-            if let Some(range) = or.incl_range_from_size(size) {
+            let size = client
+                .get_size(
+                    location.clone(),
+                    condow.config.size_request_timeout(),
+                    &reporter,
+                )
+                .await?;
+            blob_size = Some(size);
+            let range = DownloadRange::Open(or);
+            if let Some(range) = range_from_size(range, size, condow.config.out_of_bounds_policy)? {
                 (range, BytesHint::new_exact(range.len()))
             } else {
                 return Ok(StreamWithReport::new(ChunkStream::empty(), reporter));
@@ -53,8 +82,18 @@ pub async fn download_range<C: CondowClient, DR: Into<DownloadRange>, R: Reporte
         }
         DownloadRange::Closed(cl) => {
             if get_size_mode.is_load_size_enforced(condow.config.always_get_size) {
-                let size = condow.client.get_size(location.clone(), &reporter).await?;
-                if let Some(range) = cl.incl_range_from_size(size) {
+                let size = client
+                    .get_size(
+                        location.clone(),
+                        condow.config.size_request_timeout(),
+                        &reporter,
+                    )
+                    .await?;
+                blob_size = Some(size);
+                let range = DownloadRange::Closed(cl);
+                if let Some(range) =
+                    range_from_size(range, size, condow.config.out_of_bounds_policy)?
+                {
                     (range, BytesHint::new_exact(range.len()))
                 } else {
                     return Ok(StreamWithReport::new(ChunkStream::empty(), reporter));
@@ -67,8 +106,20 @@ pub async fn download_range<C: CondowClient, DR: Into<DownloadRange>, R: Reporte
         }
     };
 
+    if matches!(blob_size, Some(size) if size < condow.config.concurrency_min_bytes.into_inner()) {
+        let stream = download_chunks_with_single_request(
+            client,
+            location,
+            inclusive_range,
+            bytes_hint,
+            condow.config.clone(),
+            reporter.clone(),
+        );
+        return Ok(StreamWithReport { reporter, stream });
+    }
+
     let stream = download_chunks(
-        condow.client.clone(),
+        client,
         location,
         inclusive_range,
         bytes_hint,
@@ -80,6 +131,257 @@ pub async fn download_range<C: CondowClient, DR: Into<DownloadRange>, R: Reporte
     Ok(StreamWithReport { reporter, stream })
 }
 
+/// Download a range of a BLOB whose size is already known
+///
+/// No size request is made regardless of the [GetSizeMode] or the [Config].
+/// Open ranges are resolved and closed ranges are clamped with `size`.
+pub async fn download_with_known_size<C: CondowClient, DR: Into<DownloadRange>, R: Reporter>(
+    condow: &Condow<C>,
+    location: url::Url,
+    range: DR,
+    size: u64,
+    reporter: R,
+) -> Result<StreamWithReport<ChunkStream, R>, CondowError> {
+    download_range_with_known_size(condow, location, range, size, reporter.clone())
+        .await
+        .inspect_err(|_| reporter.download_failed(None, KillReason::NotStarted))
+}
+
+async fn download_range_with_known_size<C: CondowClient, DR: Into<DownloadRange>, R: Reporter>(
+    condow: &Condow<C>,
+    location: url::Url,
+    range: DR,
+    size: u64,
+    reporter: R,
+) -> Result<StreamWithReport<ChunkStream, R>, CondowError> {
+    let range: DownloadRange = range.into();
+    range.validate()?;
+    let range = if let Some(range) = range.sanitized() {
+        range
+    } else {
+        return Ok(StreamWithReport::new(ChunkStream::empty(), reporter));
+    };
+
+    let inclusive_range =
+        if let Some(range) = range_from_size(range, size, condow.config.out_of_bounds_policy)? {
+            range
+        } else {
+            return Ok(StreamWithReport::new(ChunkStream::empty(), reporter));
+        };
+
+    let client = condow.client.for_download().await?;
+
+    let stream = download_chunks(
+        client,
+        location,
+        inclusive_range,
+        BytesHint::new_exact(inclusive_range.len()),
+        condow.config.clone(),
+        reporter.clone(),
+    )
+    .await?;
+
+    Ok(StreamWithReport { reporter, stream })
+}
+
+/// Resolves `range` with the size of the BLOB
+///
+/// Returns `None` if there is nothing to download.
+fn range_from_size(
+    range: DownloadRange,
+    size: u64,
+    out_of_bounds_policy: OutOfBoundsPolicy,
+) -> Result<Option<InclusiveRange>, CondowError> {
+    match range {
+        DownloadRange::Open(or) => {
+            if out_of_bounds_policy == OutOfBoundsPolicy::Error {
+                check_open_in_bounds(or, size)?;
+            }
+            Ok(or.incl_range_from_size(size))
+        }
+        DownloadRange::Closed(cl) => {
+            if out_of_bounds_policy == OutOfBoundsPolicy::Error {
+                check_in_bounds(cl, size)?;
+            }
+            Ok(cl.incl_range_from_size(size))
+        }
+    }
+}
+
+/// Fails if the upper bound of the range is beyond the end of the BLOB
+fn check_in_bounds(range: ClosedRange, size: u64) -> Result<(), CondowError> {
+    if let Some(incl_range) = range.incl_range() {
+        if incl_range.end_incl() >= size {
+            return Err(CondowError::new_invalid_range(format!(
+                "range {} is out of bounds for a BLOB of {} bytes",
+                range, size
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Fails if an open range starts beyond the last byte of the BLOB
+///
+/// A range starting at 0 is the complete BLOB and never fails.
+fn check_open_in_bounds(range: OpenRange, size: u64) -> Result<(), CondowError> {
+    if let OpenRange::From(start) = range {
+        if start > 0 && start >= size {
+            return Err(CondowError::new_invalid_range(format!(
+                "range {} is out of bounds for a BLOB of {} bytes",
+                range, size
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Download a range of a BLOB with a single request.
+///
+/// The concurrent machinery is bypassed. A range to the end of the BLOB
+/// is downloaded with [CondowClient::download_from] which makes a size
+/// request only if the client does not support open ranges.
+pub async fn download_raw<C: CondowClient, DR: Into<DownloadRange>, R: Reporter>(
+    condow: &Condow<C>,
+    location: url::Url,
+    range: DR,
+    reporter: R,
+) -> Result<BytesStream, CondowError> {
+    let range: DownloadRange = range.into();
+    range.validate()?;
+    let range = if let Some(range) = range.sanitized() {
+        range
+    } else {
+        return Ok(stream::empty().boxed());
+    };
+
+    let client = condow.client.for_download().await?;
+
+    let spec = match range {
+        DownloadRange::Open(OpenRange::Full) => DownloadSpec::Complete,
+        DownloadRange::Open(OpenRange::From(start)) => {
+            // The client streams the tail without a size request if it can
+            return client
+                .download_from(location, start, &reporter)
+                .await
+                .map(|(stream, _bytes_hint)| stream);
+        }
+        DownloadRange::Closed(cl) => {
+            if let Some(range) = cl.incl_range() {
+                DownloadSpec::Range(range)
+            } else {
+                return Ok(stream::empty().boxed());
+            }
+        }
+    };
+
+    client
+        .download(location, spec, &reporter)
+        .await
+        .map(|(stream, _bytes_hint)| stream)
+}
+
+/// Download the BLOB from `start` to its end with a single request.
+///
+/// The request is made with [CondowClient::download_from] which makes
+/// a size request only if the client does not support open ranges.
+/// The returned [BytesHint] is the one given by the client.
+///
+/// The download is reported as completed once the returned stream ended.
+pub async fn download_from_sequential<C: CondowClient, R: Reporter>(
+    condow: &Condow<C>,
+    location: url::Url,
+    start: u64,
+    reporter: R,
+) -> Result<(BytesStream, BytesHint), CondowError> {
+    let started_at = Instant::now();
+    reporter.download_started();
+
+    match condow
+        .client
+        .download_from(location, start, &reporter)
+        .await
+    {
+        Ok((stream, bytes_hint)) => {
+            let stream = ReportingBytesStream {
+                stream,
+                outcome: Some(DownloadOutcome {
+                    reporter,
+                    started_at,
+                    is_reported: false,
+                }),
+            };
+            Ok((stream.boxed(), bytes_hint))
+        }
+        Err(err) => {
+            reporter.download_failed(Some(started_at.elapsed()), KillReason::ClientError);
+            Err(err)
+        }
+    }
+}
+
+pin_project! {
+    /// Reports the end of a download with a single request
+    /// once its stream ended, failed or was dropped
+    struct ReportingBytesStream<R: Reporter> {
+        stream: BytesStream,
+        outcome: Option<DownloadOutcome<R>>,
+    }
+}
+
+impl<R: Reporter> Stream for ReportingBytesStream<R> {
+    type Item = Result<Bytes, IoError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        let next = ready!(this.stream.poll_next_unpin(cx));
+
+        match next {
+            Some(Ok(_)) => {}
+            Some(Err(_)) => {
+                if let Some(outcome) = this.outcome.take() {
+                    outcome.failed(KillReason::StreamError);
+                }
+            }
+            None => {
+                if let Some(outcome) = this.outcome.take() {
+                    outcome.completed();
+                }
+            }
+        }
+
+        Poll::Ready(next)
+    }
+}
+
+/// Reports the download as cancelled when dropped before the outcome was reported
+struct DownloadOutcome<R: Reporter> {
+    reporter: R,
+    started_at: Instant,
+    is_reported: bool,
+}
+
+impl<R: Reporter> DownloadOutcome<R> {
+    fn completed(mut self) {
+        self.is_reported = true;
+        self.reporter.download_completed(self.started_at.elapsed());
+    }
+
+    fn failed(mut self, reason: KillReason) {
+        self.is_reported = true;
+        self.reporter
+            .download_failed(Some(self.started_at.elapsed()), reason);
+    }
+}
+
+impl<R: Reporter> Drop for DownloadOutcome<R> {
+    fn drop(&mut self) {
+        if !self.is_reported {
+            self.reporter.download_cancelled(self.started_at.elapsed());
+        }
+    }
+}
+
 async fn download_chunks<C: CondowClient, R: Reporter>(
     client: ClientRetryWrapper<C>,
     location: url::Url,
@@ -90,35 +392,105 @@ async fn download_chunks<C: CondowClient, R: Reporter>(
 ) -> Result<ChunkStream, CondowError> {
     reporter.effective_range(range);
 
-    let (n_parts, ranges_stream) = RangeStream::create(range, config.part_size_bytes.into());
+    let part_size_bytes = config.effective_part_size_bytes(range.len());
+    let request_coalescing = config.request_coalescing.into_inner() as u64;
+    let request_size_bytes = part_size_bytes.saturating_mul(request_coalescing);
+    let boundaries = PartBoundaries::new(range, request_size_bytes);
+    reporter.parts_planned(boundaries);
+    let (n_requests, ranges_stream) = RangeStream::from_boundaries(boundaries);
 
-    if n_parts == 0 {
-        panic!("n_parts must not be 0. This is a bug");
+    if n_requests == 0 {
+        panic!("n_requests must not be 0. This is a bug");
     }
 
-    let (chunk_stream, sender) = ChunkStream::new(bytes_hint);
+    // The parts of the stream, each request covers `request_coalescing` of them
+    let n_parts = range_stream::calc_num_parts(range, part_size_bytes);
+    let split_part_size_bytes = if request_coalescing > 1 {
+        Some(part_size_bytes)
+    } else {
+        None
+    };
 
-    if n_parts > usize::MAX as u64 {
+    let (mut chunk_stream, sender) = ChunkStream::new(bytes_hint);
+    chunk_stream.set_n_parts(n_parts);
+    chunk_stream.set_max_preallocation_bytes(config.max_preallocation_bytes.into_inner());
+
+    if n_requests > usize::MAX as u64 {
         return Err(CondowError::new_other(
             "usize overflow while casting from u64",
         ));
     }
-    let n_parts = n_parts as usize;
+    let n_requests = n_requests as usize;
+
+    let n_concurrent = config.max_concurrency.into_inner().min(n_requests);
+    chunk_stream.set_download_mode(DownloadMode::from_workers(n_concurrent));
+
+    let missing_ranges = match config.on_part_failure {
+        OnPartFailure::Abort => None,
+        OnPartFailure::Skip => Some(chunk_stream.missing_ranges_handle()),
+    };
 
-    tokio::spawn(async move {
+    let retry_records = chunk_stream.retry_records_handle();
+
+    crate::helpers::spawn(async move {
         download::download_concurrently(
             ranges_stream,
-            config.max_concurrency.into_inner().min(n_parts),
+            n_concurrent,
             sender,
             client,
             config,
             location,
             reporter,
+            missing_ranges,
+            split_part_size_bytes,
+            retry_records,
         )
         .await
     });
 
     Ok(chunk_stream)
 }
+/// Download `range` as a single part with a single request
+///
+/// Used instead of [download_chunks] for small BLOBs (see
+/// [Config::concurrency_min_bytes]). No download tasks are spawned,
+/// the request is made by the task feeding the [ChunkStream].
+fn download_chunks_with_single_request<C: CondowClient, R: Reporter>(
+    client: ClientRetryWrapper<C>,
+    location: url::Url,
+    range: InclusiveRange,
+    bytes_hint: BytesHint,
+    config: Config,
+    reporter: R,
+) -> ChunkStream {
+    reporter.effective_range(range);
+    reporter.parts_planned(PartBoundaries::new(range, range.len()));
+
+    let (mut chunk_stream, sender) = ChunkStream::new(bytes_hint);
+    chunk_stream.set_n_parts(1);
+    chunk_stream.set_max_preallocation_bytes(config.max_preallocation_bytes.into_inner());
+    chunk_stream.set_download_mode(DownloadMode::Sequential);
+
+    let missing_ranges = match config.on_part_failure {
+        OnPartFailure::Abort => None,
+        OnPartFailure::Skip => Some(chunk_stream.missing_ranges_handle()),
+    };
+
+    let retry_records = chunk_stream.retry_records_handle();
+
+    crate::helpers::spawn(download::download_single_request(
+        range,
+        sender,
+        client,
+        config,
+        location,
+        reporter,
+        missing_ranges,
+        retry_records,
+    ));
+
+    chunk_stream
+}
+
 #[cfg(test)]
 mod tests;
@@ -1,18 +1,53 @@
+use std::fmt;
+
 use futures::Stream;
 
-use crate::InclusiveRange;
+use crate::{InclusiveRange, PartBoundaries};
 
 /// A request to downlaod a range.
 ///
 /// This is usually a part of a download.
-#[derive(Debug)]
+///
+/// A [RangeRequest] can only be inspected from outside of this crate,
+/// e.g. by a [PartPriority](crate::config::PartPriority) to decide on the
+/// order in which pending parts are downloaded.
+///
+/// The [Display](fmt::Display) output looks like `part 2 [30,39] at offset 20`.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RangeRequest {
     /// Index of the part
-    pub part_index: u64,
+    pub(crate) part_index: u64,
     /// The range to be downloaded from the BLOB.
-    pub blob_range: InclusiveRange,
+    pub(crate) blob_range: InclusiveRange,
+    /// Offset of the part within the downloaded range
+    pub(crate) range_offset: u64,
+}
+
+impl RangeRequest {
+    /// Index of the part within the download
+    pub fn part_index(&self) -> u64 {
+        self.part_index
+    }
+
+    /// The range to be downloaded from the BLOB
+    pub fn blob_range(&self) -> InclusiveRange {
+        self.blob_range
+    }
+
     /// Offset of the part within the downloaded range
-    pub range_offset: u64,
+    pub fn range_offset(&self) -> u64 {
+        self.range_offset
+    }
+}
+
+impl fmt::Display for RangeRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "part {} {} at offset {}",
+            self.part_index, self.blob_range, self.range_offset
+        )
+    }
 }
 
 #[cfg(test)]
@@ -25,6 +60,8 @@ impl RangeRequest {
 pub struct RangeStream;
 
 impl RangeStream {
+    /// Create a stream of the [RangeRequest]s for the parts of `range`
+    #[cfg(test)]
     pub fn create(
         range: InclusiveRange,
         part_size: u64,
@@ -33,44 +70,38 @@ impl RangeStream {
             panic!("part_size must not be 0. This is a bug.");
         }
 
-        let mut start = range.start();
-        let mut next_range_offset = 0;
-
-        let num_parts = calc_num_parts(range, part_size);
-
-        let mut counter = 0;
-        let iter = std::iter::from_fn(move || {
-            if start > range.end_incl() {
-                return None;
-            }
-
-            let current_end_incl = (start + part_size - 1).min(range.end_incl());
-            let blob_range = InclusiveRange(start, current_end_incl);
-            start = current_end_incl + 1;
+        Self::from_boundaries(PartBoundaries::new(range, part_size))
+    }
 
-            let res = Some(RangeRequest {
-                part_index: counter,
-                blob_range,
-                range_offset: next_range_offset,
+    /// Create a stream of the [RangeRequest]s for the parts given by `boundaries`
+    ///
+    /// The requests are exactly the ranges of [PartBoundaries::iter].
+    ///
+    /// The requests are created lazily when pulled from the stream.
+    /// Since the downloader pulls a request only when it can be handed over
+    /// to a download task, the number of outstanding requests is bounded
+    /// by the concurrency and the buffer sizes, not by the number of parts.
+    pub fn from_boundaries(boundaries: PartBoundaries) -> (u64, impl Stream<Item = RangeRequest>) {
+        let mut next_range_offset = 0;
+        let iter = boundaries
+            .iter()
+            .enumerate()
+            .map(move |(part_index, blob_range)| {
+                let req = RangeRequest {
+                    part_index: part_index as u64,
+                    blob_range,
+                    range_offset: next_range_offset,
+                };
+                next_range_offset += blob_range.len();
+                req
             });
 
-            next_range_offset += blob_range.len();
-            counter += 1;
-
-            res
-        });
-
-        (num_parts, futures::stream::iter(iter))
+        (boundaries.n_parts(), futures::stream::iter(iter))
     }
 }
 
-fn calc_num_parts(range: InclusiveRange, part_size: u64) -> u64 {
-    let mut n_parts = range.len() / part_size;
-    if range.len() % part_size != 0 {
-        n_parts += 1;
-    }
-
-    n_parts
+pub(super) fn calc_num_parts(range: InclusiveRange, part_size: u64) -> u64 {
+    PartBoundaries::new(range, part_size).n_parts()
 }
 
 #[test]
@@ -282,3 +313,156 @@ async fn test_n_parts_vs_stream_count() {
         }
     }
 }
+
+#[tokio::test]
+async fn stream_boundaries_match_split_into_parts() {
+    use futures::StreamExt as _;
+
+    /// The splitting of the parts as originally done by [RangeStream::create]
+    ///
+    /// Kept as an independent reference for the shared splitting logic.
+    fn reference_parts(range: InclusiveRange, part_size: u64) -> Vec<InclusiveRange> {
+        let mut start = range.start();
+        std::iter::from_fn(move || {
+            if start > range.end_incl() {
+                return None;
+            }
+
+            let current_end_incl = (start + part_size - 1).min(range.end_incl());
+            let blob_range = InclusiveRange(start, current_end_incl);
+            start = current_end_incl + 1;
+
+            Some(blob_range)
+        })
+        .collect()
+    }
+
+    for part_size in 1..30 {
+        for start in 0..30 {
+            for end_offset in 0..30 {
+                let range = InclusiveRange(start, start + end_offset);
+                let expected = reference_parts(range, part_size);
+
+                let (n_parts, stream) = RangeStream::create(range, part_size);
+                let from_stream = stream.map(|r| r.blob_range).collect::<Vec<_>>().await;
+                let from_split = range.split_into_parts(part_size).collect::<Vec<_>>();
+
+                assert_eq!(
+                    from_stream, expected,
+                    "stream: part_size={} range={}",
+                    part_size, range
+                );
+                assert_eq!(
+                    from_split, expected,
+                    "split: part_size={} range={}",
+                    part_size, range
+                );
+                assert_eq!(
+                    n_parts,
+                    expected.len() as u64,
+                    "n_parts: part_size={} range={}",
+                    part_size,
+                    range
+                );
+                assert_eq!(
+                    calc_num_parts(range, part_size),
+                    expected.len() as u64,
+                    "calc_num_parts: part_size={} range={}",
+                    part_size,
+                    range
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn split_into_parts_has_the_expected_boundaries() {
+    let range = InclusiveRange(5, 27);
+
+    assert_eq!(
+        range.split_into_parts(10).collect::<Vec<_>>(),
+        vec![
+            InclusiveRange(5, 14),
+            InclusiveRange(15, 24),
+            InclusiveRange(25, 27)
+        ]
+    );
+    assert_eq!(calc_num_parts(range, 10), 3);
+
+    assert_eq!(
+        range.split_into_parts(23).collect::<Vec<_>>(),
+        vec![InclusiveRange(5, 27)]
+    );
+    assert_eq!(calc_num_parts(range, 23), 1);
+
+    assert_eq!(
+        range.split_into_parts(22).collect::<Vec<_>>(),
+        vec![InclusiveRange(5, 26), InclusiveRange(27, 27)]
+    );
+    assert_eq!(calc_num_parts(range, 22), 2);
+}
+
+#[tokio::test]
+async fn a_final_part_with_a_remainder_has_exactly_the_remaining_bytes() {
+    use futures::StreamExt as _;
+
+    // 11 bytes in parts of 3 leave a remainder of 2
+    let (n_parts, stream) = RangeStream::create(InclusiveRange(0, 10), 3);
+    let requests = stream.collect::<Vec<_>>().await;
+
+    assert_eq!(n_parts, 4);
+    assert_eq!(
+        requests,
+        vec![
+            RangeRequest {
+                part_index: 0,
+                blob_range: InclusiveRange(0, 2),
+                range_offset: 0
+            },
+            RangeRequest {
+                part_index: 1,
+                blob_range: InclusiveRange(3, 5),
+                range_offset: 3
+            },
+            RangeRequest {
+                part_index: 2,
+                blob_range: InclusiveRange(6, 8),
+                range_offset: 6
+            },
+            RangeRequest {
+                part_index: 3,
+                blob_range: InclusiveRange(9, 10),
+                range_offset: 9
+            },
+        ]
+    );
+    assert_eq!(requests[3].len(), 2);
+
+    // The same with a range not starting at 0
+    let (n_parts, stream) = RangeStream::create(InclusiveRange(5, 15), 3);
+    let last = stream.collect::<Vec<_>>().await.pop().unwrap();
+
+    assert_eq!(n_parts, 4);
+    assert_eq!(
+        last,
+        RangeRequest {
+            part_index: 3,
+            blob_range: InclusiveRange(14, 15),
+            range_offset: 9
+        }
+    );
+}
+
+#[tokio::test]
+async fn range_request_display() {
+    use futures::StreamExt as _;
+
+    let (_n_parts, stream) = RangeStream::create(InclusiveRange(10, 39), 10);
+    let requests = stream.collect::<Vec<_>>().await;
+
+    assert_eq!(requests[2].part_index(), 2);
+    assert_eq!(requests[2].blob_range(), InclusiveRange(30, 39));
+    assert_eq!(requests[2].range_offset(), 20);
+    assert_eq!(requests[2].to_string(), "part 2 [30,39] at offset 20");
+}
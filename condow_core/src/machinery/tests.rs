@@ -259,11 +259,45 @@ mod download {
 }
 
 mod download_chunks {
+    use futures::StreamExt;
+
     use crate::{
         condow_client::NoLocation, config::Config, machinery::download_chunks,
         reporter::NoReporting, streams::BytesHint, test_utils::*, InclusiveRange,
     };
 
+    #[tokio::test]
+    async fn buffer_size_0() {
+        let client = TestCondowClient::new().max_chunk_size(3);
+        let data = client.data();
+
+        let config = Config::default()
+            .buffer_size(0)
+            .buffers_full_delay_ms(0)
+            .part_size_bytes(7)
+            .max_concurrency(2)
+            .validated()
+            .unwrap();
+
+        let range = InclusiveRange(3, 97);
+        let bytes_hint = BytesHint::new(range.len(), Some(range.len()));
+
+        let result_stream = download_chunks(
+            client.into(),
+            url::Url::parse("noscheme://").expect("a valid URL"),
+            range,
+            bytes_hint,
+            config,
+            NoReporting,
+        )
+        .await
+        .unwrap();
+
+        let result = result_stream.into_vec().await.unwrap();
+
+        assert_eq!(&result, &data[range.to_std_range_usize()]);
+    }
+
     #[tokio::test]
     async fn from_0_to_inclusive_range_smaller_than_part_size() {
         let buffer_size = 10;
@@ -356,4 +390,79 @@ mod download_chunks {
 
         assert_eq!(&result, &data[range.to_std_range_usize()]);
     }
+
+    #[tokio::test]
+    async fn download_mode() {
+        use crate::streams::{DownloadMode, PartStream};
+
+        let client = TestCondowClient::new().max_chunk_size(3);
+
+        let config = Config::default()
+            .buffers_full_delay_ms(0)
+            .part_size_bytes(10)
+            .max_concurrency(4);
+
+        for (range, expected) in [
+            (InclusiveRange(0, 9), DownloadMode::Sequential),
+            (
+                InclusiveRange(0, 19),
+                DownloadMode::Concurrent { workers: 2 },
+            ),
+            (
+                InclusiveRange(0, 99),
+                DownloadMode::Concurrent { workers: 4 },
+            ),
+        ] {
+            let bytes_hint = BytesHint::new(range.len(), Some(range.len()));
+            let chunk_stream = download_chunks(
+                client.clone().into(),
+                url::Url::parse("noscheme://").expect("a valid URL"),
+                range,
+                bytes_hint,
+                config.clone(),
+                NoReporting,
+            )
+            .await
+            .unwrap();
+
+            assert_eq!(chunk_stream.download_mode(), expected, "{}", range);
+            let part_stream = PartStream::from_chunk_stream(chunk_stream).unwrap();
+            assert_eq!(part_stream.download_mode(), expected, "{}", range);
+        }
+    }
+
+    #[tokio::test]
+    async fn n_parts() {
+        let client = TestCondowClient::new().max_chunk_size(3);
+
+        let config = Config::default()
+            .buffers_full_delay_ms(0)
+            .part_size_bytes(10)
+            .max_concurrency(4);
+
+        for (range, expected) in [
+            (InclusiveRange(0, 9), 1),
+            (InclusiveRange(0, 10), 2),
+            (InclusiveRange(5, 99), 10),
+        ] {
+            let bytes_hint = BytesHint::new(range.len(), Some(range.len()));
+            let chunk_stream = download_chunks(
+                client.clone().into(),
+                url::Url::parse("noscheme://").expect("a valid URL"),
+                range,
+                bytes_hint,
+                config.clone(),
+                NoReporting,
+            )
+            .await
+            .unwrap();
+
+            assert_eq!(chunk_stream.n_parts(), Some(expected), "{}", range);
+
+            let chunks = chunk_stream.collect::<Vec<_>>().await;
+            assert!(chunks
+                .into_iter()
+                .all(|chunk| chunk.unwrap().part_index < expected));
+        }
+    }
 }
@@ -0,0 +1,95 @@
+//! Writing the parts of a download to separate files
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use futures::{stream::BoxStream, StreamExt, TryStreamExt};
+
+use crate::{errors::CondowError, helpers::spawn_blocking, streams::ChunkStream, InclusiveRange};
+
+/// A part of a download which was written to its own file
+///
+/// See [Downloads::download_parts_to_dir](crate::Downloads::download_parts_to_dir)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartFile {
+    /// Index of the part within the download
+    pub part_index: u64,
+    /// The file containing the bytes of the part
+    pub path: PathBuf,
+    /// The range of the part within the BLOB
+    pub range: InclusiveRange,
+}
+
+/// A part file which is still being written
+struct OpenPart {
+    file: File,
+    path: PathBuf,
+    start: u64,
+}
+
+/// Write the parts of `chunk_stream` to files named `part_000`, `part_001`, ... in `dir`
+///
+/// The directory is created if it does not exist. Existing files are truncated.
+/// All file operations run on the blocking thread pool of the runtime.
+pub(crate) async fn write_parts_to_dir(
+    chunk_stream: ChunkStream,
+    dir: &Path,
+) -> Result<BoxStream<'static, Result<PartFile, CondowError>>, CondowError> {
+    let dir = dir.to_path_buf();
+    spawn_blocking({
+        let dir = dir.clone();
+        move || fs::create_dir_all(dir)
+    })
+    .await??;
+
+    let state = (chunk_stream, HashMap::<u64, OpenPart>::new());
+    let stream = futures::stream::try_unfold(state, move |(mut chunk_stream, mut open_parts)| {
+        let dir = dir.clone();
+        async move {
+            while let Some(chunk) = chunk_stream.try_next().await? {
+                let part = open_parts.remove(&chunk.part_index);
+                let path = dir.join(format!("part_{:03}", chunk.part_index));
+                let blob_offset = chunk.blob_offset;
+                let is_last = chunk.is_last();
+                let bytes = chunk.bytes.clone();
+
+                let OpenPart { file, path, start } =
+                    spawn_blocking(move || -> Result<OpenPart, CondowError> {
+                        let mut part = match part {
+                            Some(part) => part,
+                            None => OpenPart {
+                                file: File::create(&path)?,
+                                path,
+                                start: blob_offset,
+                            },
+                        };
+
+                        part.file.write_all(&bytes)?;
+                        if is_last {
+                            part.file.sync_data()?;
+                        }
+                        Ok(part)
+                    })
+                    .await??;
+
+                if is_last {
+                    let part_file = PartFile {
+                        part_index: chunk.part_index,
+                        path,
+                        range: InclusiveRange(start, chunk.blob_offset + chunk.len() as u64 - 1),
+                    };
+                    return Ok(Some((part_file, (chunk_stream, open_parts))));
+                }
+
+                open_parts.insert(chunk.part_index, OpenPart { file, path, start });
+            }
+
+            Ok(None)
+        }
+    });
+
+    Ok(stream.boxed())
+}
@@ -6,29 +6,41 @@ pub use random_access_reader::*;
 
 mod random_access_reader {
     use std::{
-        io::{Error as IoError, ErrorKind as IoErrorKind, Result as IoResult, SeekFrom},
+        io::{
+            Error as IoError, ErrorKind as IoErrorKind, IoSliceMut, Result as IoResult, SeekFrom,
+        },
         pin::Pin,
         task,
     };
 
-    use bytes::Bytes;
+    use bytes::{Bytes, BytesMut};
     use futures::{
-        future::{BoxFuture, FutureExt, TryFutureExt},
-        stream::{BoxStream, StreamExt},
-        AsyncRead, AsyncSeek,
+        future::{self, BoxFuture, FutureExt, TryFutureExt},
+        stream::{BoxStream, StreamExt, TryStreamExt},
+        AsyncBufRead, AsyncRead, AsyncSeek,
     };
 
-    use crate::{config::Mebi, errors::CondowError, DownloadRange, Downloads};
+    use crate::{
+        config::{Kibi, Mebi},
+        errors::CondowError,
+        DownloadRange, Downloads,
+    };
 
     use super::BytesAsyncReader;
 
     type BytesStream = BoxStream<'static, Result<Bytes, CondowError>>;
     type AsyncReader = BytesAsyncReader<BytesStream>;
     type GetNewReaderFuture = BoxFuture<'static, Result<AsyncReader, CondowError>>;
+    type DiscoverLengthFuture = BoxFuture<'static, Result<(AsyncReader, u64), CondowError>>;
 
     /// 8 MiBytes
     const FETCH_AHEAD_BYTES: u64 = Mebi(8).value();
 
+    /// 8 KiBytes
+    ///
+    /// The bytes wanted when a download is started by [AsyncBufRead::poll_fill_buf]
+    const FILL_BUF_BYTES_WANTED: u64 = Kibi(8).value();
+
     /// Specifies whether to fetch data ahead and if so how.
     ///
     /// The default is to fetch 8 Mebibytes ahead.
@@ -72,15 +84,23 @@ mod random_access_reader {
         Initial,
         /// Wait for a new stream to be created
         GetNewReaderFuture(GetNewReaderFuture),
+        /// Wait for a stream of the complete BLOB which also reveals the length of the BLOB
+        DiscoverLengthFuture(DiscoverLengthFuture),
         PollingReader(AsyncReader),
         Finished,
         Error,
     }
 
-    /// Implements [AsyncRead] and [AsyncSeek]
+    /// Implements [AsyncRead], [AsyncBufRead] and [AsyncSeek]
     ///
     /// This reader allows for random access on the BLOB.
     ///
+    /// [AsyncBufRead] exposes the bytes already received from the download
+    /// so that e.g. [lines](futures::AsyncBufReadExt::lines) can be used without wrapping
+    /// the reader into a `BufReader`. If a new download has to be started by
+    /// [AsyncBufRead::poll_fill_buf] at least 8 KiB or the bytes to fetch ahead
+    /// are requested.
+    ///
     /// # Behaviour
     ///
     /// The download is initiated once the first bytes have been
@@ -93,6 +113,12 @@ mod random_access_reader {
     /// [FetchAheadMode::ToEnd]. The In these cases the number of bytes
     /// to be downloaded must be greater than the configured part size
     /// for concurrent downloading.
+    ///
+    /// A reader created via [RandomAccessReader::new_streaming] does not know
+    /// the length of the BLOB until the first bytes were requested. The first read
+    /// will then download the BLOB from the current position to its end with a
+    /// single request and learn the length from the response. If the position is
+    /// beyond the end of the BLOB, the position is taken as the length.
     pub struct RandomAccessReader<D> {
         /// Reading position of the next byte
         pos: u64,
@@ -101,7 +127,9 @@ mod random_access_reader {
         /// Location of the BLOB
         location: url::Url,
         /// Total length of the BLOB
-        length: u64,
+        ///
+        /// `None` if the length was not yet discovered
+        length: Option<u64>,
         state: State,
         fetch_ahead_mode: FetchAheadMode,
     }
@@ -115,7 +143,7 @@ mod random_access_reader {
         /// This function will query the size of the BLOB. If the size is already known
         /// call [RandomAccessReader::new_with_length]
         pub async fn new(downloader: D, location: url::Url) -> Result<Self, CondowError> {
-            let length = downloader.get_size(location.clone()).await?;
+            let length = downloader.get_size(&location).await?;
             Ok(Self::new_with_length(downloader, location, length))
         }
 
@@ -127,12 +155,69 @@ mod random_access_reader {
                 downloader,
                 location,
                 pos: 0,
-                length,
+                length: Some(length),
+                state: State::Initial,
+                fetch_ahead_mode: FetchAheadMode::default(),
+            }
+        }
+
+        /// Will create a reader without knowing the length of the BLOB.
+        ///
+        /// No request to get the size of the BLOB is made. The length is
+        /// discovered once bytes are read for the first time. This requires the
+        /// client to return an exact [BytesHint](crate::streams::BytesHint) when
+        /// downloading to the end of the BLOB.
+        ///
+        /// Seeking relative to the end fails until the length is known.
+        pub fn new_streaming(downloader: D, location: url::Url) -> Self {
+            Self {
+                downloader,
+                location,
+                pos: 0,
+                length: None,
                 state: State::Initial,
                 fetch_ahead_mode: FetchAheadMode::default(),
             }
         }
 
+        /// Points this reader to another BLOB with the given length.
+        ///
+        /// The position is reset to the start of the BLOB and a running download
+        /// is dropped. The downloader and the [FetchAheadMode] are kept.
+        ///
+        /// This allows to reuse readers e.g. in a pool.
+        pub fn retarget(&mut self, location: url::Url, length: u64) {
+            self.location = location;
+            self.length = Some(length);
+            self.pos = 0;
+            self.state = State::Initial;
+        }
+
+        /// Moves the position of the next byte to read to `pos`.
+        ///
+        /// Unlike [AsyncSeek] this fails if the length of the BLOB is known
+        /// and `pos` is beyond the end of the BLOB. Seeking to the end is allowed.
+        ///
+        /// If the position changes, a running download is dropped and a new one
+        /// is started once bytes are read again.
+        pub fn seek_to(&mut self, pos: u64) -> Result<(), CondowError> {
+            if let Some(length) = self.length {
+                if pos > length {
+                    return Err(CondowError::new_invalid_range(format!(
+                        "Seek to {} beyond the end of the BLOB with length {}",
+                        pos, length
+                    )));
+                }
+            }
+
+            if pos != self.pos {
+                self.pos = pos;
+                self.state = State::Initial;
+            }
+
+            Ok(())
+        }
+
         /// Returns the current offset of the next byte to read.
         ///
         /// The offset is from the start of the BLOB.
@@ -140,20 +225,27 @@ mod random_access_reader {
             return self.pos;
         }
 
-        fn get_next_reader(&self, dest_buf_len: u64) -> GetNewReaderFuture {
+        /// Returns the length of the BLOB.
+        ///
+        /// `None` if the length has not been discovered yet.
+        pub fn length(&self) -> Option<u64> {
+            self.length
+        }
+
+        fn get_next_reader(&self, dest_buf_len: u64, length: u64) -> GetNewReaderFuture {
             let len = match self.fetch_ahead_mode {
                 FetchAheadMode::None => dest_buf_len,
                 FetchAheadMode::Bytes(n_bytes) => dest_buf_len.max(n_bytes),
-                FetchAheadMode::ToEnd => self.length,
+                FetchAheadMode::ToEnd => length,
             };
 
-            let end_incl = (self.pos + len - 1).min(self.length - 1);
+            let end_incl = (self.pos + len - 1).min(length - 1);
 
             let dl = self.downloader.clone();
             let location = self.location.clone();
             let range = DownloadRange::from(self.pos..=end_incl);
             async move {
-                dl.download(location, range)
+                dl.download(&location, range)
                     .map_ok(|stream| {
                         let stream = stream.bytes_stream().boxed();
                         super::BytesAsyncReader::new(stream)
@@ -163,6 +255,31 @@ mod random_access_reader {
             .boxed()
         }
 
+        /// Download from the current position to the end of the BLOB
+        ///
+        /// The length of the BLOB is the current position plus the
+        /// length of the downloaded tail.
+        fn discover_length(&self) -> DiscoverLengthFuture {
+            let dl = self.downloader.clone();
+            let location = self.location.clone();
+            let start = self.pos;
+            async move {
+                let (stream, bytes_hint) = dl.download_from_sequential(&location, start).await?;
+                let tail_len = bytes_hint.exact().ok_or_else(|| {
+                    CondowError::new_other(format!(
+                        "the length of the BLOB could not be determined \
+                        from the download (bytes hint: {})",
+                        bytes_hint
+                    ))
+                })?;
+
+                let stream = stream.map_err(CondowError::from).boxed();
+
+                Ok((super::BytesAsyncReader::new(stream), start + tail_len))
+            }
+            .boxed()
+        }
+
         pub fn set_fetch_ahead_mode<T: Into<FetchAheadMode>>(&mut self, mode: T) {
             self.fetch_ahead_mode = mode.into();
         }
@@ -172,86 +289,348 @@ mod random_access_reader {
         }
     }
 
-    impl<D> AsyncRead for RandomAccessReader<D>
+    impl<D> RandomAccessReader<D>
     where
         D: Downloads + Clone + Send + Sync + 'static + Unpin,
     {
-        fn poll_read(
-            mut self: Pin<&mut Self>,
+        /// Drives the state until there is a reader to poll for bytes.
+        ///
+        /// Returns `false` if the end of the BLOB was reached.
+        ///
+        /// If a new download has to be started, it will request at least
+        /// `bytes_wanted` bytes.
+        fn poll_reader_ready(
+            &mut self,
+            cx: &mut task::Context<'_>,
+            bytes_wanted: u64,
+        ) -> task::Poll<IoResult<bool>> {
+            loop {
+                // Get ownership of the state to not deal with mutable references
+                let current_state = std::mem::replace(&mut self.state, State::Initial);
+
+                match current_state {
+                    State::Initial => {
+                        if let Some(length) = self.length {
+                            if self.pos >= length {
+                                self.state = State::Finished;
+                                return task::Poll::Ready(Ok(false));
+                            }
+                            // Get next stream with a future
+                            let fut = self.get_next_reader(bytes_wanted, length);
+                            self.state = State::GetNewReaderFuture(fut);
+                        } else {
+                            let fut = self.discover_length();
+                            self.state = State::DiscoverLengthFuture(fut);
+                        }
+                    }
+                    State::GetNewReaderFuture(mut fut) => match fut.as_mut().poll(cx) {
+                        task::Poll::Ready(Ok(reader)) => {
+                            self.state = State::PollingReader(reader);
+                        }
+                        task::Poll::Ready(Err(err)) => {
+                            self.state = State::Error;
+                            return task::Poll::Ready(Err(IoError::new(IoErrorKind::Other, err)));
+                        }
+                        task::Poll::Pending => {
+                            self.state = State::GetNewReaderFuture(fut);
+                            return task::Poll::Pending;
+                        }
+                    },
+                    State::DiscoverLengthFuture(mut fut) => match fut.as_mut().poll(cx) {
+                        task::Poll::Ready(Ok((reader, length))) => {
+                            self.length = Some(length);
+                            if self.pos >= length {
+                                self.state = State::Finished;
+                                return task::Poll::Ready(Ok(false));
+                            }
+                            self.state = State::PollingReader(reader);
+                        }
+                        task::Poll::Ready(Err(err)) => {
+                            self.state = State::Error;
+                            return task::Poll::Ready(Err(IoError::new(IoErrorKind::Other, err)));
+                        }
+                        task::Poll::Pending => {
+                            self.state = State::DiscoverLengthFuture(fut);
+                            return task::Poll::Pending;
+                        }
+                    },
+                    State::PollingReader(reader) => {
+                        self.state = State::PollingReader(reader);
+                        return task::Poll::Ready(Ok(true));
+                    }
+                    State::Finished => {
+                        self.state = State::Finished;
+                        return task::Poll::Ready(Ok(false));
+                    }
+                    State::Error => {
+                        self.state = State::Error;
+                        return task::Poll::Ready(Err(IoError::new(
+                            IoErrorKind::Other,
+                            "the reader is broken and will not yield any more values",
+                        )));
+                    }
+                }
+            }
+        }
+
+        /// Handles a reader which did not yield any more bytes
+        ///
+        /// Starts a new download on the next poll unless the end of the BLOB was reached.
+        fn reader_exhausted(&mut self) {
+            let length = self
+                .length
+                .expect("length must be known when polling a reader");
+            if self.pos >= length {
+                self.state = State::Finished;
+            } else {
+                self.state = State::Initial;
+            }
+        }
+
+        /// Reads into `dest_buf`.
+        ///
+        /// If a new download has to be started, it will request at least
+        /// `bytes_wanted` bytes (instead of the length of `dest_buf`).
+        fn poll_read_internal(
+            self: Pin<&mut Self>,
             cx: &mut task::Context<'_>,
             dest_buf: &mut [u8],
+            bytes_wanted: u64,
         ) -> task::Poll<IoResult<usize>> {
             if dest_buf.len() == 0 {
                 return task::Poll::Ready(Ok(0));
             }
 
-            // Get ownership of the state to not deal with mutable references
-            let current_state = std::mem::replace(&mut self.state, State::Initial);
+            let this = self.get_mut();
 
-            match current_state {
-                State::Initial => {
-                    // Get next stream with a future
-                    let fut = self.get_next_reader(dest_buf.len() as u64);
-                    self.state = State::GetNewReaderFuture(fut);
-                    cx.waker().wake_by_ref();
-                    task::Poll::Pending
+            loop {
+                match this.poll_reader_ready(cx, bytes_wanted) {
+                    task::Poll::Ready(Ok(true)) => {}
+                    task::Poll::Ready(Ok(false)) => return task::Poll::Ready(Ok(0)),
+                    task::Poll::Ready(Err(err)) => return task::Poll::Ready(Err(err)),
+                    task::Poll::Pending => return task::Poll::Pending,
                 }
-                State::GetNewReaderFuture(mut fut) => match fut.as_mut().poll(cx) {
-                    task::Poll::Ready(Ok(reader)) => {
-                        self.state = State::PollingReader(reader);
-                        cx.waker().wake_by_ref();
-                        task::Poll::Pending
+
+                let reader = match &mut this.state {
+                    State::PollingReader(reader) => reader,
+                    _ => unreachable!("there must be a reader to poll"),
+                };
+
+                match Pin::new(reader).poll_read(cx, dest_buf) {
+                    task::Poll::Ready(Ok(0)) => this.reader_exhausted(),
+                    task::Poll::Ready(Ok(bytes_written)) => {
+                        let length = this
+                            .length
+                            .expect("length must be known when polling a reader");
+                        this.pos += bytes_written as u64;
+                        assert!(
+                            !(this.pos > length),
+                            "Position can not be larger than length"
+                        );
+                        if this.pos == length {
+                            this.state = State::Finished;
+                        }
+                        return task::Poll::Ready(Ok(bytes_written));
                     }
                     task::Poll::Ready(Err(err)) => {
-                        self.state = State::Error;
-                        task::Poll::Ready(Err(IoError::new(IoErrorKind::Other, err)))
+                        this.state = State::Error;
+                        return task::Poll::Ready(Err(IoError::new(IoErrorKind::Other, err)));
                     }
-                    task::Poll::Pending => {
-                        self.state = State::GetNewReaderFuture(fut);
-                        task::Poll::Pending
+                    task::Poll::Pending => return task::Poll::Pending,
+                }
+            }
+        }
+
+        /// Reads up to `len` bytes from the current position and advances it
+        ///
+        /// Fewer bytes are only returned if the end of the BLOB was reached.
+        /// If the bytes are part of a single chunk received from the client
+        /// they are returned without copying.
+        pub async fn read_bytes(&mut self, len: usize) -> Result<Bytes, CondowError> {
+            let mut received: Vec<Bytes> = Vec::new();
+            let mut n_received = 0;
+            while n_received < len {
+                let remaining = len - n_received;
+                let bytes = future::poll_fn(|cx| self.poll_take_bytes(cx, remaining))
+                    .await
+                    .map_err(io_to_condow_error)?;
+                if bytes.is_empty() {
+                    break;
+                }
+                n_received += bytes.len();
+                received.push(bytes);
+            }
+
+            if received.len() == 1 {
+                return Ok(received.pop().expect("there is one element"));
+            }
+
+            let mut bytes = BytesMut::with_capacity(n_received);
+            received
+                .iter()
+                .for_each(|next| bytes.extend_from_slice(next));
+            Ok(bytes.freeze())
+        }
+
+        /// Takes up to `max_len` bytes from the current position without copying them
+        ///
+        /// Returns empty [Bytes] at the end of the BLOB.
+        fn poll_take_bytes(
+            &mut self,
+            cx: &mut task::Context<'_>,
+            max_len: usize,
+        ) -> task::Poll<IoResult<Bytes>> {
+            loop {
+                match self.poll_reader_ready(cx, max_len as u64) {
+                    task::Poll::Ready(Ok(true)) => {}
+                    task::Poll::Ready(Ok(false)) => return task::Poll::Ready(Ok(Bytes::new())),
+                    task::Poll::Ready(Err(err)) => return task::Poll::Ready(Err(err)),
+                    task::Poll::Pending => return task::Poll::Pending,
+                }
+
+                let reader = match &mut self.state {
+                    State::PollingReader(reader) => reader,
+                    _ => unreachable!("there must be a reader to poll"),
+                };
+
+                match reader.poll_take_bytes(cx, max_len) {
+                    task::Poll::Ready(Ok(bytes)) if bytes.is_empty() => self.reader_exhausted(),
+                    task::Poll::Ready(Ok(bytes)) => {
+                        self.pos += bytes.len() as u64;
+                        if Some(self.pos) == self.length {
+                            self.state = State::Finished;
+                        }
+                        return task::Poll::Ready(Ok(bytes));
                     }
-                },
-                State::PollingReader(mut reader) => {
-                    match Pin::new(&mut reader).poll_read(cx, dest_buf) {
-                        task::Poll::Ready(Ok(bytes_written)) => {
-                            assert!(
-                                !(self.pos > self.length),
-                                "Position can not be larger than length"
-                            );
-                            self.pos += bytes_written as u64;
-                            if self.pos == self.length {
-                                assert!(!(bytes_written == 0), "Still bytes left");
-                                self.state = State::Finished;
-                                task::Poll::Ready(Ok(bytes_written))
-                            } else if bytes_written == 0 {
-                                self.state = State::Initial;
-                                cx.waker().wake_by_ref();
-                                task::Poll::Pending
-                            } else {
-                                self.state = State::PollingReader(reader);
-                                task::Poll::Ready(Ok(bytes_written))
-                            }
+                    task::Poll::Ready(Err(err)) => {
+                        self.state = State::Error;
+                        return task::Poll::Ready(Err(err));
+                    }
+                    task::Poll::Pending => return task::Poll::Pending,
+                }
+            }
+        }
+    }
+
+    /// Returns the [CondowError] wrapped by `err` or wraps `err` otherwise
+    fn io_to_condow_error(err: IoError) -> CondowError {
+        if err.get_ref().is_some_and(|inner| inner.is::<CondowError>()) {
+            let inner = err.into_inner().expect("there is an inner error");
+            *inner
+                .downcast::<CondowError>()
+                .expect("the inner error is a CondowError")
+        } else {
+            CondowError::from(err)
+        }
+    }
+
+    impl<D> AsyncRead for RandomAccessReader<D>
+    where
+        D: Downloads + Clone + Send + Sync + 'static + Unpin,
+    {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            cx: &mut task::Context<'_>,
+            dest_buf: &mut [u8],
+        ) -> task::Poll<IoResult<usize>> {
+            let bytes_wanted = dest_buf.len() as u64;
+            self.poll_read_internal(cx, dest_buf, bytes_wanted)
+        }
+
+        /// Fills the buffers in order.
+        ///
+        /// If a new download has to be started it covers all buffers
+        /// so that not each buffer causes a download of its own.
+        fn poll_read_vectored(
+            mut self: Pin<&mut Self>,
+            cx: &mut task::Context<'_>,
+            bufs: &mut [IoSliceMut<'_>],
+        ) -> task::Poll<IoResult<usize>> {
+            let bytes_wanted: u64 = bufs.iter().map(|buf| buf.len() as u64).sum();
+
+            let mut bytes_read = 0;
+            for buf in bufs.iter_mut() {
+                let mut offset = 0;
+                while offset < buf.len() {
+                    let remaining = bytes_wanted - bytes_read as u64;
+                    match self
+                        .as_mut()
+                        .poll_read_internal(cx, &mut buf[offset..], remaining)
+                    {
+                        task::Poll::Ready(Ok(0)) => return task::Poll::Ready(Ok(bytes_read)),
+                        task::Poll::Ready(Ok(n)) => {
+                            offset += n;
+                            bytes_read += n;
                         }
                         task::Poll::Ready(Err(err)) => {
-                            self.state = State::Error;
-                            task::Poll::Ready(Err(IoError::new(IoErrorKind::Other, err)))
+                            if bytes_read == 0 {
+                                return task::Poll::Ready(Err(err));
+                            }
+                            return task::Poll::Ready(Ok(bytes_read));
                         }
                         task::Poll::Pending => {
-                            self.state = State::PollingReader(reader);
-                            task::Poll::Pending
+                            if bytes_read == 0 {
+                                return task::Poll::Pending;
+                            }
+                            return task::Poll::Ready(Ok(bytes_read));
                         }
                     }
                 }
-                State::Finished => {
-                    self.state = State::Finished;
-                    task::Poll::Ready(Ok(0))
+            }
+
+            task::Poll::Ready(Ok(bytes_read))
+        }
+    }
+
+    impl<D> AsyncBufRead for RandomAccessReader<D>
+    where
+        D: Downloads + Clone + Send + Sync + 'static + Unpin,
+    {
+        fn poll_fill_buf(
+            self: Pin<&mut Self>,
+            cx: &mut task::Context<'_>,
+        ) -> task::Poll<IoResult<&[u8]>> {
+            let this = self.get_mut();
+
+            loop {
+                match this.poll_reader_ready(cx, FILL_BUF_BYTES_WANTED) {
+                    task::Poll::Ready(Ok(true)) => {}
+                    task::Poll::Ready(Ok(false)) => return task::Poll::Ready(Ok(&[])),
+                    task::Poll::Ready(Err(err)) => return task::Poll::Ready(Err(err)),
+                    task::Poll::Pending => return task::Poll::Pending,
                 }
-                State::Error => {
-                    self.state = State::Error;
-                    task::Poll::Ready(Err(IoError::new(
-                        IoErrorKind::Other,
-                        "the reader is broken and will not yield any more values",
-                    )))
+
+                let reader = match &mut this.state {
+                    State::PollingReader(reader) => reader,
+                    _ => unreachable!("there must be a reader to poll"),
+                };
+
+                match Pin::new(reader).poll_fill_buf(cx) {
+                    task::Poll::Ready(Ok(buf)) if !buf.is_empty() => break,
+                    task::Poll::Ready(Ok(_)) => this.reader_exhausted(),
+                    task::Poll::Ready(Err(err)) => {
+                        this.state = State::Error;
+                        return task::Poll::Ready(Err(err));
+                    }
+                    task::Poll::Pending => return task::Poll::Pending,
+                }
+            }
+
+            // The reader already holds the bytes and returns them immediately
+            match &mut this.state {
+                State::PollingReader(reader) => Pin::new(reader).poll_fill_buf(cx),
+                _ => unreachable!("there must be a reader with bytes"),
+            }
+        }
+
+        fn consume(self: Pin<&mut Self>, amt: usize) {
+            let this = self.get_mut();
+
+            if let State::PollingReader(reader) = &mut this.state {
+                Pin::new(reader).consume(amt);
+                this.pos += amt as u64;
+                if Some(this.pos) == this.length {
+                    this.state = State::Finished;
                 }
             }
         }
@@ -270,13 +649,21 @@ mod random_access_reader {
             let new_pos = match pos {
                 SeekFrom::Start(offset) => offset,
                 SeekFrom::End(offset) => {
-                    if offset < 0 && -offset as u64 > this.length {
+                    let length = if let Some(length) = this.length {
+                        length
+                    } else {
+                        let err = CondowError::new_other(
+                            "Seek from end is not possible since the length is not yet known",
+                        );
+                        return task::Poll::Ready(Err(IoError::new(IoErrorKind::Other, err)));
+                    };
+                    if offset < 0 && -offset as u64 > length {
                         // This would go before the start
                         // and is an error by the specification of SeekFrom::End
                         let err = CondowError::new_invalid_range("Seek before start");
                         return task::Poll::Ready(Err(IoError::new(IoErrorKind::Other, err)));
                     }
-                    (this.length as i64 + offset) as u64
+                    (length as i64 + offset) as u64
                 }
                 SeekFrom::Current(offset) => {
                     if offset < 0 && -offset as u64 > this.pos {
@@ -299,7 +686,10 @@ mod random_access_reader {
 
     #[cfg(test)]
     mod tests {
-        use futures::io::{AsyncReadExt as _, AsyncSeekExt as _};
+        use futures::{
+            io::{AsyncBufReadExt as _, AsyncReadExt as _, AsyncSeekExt as _},
+            TryStreamExt as _,
+        };
 
         use crate::{condow_client::NoLocation, test_utils::TestDownloader};
 
@@ -313,7 +703,7 @@ mod random_access_reader {
                 let downloader = TestDownloader::new(n as usize);
 
                 let mut reader = downloader
-                    .reader(url::Url::parse("noscheme://").expect("a valid URL"))
+                    .reader(&url::Url::parse("noscheme://").expect("a valid URL"))
                     .await
                     .unwrap();
 
@@ -328,7 +718,7 @@ mod random_access_reader {
         #[tokio::test]
         async fn offsets_and_seek_from_start() {
             let mut reader = TestDownloader::new_with_blob(vec![0, 1, 2, 3])
-                .reader(url::Url::parse("noscheme://").expect("a valid URL"))
+                .reader(&url::Url::parse("noscheme://").expect("a valid URL"))
                 .await
                 .unwrap();
 
@@ -347,7 +737,7 @@ mod random_access_reader {
         #[tokio::test]
         async fn offsets_and_seek_from_end() {
             let mut reader = TestDownloader::new_with_blob(vec![0, 1, 2, 3])
-                .reader(url::Url::parse("noscheme://").expect("a valid URL"))
+                .reader(&url::Url::parse("noscheme://").expect("a valid URL"))
                 .await
                 .unwrap();
 
@@ -364,7 +754,7 @@ mod random_access_reader {
         #[tokio::test]
         async fn offsets_and_seek_from_current() {
             let mut reader = TestDownloader::new_with_blob(vec![0, 1, 2, 3])
-                .reader(url::Url::parse("noscheme://").expect("a valid URL"))
+                .reader(&url::Url::parse("noscheme://").expect("a valid URL"))
                 .await
                 .unwrap();
 
@@ -388,7 +778,7 @@ mod random_access_reader {
             let expected = vec![0, 1, 2, 3, 0, 0, 4, 5, 0, 6, 7];
             let downloader = TestDownloader::new_with_blob(expected.clone());
             let mut reader = downloader
-                .reader(url::Url::parse("noscheme://").expect("a valid URL"))
+                .reader(&url::Url::parse("noscheme://").expect("a valid URL"))
                 .await
                 .unwrap();
 
@@ -413,7 +803,7 @@ mod random_access_reader {
             let expected = vec![0, 1, 2, 3, 0, 0, 4, 5, 0, 6, 7];
             let downloader = TestDownloader::new_with_blob(expected.clone());
             let mut reader = downloader
-                .reader(url::Url::parse("noscheme://").expect("a valid URL"))
+                .reader(&url::Url::parse("noscheme://").expect("a valid URL"))
                 .await
                 .unwrap();
 
@@ -433,10 +823,58 @@ mod random_access_reader {
             assert_eq!(buf, vec![6, 7]);
         }
 
+        #[tokio::test]
+        async fn check_reader_streaming() {
+            for n in 1..255 {
+                let expected: Vec<u8> = (0..n).collect();
+
+                let downloader = TestDownloader::new(n as usize);
+
+                let mut reader = downloader
+                    .reader_streaming(&url::Url::parse("noscheme://").expect("a valid URL"));
+                assert_eq!(reader.length(), None);
+
+                let mut buf = Vec::new();
+                let bytes_read = reader.read_to_end(&mut buf).await.unwrap();
+
+                assert_eq!(bytes_read, expected.len(), "n bytes read ({} items)", n);
+                assert_eq!(buf, expected, "bytes read ({} items)", n);
+                assert_eq!(reader.length(), Some(n as u64), "length ({} items)", n);
+            }
+        }
+
+        #[tokio::test]
+        async fn reader_streaming_seek_before_first_read() {
+            let expected = vec![0, 1, 2, 3, 0, 0, 4, 5, 0, 6, 7];
+            let downloader = TestDownloader::new_with_blob(expected.clone());
+            let mut reader =
+                downloader.reader_streaming(&url::Url::parse("noscheme://").expect("a valid URL"));
+
+            reader.seek(SeekFrom::Start(6)).await.unwrap();
+            let mut buf = vec![0, 0];
+            reader.read_exact(&mut buf).await.unwrap();
+            assert_eq!(buf, vec![4, 5]);
+            assert_eq!(reader.length(), Some(11));
+
+            reader.seek(SeekFrom::End(-2)).await.unwrap();
+            let mut buf = vec![0, 0];
+            reader.read_exact(&mut buf).await.unwrap();
+            assert_eq!(buf, vec![6, 7]);
+        }
+
+        #[tokio::test]
+        async fn reader_streaming_seek_from_end_before_first_read_must_err() {
+            let mut reader = TestDownloader::new_with_blob(vec![0, 1, 2, 3])
+                .reader_streaming(&url::Url::parse("noscheme://").expect("a valid URL"));
+
+            let result = reader.seek(SeekFrom::End(-1)).await;
+            assert!(result.is_err());
+        }
+
         #[tokio::test]
         async fn seek_from_end_before_byte_zero_must_err() {
             let mut reader = TestDownloader::new_with_blob(vec![0, 1, 2, 3])
-                .reader(url::Url::parse("noscheme://").expect("a valid URL"))
+                .reader(&url::Url::parse("noscheme://").expect("a valid URL"))
                 .await
                 .unwrap();
             // Hit 0 is ok
@@ -452,7 +890,7 @@ mod random_access_reader {
             let expected = vec![0, 1, 2, 3, 0, 0, 4, 5, 0, 6, 7];
             let downloader = TestDownloader::new_with_blob(expected.clone());
             let mut reader = downloader
-                .reader(url::Url::parse("noscheme://").expect("a valid URL"))
+                .reader(&url::Url::parse("noscheme://").expect("a valid URL"))
                 .await
                 .unwrap();
 
@@ -475,7 +913,7 @@ mod random_access_reader {
         #[tokio::test]
         async fn seek_from_current_before_byte_zero_must_err() {
             let mut reader = TestDownloader::new_with_blob(vec![0, 1, 2, 3])
-                .reader(url::Url::parse("noscheme://").expect("a valid URL"))
+                .reader(&url::Url::parse("noscheme://").expect("a valid URL"))
                 .await
                 .unwrap();
 
@@ -488,6 +926,197 @@ mod random_access_reader {
             assert!(result.is_err());
         }
 
+        #[tokio::test]
+        async fn read_vectored_with_a_single_download() {
+            let expected: Vec<u8> = (0..20).collect();
+            let downloader = TestDownloader::new_with_blob(expected.clone());
+            let mut reader = downloader
+                .reader(&url::Url::parse("noscheme://").expect("a valid URL"))
+                .await
+                .unwrap();
+            reader.set_fetch_ahead_mode(FetchAheadMode::None);
+
+            reader.seek(SeekFrom::Start(2)).await.unwrap();
+
+            let mut buf_a = [0u8; 3];
+            let mut buf_b = [0u8; 0];
+            let mut buf_c = [0u8; 10];
+            let bytes_read = reader
+                .read_vectored(&mut [
+                    IoSliceMut::new(&mut buf_a),
+                    IoSliceMut::new(&mut buf_b),
+                    IoSliceMut::new(&mut buf_c),
+                ])
+                .await
+                .unwrap();
+
+            assert_eq!(bytes_read, 13);
+            assert_eq!(buf_a, [2, 3, 4]);
+            assert_eq!(buf_c, [5, 6, 7, 8, 9, 10, 11, 12, 13, 14]);
+            assert_eq!(downloader.n_downloads(), 1);
+            assert_eq!(reader.pos(), 15);
+        }
+
+        #[tokio::test]
+        async fn read_vectored_at_end() {
+            let expected: Vec<u8> = (0..5).collect();
+            let downloader = TestDownloader::new_with_blob(expected.clone());
+            let mut reader = downloader
+                .reader(&url::Url::parse("noscheme://").expect("a valid URL"))
+                .await
+                .unwrap();
+
+            let mut buf_a = [0u8; 3];
+            let mut buf_b = [0u8; 10];
+            let bytes_read = reader
+                .read_vectored(&mut [IoSliceMut::new(&mut buf_a), IoSliceMut::new(&mut buf_b)])
+                .await
+                .unwrap();
+
+            assert_eq!(bytes_read, 5);
+            assert_eq!(buf_a, [0, 1, 2]);
+            assert_eq!(buf_b[..2], [3, 4]);
+
+            let bytes_read = reader
+                .read_vectored(&mut [IoSliceMut::new(&mut buf_a)])
+                .await
+                .unwrap();
+            assert_eq!(bytes_read, 0);
+        }
+
+        #[tokio::test]
+        async fn retarget() {
+            let mut reader = TestDownloader::new_with_blob((0..10).collect())
+                .reader(&url::Url::parse("noscheme://a").expect("a valid URL"))
+                .await
+                .unwrap();
+            reader.set_fetch_ahead_mode(FetchAheadMode::Bytes(3));
+
+            let mut buf = [0; 4];
+            reader.read_exact(&mut buf).await.unwrap();
+            assert_eq!(buf, [0, 1, 2, 3]);
+
+            reader.retarget(url::Url::parse("noscheme://b").expect("a valid URL"), 5);
+
+            assert_eq!(reader.pos(), 0);
+            assert_eq!(reader.length(), Some(5));
+            assert_eq!(reader.fetch_ahead_mode(), FetchAheadMode::Bytes(3));
+
+            let mut buf = Vec::new();
+            reader.read_to_end(&mut buf).await.unwrap();
+            assert_eq!(buf, vec![0, 1, 2, 3, 4]);
+        }
+
+        #[tokio::test]
+        async fn seek_to() {
+            let mut reader = TestDownloader::new_with_blob((0..10).collect())
+                .reader(&url::Url::parse("noscheme://").expect("a valid URL"))
+                .await
+                .unwrap();
+
+            let mut buf = [0; 2];
+            reader.read_exact(&mut buf).await.unwrap();
+            assert_eq!(buf, [0, 1]);
+
+            reader.seek_to(7).unwrap();
+            assert_eq!(reader.pos(), 7);
+            reader.read_exact(&mut buf).await.unwrap();
+            assert_eq!(buf, [7, 8]);
+
+            reader.seek_to(3).unwrap();
+            reader.read_exact(&mut buf).await.unwrap();
+            assert_eq!(buf, [3, 4]);
+
+            reader.seek_to(10).unwrap();
+            let mut buf = Vec::new();
+            assert_eq!(reader.read_to_end(&mut buf).await.unwrap(), 0);
+        }
+
+        #[tokio::test]
+        async fn seek_to_beyond_the_end_must_err() {
+            let mut reader = TestDownloader::new_with_blob((0..10).collect())
+                .reader(&url::Url::parse("noscheme://").expect("a valid URL"))
+                .await
+                .unwrap();
+
+            assert!(reader.seek_to(11).is_err());
+            assert_eq!(reader.pos(), 0);
+        }
+
+        #[tokio::test]
+        async fn seek_to_with_unknown_length() {
+            let mut reader = TestDownloader::new_with_blob((0..10).collect())
+                .reader_streaming(&url::Url::parse("noscheme://").expect("a valid URL"));
+
+            reader.seek_to(6).unwrap();
+
+            let mut buf = Vec::new();
+            reader.read_to_end(&mut buf).await.unwrap();
+            assert_eq!(buf, vec![6, 7, 8, 9]);
+        }
+
+        #[tokio::test]
+        async fn buf_read_lines() {
+            let text = (0..1_000)
+                .map(|n| format!("line {}\n", n))
+                .collect::<String>();
+            for mode in [
+                FetchAheadMode::None,
+                FetchAheadMode::Bytes(7),
+                FetchAheadMode::default(),
+            ] {
+                let mut reader = TestDownloader::new_with_blob(text.as_bytes().to_vec())
+                    .reader(&url::Url::parse("noscheme://").expect("a valid URL"))
+                    .await
+                    .unwrap();
+                reader.set_fetch_ahead_mode(mode);
+
+                let lines: Vec<String> = reader.lines().try_collect().await.unwrap();
+
+                assert_eq!(lines.len(), 1_000, "mode: {:?}", mode);
+                assert_eq!(lines[0], "line 0", "mode: {:?}", mode);
+                assert_eq!(lines[999], "line 999", "mode: {:?}", mode);
+            }
+        }
+
+        #[tokio::test]
+        async fn buf_read_mixed_with_read_and_seek() {
+            let mut reader = TestDownloader::new_with_blob((0..100).collect())
+                .reader(&url::Url::parse("noscheme://").expect("a valid URL"))
+                .await
+                .unwrap();
+            reader.set_fetch_ahead_mode(FetchAheadMode::None);
+
+            let mut buf = Vec::new();
+            reader.read_until(4, &mut buf).await.unwrap();
+            assert_eq!(buf, vec![0, 1, 2, 3, 4]);
+            assert_eq!(reader.pos(), 5);
+
+            let mut buf = [0; 3];
+            reader.read_exact(&mut buf).await.unwrap();
+            assert_eq!(buf, [5, 6, 7]);
+
+            reader.seek(SeekFrom::Start(97)).await.unwrap();
+            let mut buf = Vec::new();
+            reader.read_until(200, &mut buf).await.unwrap();
+            assert_eq!(buf, vec![97, 98, 99]);
+            assert_eq!(reader.pos(), 100);
+
+            assert!(reader.fill_buf().await.unwrap().is_empty());
+        }
+
+        #[tokio::test]
+        async fn buf_read_streaming() {
+            let mut reader = TestDownloader::new_with_blob((0..10).collect())
+                .reader_streaming(&url::Url::parse("noscheme://").expect("a valid URL"));
+
+            let mut buf = Vec::new();
+            reader.read_until(9, &mut buf).await.unwrap();
+
+            assert_eq!(buf, (0..10).collect::<Vec<u8>>());
+            assert!(reader.fill_buf().await.unwrap().is_empty());
+        }
+
         #[tokio::test]
         async fn fetch_ahead() {
             for n in 1..255 {
@@ -507,7 +1136,7 @@ mod random_access_reader {
                     let downloader = TestDownloader::new_with_blob(expected.clone());
 
                     let mut reader = downloader
-                        .reader(url::Url::parse("noscheme://").expect("a valid URL"))
+                        .reader(&url::Url::parse("noscheme://").expect("a valid URL"))
                         .await
                         .unwrap();
                     reader.set_fetch_ahead_mode(mode);
@@ -526,21 +1155,79 @@ mod random_access_reader {
                 }
             }
         }
+
+        #[tokio::test]
+        async fn read_bytes() {
+            let blob: Vec<u8> = (0..100).collect();
+            for mode in [FetchAheadMode::None, FetchAheadMode::Bytes(7)] {
+                for len in 1..30 {
+                    let mut reader = TestDownloader::new_with_blob(blob.clone())
+                        .reader(&url::Url::parse("noscheme://").expect("a valid URL"))
+                        .await
+                        .unwrap();
+                    reader.set_fetch_ahead_mode(mode);
+
+                    let mut pos = 0;
+                    while pos < blob.len() {
+                        let bytes = reader.read_bytes(len).await.unwrap();
+                        let end = (pos + len).min(blob.len());
+                        assert_eq!(bytes, blob[pos..end], "len={}, mode={:?}", len, mode);
+                        pos = end;
+                        assert_eq!(reader.pos(), pos as u64);
+                    }
+
+                    assert!(reader.read_bytes(len).await.unwrap().is_empty());
+                }
+            }
+        }
+
+        #[tokio::test]
+        async fn read_bytes_mixed_with_read_and_seek() {
+            let mut reader = TestDownloader::new_with_blob((0..20).collect())
+                .reader(&url::Url::parse("noscheme://").expect("a valid URL"))
+                .await
+                .unwrap();
+
+            let mut buf = [0; 3];
+            reader.read_exact(&mut buf).await.unwrap();
+            assert_eq!(buf, [0, 1, 2]);
+
+            let bytes = reader.read_bytes(4).await.unwrap();
+            assert_eq!(bytes, vec![3, 4, 5, 6]);
+
+            reader.seek_to(15).unwrap();
+            let bytes = reader.read_bytes(10).await.unwrap();
+            assert_eq!(bytes, vec![15, 16, 17, 18, 19]);
+        }
+
+        #[tokio::test]
+        async fn read_bytes_of_zero_bytes() {
+            let mut reader = TestDownloader::new_with_blob((0..20).collect())
+                .reader(&url::Url::parse("noscheme://").expect("a valid URL"))
+                .await
+                .unwrap();
+
+            assert!(reader.read_bytes(0).await.unwrap().is_empty());
+            assert_eq!(reader.pos(), 0);
+        }
     }
 }
 
 mod bytes_async_reader {
-    use std::io::{Error as IoError, ErrorKind as IoErrorKind, Result as IoResult};
+    use std::io::{Error as IoError, Result as IoResult};
     use std::pin::Pin;
 
     use bytes::Bytes;
-    use futures::{task, AsyncRead, Stream};
+    use futures::{task, AsyncBufRead, AsyncRead, Stream};
 
     use crate::errors::CondowError;
 
     /// A reader for streams of `Result<Bytes, CondowError>`.
     ///
     /// Consumes a stream of bytes and wraps it into an `AsyncRead`.
+    ///
+    /// Also implements `AsyncBufRead` with the chunk of [Bytes] last received
+    /// from the stream as the buffer.
     pub struct BytesAsyncReader<St> {
         state: State<St>,
     }
@@ -554,6 +1241,34 @@ mod bytes_async_reader {
                 state: State::PollingStream(stream),
             }
         }
+
+        /// Takes up to `max_len` bytes of the chunk last received without copying them
+        ///
+        /// A new chunk is only polled from the stream if there are no more
+        /// buffered bytes. Returns empty [Bytes] at the end of the stream.
+        pub(crate) fn poll_take_bytes(
+            &mut self,
+            cx: &mut task::Context<'_>,
+            max_len: usize,
+        ) -> task::Poll<IoResult<Bytes>> {
+            match Pin::new(&mut *self).poll_fill_buf(cx) {
+                task::Poll::Ready(Ok([])) => return task::Poll::Ready(Ok(Bytes::new())),
+                task::Poll::Ready(Ok(_)) => {}
+                task::Poll::Ready(Err(err)) => return task::Poll::Ready(Err(err)),
+                task::Poll::Pending => return task::Poll::Pending,
+            }
+
+            let bytes = match &self.state {
+                State::Buffered { buffer, .. } => {
+                    let n_bytes = buffer.as_slice().len().min(max_len);
+                    buffer.1.slice(buffer.0..buffer.0 + n_bytes)
+                }
+                _ => unreachable!("there must be buffered bytes"),
+            };
+            Pin::new(&mut *self).consume(bytes.len());
+
+            task::Poll::Ready(Ok(bytes))
+        }
     }
 
     impl<St> AsyncRead for BytesAsyncReader<St>
@@ -587,7 +1302,7 @@ mod bytes_async_reader {
                     }
                     task::Poll::Ready(Some(Err(err))) => {
                         self.state = State::Error;
-                        task::Poll::Ready(Err(IoError::new(IoErrorKind::Other, err)))
+                        task::Poll::Ready(Err(stream_error(err)))
                     }
                     task::Poll::Ready(None) => {
                         self.state = State::Finished;
@@ -615,15 +1330,95 @@ mod bytes_async_reader {
                 }
                 State::Error => {
                     self.state = State::Error;
-                    task::Poll::Ready(Err(IoError::new(
-                        IoErrorKind::Other,
-                        "the reader is broken and will not yield any mor values",
-                    )))
+                    task::Poll::Ready(Err(broken_reader_error()))
+                }
+            }
+        }
+    }
+
+    impl<St> AsyncBufRead for BytesAsyncReader<St>
+    where
+        St: Stream<Item = Result<Bytes, CondowError>> + Send + 'static + Unpin,
+    {
+        fn poll_fill_buf(
+            self: Pin<&mut Self>,
+            cx: &mut task::Context<'_>,
+        ) -> task::Poll<IoResult<&[u8]>> {
+            let this = self.get_mut();
+
+            loop {
+                let current_state = std::mem::replace(&mut this.state, State::Finished);
+
+                match current_state {
+                    State::PollingStream(mut stream) => match Pin::new(&mut stream).poll_next(cx) {
+                        task::Poll::Ready(Some(Ok(bytes))) => {
+                            let buffer = Buffer(0, bytes);
+                            if buffer.is_empty() {
+                                this.state = State::PollingStream(stream);
+                            } else {
+                                this.state = State::Buffered { buffer, stream };
+                            }
+                        }
+                        task::Poll::Ready(Some(Err(err))) => {
+                            this.state = State::Error;
+                            return task::Poll::Ready(Err(stream_error(err)));
+                        }
+                        task::Poll::Ready(None) => {
+                            this.state = State::Finished;
+                            return task::Poll::Ready(Ok(&[]));
+                        }
+                        task::Poll::Pending => {
+                            this.state = State::PollingStream(stream);
+                            return task::Poll::Pending;
+                        }
+                    },
+                    buffered @ State::Buffered { .. } => {
+                        this.state = buffered;
+                        break;
+                    }
+                    State::Finished => {
+                        this.state = State::Finished;
+                        return task::Poll::Ready(Ok(&[]));
+                    }
+                    State::Error => {
+                        this.state = State::Error;
+                        return task::Poll::Ready(Err(broken_reader_error()));
+                    }
                 }
             }
+
+            match &this.state {
+                State::Buffered { buffer, .. } => task::Poll::Ready(Ok(buffer.as_slice())),
+                _ => unreachable!("there must be buffered bytes"),
+            }
+        }
+
+        fn consume(mut self: Pin<&mut Self>, amt: usize) {
+            let current_state = std::mem::replace(&mut self.state, State::Finished);
+
+            self.state = match current_state {
+                State::Buffered { mut buffer, stream } => {
+                    buffer.0 = (buffer.0 + amt).min(buffer.1.len());
+                    if buffer.is_empty() {
+                        State::PollingStream(stream)
+                    } else {
+                        State::Buffered { buffer, stream }
+                    }
+                }
+                state => state,
+            };
         }
     }
 
+    /// Keeps the [CondowError] as the inner error with a matching [std::io::ErrorKind]
+    fn stream_error(err: CondowError) -> IoError {
+        err.into()
+    }
+
+    fn broken_reader_error() -> IoError {
+        IoError::other("the reader is broken and will not yield any more values")
+    }
+
     fn fill_destination_buffer(buf: &mut Buffer, dest: &mut [u8]) -> usize {
         let buf_slice = buf.as_slice();
         let mut bytes_written = 0;
@@ -664,6 +1459,29 @@ mod bytes_async_reader {
         }
     }
 
+    #[tokio::test]
+    async fn test_take_bytes_does_not_copy() {
+        let chunk = Bytes::from(vec![0_u8, 1, 2, 3, 4]);
+        let bytes_stream = futures::stream::iter(vec![Ok(chunk.clone())]);
+        let mut reader = BytesAsyncReader::new(bytes_stream);
+
+        let first = futures::future::poll_fn(|cx| reader.poll_take_bytes(cx, 2))
+            .await
+            .unwrap();
+        let second = futures::future::poll_fn(|cx| reader.poll_take_bytes(cx, 10))
+            .await
+            .unwrap();
+        let end = futures::future::poll_fn(|cx| reader.poll_take_bytes(cx, 10))
+            .await
+            .unwrap();
+
+        assert_eq!(first, vec![0, 1]);
+        assert_eq!(first.as_ptr(), chunk.as_ptr());
+        assert_eq!(second, vec![2, 3, 4]);
+        assert_eq!(second.as_ptr(), chunk[2..].as_ptr());
+        assert!(end.is_empty());
+    }
+
     #[tokio::test]
     async fn test_read_from_stream_with_stream_chunk_and_destination_buffer_same_size() {
         use futures::io::AsyncReadExt as _;
@@ -770,6 +1588,45 @@ mod bytes_async_reader {
         assert_eq!(buf, vec![0, 1, 2, 3, 4, 5]);
     }
 
+    #[tokio::test]
+    async fn test_fill_buf_and_consume() {
+        use futures::io::AsyncBufReadExt as _;
+        // create stream
+        let bytes_stream: Vec<Result<Bytes, CondowError>> = vec![
+            Ok(vec![0_u8, 1, 2].into()),
+            Ok(Bytes::new()),
+            Ok(vec![3_u8, 4].into()),
+        ];
+        let bytes_stream = futures::stream::iter(bytes_stream.into_iter());
+        let mut reader = BytesAsyncReader::new(bytes_stream);
+
+        assert_eq!(reader.fill_buf().await.unwrap(), &[0, 1, 2]);
+        reader.consume_unpin(2);
+        assert_eq!(reader.fill_buf().await.unwrap(), &[2]);
+        reader.consume_unpin(1);
+        assert_eq!(reader.fill_buf().await.unwrap(), &[3, 4]);
+        reader.consume_unpin(2);
+        assert!(reader.fill_buf().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_read_until() {
+        use futures::io::AsyncBufReadExt as _;
+        // create stream
+        let bytes_stream: Vec<Result<Bytes, CondowError>> =
+            vec![Ok(vec![0_u8, 1, 2].into()), Ok(vec![3_u8, 4, 5].into())];
+        let bytes_stream = futures::stream::iter(bytes_stream.into_iter());
+        let mut reader = BytesAsyncReader::new(bytes_stream);
+
+        let mut buf = Vec::new();
+        reader.read_until(4, &mut buf).await.unwrap();
+        assert_eq!(buf, vec![0, 1, 2, 3, 4]);
+
+        let mut buf = Vec::new();
+        reader.read_until(4, &mut buf).await.unwrap();
+        assert_eq!(buf, vec![5]);
+    }
+
     #[test]
     fn test_buffer_is_empty() {
         let buffer = Buffer(0, Bytes::new());
@@ -785,13 +1642,13 @@ mod bytes_async_reader {
     #[test]
     fn test_buffer_slice() {
         let buffer = Buffer(0, Bytes::new());
-        assert_eq!(buffer.as_slice(), &[]);
+        assert_eq!(buffer.as_slice(), &[] as &[u8]);
 
         let mut buffer = Buffer(0, vec![0_u8].into());
         assert_eq!(buffer.as_slice(), &[0]);
 
         buffer.0 = 1;
-        assert_eq!(buffer.as_slice(), &[]);
+        assert_eq!(buffer.as_slice(), &[] as &[u8]);
 
         let mut buffer = Buffer(0, vec![0_u8, 1_u8].into());
         assert_eq!(buffer.as_slice(), &[0, 1]);
@@ -800,7 +1657,7 @@ mod bytes_async_reader {
         assert_eq!(buffer.as_slice(), &[1]);
 
         buffer.0 = 2;
-        assert_eq!(buffer.as_slice(), &[]);
+        assert_eq!(buffer.as_slice(), &[] as &[u8]);
     }
 
     #[test]
@@ -811,8 +1668,8 @@ mod bytes_async_reader {
         let bytes_written = fill_destination_buffer(&mut buffer, dest_buf);
         assert_eq!(bytes_written, 0, "bytes_written");
         assert_eq!(buffer.0, 0, "buffer.0");
-        assert_eq!(dest_buf, &[], "buffer.as_slice");
-        assert_eq!(buffer.as_slice(), &[]);
+        assert_eq!(dest_buf, &[] as &[u8], "buffer.as_slice");
+        assert_eq!(buffer.as_slice(), &[] as &[u8]);
     }
 
     #[test]
@@ -823,13 +1680,13 @@ mod bytes_async_reader {
         let bytes_written = fill_destination_buffer(&mut buffer, dest_buf);
         assert_eq!(bytes_written, 1, "bytes_written");
         assert_eq!(buffer.0, 1, "buffer.0");
-        assert_eq!(buffer.as_slice(), &[], "buffer.as_slice");
+        assert_eq!(buffer.as_slice(), &[] as &[u8], "buffer.as_slice");
         assert_eq!(dest_buf, &[0]);
 
         let bytes_written = fill_destination_buffer(&mut buffer, dest_buf);
         assert_eq!(bytes_written, 0, "bytes_written");
         assert_eq!(buffer.0, 1, "buffer.0");
-        assert_eq!(buffer.as_slice(), &[], "buffer.as_slice");
+        assert_eq!(buffer.as_slice(), &[] as &[u8], "buffer.as_slice");
         assert_eq!(dest_buf, &[0]);
     }
 
@@ -841,13 +1698,13 @@ mod bytes_async_reader {
         let bytes_written = fill_destination_buffer(&mut buffer, dest_buf);
         assert_eq!(bytes_written, 1, "bytes_written");
         assert_eq!(buffer.0, 1, "buffer.0");
-        assert_eq!(buffer.as_slice(), &[], "buffer.as_slice");
+        assert_eq!(buffer.as_slice(), &[] as &[u8], "buffer.as_slice");
         assert_eq!(dest_buf, &[0, 11]);
 
         let bytes_written = fill_destination_buffer(&mut buffer, dest_buf);
         assert_eq!(bytes_written, 0, "bytes_written");
         assert_eq!(buffer.0, 1, "buffer.0");
-        assert_eq!(buffer.as_slice(), &[], "buffer.as_slice");
+        assert_eq!(buffer.as_slice(), &[] as &[u8], "buffer.as_slice");
         assert_eq!(dest_buf, &[0, 11]);
     }
 
@@ -865,13 +1722,13 @@ mod bytes_async_reader {
         let bytes_written = fill_destination_buffer(&mut buffer, dest_buf);
         assert_eq!(bytes_written, 1, "buffer.0");
         assert_eq!(buffer.0, 2);
-        assert_eq!(buffer.as_slice(), &[]);
+        assert_eq!(buffer.as_slice(), &[] as &[u8]);
         assert_eq!(dest_buf, &[1]);
 
         let bytes_written = fill_destination_buffer(&mut buffer, dest_buf);
         assert_eq!(bytes_written, 0, "buffer.0");
         assert_eq!(buffer.0, 2);
-        assert_eq!(buffer.as_slice(), &[]);
+        assert_eq!(buffer.as_slice(), &[] as &[u8]);
         assert_eq!(dest_buf, &[1]);
     }
 
@@ -883,13 +1740,13 @@ mod bytes_async_reader {
         let bytes_written = fill_destination_buffer(&mut buffer, dest_buf);
         assert_eq!(bytes_written, 2, "bytes_written");
         assert_eq!(buffer.0, 2, "buffer.0");
-        assert_eq!(buffer.as_slice(), &[], "buffer.as_slice");
+        assert_eq!(buffer.as_slice(), &[] as &[u8], "buffer.as_slice");
         assert_eq!(dest_buf, &[0, 1]);
 
         let bytes_written = fill_destination_buffer(&mut buffer, dest_buf);
         assert_eq!(bytes_written, 0, "bytes_written");
         assert_eq!(buffer.0, 2, "buffer.0");
-        assert_eq!(buffer.as_slice(), &[]);
+        assert_eq!(buffer.as_slice(), &[] as &[u8]);
         assert_eq!(dest_buf, &[0, 1]);
     }
 
@@ -907,13 +1764,13 @@ mod bytes_async_reader {
         let bytes_written = fill_destination_buffer(&mut buffer, dest_buf);
         assert_eq!(bytes_written, 1, "bytes_written");
         assert_eq!(buffer.0, 3, "buffer.0");
-        assert_eq!(buffer.as_slice(), &[]);
+        assert_eq!(buffer.as_slice(), &[] as &[u8]);
         assert_eq!(dest_buf, &[2, 1]);
 
         let bytes_written = fill_destination_buffer(&mut buffer, dest_buf);
         assert_eq!(bytes_written, 0, "bytes_written");
         assert_eq!(buffer.0, 3, "buffer.0");
-        assert_eq!(buffer.as_slice(), &[]);
+        assert_eq!(buffer.as_slice(), &[] as &[u8]);
         assert_eq!(dest_buf, &[2, 1]);
     }
 
@@ -926,13 +1783,13 @@ mod bytes_async_reader {
         assert_eq!(bytes_written, 0, "bytes_written");
         assert_eq!(buffer.0, 0, "buffer.0");
         assert_eq!(buffer.as_slice(), &[0], "buffer.as_slice");
-        assert_eq!(dest_buf, &[]);
+        assert_eq!(dest_buf, &[] as &[u8]);
 
         buffer.0 = 1;
         let bytes_written = fill_destination_buffer(&mut buffer, dest_buf);
         assert_eq!(bytes_written, 0, "bytes_written");
         assert_eq!(buffer.0, 1, "buffer.0");
-        assert_eq!(buffer.as_slice(), &[], "buffer.as_slice");
-        assert_eq!(dest_buf, &[]);
+        assert_eq!(buffer.as_slice(), &[] as &[u8], "buffer.as_slice");
+        assert_eq!(dest_buf, &[] as &[u8]);
     }
 }
@@ -7,11 +7,14 @@ use std::{fmt, time::Duration};
 
 use crate::{
     errors::{CondowError, IoError},
-    InclusiveRange,
+    DownloadRange, InclusiveRange, PartBoundaries,
 };
 
 pub use simple_reporter::*;
 
+#[cfg(any(test, feature = "test-support"))]
+pub use recording_reporter::*;
+
 pub trait ReporterFactory: Send + Sync + 'static {
     type ReporterType: Reporter;
 
@@ -19,6 +22,16 @@ pub trait ReporterFactory: Send + Sync + 'static {
     ///
     /// This might share state with the factory or not
     fn make(&self, location: &dyn fmt::Display) -> Self::ReporterType;
+
+    /// Create a new [Reporter] for a download of the given range.
+    ///
+    /// This can be used to label or route reporters by the range requested.
+    /// The default implementation ignores the range and calls
+    /// [ReporterFactory::make].
+    fn make_for(&self, location: &dyn fmt::Display, range: &DownloadRange) -> Self::ReporterType {
+        let _ = range;
+        self.make(location)
+    }
 }
 
 /// A Reporter is an interface to track occurences of different kinds
@@ -31,6 +44,16 @@ pub trait ReporterFactory: Send + Sync + 'static {
 #[allow(unused_variables)]
 pub trait Reporter: Clone + Send + Sync + 'static {
     fn effective_range(&self, range: InclusiveRange) {}
+
+    /// The effective range was split into the parts given by `parts`
+    ///
+    /// Called once right after [Reporter::effective_range] and before any
+    /// part is requested. The ranges are exactly the ones passed to
+    /// [Reporter::part_started]. If requests are coalesced (see
+    /// [Config::request_coalescing](crate::config::Config::request_coalescing))
+    /// these are the ranges of the requests.
+    fn parts_planned(&self, parts: PartBoundaries) {}
+
     /// The actual IO started
     fn download_started(&self) {}
 
@@ -42,7 +65,17 @@ pub trait Reporter: Clone + Send + Sync + 'static {
     /// IO tasks finished
     ///
     /// **This always is the last method called on a [Reporter] if the download failed.**
-    fn download_failed(&self, time: Option<Duration>) {}
+    ///
+    /// `reason` tells why the download was killed.
+    fn download_failed(&self, time: Option<Duration>, reason: KillReason) {}
+
+    /// IO tasks finished because the consumer dropped the stream before
+    /// all bytes were received
+    ///
+    /// **This always is the last method called on a [Reporter] if the download was cancelled.**
+    ///
+    /// This is not a failure. [Reporter::download_failed] is not called.
+    fn download_cancelled(&self, time: Duration) {}
 
     /// An error occurd but a retry will be attempted
     fn retry_attempt(&self, location: &dyn fmt::Display, error: &CondowError, next_in: Duration) {}
@@ -71,16 +104,70 @@ pub trait Reporter: Clone + Send + Sync + 'static {
     /// A part was completed
     fn chunk_completed(&self, part_index: u64, chunk_index: usize, n_bytes: usize, time: Duration) {
     }
+    /// A part is about to be handed over to a download task
+    ///
+    /// The part waits until a download task has room in its buffer
+    /// and then until the task gets to it. The time until
+    /// [Reporter::part_started] is the time the part was queued.
+    fn part_queued(&self, part_index: u64) {}
+
     /// Download of a part has started
+    ///
+    /// A download task took the part and is about to request it from the client.
     fn part_started(&self, part_index: u64, range: InclusiveRange) {}
 
+    /// The first chunk of a part was received
+    ///
+    /// `elapsed` is the time since the part was started.
+    fn part_first_byte(&self, part_index: u64, elapsed: Duration) {}
+
     /// Download of a part was completed
+    ///
+    /// `n_bytes` is the size of the part in bytes.
     fn part_completed(&self, part_index: u64, n_chunks: usize, n_bytes: u64, time: Duration) {}
 
     /// Download of a part failed
     fn part_failed(&self, error: &CondowError, part_index: u64, range: &InclusiveRange) {}
 }
 
+/// The reason why a download was killed
+///
+/// Passed to [Reporter::download_failed]. If several reasons occur
+/// during a download only the first one is reported.
+///
+/// If the first reason is [KillReason::ConsumerDropped]
+/// [Reporter::download_cancelled] is called instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KillReason {
+    /// The download could not be started, e.g. because the size of the BLOB
+    /// could not be queried or the range was invalid
+    NotStarted,
+    /// A request to the client failed
+    ClientError,
+    /// A stream of bytes failed or did not deliver the expected number of bytes
+    StreamError,
+    /// A panic was detected while downloading
+    Panic,
+    /// The consumer of the download dropped the stream
+    ConsumerDropped,
+    /// The download ended unexpectedly without an error, e.g. because
+    /// the runtime was shut down
+    Aborted,
+}
+
+impl fmt::Display for KillReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KillReason::NotStarted => write!(f, "not started"),
+            KillReason::ClientError => write!(f, "client error"),
+            KillReason::StreamError => write!(f, "stream error"),
+            KillReason::Panic => write!(f, "panic"),
+            KillReason::ConsumerDropped => write!(f, "consumer dropped"),
+            KillReason::Aborted => write!(f, "aborted"),
+        }
+    }
+}
+
 /// Disables reporting
 #[derive(Copy, Clone)]
 pub struct NoReporting;
@@ -106,6 +193,11 @@ impl<RA: Reporter, RB: Reporter> Reporter for CompositeReporter<RA, RB> {
         self.1.effective_range(range);
     }
 
+    fn parts_planned(&self, parts: PartBoundaries) {
+        self.0.parts_planned(parts);
+        self.1.parts_planned(parts);
+    }
+
     fn download_started(&self) {
         self.0.download_started();
         self.1.download_started();
@@ -116,9 +208,14 @@ impl<RA: Reporter, RB: Reporter> Reporter for CompositeReporter<RA, RB> {
         self.1.download_completed(time);
     }
 
-    fn download_failed(&self, time: Option<Duration>) {
-        self.0.download_failed(time);
-        self.1.download_failed(time);
+    fn download_failed(&self, time: Option<Duration>, reason: KillReason) {
+        self.0.download_failed(time, reason);
+        self.1.download_failed(time, reason);
+    }
+
+    fn download_cancelled(&self, time: Duration) {
+        self.0.download_cancelled(time);
+        self.1.download_cancelled(time);
     }
 
     fn retry_attempt(&self, location: &dyn fmt::Display, error: &CondowError, next_in: Duration) {
@@ -162,11 +259,21 @@ impl<RA: Reporter, RB: Reporter> Reporter for CompositeReporter<RA, RB> {
             .chunk_completed(part_index, chunk_index, n_bytes, time);
     }
 
+    fn part_queued(&self, part_index: u64) {
+        self.0.part_queued(part_index);
+        self.1.part_queued(part_index);
+    }
+
     fn part_started(&self, part_index: u64, range: crate::InclusiveRange) {
         self.0.part_started(part_index, range);
         self.1.part_started(part_index, range);
     }
 
+    fn part_first_byte(&self, part_index: u64, elapsed: Duration) {
+        self.0.part_first_byte(part_index, elapsed);
+        self.1.part_first_byte(part_index, elapsed);
+    }
+
     fn part_completed(
         &self,
         part_index: u64,
@@ -187,6 +294,7 @@ mod simple_reporter {
     //! Simple reporting with (mostly) counters
 
     use std::{
+        collections::HashMap,
         fmt,
         sync::{
             atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
@@ -200,7 +308,7 @@ mod simple_reporter {
         InclusiveRange,
     };
 
-    use super::{Reporter, ReporterFactory};
+    use super::{KillReason, Reporter, ReporterFactory};
 
     /// Creates [SimpleReporter]s
     pub struct SimpleReporterFactory {
@@ -283,6 +391,7 @@ mod simple_reporter {
                 effective_range: *inner.effective_range.lock().unwrap(),
                 is_finished: self.is_download_finished(),
                 is_failed: inner.is_failed.load(Ordering::SeqCst),
+                is_cancelled: inner.is_cancelled.load(Ordering::SeqCst),
                 n_retries: inner.n_retries.load(Ordering::SeqCst),
                 n_stream_resume_attempts: inner.n_resume_stream_attempts.load(Ordering::SeqCst),
                 n_panics: inner.n_panics_detected.load(Ordering::SeqCst),
@@ -306,6 +415,18 @@ mod simple_reporter {
                 max_chunks_per_part: inner.max_chunks_per_part.load(Ordering::SeqCst),
                 min_part_time: Duration::from_micros(inner.min_part_us.load(Ordering::SeqCst)),
                 max_part_time: Duration::from_micros(inner.max_part_us.load(Ordering::SeqCst)),
+                min_part_first_byte_time: Duration::from_micros(
+                    inner.min_part_first_byte_us.load(Ordering::SeqCst),
+                ),
+                max_part_first_byte_time: Duration::from_micros(
+                    inner.max_part_first_byte_us.load(Ordering::SeqCst),
+                ),
+                min_part_queue_time: Duration::from_micros(
+                    inner.min_part_queue_us.load(Ordering::SeqCst),
+                ),
+                max_part_queue_time: Duration::from_micros(
+                    inner.max_part_queue_us.load(Ordering::SeqCst),
+                ),
             }
         }
     }
@@ -323,6 +444,8 @@ mod simple_reporter {
         /// `true` if the download was finished
         pub is_finished: bool,
         pub is_failed: bool,
+        /// `true` if the consumer dropped the stream before the download was completed
+        pub is_cancelled: bool,
         pub n_retries: usize,
         pub n_stream_resume_attempts: usize,
         pub n_panics: usize,
@@ -348,6 +471,14 @@ mod simple_reporter {
         pub max_chunks_per_part: usize,
         pub min_part_time: Duration,
         pub max_part_time: Duration,
+        /// Minimum time from starting a part until its first chunk was received
+        pub min_part_first_byte_time: Duration,
+        /// Maximum time from starting a part until its first chunk was received
+        pub max_part_first_byte_time: Duration,
+        /// Minimum time from queueing a part until it was started
+        pub min_part_queue_time: Duration,
+        /// Maximum time from queueing a part until it was started
+        pub max_part_queue_time: Duration,
     }
 
     impl Reporter for SimpleReporter {
@@ -363,11 +494,16 @@ mod simple_reporter {
             *self.inner.download_finished_at.lock().unwrap() = Some(Instant::now());
         }
 
-        fn download_failed(&self, _time: Option<Duration>) {
+        fn download_failed(&self, _time: Option<Duration>, _reason: KillReason) {
             *self.inner.download_finished_at.lock().unwrap() = Some(Instant::now());
             self.inner.is_failed.store(true, Ordering::SeqCst);
         }
 
+        fn download_cancelled(&self, _time: Duration) {
+            *self.inner.download_finished_at.lock().unwrap() = Some(Instant::now());
+            self.inner.is_cancelled.store(true, Ordering::SeqCst);
+        }
+
         fn retry_attempt(
             &self,
             _location: &dyn fmt::Display,
@@ -415,6 +551,31 @@ mod simple_reporter {
             }
         }
 
+        fn part_queued(&self, part_index: u64) {
+            self.inner
+                .part_queued_at
+                .lock()
+                .unwrap()
+                .insert(part_index, Instant::now());
+        }
+
+        fn part_started(&self, part_index: u64, _range: InclusiveRange) {
+            let inner = self.inner.as_ref();
+            let queued_at = inner.part_queued_at.lock().unwrap().remove(&part_index);
+            if let Some(queued_at) = queued_at {
+                let us = queued_at.elapsed().as_micros() as u64;
+                inner.min_part_queue_us.fetch_min(us, Ordering::SeqCst);
+                inner.max_part_queue_us.fetch_max(us, Ordering::SeqCst);
+            }
+        }
+
+        fn part_first_byte(&self, _part_index: u64, elapsed: Duration) {
+            let inner = self.inner.as_ref();
+            let us = elapsed.as_micros() as u64;
+            inner.min_part_first_byte_us.fetch_min(us, Ordering::SeqCst);
+            inner.max_part_first_byte_us.fetch_max(us, Ordering::SeqCst);
+        }
+
         fn part_completed(&self, _part_index: u64, n_chunks: usize, n_bytes: u64, time: Duration) {
             let inner = self.inner.as_ref();
             inner.n_parts_received.fetch_add(1, Ordering::SeqCst);
@@ -439,6 +600,7 @@ mod simple_reporter {
         download_started_at: Mutex<Instant>,
         download_finished_at: Mutex<Option<Instant>>,
         is_failed: AtomicBool,
+        is_cancelled: AtomicBool,
         n_queue_full: AtomicUsize,
         n_bytes_received: AtomicU64,
         n_chunks_received: AtomicU64,
@@ -456,6 +618,11 @@ mod simple_reporter {
         max_chunks_per_part: AtomicUsize,
         min_part_us: AtomicU64,
         max_part_us: AtomicU64,
+        min_part_first_byte_us: AtomicU64,
+        max_part_first_byte_us: AtomicU64,
+        part_queued_at: Mutex<HashMap<u64, Instant>>,
+        min_part_queue_us: AtomicU64,
+        max_part_queue_us: AtomicU64,
     }
 
     impl Inner {
@@ -466,6 +633,7 @@ mod simple_reporter {
                 download_started_at: Mutex::new(Instant::now()),
                 download_finished_at: Mutex::new(None),
                 is_failed: AtomicBool::new(false),
+                is_cancelled: AtomicBool::new(false),
                 n_retries: AtomicUsize::new(0),
                 n_resume_stream_attempts: AtomicUsize::new(0),
                 n_panics_detected: AtomicUsize::new(0),
@@ -483,7 +651,417 @@ mod simple_reporter {
                 max_chunks_per_part: AtomicUsize::new(0),
                 min_part_us: AtomicU64::new(u64::MAX),
                 max_part_us: AtomicU64::new(0),
+                min_part_first_byte_us: AtomicU64::new(u64::MAX),
+                max_part_first_byte_us: AtomicU64::new(0),
+                part_queued_at: Mutex::new(HashMap::new()),
+                min_part_queue_us: AtomicU64::new(u64::MAX),
+                max_part_queue_us: AtomicU64::new(0),
+            }
+        }
+    }
+}
+
+#[cfg(any(test, feature = "test-support"))]
+mod recording_reporter {
+    //! A [Reporter] which records all events for testing
+
+    use std::{
+        fmt,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use crate::{
+        errors::{CondowError, IoError},
+        InclusiveRange, PartBoundaries,
+    };
+
+    use super::{KillReason, Reporter, ReporterFactory};
+
+    /// An event recorded by a [RecordingReporter]
+    ///
+    /// There is one variant for each method of [Reporter] containing its arguments.
+    /// Errors and locations are recorded with their `Display` representation.
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum ReporterEvent {
+        EffectiveRange(InclusiveRange),
+        PartsPlanned(PartBoundaries),
+        DownloadStarted,
+        DownloadCompleted {
+            time: Duration,
+        },
+        DownloadFailed {
+            time: Option<Duration>,
+            reason: KillReason,
+        },
+        DownloadCancelled {
+            time: Duration,
+        },
+        RetryAttempt {
+            location: String,
+            error: String,
+            next_in: Duration,
+        },
+        StreamResumeAttempt {
+            location: String,
+            error: String,
+            orig_range: InclusiveRange,
+            remaining_range: InclusiveRange,
+        },
+        PanicDetected {
+            msg: String,
+        },
+        QueueFull,
+        ChunkCompleted {
+            part_index: u64,
+            chunk_index: usize,
+            n_bytes: usize,
+            time: Duration,
+        },
+        PartQueued {
+            part_index: u64,
+        },
+        PartStarted {
+            part_index: u64,
+            range: InclusiveRange,
+        },
+        PartFirstByte {
+            part_index: u64,
+            elapsed: Duration,
+        },
+        PartCompleted {
+            part_index: u64,
+            n_chunks: usize,
+            n_bytes: u64,
+            time: Duration,
+        },
+        PartFailed {
+            error: String,
+            part_index: u64,
+            range: InclusiveRange,
+        },
+    }
+
+    impl ReporterEvent {
+        /// The name of the [Reporter] method which caused this event
+        ///
+        /// Timings are not deterministic. Compare the names to
+        /// assert the sequence of events.
+        pub fn name(&self) -> &'static str {
+            match self {
+                ReporterEvent::EffectiveRange(_) => "effective_range",
+                ReporterEvent::PartsPlanned(_) => "parts_planned",
+                ReporterEvent::DownloadStarted => "download_started",
+                ReporterEvent::DownloadCompleted { .. } => "download_completed",
+                ReporterEvent::DownloadFailed { .. } => "download_failed",
+                ReporterEvent::DownloadCancelled { .. } => "download_cancelled",
+                ReporterEvent::RetryAttempt { .. } => "retry_attempt",
+                ReporterEvent::StreamResumeAttempt { .. } => "stream_resume_attempt",
+                ReporterEvent::PanicDetected { .. } => "panic_detected",
+                ReporterEvent::QueueFull => "queue_full",
+                ReporterEvent::ChunkCompleted { .. } => "chunk_completed",
+                ReporterEvent::PartQueued { .. } => "part_queued",
+                ReporterEvent::PartStarted { .. } => "part_started",
+                ReporterEvent::PartFirstByte { .. } => "part_first_byte",
+                ReporterEvent::PartCompleted { .. } => "part_completed",
+                ReporterEvent::PartFailed { .. } => "part_failed",
             }
         }
     }
+
+    /// A [Reporter] which records every call with its arguments
+    ///
+    /// Clones share the recorded events. As a [ReporterFactory] all
+    /// created reporters record into the same events.
+    ///
+    /// Requires the feature `test-support`.
+    #[derive(Debug, Clone, Default)]
+    pub struct RecordingReporter {
+        events: Arc<Mutex<Vec<ReporterEvent>>>,
+    }
+
+    impl RecordingReporter {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Returns all events recorded so far in the order they occurred
+        pub fn events(&self) -> Vec<ReporterEvent> {
+            self.events.lock().unwrap().clone()
+        }
+
+        /// Returns the names of all events recorded so far in the order they occurred
+        pub fn event_names(&self) -> Vec<&'static str> {
+            self.events
+                .lock()
+                .unwrap()
+                .iter()
+                .map(ReporterEvent::name)
+                .collect()
+        }
+
+        /// Removes all recorded events
+        pub fn clear(&self) {
+            self.events.lock().unwrap().clear();
+        }
+
+        fn record(&self, event: ReporterEvent) {
+            self.events.lock().unwrap().push(event);
+        }
+    }
+
+    impl ReporterFactory for RecordingReporter {
+        type ReporterType = Self;
+
+        fn make(&self, _location: &dyn fmt::Display) -> Self {
+            self.clone()
+        }
+    }
+
+    impl Reporter for RecordingReporter {
+        fn effective_range(&self, range: InclusiveRange) {
+            self.record(ReporterEvent::EffectiveRange(range));
+        }
+
+        fn parts_planned(&self, parts: PartBoundaries) {
+            self.record(ReporterEvent::PartsPlanned(parts));
+        }
+
+        fn download_started(&self) {
+            self.record(ReporterEvent::DownloadStarted);
+        }
+
+        fn download_completed(&self, time: Duration) {
+            self.record(ReporterEvent::DownloadCompleted { time });
+        }
+
+        fn download_failed(&self, time: Option<Duration>, reason: KillReason) {
+            self.record(ReporterEvent::DownloadFailed { time, reason });
+        }
+
+        fn download_cancelled(&self, time: Duration) {
+            self.record(ReporterEvent::DownloadCancelled { time });
+        }
+
+        fn retry_attempt(
+            &self,
+            location: &dyn fmt::Display,
+            error: &CondowError,
+            next_in: Duration,
+        ) {
+            self.record(ReporterEvent::RetryAttempt {
+                location: location.to_string(),
+                error: error.to_string(),
+                next_in,
+            });
+        }
+
+        fn stream_resume_attempt(
+            &self,
+            location: &dyn fmt::Display,
+            error: &IoError,
+            orig_range: InclusiveRange,
+            remaining_range: InclusiveRange,
+        ) {
+            self.record(ReporterEvent::StreamResumeAttempt {
+                location: location.to_string(),
+                error: error.to_string(),
+                orig_range,
+                remaining_range,
+            });
+        }
+
+        fn panic_detected(&self, msg: &str) {
+            self.record(ReporterEvent::PanicDetected {
+                msg: msg.to_string(),
+            });
+        }
+
+        fn queue_full(&self) {
+            self.record(ReporterEvent::QueueFull);
+        }
+
+        fn chunk_completed(
+            &self,
+            part_index: u64,
+            chunk_index: usize,
+            n_bytes: usize,
+            time: Duration,
+        ) {
+            self.record(ReporterEvent::ChunkCompleted {
+                part_index,
+                chunk_index,
+                n_bytes,
+                time,
+            });
+        }
+
+        fn part_queued(&self, part_index: u64) {
+            self.record(ReporterEvent::PartQueued { part_index });
+        }
+
+        fn part_started(&self, part_index: u64, range: InclusiveRange) {
+            self.record(ReporterEvent::PartStarted { part_index, range });
+        }
+
+        fn part_first_byte(&self, part_index: u64, elapsed: Duration) {
+            self.record(ReporterEvent::PartFirstByte {
+                part_index,
+                elapsed,
+            });
+        }
+
+        fn part_completed(&self, part_index: u64, n_chunks: usize, n_bytes: u64, time: Duration) {
+            self.record(ReporterEvent::PartCompleted {
+                part_index,
+                n_chunks,
+                n_bytes,
+                time,
+            });
+        }
+
+        fn part_failed(&self, error: &CondowError, part_index: u64, range: &InclusiveRange) {
+            self.record(ReporterEvent::PartFailed {
+                error: error.to_string(),
+                part_index,
+                range: *range,
+            });
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use futures::TryStreamExt;
+
+        use crate::{
+            condow_client::InMemoryClient, config::Config, reporter::KillReason, Condow,
+            InclusiveRange, PartBoundaries,
+        };
+
+        use super::{RecordingReporter, ReporterEvent};
+
+        #[tokio::test]
+        async fn records_the_events_of_a_sequential_download() {
+            let client = InMemoryClient::new((0u8..20).collect()).chunk_size(5);
+            let config = Config::default()
+                .part_size_bytes(10)
+                .max_concurrency(1)
+                .buffers_full_delay_ms(0);
+            let condow = Condow::new(client, config).unwrap();
+            let location = url::Url::parse("noscheme://").expect("a valid URL");
+            let reporter = RecordingReporter::new();
+
+            let _ = condow
+                .downloader()
+                .download_chunks_wrep(&location, .., reporter.clone())
+                .await
+                .unwrap()
+                .into_stream()
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap();
+
+            // Whether the queue runs full and when the next part is queued
+            // depends on the scheduling
+            let event_names = reporter
+                .event_names()
+                .into_iter()
+                .filter(|name| *name != "queue_full" && *name != "part_queued")
+                .collect::<Vec<_>>();
+            assert_eq!(
+                event_names,
+                vec![
+                    "effective_range",
+                    "parts_planned",
+                    "download_started",
+                    "part_started",
+                    "part_first_byte",
+                    "chunk_completed",
+                    "chunk_completed",
+                    "part_completed",
+                    "part_started",
+                    "part_first_byte",
+                    "chunk_completed",
+                    "chunk_completed",
+                    "part_completed",
+                    "download_completed",
+                ]
+            );
+            let events = reporter.events();
+            assert_eq!(
+                events[0],
+                ReporterEvent::EffectiveRange(InclusiveRange(0, 19))
+            );
+            assert_eq!(
+                events[1],
+                ReporterEvent::PartsPlanned(PartBoundaries::new(InclusiveRange(0, 19), 10))
+            );
+            assert!(events.contains(&ReporterEvent::PartStarted {
+                part_index: 1,
+                range: InclusiveRange(10, 19)
+            }));
+        }
+
+        #[tokio::test]
+        async fn each_part_is_queued_before_it_is_started() {
+            let client = InMemoryClient::new((0u8..50).collect());
+            let config = Config::default()
+                .part_size_bytes(10)
+                .max_concurrency(2)
+                .buffer_size(1)
+                .buffers_full_delay_ms(0);
+            let condow = Condow::new(client, config).unwrap();
+            let location = url::Url::parse("noscheme://").expect("a valid URL");
+            let reporter = RecordingReporter::new();
+
+            let _ = condow
+                .downloader()
+                .download_chunks_wrep(&location, .., reporter.clone())
+                .await
+                .unwrap()
+                .into_stream()
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap();
+
+            let events = reporter.events();
+            for part_index in 0..5 {
+                let queued = events
+                    .iter()
+                    .position(|event| *event == ReporterEvent::PartQueued { part_index });
+                let started = events.iter().position(|event| {
+                    matches!(event, ReporterEvent::PartStarted { part_index: idx, .. } if *idx == part_index)
+                });
+                assert!(
+                    queued.is_some() && queued < started,
+                    "part {} queued at {:?} started at {:?}",
+                    part_index,
+                    queued,
+                    started
+                );
+            }
+        }
+
+        #[tokio::test]
+        async fn records_a_failed_download() {
+            let client = InMemoryClient::new((0u8..20).collect());
+            let condow = Condow::new(client, Config::default()).unwrap();
+            let location = url::Url::parse("noscheme://").expect("a valid URL");
+            let reporter = RecordingReporter::new();
+
+            let result = condow
+                .downloader()
+                .download_chunks_wrep(&location, 10..5, reporter.clone())
+                .await;
+
+            assert!(result.is_err());
+            assert_eq!(
+                reporter.events(),
+                vec![ReporterEvent::DownloadFailed {
+                    time: None,
+                    reason: KillReason::NotStarted
+                }]
+            );
+        }
+    }
 }
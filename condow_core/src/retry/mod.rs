@@ -1,11 +1,16 @@
-use std::{sync::Arc, time::Duration};
+use std::{
+    borrow::Cow,
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
 use anyhow::{bail, Error as AnyError};
 use bytes::Bytes;
 use futures::{channel::mpsc, Stream, StreamExt};
 
 use crate::{
-    condow_client::{CondowClient, DownloadSpec},
+    condow_client::{ClientFactory, CondowClient, ContentRangeResponse, DownloadSpec},
     errors::{CondowError, IoError},
     reporter::Reporter,
     streams::{BytesHint, BytesStream},
@@ -123,8 +128,22 @@ impl Default for RetryMaxStreamResumeAttempts {
 /// Streaming will always be aborted if no bytes were read 3 times in a row even
 /// though successful requests for a stream were made.
 ///
+/// These are two independent mechanisms:
+///
+/// * A request which fails before a stream was received (e.g. with a 503)
+///   is retried from scratch. `max_attempts` limits these retries.
+/// * A stream which breaks after the request succeeded is resumed
+///   with a new request starting at the offset where it broke.
+///   `max_stream_resume_attempts` limits the resumes without any progress.
+///   Each of these new requests is itself retried as described above.
+///
 /// Retries can also be attempted on size requests.
 ///
+/// The delays and the number of retries configured on the [RetryConfig]
+/// itself apply to all requests by default. They can be overridden
+/// with a [RetryPolicy] for initial requests (`initial_request`) and
+/// for the requests made to resume a broken stream (`stream_resume`).
+///
 /// Be aware that some clients might also do retries themselves based on
 /// their underlying implementation. In this case you should disable retries for either the
 /// client or ConDow itself.
@@ -149,6 +168,30 @@ pub struct RetryConfig {
     /// Setting this to 0 will disable resumes. Enabling them has a small overhead
     /// since the current progress on a byte stream must be tracked.
     pub max_stream_resume_attempts: RetryMaxStreamResumeAttempts,
+    /// Rewrites the location before each retry attempt
+    ///
+    /// Can not be initialized from the environment.
+    ///
+    /// The default is `None` which retries on the same location.
+    pub location_rewrite: Option<RetryLocationRewrite>,
+    /// Retries of the initial requests for a download and of size requests
+    ///
+    /// Can not be initialized from the environment.
+    ///
+    /// The default is `None` which uses the retry settings of the [RetryConfig].
+    pub initial_request: Option<RetryPolicy>,
+    /// Retries of the requests made to resume a broken stream
+    ///
+    /// Can not be initialized from the environment.
+    ///
+    /// The default is `None` which uses the retry settings of the [RetryConfig].
+    pub stream_resume: Option<RetryPolicy>,
+    /// Limits the retries of all downloads sharing the [RetryBudget]
+    ///
+    /// Can not be initialized from the environment.
+    ///
+    /// The default is `None` which only limits the retries per request.
+    pub budget: Option<RetryBudget>,
     // TODO: Add possibility to jitter
 }
 
@@ -199,22 +242,53 @@ impl RetryConfig {
         self
     }
 
+    /// Set a function to rewrite the location before each retry attempt
+    ///
+    /// See [RetryLocationRewrite]
+    pub fn location_rewrite<T: Into<RetryLocationRewrite>>(mut self, location_rewrite: T) -> Self {
+        self.location_rewrite = Some(location_rewrite.into());
+        self
+    }
+
+    /// Set the [RetryPolicy] for the initial requests of a download and size requests
+    pub fn initial_request(mut self, policy: RetryPolicy) -> Self {
+        self.initial_request = Some(policy);
+        self
+    }
+
+    /// Set the [RetryPolicy] for the requests made to resume a broken stream
+    pub fn stream_resume(mut self, policy: RetryPolicy) -> Self {
+        self.stream_resume = Some(policy);
+        self
+    }
+
+    /// Set a [RetryBudget] which limits the retries of all downloads sharing it
+    ///
+    /// See also [DownloadSession::with_retry_budget](crate::DownloadSession::with_retry_budget)
+    pub fn budget(mut self, budget: RetryBudget) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
     /// Validate this [RetryConfig]
     ///
     /// Succeeds if
     /// * `delay_factor` is at least 1.0
     /// * `delay_factor` is a number
+    /// * `initial_request` and `stream_resume` are valid if set
     pub fn validate(&self) -> Result<(), AnyError> {
-        if self.delay_factor.0 < 1.0 {
-            bail!("'delay_factor' must be at least 1.0");
-        }
+        validate_delay_factor(self.delay_factor)?;
 
-        if self.delay_factor.0.is_nan() {
-            bail!("'delay_factor' must not be NaN");
+        if let Some(initial_request) = &self.initial_request {
+            initial_request
+                .validate()
+                .map_err(|err| err.context("invalid 'initial_request'"))?;
         }
 
-        if self.delay_factor.0.is_infinite() {
-            bail!("'delay_factor' must not be infinite");
+        if let Some(stream_resume) = &self.stream_resume {
+            stream_resume
+                .validate()
+                .map_err(|err| err.context("invalid 'stream_resume'"))?;
         }
 
         Ok(())
@@ -236,16 +310,53 @@ impl RetryConfig {
     /// was created from is meaningful from a users perspective. Therefore
     /// `RetryConfig::validate` should be called before creating a
     /// [RetryDelaysIterator].
+    #[cfg(test)]
     pub(crate) fn iterator(&self) -> impl Iterator<Item = Duration> {
-        RetryDelaysIterator::new(
-            self.max_attempts.into_inner(),
-            self.initial_delay_ms.into_inner() as f64 / 1_000.0,
-            self.max_delay_ms.into_inner() as f64 / 1_000.0,
-            self.delay_factor.into_inner(),
-        )
+        self.top_level_policy().iterator()
+    }
+
+    /// Returns the [RetryPolicy] made of the settings of this [RetryConfig] itself
+    fn top_level_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: self.max_attempts,
+            initial_delay_ms: self.initial_delay_ms,
+            delay_factor: self.delay_factor,
+            max_delay_ms: self.max_delay_ms,
+        }
     }
 
-    fn fill_from_env_prefixed_internal<T: AsRef<str>>(
+    /// Returns the [RetryPolicy] for initial requests and size requests
+    fn initial_request_policy(&self) -> RetryPolicy {
+        self.initial_request
+            .unwrap_or_else(|| self.top_level_policy())
+    }
+
+    /// Returns the [RetryPolicy] for requests resuming a broken stream
+    fn stream_resume_policy(&self) -> RetryPolicy {
+        self.stream_resume
+            .unwrap_or_else(|| self.top_level_policy())
+    }
+
+    /// Returns the location to be used for the given retry attempt
+    fn location_for_attempt(&self, location: &url::Url, attempt: usize) -> url::Url {
+        if let Some(location_rewrite) = &self.location_rewrite {
+            location_rewrite.rewrite(location, attempt)
+        } else {
+            location.clone()
+        }
+    }
+
+    /// Takes a token from the [RetryBudget] if there is one
+    ///
+    /// Returns `false` if the budget is exhausted and no retry must be made.
+    fn withdraw_from_budget(&self) -> bool {
+        self.budget
+            .as_ref()
+            .map(RetryBudget::try_withdraw)
+            .unwrap_or(true)
+    }
+
+    pub(crate) fn fill_from_env_prefixed_internal<T: AsRef<str>>(
         &mut self,
         prefix: T,
     ) -> Result<bool, AnyError> {
@@ -268,11 +379,269 @@ impl RetryConfig {
             found_any = true;
             self.max_delay_ms = max_delay_ms;
         }
+        if let Some(max_stream_resume_attempts) =
+            RetryMaxStreamResumeAttempts::try_from_env_prefixed(prefix.as_ref())?
+        {
+            found_any = true;
+            self.max_stream_resume_attempts = max_stream_resume_attempts;
+        }
 
         Ok(found_any)
     }
 }
 
+/// Retries with exponential backoff for one kind of request
+///
+/// Overrides the retry settings of a [RetryConfig] either for the
+/// initial requests or for the requests resuming a broken stream.
+///
+/// # Examples
+///
+/// ```rust
+/// # use condow_core::config::{RetryConfig, RetryPolicy};
+/// // Give up on a request quickly but keep trying to resume broken streams
+/// let config = RetryConfig::default()
+///     .initial_request(RetryPolicy::default().max_attempts(1))
+///     .stream_resume(RetryPolicy::default().max_attempts(5));
+///
+/// assert!(config.validate().is_ok());
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub struct RetryPolicy {
+    /// The maximum number of retry attempts.
+    pub max_attempts: RetryMaxAttempts,
+    /// The delay before the first retry attempt.
+    pub initial_delay_ms: RetryInitialDelayMs,
+    /// A factor to multiply a current delay with to get the next one
+    pub delay_factor: RetryDelayFactor,
+    /// The maximum delay for a retry.
+    pub max_delay_ms: RetryDelayMaxMs,
+}
+
+impl RetryPolicy {
+    /// Set the maximum number of attempts for retries
+    pub fn max_attempts<T: Into<RetryMaxAttempts>>(mut self, max_attempts: T) -> Self {
+        self.max_attempts = max_attempts.into();
+        self
+    }
+
+    /// Set the delay for the first retry attempt after the original operation failed
+    pub fn initial_delay_ms<T: Into<RetryInitialDelayMs>>(mut self, initial_delay_ms: T) -> Self {
+        self.initial_delay_ms = initial_delay_ms.into();
+        self
+    }
+
+    /// Set the factor by which each current delay will be multiplied by to get the next delay
+    pub fn delay_factor<T: Into<RetryDelayFactor>>(mut self, delay_factor: T) -> Self {
+        self.delay_factor = delay_factor.into();
+        self
+    }
+
+    /// Set the maximum duration in milliseconds for a single delay
+    pub fn max_delay_ms<T: Into<RetryDelayMaxMs>>(mut self, max_delay_ms: T) -> Self {
+        self.max_delay_ms = max_delay_ms.into();
+        self
+    }
+
+    /// Validate this [RetryPolicy]
+    ///
+    /// Succeeds if
+    /// * `delay_factor` is at least 1.0
+    /// * `delay_factor` is a number
+    pub fn validate(&self) -> Result<(), AnyError> {
+        validate_delay_factor(self.delay_factor)
+    }
+
+    /// Create an [Iterator] of delays to be applied before each retry attempt
+    fn iterator(&self) -> impl Iterator<Item = Duration> {
+        RetryDelaysIterator::new(
+            self.max_attempts.into_inner(),
+            self.initial_delay_ms.into_inner() as f64 / 1_000.0,
+            self.max_delay_ms.into_inner() as f64 / 1_000.0,
+            self.delay_factor.into_inner(),
+        )
+    }
+}
+
+fn validate_delay_factor(delay_factor: RetryDelayFactor) -> Result<(), AnyError> {
+    if delay_factor.0 < 1.0 {
+        bail!("'delay_factor' must be at least 1.0");
+    }
+
+    if delay_factor.0.is_nan() {
+        bail!("'delay_factor' must not be NaN");
+    }
+
+    if delay_factor.0.is_infinite() {
+        bail!("'delay_factor' must not be infinite");
+    }
+
+    Ok(())
+}
+
+/// Rewrites the location of a request before a retry attempt
+///
+/// The function receives the original location and the number of the
+/// retry attempt starting with 1 for the first retry. The original
+/// request is always made to the original location. This also applies
+/// to the first request made to resume a broken stream.
+///
+/// This allows to fail over to mirrors of the same BLOB.
+///
+/// # Examples
+///
+/// ```rust
+/// # use condow_core::config::RetryLocationRewrite;
+/// let mirrors = ["primary.example.com", "mirror.example.com"];
+/// let rewrite = RetryLocationRewrite::new(move |location: &url::Url, attempt: usize| {
+///     let mut location = location.clone();
+///     let _ = location.set_host(Some(mirrors[attempt % mirrors.len()]));
+///     location
+/// });
+///
+/// let location = url::Url::parse("https://primary.example.com/blob").unwrap();
+/// assert_eq!(
+///     rewrite.rewrite(&location, 1).as_str(),
+///     "https://mirror.example.com/blob"
+/// );
+/// ```
+#[derive(Clone)]
+pub struct RetryLocationRewrite(Arc<RewriteFn>);
+
+type RewriteFn = dyn Fn(&url::Url, usize) -> url::Url + Send + Sync;
+
+impl RetryLocationRewrite {
+    /// Create a [RetryLocationRewrite] from the given function
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(&url::Url, usize) -> url::Url + Send + Sync + 'static,
+    {
+        Self(Arc::new(f))
+    }
+
+    /// Returns the location for the given retry attempt
+    pub fn rewrite(&self, location: &url::Url, attempt: usize) -> url::Url {
+        (self.0)(location, attempt)
+    }
+}
+
+impl<F> From<F> for RetryLocationRewrite
+where
+    F: Fn(&url::Url, usize) -> url::Url + Send + Sync + 'static,
+{
+    fn from(f: F) -> Self {
+        Self::new(f)
+    }
+}
+
+impl fmt::Debug for RetryLocationRewrite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RetryLocationRewrite")
+    }
+}
+
+impl PartialEq for RetryLocationRewrite {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// A budget of retries shared by all downloads using it
+///
+/// The budget is a token bucket. Each retry attempt and each attempt to
+/// resume a broken stream takes a token. If no token is left, the last error
+/// is returned right away without retrying. Tokens are refilled continuously
+/// by `refill_per_second` up to `max_tokens`.
+///
+/// Clones share the same tokens.
+///
+/// # Examples
+///
+/// ```rust
+/// # use condow_core::config::RetryBudget;
+/// let budget = RetryBudget::new(10, 0.5);
+///
+/// assert_eq!(budget.available(), 10);
+/// ```
+#[derive(Clone)]
+pub struct RetryBudget(Arc<Mutex<TokenBucket>>);
+
+struct TokenBucket {
+    max_tokens: f64,
+    refill_per_second: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed_secs = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed_secs * self.refill_per_second).min(self.max_tokens);
+        self.last_refill = now;
+    }
+}
+
+impl RetryBudget {
+    /// Create a full [RetryBudget] which regains `refill_per_second` tokens each second
+    ///
+    /// # Panics
+    ///
+    /// If `refill_per_second` is negative, NaN or infinite.
+    pub fn new(max_tokens: u32, refill_per_second: f64) -> Self {
+        assert!(
+            refill_per_second.is_finite() && refill_per_second >= 0.0,
+            "'refill_per_second' must be a finite number of at least 0.0"
+        );
+
+        Self(Arc::new(Mutex::new(TokenBucket {
+            max_tokens: max_tokens as f64,
+            refill_per_second,
+            tokens: max_tokens as f64,
+            last_refill: Instant::now(),
+        })))
+    }
+
+    /// The number of tokens currently available for retries
+    pub fn available(&self) -> u32 {
+        let mut bucket = self.0.lock().unwrap();
+        bucket.refill();
+        bucket.tokens as u32
+    }
+
+    /// Takes a token for a retry
+    ///
+    /// Returns `false` if no token is left.
+    pub(crate) fn try_withdraw(&self) -> bool {
+        let mut bucket = self.0.lock().unwrap();
+        bucket.refill();
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl fmt::Debug for RetryBudget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bucket = self.0.lock().unwrap();
+        f.debug_struct("RetryBudget")
+            .field("max_tokens", &bucket.max_tokens)
+            .field("refill_per_second", &bucket.refill_per_second)
+            .field("tokens", &bucket.tokens)
+            .finish()
+    }
+}
+
+impl PartialEq for RetryBudget {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
 /// An [Iterator] over delays to be applied before each retry
 ///
 /// The iterator returns a number of delays as
@@ -334,7 +703,16 @@ impl Iterator for RetryDelaysIterator {
 /// retries and broken streams.
 #[derive(Clone)]
 pub(crate) struct ClientRetryWrapper<C> {
-    inner: Arc<(C, Option<RetryConfig>)>,
+    inner: Arc<(ClientSource<C>, Option<RetryConfig>)>,
+}
+
+/// Where the client of a [ClientRetryWrapper] comes from
+#[derive(Clone)]
+enum ClientSource<C> {
+    /// A single client used for all requests
+    Shared(C),
+    /// A new client is created for each download
+    Factory(ClientFactory<C>),
 }
 
 impl<C> ClientRetryWrapper<C>
@@ -343,23 +721,62 @@ where
 {
     pub fn new(client: C, config: Option<RetryConfig>) -> Self {
         Self {
-            inner: Arc::new((client, config)),
+            inner: Arc::new((ClientSource::Shared(client), config)),
+        }
+    }
+
+    pub fn from_factory(factory: ClientFactory<C>, config: Option<RetryConfig>) -> Self {
+        Self {
+            inner: Arc::new((ClientSource::Factory(factory), config)),
         }
     }
 
+    /// Returns a wrapper with the same client (or factory) but other retries
+    pub fn with_retries(&self, config: Option<RetryConfig>) -> Self {
+        Self {
+            inner: Arc::new((self.inner.0.clone(), config)),
+        }
+    }
+
+    /// Returns a wrapper with the client to be used for all requests of a single download
+    ///
+    /// If the client is created by a [ClientFactory] a new client is created.
+    pub async fn for_download(&self) -> Result<Self, CondowError> {
+        let (source, config) = self.inner.as_ref();
+        match source {
+            ClientSource::Shared(_) => Ok(self.clone()),
+            ClientSource::Factory(factory) => {
+                Ok(Self::new(factory.create().await?, config.clone()))
+            }
+        }
+    }
+
+    /// Returns the client or creates a new one with the [ClientFactory]
+    async fn client(&self) -> Result<Cow<'_, C>, CondowError> {
+        match &self.inner.0 {
+            ClientSource::Shared(client) => Ok(Cow::Borrowed(client)),
+            ClientSource::Factory(factory) => Ok(Cow::Owned(factory.create().await?)),
+        }
+    }
+
+    /// Get the size of a BLOB
+    ///
+    /// Each attempt fails with [CondowErrorKind::Timeout](crate::errors::CondowErrorKind::Timeout)
+    /// if it takes longer than `timeout`.
     pub async fn get_size<R: Reporter>(
         &self,
         location: url::Url,
+        timeout: Option<Duration>,
         reporter: &R,
     ) -> Result<u64, CondowError>
     where
         R: Reporter,
     {
-        let (client, config) = self.inner.as_ref();
-        if let Some(config) = config {
-            retry_get_size(client, location, config, reporter).await
+        let client = self.client().await?;
+        if let Some(config) = &self.inner.1 {
+            retry_get_size(client.as_ref(), location, timeout, config, reporter).await
         } else {
-            Ok(client.get_size(location).await?)
+            get_size_with_timeout(client.as_ref(), location, timeout).await
         }
     }
 
@@ -369,13 +786,30 @@ where
         spec: DownloadSpec,
         reporter: &R,
     ) -> Result<(BytesStream, BytesHint), CondowError> {
-        let (client, config) = self.inner.as_ref();
-        if let Some(config) = config {
-            retry_download(client, location, spec, config, reporter).await
+        let client = self.client().await?;
+        if let Some(config) = &self.inner.1 {
+            retry_download(client.as_ref(), location, spec, config, reporter).await
         } else {
             Ok(client.download(location, spec).await?)
         }
     }
+
+    /// Download from `start` to the end of a BLOB
+    ///
+    /// See [CondowClient::download_from]
+    pub async fn download_from<R: Reporter>(
+        &self,
+        location: url::Url,
+        start: u64,
+        reporter: &R,
+    ) -> Result<(BytesStream, BytesHint), CondowError> {
+        let client = self.client().await?;
+        if let Some(config) = &self.inner.1 {
+            retry_download_from(client.as_ref(), location, start, config, reporter).await
+        } else {
+            Ok(client.download_from(location, start).await?)
+        }
+    }
 }
 
 #[cfg(test)]
@@ -388,10 +822,33 @@ where
     }
 }
 
+/// Request the size of a BLOB and fail if it takes longer than `timeout`
+async fn get_size_with_timeout<C: CondowClient>(
+    client: &C,
+    location: url::Url,
+    timeout: Option<Duration>,
+) -> Result<u64, CondowError> {
+    let timeout = if let Some(timeout) = timeout {
+        timeout
+    } else {
+        return client.get_size(location).await;
+    };
+
+    match tokio::time::timeout(timeout, client.get_size(location.clone())).await {
+        Ok(result) => result,
+        Err(_elapsed) => Err(CondowError::new_timeout(format!(
+            "size request for {} timed out after {} ms",
+            location,
+            timeout.as_millis()
+        ))),
+    }
+}
+
 /// Retries on the `get_size` request according to the [RetryConfig]
 async fn retry_get_size<C, R>(
     client: &C,
     location: url::Url,
+    timeout: Option<Duration>,
     config: &RetryConfig,
     reporter: &R,
 ) -> Result<u64, CondowError>
@@ -400,20 +857,26 @@ where
     R: Reporter,
 {
     // The first attempt
-    let mut last_err = match client.get_size(location.clone()).await {
+    let mut last_err = match get_size_with_timeout(client, location.clone(), timeout).await {
         Ok(v) => return Ok(v),
         Err(err) if err.is_retryable() => err,
         Err(err) => return Err(err),
     };
 
     // Retries if the first attempt failed
-    let mut delays = config.iterator();
+    let mut delays = config.initial_request_policy().iterator();
+    let mut attempt = 0;
     while let Some(delay) = delays.next() {
+        if !config.withdraw_from_budget() {
+            break;
+        }
+        attempt += 1;
+        let location = config.location_for_attempt(&location, attempt);
         reporter.retry_attempt(&location, &last_err, delay);
 
         tokio::time::sleep(delay).await;
 
-        last_err = match client.get_size(location.clone()).await {
+        last_err = match get_size_with_timeout(client, location, timeout).await {
             Ok(v) => return Ok(v),
             Err(err) if err.is_retryable() => err,
             Err(err) => return Err(err),
@@ -439,15 +902,95 @@ where
     R: Reporter,
 {
     // The initial stream for the whole download
-    let (stream, bytes_hint) =
-        retry_download_get_stream(client, location.clone(), spec, config, reporter).await?;
+    let (stream, bytes_hint, _content_range) = retry_download_get_stream(
+        client,
+        location.clone(),
+        spec,
+        config,
+        config.initial_request_policy(),
+        reporter,
+    )
+    .await?;
+
+    resume_broken_stream(
+        client,
+        location,
+        spec.start(),
+        (stream, bytes_hint),
+        config,
+        reporter,
+    )
+}
+
+/// Retries on attempts to get a stream from `start` to the end of a BLOB.
+///
+/// Broken streams are resumed like with [retry_download].
+async fn retry_download_from<C, R>(
+    client: &C,
+    location: url::Url,
+    start: u64,
+    config: &RetryConfig,
+    reporter: &R,
+) -> Result<(BytesStream, BytesHint), CondowError>
+where
+    C: CondowClient,
+    R: Reporter,
+{
+    // The first attempt
+    let mut last_err = match client.download_from(location.clone(), start).await {
+        Ok(response) => {
+            return resume_broken_stream(client, location, start, response, config, reporter)
+        }
+        Err(err) if err.is_retryable() => err,
+        Err(err) => return Err(err),
+    };
 
+    // Retries if the first attempt failed
+    let mut attempt = 0;
+    for delay in config.initial_request_policy().iterator() {
+        if !config.withdraw_from_budget() {
+            break;
+        }
+        attempt += 1;
+        let attempt_location = config.location_for_attempt(&location, attempt);
+        reporter.retry_attempt(&attempt_location, &last_err, delay);
+
+        tokio::time::sleep(delay).await;
+
+        last_err = match client.download_from(attempt_location, start).await {
+            Ok(response) => {
+                return resume_broken_stream(client, location, start, response, config, reporter)
+            }
+            Err(err) if err.is_retryable() => err,
+            Err(err) => return Err(err),
+        };
+    }
+
+    Err(last_err)
+}
+
+/// Wraps the stream of a download starting at `start` so that
+/// it is resumed if it breaks
+///
+/// The stream is returned as it is if its length is not known.
+fn resume_broken_stream<C, R>(
+    client: &C,
+    location: url::Url,
+    start: u64,
+    (stream, bytes_hint): (BytesStream, BytesHint),
+    config: &RetryConfig,
+    reporter: &R,
+) -> Result<(BytesStream, BytesHint), CondowError>
+where
+    C: CondowClient,
+    R: Reporter,
+{
     // Only if we have an length we can try to continue broken streams
     // because we can only download whole BLOBs or ranges. We use a range for
     // the complete BLOB to be able to determine the remainder after a stream broke.
     // If the mximum number to resume is 0 we also do not want to resume on broken streams.
     let blob_len_for_resume = bytes_hint.exact().and_then(|blob_len| {
-        if config.max_stream_resume_attempts.into_inner() > 0 {
+        if config.max_stream_resume_attempts.into_inner() > 0 && blob_len > 0 {
             Some(blob_len)
         } else {
             None
@@ -455,7 +998,7 @@ where
     });
 
     let original_range = if let Some(blob_len_for_resume) = blob_len_for_resume {
-        InclusiveRange(spec.start(), spec.start() + blob_len_for_resume - 1) // original range has at least len 1
+        InclusiveRange(start, start + blob_len_for_resume - 1) // original range has at least len 1
     } else {
         // We are done because we will not do any resume attempts
         return Ok((stream, bytes_hint));
@@ -466,9 +1009,9 @@ where
 
     // Now we try to complete the stream by requesting new streams with the remaining
     // bytes if a stream broke
-    tokio::spawn(loop_retry_complete_stream(
+    crate::helpers::spawn(loop_retry_complete_stream(
         stream,
-        location.clone(),
+        location,
         original_range,
         client.clone(),
         next_elem_tx,
@@ -555,6 +1098,14 @@ async fn loop_retry_complete_stream<C, R>(
                 break;
             }
 
+            if !config.withdraw_from_budget() {
+                let _ = next_elem_tx.unbounded_send(Err(IoError(format!(
+                    "retry budget exhausted after stream broke with \"{}\"",
+                    stream_io_error
+                ))));
+                break;
+            }
+
             let new_spec = DownloadSpec::Range(remaining_range);
             reporter.stream_resume_attempt(
                 &location,
@@ -562,10 +1113,24 @@ async fn loop_retry_complete_stream<C, R>(
                 original_range,
                 remaining_range,
             );
-            match retry_download_get_stream(&client, location.clone(), new_spec, &config, &reporter)
-                .await
+            match retry_download_get_stream(
+                &client,
+                location.clone(),
+                new_spec,
+                &config,
+                config.stream_resume_policy(),
+                &reporter,
+            )
+            .await
             {
-                Ok((new_stream, _)) => {
+                Ok((new_stream, _, content_range)) => {
+                    if let Err(err) = check_resumed_at(remaining_range, content_range) {
+                        let _ = next_elem_tx.unbounded_send(Err(IoError(format!(
+                            "{} after previous stream broke with \"{}\"",
+                            err, stream_io_error
+                        ))));
+                        break;
+                    }
                     stream = new_stream;
                 }
                 Err(err_new_stream) => {
@@ -588,6 +1153,28 @@ async fn loop_retry_complete_stream<C, R>(
     panic_guard.completed_without_panic = true;
 }
 
+/// Fails if the response to a resume request does not start at the requested offset
+///
+/// A missing `content_range` can not be verified and is accepted.
+fn check_resumed_at(
+    requested: InclusiveRange,
+    content_range: Option<InclusiveRange>,
+) -> Result<(), CondowError> {
+    match content_range {
+        Some(content_range) if content_range.start() != requested.start() => {
+            Err(CondowError::new_other(format!(
+                "the stream was resumed at byte {} instead of the requested byte {} \
+                (requested range {}, content range {})",
+                content_range.start(),
+                requested.start(),
+                requested,
+                content_range
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
 /// Consume a stream until it is finished or broken.
 ///
 /// If it finished [Ok] will be returned otherwise an [Err] containing
@@ -618,33 +1205,49 @@ async fn try_consume_stream<St: Stream<Item = Result<Bytes, IoError>>>(
 }
 
 /// Retries to get a new stream for the given download spec.
+///
+/// Returns the range claimed by the response (e.g. the `Content-Range`)
+/// if the client knows it.
+///
+/// The delays are taken from `policy` while the location for each
+/// retry attempt is determined by `config`.
 async fn retry_download_get_stream<C, R>(
     client: &C,
     location: url::Url,
     spec: DownloadSpec,
     config: &RetryConfig,
+    policy: RetryPolicy,
     reporter: &R,
-) -> Result<(BytesStream, BytesHint), CondowError>
+) -> Result<ContentRangeResponse, CondowError>
 where
     C: CondowClient,
     R: Reporter,
 {
     // The first attempt
-    let mut last_err = match client.download(location.clone(), spec).await {
-        Ok(stream_and_hint) => return Ok(stream_and_hint),
+    let mut last_err = match client
+        .download_with_content_range(location.clone(), spec)
+        .await
+    {
+        Ok(response) => return Ok(response),
         Err(err) if err.is_retryable() => err,
         Err(err) => return Err(err),
     };
 
     // Retries if the first attempt failed
-    let mut delays = config.iterator();
+    let mut delays = policy.iterator();
+    let mut attempt = 0;
     while let Some(delay) = delays.next() {
+        if !config.withdraw_from_budget() {
+            break;
+        }
+        attempt += 1;
+        let location = config.location_for_attempt(&location, attempt);
         reporter.retry_attempt(&location, &last_err, delay);
 
         tokio::time::sleep(delay).await;
 
-        last_err = match client.download(location.clone(), spec).await {
-            Ok(stream_and_hint) => return Ok(stream_and_hint),
+        last_err = match client.download_with_content_range(location, spec).await {
+            Ok(response) => return Ok(response),
             Err(err) if err.is_retryable() => err,
             Err(err) => return Err(err),
         };
@@ -39,7 +39,7 @@ mod retry_download {
         condow_client::{
             failing_client_simulator::FailingClientSimulatorBuilder, DownloadSpec, NoLocation,
         },
-        config::RetryConfig,
+        config::{RetryBudget, RetryConfig, RetryPolicy},
         errors::{CondowError, IoError},
         reporter::Reporter,
         retry::{
@@ -584,6 +584,233 @@ mod retry_download {
         assert_eq!(received, Err(BLOB[0..8].to_vec()));
     }
 
+    #[tokio::test]
+    async fn failed_request_is_retried_without_resume_attempts() {
+        let n_retries = 2;
+        let n_resumes = 0;
+
+        let client_builder = get_builder()
+            .responses()
+            .failures([RETRYABLE, RETRYABLE]) // e.g. 503
+            .success()
+            .never();
+
+        let (num_retries, stream_resume_attempts, received) =
+            download(client_builder, n_retries, n_resumes, 2..=12)
+                .await
+                .unwrap();
+
+        assert_eq!(num_retries, 2, "num_retries");
+        assert_eq!(stream_resume_attempts, 0, "stream_resume_attempts");
+        assert_eq!(received, Ok(BLOB[2..=12].to_vec()));
+    }
+
+    #[tokio::test]
+    async fn broken_stream_is_resumed_without_retries() {
+        let n_retries = 0;
+        let n_resumes = 2;
+
+        let client_builder = get_builder()
+            .responses()
+            .success_with_stream_failure(4)
+            .success_with_stream_failure(3)
+            .success()
+            .never();
+
+        let (num_retries, stream_resume_attempts, received) =
+            download(client_builder, n_retries, n_resumes, 2..=12)
+                .await
+                .unwrap();
+
+        assert_eq!(num_retries, 0, "num_retries");
+        assert_eq!(stream_resume_attempts, 2, "stream_resume_attempts");
+        assert_eq!(received, Ok(BLOB[2..=12].to_vec()));
+    }
+
+    #[tokio::test]
+    async fn initial_request_policy_does_not_apply_to_resumes() {
+        let config = RetryConfig::default()
+            .max_attempts(0)
+            .max_stream_resume_attempts(2)
+            .max_delay_ms(0)
+            .initial_request(RetryPolicy::default().max_attempts(2).max_delay_ms(0));
+
+        let client_builder = get_builder()
+            .responses()
+            .failures([RETRYABLE, RETRYABLE])
+            .success_with_stream_failure(4)
+            .failure(RETRYABLE)
+            .never();
+
+        let (num_retries, stream_resume_attempts, received) =
+            download_with_config(client_builder, config, 2..=12)
+                .await
+                .unwrap();
+
+        assert_eq!(num_retries, 2, "num_retries");
+        assert_eq!(stream_resume_attempts, 1, "stream_resume_attempts");
+        assert_eq!(received, Err(BLOB[2..6].to_vec()));
+    }
+
+    #[tokio::test]
+    async fn stream_resume_policy_does_not_apply_to_initial_requests() {
+        let config = RetryConfig::default()
+            .max_attempts(0)
+            .max_stream_resume_attempts(2)
+            .max_delay_ms(0)
+            .stream_resume(RetryPolicy::default().max_attempts(2).max_delay_ms(0));
+
+        let client_builder = get_builder().responses().failure(RETRYABLE).never();
+
+        let result = download_with_config(client_builder, config, 2..=12).await;
+
+        assert_eq!(result.unwrap_err().kind(), RETRYABLE);
+    }
+
+    #[tokio::test]
+    async fn stream_resume_policy_applies_to_resumes() {
+        let config = RetryConfig::default()
+            .max_attempts(0)
+            .max_stream_resume_attempts(2)
+            .max_delay_ms(0)
+            .stream_resume(RetryPolicy::default().max_attempts(2).max_delay_ms(0));
+
+        let client_builder = get_builder()
+            .responses()
+            .success_with_stream_failure(4)
+            .failures([RETRYABLE, RETRYABLE])
+            .success()
+            .never();
+
+        let (num_retries, stream_resume_attempts, received) =
+            download_with_config(client_builder, config, 2..=12)
+                .await
+                .unwrap();
+
+        assert_eq!(num_retries, 2, "num_retries");
+        assert_eq!(stream_resume_attempts, 1, "stream_resume_attempts");
+        assert_eq!(received, Ok(BLOB[2..=12].to_vec()));
+    }
+
+    #[tokio::test]
+    async fn top_level_policy_applies_to_both_by_default() {
+        let config = RetryConfig::default()
+            .max_attempts(1)
+            .max_stream_resume_attempts(2)
+            .max_delay_ms(0);
+
+        let client_builder = get_builder()
+            .responses()
+            .failure(RETRYABLE)
+            .success_with_stream_failure(4)
+            .failure(RETRYABLE)
+            .success()
+            .never();
+
+        let (num_retries, stream_resume_attempts, received) =
+            download_with_config(client_builder, config, 2..=12)
+                .await
+                .unwrap();
+
+        assert_eq!(num_retries, 2, "num_retries");
+        assert_eq!(stream_resume_attempts, 1, "stream_resume_attempts");
+        assert_eq!(received, Ok(BLOB[2..=12].to_vec()));
+    }
+
+    #[tokio::test]
+    async fn exhausted_budget_stops_retries() {
+        let config = RetryConfig::default()
+            .max_attempts(3)
+            .max_delay_ms(0)
+            .budget(RetryBudget::new(1, 0.0));
+
+        let client_builder = get_builder()
+            .responses()
+            .failures([RETRYABLE, RETRYABLE])
+            .success()
+            .never();
+
+        let result = download_with_config(client_builder, config.clone(), 2..=12).await;
+
+        assert_eq!(result.unwrap_err().kind(), RETRYABLE);
+        assert_eq!(config.budget.unwrap().available(), 0);
+    }
+
+    #[tokio::test]
+    async fn exhausted_budget_stops_resumes() {
+        let config = RetryConfig::default()
+            .max_attempts(0)
+            .max_stream_resume_attempts(2)
+            .max_delay_ms(0)
+            .budget(RetryBudget::new(0, 0.0));
+
+        let client_builder = get_builder()
+            .responses()
+            .success_with_stream_failure(4)
+            .success()
+            .never();
+
+        let (num_retries, stream_resume_attempts, received) =
+            download_with_config(client_builder, config, 2..=12)
+                .await
+                .unwrap();
+
+        assert_eq!(num_retries, 0, "num_retries");
+        assert_eq!(stream_resume_attempts, 0, "stream_resume_attempts");
+        assert_eq!(received, Err(BLOB[2..6].to_vec()));
+    }
+
+    #[tokio::test]
+    async fn connection_reset_mid_part_resumes_at_failed_offset() {
+        #[derive(Clone, Default)]
+        struct ResumeProbe(Arc<std::sync::Mutex<Vec<(InclusiveRange, InclusiveRange)>>>);
+
+        impl Reporter for ResumeProbe {
+            fn stream_resume_attempt(
+                &self,
+                _location: &dyn fmt::Display,
+                _error: &IoError,
+                orig_range: InclusiveRange,
+                remaining_range: InclusiveRange,
+            ) {
+                self.0.lock().unwrap().push((orig_range, remaining_range));
+            }
+        }
+
+        let client = get_builder()
+            .responses()
+            .success_with_stream_failure(5) // connection reset
+            .success()
+            .never()
+            .finish();
+        let config = RetryConfig::default()
+            .max_attempts(0)
+            .max_stream_resume_attempts(1)
+            .max_delay_ms(0);
+        let probe = ResumeProbe::default();
+
+        let (mut stream, _bytes_hint) = retry_download(
+            &client,
+            url::Url::parse("noscheme://").expect("a valid URL"),
+            InclusiveRange(4, 13).into(),
+            &config,
+            &probe,
+        )
+        .await
+        .unwrap();
+
+        let mut received = Vec::new();
+        while let Some(next) = stream.next().await {
+            received.extend_from_slice(&next.unwrap());
+        }
+
+        assert_eq!(received, BLOB[4..=13].to_vec());
+        assert_eq!(
+            *probe.0.lock().unwrap(),
+            vec![(InclusiveRange(4, 13), InclusiveRange(9, 13))]
+        );
+    }
+
     const BLOB: &[u8] = &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
 
     fn get_builder() -> FailingClientSimulatorBuilder {
@@ -1056,6 +1283,119 @@ mod loop_retry_complete_stream {
         assert_eq!(received, Err(BLOB[0..8].to_vec()));
     }
 
+    #[tokio::test]
+    async fn resume_at_the_wrong_offset_fails() {
+        let client_builder = get_builder()
+            .responses()
+            .success_with_stream_failure(4)
+            .success()
+            .never();
+
+        let (received, err) = download_claiming_start(client_builder, 0).await;
+
+        assert_eq!(received, BLOB[0..4].to_vec());
+        let err = err.expect("an error");
+        assert!(
+            err.0.starts_with(
+                "the stream was resumed at byte 0 instead of the requested byte 4 \
+                (requested range [4,15], content range [0,15])"
+            ),
+            "{}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn resume_at_the_requested_offset_succeeds() {
+        let client_builder = get_builder()
+            .responses()
+            .success_with_stream_failure(4)
+            .success()
+            .never();
+
+        let (received, err) = download_claiming_start(client_builder, 4).await;
+
+        assert!(err.is_none(), "{:?}", err);
+        assert_eq!(received, BLOB.to_vec());
+    }
+
+    /// Resumes a download of the complete [BLOB] with a client whose
+    /// responses claim to start at `claimed_start`
+    async fn download_claiming_start(
+        client_builder: crate::condow_client::failing_client_simulator::ResponsesBuilder,
+        claimed_start: u64,
+    ) -> (Vec<u8>, Option<IoError>) {
+        use futures::{future::BoxFuture, FutureExt, TryFutureExt};
+
+        use crate::{
+            condow_client::{
+                failing_client_simulator::FailingClientSimulator, ContentRangeResponse,
+                DownloadSpec,
+            },
+            streams::{BytesHint, BytesStream},
+        };
+
+        #[derive(Clone)]
+        struct ClaimsStartAt(FailingClientSimulator, u64);
+
+        impl CondowClient for ClaimsStartAt {
+            fn get_size(&self, location: url::Url) -> BoxFuture<'static, Result<u64, CondowError>> {
+                self.0.get_size(location)
+            }
+
+            fn download(
+                &self,
+                location: url::Url,
+                spec: DownloadSpec,
+            ) -> BoxFuture<'static, Result<(BytesStream, BytesHint), CondowError>> {
+                self.0.download(location, spec)
+            }
+
+            fn download_with_content_range(
+                &self,
+                location: url::Url,
+                spec: DownloadSpec,
+            ) -> BoxFuture<'static, Result<ContentRangeResponse, CondowError>> {
+                let claimed = match spec {
+                    DownloadSpec::Complete => None,
+                    DownloadSpec::Range(range) => Some(InclusiveRange(self.1, range.end_incl())),
+                };
+                self.0
+                    .download(location, spec)
+                    .map_ok(move |(stream, bytes_hint)| (stream, bytes_hint, claimed))
+                    .boxed()
+            }
+        }
+
+        let client = ClaimsStartAt(client_builder.finish(), claimed_start);
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+        let (next_elem_tx, mut rx) = mpsc::unbounded();
+        let (initial_stream, _) = client
+            .download(location.clone(), DownloadSpec::Range(FULL_RANGE.into()))
+            .await
+            .unwrap();
+
+        tokio::spawn(loop_retry_complete_stream(
+            initial_stream,
+            location,
+            FULL_RANGE.into(),
+            client,
+            next_elem_tx,
+            RetryConfig::default().max_delay_ms(0),
+            crate::reporter::NoReporting,
+        ));
+
+        let mut received = Vec::new();
+        while let Some(next) = rx.next().await {
+            match next {
+                Ok(bytes) => received.extend_from_slice(&bytes),
+                Err(err) => return (received, Some(err)),
+            }
+        }
+
+        (received, None)
+    }
+
     const BLOB: &[u8] = &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
     const FULL_RANGE: RangeInclusive<u64> = 0u64..=(BLOB.len() - 1) as u64;
 
@@ -1176,7 +1516,7 @@ mod retry_download_get_stream {
 
     use futures::{stream, FutureExt};
 
-    use crate::{condow_client::NoLocation, errors::CondowErrorKind};
+    use crate::{condow_client::NoLocation, errors::CondowErrorKind, reporter::NoReporting};
 
     use super::*;
 
@@ -1321,6 +1661,71 @@ mod retry_download_get_stream {
         assert_eq!(result, Ok(2));
     }
 
+    #[tokio::test]
+    async fn a_timed_out_attempt_is_retried() {
+        let client = HangsOnFirstAttempt::default();
+        let config = RetryConfig::default().max_attempts(1).max_delay_ms(0);
+
+        let size = retry_get_size(
+            &client,
+            url::Url::parse("noscheme://").expect("a valid URL"),
+            Some(Duration::from_millis(10)),
+            &config,
+            &NoReporting,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(size, 42);
+        assert_eq!(client.0.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn a_timed_out_attempt_fails_without_retries() {
+        let client = HangsOnFirstAttempt::default();
+        let config = RetryConfig::default().max_attempts(0);
+
+        let err = retry_get_size(
+            &client,
+            url::Url::parse("noscheme://").expect("a valid URL"),
+            Some(Duration::from_millis(10)),
+            &config,
+            &NoReporting,
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(err.kind(), CondowErrorKind::Timeout);
+        assert!(err.is_retryable());
+        assert_eq!(client.0.load(Ordering::SeqCst), 1);
+    }
+
+    /// A client which never answers the first size request
+    #[derive(Clone, Default)]
+    struct HangsOnFirstAttempt(Arc<AtomicUsize>);
+
+    impl CondowClient for HangsOnFirstAttempt {
+        fn get_size(
+            &self,
+            _location: url::Url,
+        ) -> futures::future::BoxFuture<'static, Result<u64, CondowError>> {
+            if self.0.fetch_add(1, Ordering::SeqCst) == 0 {
+                futures::future::pending().boxed()
+            } else {
+                futures::future::ready(Ok(42)).boxed()
+            }
+        }
+
+        fn download(
+            &self,
+            _location: url::Url,
+            _spec: DownloadSpec,
+        ) -> futures::future::BoxFuture<'static, Result<(BytesStream, BytesHint), CondowError>>
+        {
+            unimplemented!()
+        }
+    }
+
     /// Simulates a call to a client
     ///
     /// `fails` are the errors to be returned before a success is delivered
@@ -1393,6 +1798,7 @@ mod retry_download_get_stream {
             url::Url::parse("noscheme://").expect("a valid URL"),
             DownloadSpec::Complete,
             &config,
+            config.initial_request_policy(),
             &probe,
         )
         .await
@@ -1633,6 +2039,7 @@ mod retry_get_size {
         match retry_get_size(
             &client,
             url::Url::parse("noscheme://").expect("a valid URL"),
+            None,
             &config,
             &probe,
         )
@@ -1825,3 +2232,177 @@ mod iterator {
         assert!(iter.next().is_none());
     }
 }
+
+mod location_rewrite {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{
+        condow_client::{
+            failing_client_simulator::FailingClientSimulatorBuilder, InstrumentedClient,
+        },
+        config::Config,
+        Condow,
+    };
+
+    use super::*;
+
+    const BLOB: &[u8] = &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+
+    fn mirror(location: &url::Url, attempt: usize) -> url::Url {
+        let mut location = location.clone();
+        location
+            .set_host(Some(&format!("mirror-{}.example.com", attempt)))
+            .unwrap();
+        location
+    }
+
+    /// Returns the hosts of the download requests made
+    async fn download_hosts(
+        config: RetryConfig,
+        client_builder: FailingClientSimulatorBuilder,
+    ) -> Vec<String> {
+        let hosts = Arc::new(Mutex::new(Vec::new()));
+        let hosts_in_callback = Arc::clone(&hosts);
+        let client = InstrumentedClient::new(client_builder.finish()).on_download(
+            move |location, _spec, _outcome, _elapsed| {
+                hosts_in_callback
+                    .lock()
+                    .unwrap()
+                    .push(location.host_str().unwrap().to_string());
+            },
+        );
+        let config = Config::default()
+            .buffers_full_delay_ms(0)
+            .retries(config.max_delay_ms(0));
+        let condow = Condow::new(client, config).unwrap();
+        let location = url::Url::parse("https://primary.example.com/blob").unwrap();
+
+        let result = condow
+            .download(&location, 0..16)
+            .await
+            .unwrap()
+            .into_vec()
+            .await
+            .unwrap();
+        assert_eq!(result, BLOB);
+
+        let hosts = hosts.lock().unwrap().clone();
+        hosts
+    }
+
+    #[tokio::test]
+    async fn retries_on_the_same_location_by_default() {
+        let client_builder = FailingClientSimulatorBuilder::default()
+            .blob_static(BLOB)
+            .responses()
+            .failures([CondowErrorKind::Remote, CondowErrorKind::Remote])
+            .success()
+            .done();
+
+        let hosts = download_hosts(RetryConfig::default(), client_builder).await;
+
+        assert_eq!(hosts, vec!["primary.example.com"; 3]);
+    }
+
+    #[tokio::test]
+    async fn retries_are_made_on_the_rewritten_location() {
+        let client_builder = FailingClientSimulatorBuilder::default()
+            .blob_static(BLOB)
+            .responses()
+            .failures([CondowErrorKind::Remote, CondowErrorKind::Remote])
+            .success()
+            .done();
+
+        let hosts = download_hosts(
+            RetryConfig::default().location_rewrite(mirror),
+            client_builder,
+        )
+        .await;
+
+        assert_eq!(
+            hosts,
+            vec![
+                "primary.example.com",
+                "mirror-1.example.com",
+                "mirror-2.example.com"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn a_stream_is_resumed_on_the_original_location() {
+        let client_builder = FailingClientSimulatorBuilder::default()
+            .blob_static(BLOB)
+            .responses()
+            .success_with_stream_failure(4)
+            .failure(CondowErrorKind::Remote)
+            .success()
+            .done();
+
+        let hosts = download_hosts(
+            RetryConfig::default().location_rewrite(mirror),
+            client_builder,
+        )
+        .await;
+
+        assert_eq!(
+            hosts,
+            vec![
+                "primary.example.com",
+                "primary.example.com",
+                "mirror-1.example.com"
+            ]
+        );
+    }
+}
+
+mod retry_budget {
+    use std::time::Duration;
+
+    use crate::config::RetryBudget;
+
+    #[test]
+    fn a_new_budget_is_full() {
+        let budget = RetryBudget::new(3, 0.0);
+
+        assert_eq!(budget.available(), 3);
+    }
+
+    #[test]
+    fn withdrawing_fails_when_exhausted() {
+        let budget = RetryBudget::new(2, 0.0);
+
+        assert!(budget.try_withdraw());
+        assert!(budget.try_withdraw());
+        assert!(!budget.try_withdraw());
+        assert_eq!(budget.available(), 0);
+    }
+
+    #[test]
+    fn clones_share_the_tokens() {
+        let budget = RetryBudget::new(1, 0.0);
+        let cloned = budget.clone();
+
+        assert!(cloned.try_withdraw());
+        assert!(!budget.try_withdraw());
+        assert_eq!(budget, cloned);
+        assert_ne!(budget, RetryBudget::new(1, 0.0));
+    }
+
+    #[test]
+    fn tokens_are_refilled_up_to_the_maximum() {
+        let budget = RetryBudget::new(2, 1_000.0);
+        assert!(budget.try_withdraw());
+        assert!(budget.try_withdraw());
+
+        std::thread::sleep(Duration::from_millis(20));
+
+        assert_eq!(budget.available(), 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn a_negative_refill_rate_panics() {
+        RetryBudget::new(1, -1.0);
+    }
+}
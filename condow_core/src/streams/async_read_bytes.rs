@@ -0,0 +1,305 @@
+use std::{
+    collections::VecDeque,
+    io::{Error as IoError, Result as IoResult},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use futures::{ready, Stream};
+use tokio::io::{AsyncRead, ReadBuf};
+
+use super::{BytesHint, ChunkStream, PartStream};
+
+/// A [tokio::io::AsyncRead] over the bytes of a [PartStream]
+///
+/// With the feature `futures-io` it also implements [futures::AsyncRead].
+///
+/// The bytes are read in the same order as within the downloaded BLOB/range.
+/// The chunks of the part last received are buffered until they
+/// were read completely. If a chunk is larger than the buffer to read
+/// into, the buffer is filled and the remainder is kept for the next read.
+///
+/// A [CondowError](crate::errors::CondowError) is returned as a
+/// [std::io::Error] with a matching [std::io::ErrorKind]. The
+/// [CondowError](crate::errors::CondowError) is its inner error.
+/// After an error the reader is broken and every read fails.
+///
+/// Use [PartStream::into_async_read] to create an [AsyncReadBytes].
+pub struct AsyncReadBytes {
+    parts: PartStream<ChunkStream>,
+    chunks: VecDeque<Bytes>,
+    is_broken: bool,
+}
+
+impl AsyncReadBytes {
+    pub fn new(parts: PartStream<ChunkStream>) -> Self {
+        Self {
+            parts,
+            chunks: VecDeque::new(),
+            is_broken: false,
+        }
+    }
+
+    /// Hint on the remaining bytes of the underlying [PartStream]
+    ///
+    /// Bytes already buffered by the reader are not included.
+    pub fn bytes_hint(&self) -> BytesHint {
+        self.parts.bytes_hint()
+    }
+}
+
+impl AsyncReadBytes {
+    /// Takes up to `max_len` bytes of the chunk last received without copying them
+    ///
+    /// The remainder of a chunk larger than `max_len` stays buffered.
+    /// A new part is only polled if there are no more buffered bytes.
+    /// Returns empty [Bytes] at the end of the stream.
+    fn poll_take_bytes(&mut self, cx: &mut Context<'_>, max_len: usize) -> Poll<IoResult<Bytes>> {
+        if self.is_broken {
+            return Poll::Ready(Err(broken_reader_error()));
+        }
+
+        if max_len == 0 {
+            return Poll::Ready(Ok(Bytes::new()));
+        }
+
+        loop {
+            if let Some(chunk) = self.chunks.front_mut() {
+                let bytes = chunk.split_to(chunk.len().min(max_len));
+                if chunk.is_empty() {
+                    self.chunks.pop_front();
+                }
+
+                if !bytes.is_empty() {
+                    return Poll::Ready(Ok(bytes));
+                }
+
+                continue;
+            }
+
+            match ready!(Pin::new(&mut self.parts).poll_next(cx)) {
+                Some(Ok(part)) => self.chunks.extend(part.chunks),
+                Some(Err(err)) => {
+                    self.is_broken = true;
+                    return Poll::Ready(Err(err.into()));
+                }
+                None => return Poll::Ready(Ok(Bytes::new())),
+            }
+        }
+    }
+}
+
+impl AsyncRead for AsyncReadBytes {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<IoResult<()>> {
+        let bytes = ready!(self.get_mut().poll_take_bytes(cx, buf.remaining()))?;
+        buf.put_slice(&bytes);
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Requires the feature `futures-io`
+#[cfg(feature = "futures-io")]
+impl futures::AsyncRead for AsyncReadBytes {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<IoResult<usize>> {
+        let bytes = ready!(self.get_mut().poll_take_bytes(cx, buf.len()))?;
+        buf[..bytes.len()].copy_from_slice(&bytes);
+        Poll::Ready(Ok(bytes.len()))
+    }
+}
+
+fn broken_reader_error() -> IoError {
+    IoError::other("the reader is broken and will not yield any more bytes")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::ErrorKind as IoErrorKind, pin::Pin};
+
+    use futures::future::poll_fn;
+    use tokio::io::{AsyncRead, ReadBuf};
+
+    use crate::{
+        errors::CondowError,
+        streams::PartStream,
+        test_utils::{create_chunk_stream_with_err, create_part_stream},
+    };
+
+    use super::AsyncReadBytes;
+
+    #[test]
+    fn async_read_bytes_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync + Unpin + 'static>() {}
+
+        assert_send_sync::<AsyncReadBytes>();
+    }
+
+    async fn read(reader: &mut AsyncReadBytes, buf_size: usize) -> std::io::Result<Vec<u8>> {
+        let mut buf = vec![0; buf_size];
+        let n_bytes = poll_fn(|cx| {
+            let mut read_buf = ReadBuf::new(&mut buf);
+            Pin::new(&mut *reader)
+                .poll_read(cx, &mut read_buf)
+                .map_ok(|()| read_buf.filled().len())
+        })
+        .await?;
+        buf.truncate(n_bytes);
+        Ok(buf)
+    }
+
+    async fn read_to_end(reader: &mut AsyncReadBytes, buf_size: usize) -> std::io::Result<Vec<u8>> {
+        let mut collected = Vec::new();
+        loop {
+            let bytes = read(reader, buf_size).await?;
+            if bytes.is_empty() {
+                return Ok(collected);
+            }
+            collected.extend(bytes);
+        }
+    }
+
+    #[tokio::test]
+    async fn reads_the_bytes_in_order() {
+        for parts in 1..10 {
+            for chunks in 1..5 {
+                for buf_size in [1, 3, 7, 100] {
+                    let (stream, expected) = create_part_stream(parts, chunks, true, Some(10));
+                    let mut reader = stream.into_async_read();
+
+                    let result = read_to_end(&mut reader, buf_size).await.unwrap();
+
+                    assert_eq!(
+                        result, expected,
+                        "parts: {parts}, chunks: {chunks}, buf_size: {buf_size}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn an_empty_buffer_reads_nothing() {
+        let (stream, expected) = create_part_stream(2, 2, true, Some(10));
+        let mut reader = stream.into_async_read();
+
+        assert!(read(&mut reader, 0).await.unwrap().is_empty());
+        assert_eq!(read_to_end(&mut reader, 5).await.unwrap(), expected);
+    }
+
+    #[tokio::test]
+    async fn surfaces_the_condow_error() {
+        let (chunk_stream, _expected) = create_chunk_stream_with_err(3, 2, true, Some(10), 3);
+        let mut reader = PartStream::from_chunk_stream(chunk_stream)
+            .unwrap()
+            .into_async_read();
+
+        let err = read_to_end(&mut reader, 4).await.unwrap_err();
+
+        assert!(err.into_inner().unwrap().is::<CondowError>());
+        let broken = read(&mut reader, 4).await.unwrap_err();
+        assert_eq!(broken.kind(), IoErrorKind::Other);
+    }
+
+    #[cfg(feature = "futures-io")]
+    #[tokio::test]
+    async fn futures_io_reads_the_bytes_in_order() {
+        use futures::AsyncReadExt;
+
+        for parts in 1..10 {
+            for chunks in 1..5 {
+                let (stream, expected) = create_part_stream(parts, chunks, true, Some(10));
+                let mut reader = stream.into_async_read();
+
+                let mut result = Vec::new();
+                reader.read_to_end(&mut result).await.unwrap();
+
+                assert_eq!(result, expected, "parts: {parts}, chunks: {chunks}");
+            }
+        }
+    }
+
+    #[cfg(feature = "futures-io")]
+    #[tokio::test]
+    async fn futures_io_keeps_the_remainder_of_a_large_chunk() {
+        use futures::AsyncReadExt;
+
+        use crate::{condow_client::InMemoryClient, config::Config, Condow};
+
+        const BLOB: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+
+        let client = InMemoryClient::new_static(BLOB).chunk_size(10);
+        let config = Config::default().part_size_bytes(13).max_concurrency(2);
+        let condow = Condow::new(client, config).unwrap();
+        let location = url::Url::parse("noscheme://").expect("a valid URL");
+        let mut reader = condow
+            .download(&location, ..)
+            .await
+            .unwrap()
+            .into_async_read();
+
+        let mut reads = Vec::new();
+        let mut buf = [0; 4];
+        loop {
+            let n_bytes = reader.read(&mut buf).await.unwrap();
+            if n_bytes == 0 {
+                break;
+            }
+            reads.push(buf[..n_bytes].to_vec());
+        }
+
+        let expected: Vec<&[u8]> = vec![
+            b"abcd", b"efgh", b"ij", b"klm", b"nopq", b"rstu", b"vw", b"xyz",
+        ];
+        assert_eq!(reads, expected);
+    }
+
+    #[cfg(feature = "futures-io")]
+    #[tokio::test]
+    async fn futures_io_surfaces_the_condow_error() {
+        use futures::AsyncReadExt;
+
+        let (chunk_stream, _expected) = create_chunk_stream_with_err(3, 2, true, Some(10), 3);
+        let mut reader = PartStream::from_chunk_stream(chunk_stream)
+            .unwrap()
+            .into_async_read();
+
+        let mut result = Vec::new();
+        let err = reader.read_to_end(&mut result).await.unwrap_err();
+
+        assert!(err.into_inner().unwrap().is::<CondowError>());
+    }
+
+    #[test]
+    fn maps_the_error_kinds() {
+        let cases = [
+            (CondowError::new_not_found("nf"), IoErrorKind::NotFound),
+            (
+                CondowError::new_access_denied("ad"),
+                IoErrorKind::PermissionDenied,
+            ),
+            (CondowError::new_timeout("to"), IoErrorKind::TimedOut),
+            (
+                CondowError::new_cancelled("ca"),
+                IoErrorKind::ConnectionAborted,
+            ),
+            (
+                CondowError::new_invalid_range("ir"),
+                IoErrorKind::InvalidInput,
+            ),
+            (CondowError::new_remote("re"), IoErrorKind::Other),
+        ];
+
+        for (err, kind) in cases {
+            let io_err: std::io::Error = err.into();
+            assert_eq!(io_err.kind(), kind);
+        }
+    }
+}
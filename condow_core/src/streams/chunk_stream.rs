@@ -4,12 +4,21 @@ use std::{
 };
 
 use bytes::Bytes;
-use futures::{channel::mpsc, ready, Stream, StreamExt};
+use futures::{channel::mpsc, ready, stream::BoxStream, Stream, StreamExt, TryStreamExt};
 use pin_project_lite::pin_project;
+use tokio::sync::OwnedSemaphorePermit;
 
-use crate::errors::CondowError;
+use crate::{
+    config::MaxPreallocationBytes,
+    errors::CondowError,
+    helpers::{buffer_range, u64_to_usize},
+    InclusiveRange,
+};
 
-use super::{BytesHint, PartStream};
+use super::{
+    BytesHint, CoverageTrackedStream, DownloadMode, MissingRanges, PartStream, RetryRecords,
+    RetryReport,
+};
 
 /// The type of the elements returned by a [ChunkStream]
 pub type ChunkStreamItem = Result<Chunk, CondowError>;
@@ -54,16 +63,59 @@ impl Chunk {
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    /// Splits this chunk into two at the given index.
+    ///
+    /// The first chunk contains the bytes `[0, at)` and the second one
+    /// the bytes `[at, len)`. The offsets and `bytes_left` are adjusted
+    /// accordingly. Both chunks keep the `chunk_index` of this chunk.
+    ///
+    /// No bytes are copied.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at > len`.
+    pub fn split_at(mut self, at: usize) -> (Chunk, Chunk) {
+        let head_bytes = self.bytes.split_to(at);
+        let tail_len = self.bytes.len() as u64;
+
+        let head = Chunk {
+            part_index: self.part_index,
+            chunk_index: self.chunk_index,
+            blob_offset: self.blob_offset,
+            range_offset: self.range_offset,
+            bytes: head_bytes,
+            bytes_left: self.bytes_left + tail_len,
+        };
+
+        let tail = Chunk {
+            blob_offset: self.blob_offset + at as u64,
+            range_offset: self.range_offset + at as u64,
+            ..self
+        };
+
+        (head, tail)
+    }
 }
 
 pin_project! {
     /// A stream of [Chunk]s received from the network
+    ///
+    /// A [ChunkStream] is `Send` and `Sync` and can therefore be stored
+    /// anywhere a thread safe value is required.
     pub struct ChunkStream {
         bytes_hint: BytesHint,
         #[pin]
         receiver: mpsc::UnboundedReceiver<ChunkStreamItem>,
         is_closed: bool,
+        failed_with: Option<String>,
         is_fresh: bool,
+        download_mode: DownloadMode,
+        n_parts: Option<u64>,
+        max_preallocation_bytes: usize,
+        download_permit: Option<OwnedSemaphorePermit>,
+        missing_ranges: MissingRanges,
+        retry_records: RetryRecords,
     }
 }
 
@@ -75,7 +127,14 @@ impl ChunkStream {
             bytes_hint,
             receiver,
             is_closed: false,
+            failed_with: None,
             is_fresh: true,
+            download_mode: DownloadMode::default(),
+            n_parts: None,
+            max_preallocation_bytes: MaxPreallocationBytes::default().into_inner(),
+            download_permit: None,
+            missing_ranges: MissingRanges::default(),
+            retry_records: RetryRecords::default(),
         };
 
         (me, tx)
@@ -87,6 +146,7 @@ impl ChunkStream {
     pub fn empty() -> Self {
         let (mut me, _) = Self::new(BytesHint(0, Some(0)));
         me.is_closed = true;
+        me.n_parts = Some(0);
         me.receiver.close();
         me
     }
@@ -101,6 +161,67 @@ impl ChunkStream {
         self.is_fresh
     }
 
+    /// Returns how the chunks of this stream are downloaded
+    pub fn download_mode(&self) -> DownloadMode {
+        self.download_mode
+    }
+
+    pub(crate) fn set_download_mode(&mut self, download_mode: DownloadMode) {
+        self.download_mode = download_mode;
+    }
+
+    /// Returns the total number of parts of the download
+    ///
+    /// Each [Chunk] belongs to one of these parts as given by its `part_index`.
+    /// `None` if the number of parts is not known.
+    pub fn n_parts(&self) -> Option<u64> {
+        self.n_parts
+    }
+
+    pub(crate) fn set_n_parts(&mut self, n_parts: u64) {
+        self.n_parts = Some(n_parts);
+    }
+
+    pub(crate) fn max_preallocation_bytes(&self) -> usize {
+        self.max_preallocation_bytes
+    }
+
+    pub(crate) fn set_max_preallocation_bytes(&mut self, max_preallocation_bytes: usize) {
+        self.max_preallocation_bytes = max_preallocation_bytes;
+    }
+
+    /// Returns the ranges which were filled with zeros since their parts failed
+    ///
+    /// Only contains ranges if failed parts are skipped
+    /// (see [OnPartFailure::Skip](crate::config::OnPartFailure::Skip)).
+    /// The ranges are relative to the BLOB. The result is only
+    /// final after the stream ended.
+    pub fn missing_ranges(&self) -> Vec<InclusiveRange> {
+        self.missing_ranges.get()
+    }
+
+    pub(crate) fn missing_ranges_handle(&self) -> MissingRanges {
+        self.missing_ranges.clone()
+    }
+
+    /// Returns the retries made for each part of the download
+    ///
+    /// The result is only final after the stream ended.
+    pub fn retry_report(&self) -> RetryReport {
+        self.retry_records.report()
+    }
+
+    pub(crate) fn retry_records_handle(&self) -> RetryRecords {
+        self.retry_records.clone()
+    }
+
+    /// The permit is held until the stream ended, failed or was dropped.
+    pub(crate) fn set_download_permit(&mut self, permit: OwnedSemaphorePermit) {
+        if !self.is_closed {
+            self.download_permit = Some(permit);
+        }
+    }
+
     /// Writes all received bytes into the provided buffer
     ///
     /// Fails if the buffer is too small or if the stream was already iterated.
@@ -147,16 +268,13 @@ impl ChunkStream {
                 Ok(next) => next,
             };
 
-            if range_offset > usize::MAX as u64 {
-                self.receiver.close();
-                return Err(CondowError::new_other(
-                    "usize overflow while casting from u64",
-                ));
-            }
-
-            let range_offset = range_offset as usize;
-
-            let end_excl = range_offset + bytes.len();
+            let (range_offset, end_excl) = match buffer_range(range_offset, bytes.len()) {
+                Ok(buffer_range) => buffer_range,
+                Err(err) => {
+                    self.receiver.close();
+                    return Err(err);
+                }
+            };
             if end_excl > buffer.len() {
                 self.receiver.close();
                 return Err(CondowError::new_other(format!(
@@ -179,23 +297,28 @@ impl ChunkStream {
     ///
     /// Fails if the stream was already iterated.
     ///
+    /// At most [Config::max_preallocation_bytes](crate::config::Config::max_preallocation_bytes)
+    /// are allocated upfront. If the [BytesHint] exceeds this limit the `Vec`
+    /// grows as bytes are received.
+    ///
     /// Since the parts and therefore the chunks are not ordered we can
     /// not know, whether we can fill the `Vec` in a contiguous way.
     pub async fn into_vec(mut self) -> Result<Vec<u8>, CondowError> {
         if let Some(total_bytes) = self.bytes_hint.exact() {
-            if total_bytes > usize::MAX as u64 {
-                self.receiver.close();
-                return Err(CondowError::new_other(
-                    "usize overflow while casting from u64",
-                ));
-            }
+            let total_bytes = match u64_to_usize(total_bytes) {
+                Ok(total_bytes) => total_bytes,
+                Err(err) => {
+                    self.receiver.close();
+                    return Err(err);
+                }
+            };
 
-            let mut buffer = vec![0; total_bytes as usize];
-            let _ = self.write_buffer(buffer.as_mut()).await?;
-            Ok(buffer)
-        } else {
-            stream_into_vec_with_unknown_size(self).await
+            if total_bytes <= self.max_preallocation_bytes {
+                return stream_into_vec(self, total_bytes, Some(total_bytes)).await;
+            }
         }
+
+        stream_into_vec_with_unknown_size(self).await
     }
 
     /// Turns this stream into a [PartStream]
@@ -204,10 +327,66 @@ impl ChunkStream {
     pub fn try_into_part_stream(self) -> Result<PartStream<Self>, CondowError> {
         PartStream::try_from(self)
     }
+
+    /// Turns this stream into a [CoverageTrackedStream]
+    ///
+    /// The returned stream passes the chunks through unchanged and reports
+    /// whether they covered the downloaded range once it ended.
+    /// Chunks pulled from this stream before are not tracked.
+    pub fn into_coverage_tracked(self) -> CoverageTrackedStream {
+        CoverageTrackedStream::new(self)
+    }
+
+    /// Turns this stream into a stream of [Bytes]
+    ///
+    /// If `ordered` is `true` the bytes have the same ordering as within the
+    /// BLOB/range downloaded. Otherwise the bytes are streamed as they were received
+    /// from the concurrently downloaded parts.
+    ///
+    /// Fails if this [ChunkStream] was already iterated.
+    pub fn try_into_bytes_stream(
+        self,
+        ordered: bool,
+    ) -> Result<BoxStream<'static, Result<Bytes, CondowError>>, CondowError> {
+        if ordered {
+            Ok(self.try_into_part_stream()?.bytes_stream().boxed())
+        } else if self.is_fresh {
+            Ok(self.map_ok(|chunk| chunk.bytes).boxed())
+        } else {
+            Err(CondowError::new_other(
+                "chunk stream already iterated".to_string(),
+            ))
+        }
+    }
 }
 
 async fn stream_into_vec_with_unknown_size(
     mut stream: ChunkStream,
+) -> Result<Vec<u8>, CondowError> {
+    let lower_bound = match u64_to_usize(stream.bytes_hint.lower_bound()) {
+        Ok(lower_bound) => lower_bound,
+        Err(err) => {
+            stream.receiver.close();
+            return Err(err);
+        }
+    };
+
+    let capacity = lower_bound.min(stream.max_preallocation_bytes);
+    stream_into_vec(stream, capacity, None).await
+}
+
+/// Collects the stream into a `Vec` with the given initial capacity
+///
+/// Chunks which continue the bytes collected so far are simply appended.
+/// Only gaps left by chunks arriving out of order are filled with zeros
+/// first. So a download of a single part or a sequential download never
+/// initializes the `Vec` upfront.
+///
+/// Fails if `max_len` is given and a chunk would be placed beyond it.
+async fn stream_into_vec(
+    mut stream: ChunkStream,
+    capacity: usize,
+    max_len: Option<usize>,
 ) -> Result<Vec<u8>, CondowError> {
     if !stream.is_fresh {
         stream.receiver.close();
@@ -216,15 +395,7 @@ async fn stream_into_vec_with_unknown_size(
         ));
     }
 
-    let lower_bound = stream.bytes_hint.lower_bound();
-    if lower_bound > usize::MAX as u64 {
-        stream.receiver.close();
-        return Err(CondowError::new_other(
-            "usize overflow while casting from u64",
-        ));
-    }
-
-    let mut buffer = Vec::with_capacity(lower_bound as usize);
+    let mut buffer = Vec::with_capacity(capacity);
 
     while let Some(next) = stream.next().await {
         let Chunk {
@@ -236,16 +407,29 @@ async fn stream_into_vec_with_unknown_size(
             Ok(next) => next,
         };
 
-        if range_offset > usize::MAX as u64 {
-            stream.receiver.close();
-            return Err(CondowError::new_other(
-                "usize overflow while casting from u64",
-            ));
+        let (range_offset, end_excl) = match buffer_range(range_offset, bytes.len()) {
+            Ok(buffer_range) => buffer_range,
+            Err(err) => {
+                stream.receiver.close();
+                return Err(err);
+            }
+        };
+        if let Some(max_len) = max_len {
+            if end_excl > max_len {
+                stream.receiver.close();
+                return Err(CondowError::new_other(format!(
+                    "write attempt beyond buffer end (buffer len = {}). \
+                    attempted to write at index {}",
+                    max_len, end_excl
+                )));
+            }
         }
 
-        let range_offset = range_offset as usize;
+        if range_offset == buffer.len() {
+            buffer.extend_from_slice(&bytes[..]);
+            continue;
+        }
 
-        let end_excl = range_offset + bytes.len();
         if end_excl >= buffer.len() {
             let missing = end_excl - buffer.len();
             buffer.extend((0..missing).map(|_| 0));
@@ -261,11 +445,22 @@ impl Stream for ChunkStream {
     type Item = ChunkStreamItem;
 
     fn poll_next(self: std::pin::Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        if self.is_closed {
+        let mut this = self.project();
+
+        // Polling after an error is most likely a bug on the consumer's side
+        // which would go unnoticed if we simply returned `None`. The error is
+        // only emitted once so that combinators like `collect` still terminate.
+        if let Some(msg) = this.failed_with.take() {
+            return Poll::Ready(Some(Err(CondowError::new_other(format!(
+                "stream polled after an error: {}",
+                msg
+            )))));
+        }
+
+        if *this.is_closed {
             return Poll::Ready(None);
         }
 
-        let mut this = self.project();
         *this.is_fresh = false;
         let receiver = this.receiver.as_mut();
 
@@ -277,12 +472,15 @@ impl Stream for ChunkStream {
             }
             Some(Err(err)) => {
                 *this.is_closed = true;
+                *this.failed_with = Some(err.to_string());
+                *this.download_permit = None;
                 this.receiver.close();
                 *this.bytes_hint = BytesHint::new_exact(0);
                 Poll::Ready(Some(Err(err)))
             }
             None => {
                 *this.is_closed = true;
+                *this.download_permit = None;
                 Poll::Ready(None)
             }
         }
@@ -295,14 +493,35 @@ impl Stream for ChunkStream {
 
 #[cfg(test)]
 mod tests {
+    use bytes::Bytes;
     use futures::StreamExt;
 
+    #[cfg(target_pointer_width = "32")]
+    use crate::config::Gibi;
     use crate::{
-        errors::CondowError,
+        errors::{CondowError, CondowErrorKind},
         streams::{BytesHint, Chunk, ChunkStream},
         test_utils::{create_chunk_stream, create_chunk_stream_with_err},
     };
 
+    #[test]
+    fn chunk_stream_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        assert_send_sync::<ChunkStream>();
+    }
+
+    #[tokio::test]
+    #[cfg(target_pointer_width = "32")]
+    async fn into_vec_fails_for_5_gib_on_32_bit() {
+        let (stream, _sender) = ChunkStream::new(BytesHint::new_exact(Gibi(5).value()));
+
+        let err = stream.into_vec().await.unwrap_err();
+
+        assert_eq!(err.kind(), CondowErrorKind::Other);
+        assert_eq!(err.msg(), "usize overflow while casting from u64");
+    }
+
     #[tokio::test]
     async fn check_ok() {
         for n_parts in 1..20 {
@@ -348,6 +567,162 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn polling_after_an_error_fails_once_more() {
+        let (mut stream, _) = create_chunk_stream_with_err(2, 2, true, Some(10), 1);
+
+        let mut n_errors = 0;
+        while let Some(next) = stream.next().await {
+            if next.is_err() {
+                n_errors += 1;
+                break;
+            }
+        }
+        assert_eq!(n_errors, 1);
+
+        let err = stream.next().await.unwrap().unwrap_err();
+        assert_eq!(err.kind(), CondowErrorKind::Other);
+        assert!(err.msg().starts_with("stream polled after an error"));
+
+        assert!(stream.next().await.is_none());
+    }
+
+    #[test]
+    fn split_chunk_at_0() {
+        let (head, tail) = chunk_to_split().split_at(0);
+
+        assert!(head.is_empty());
+        assert_eq!(head.blob_offset, 10);
+        assert_eq!(head.range_offset, 5);
+        assert_eq!(head.bytes_left, 7);
+        assert!(!head.is_last());
+
+        assert_eq!(tail.bytes, Bytes::from_static(&[1, 2, 3, 4]));
+        assert_eq!(tail.blob_offset, 10);
+        assert_eq!(tail.range_offset, 5);
+        assert_eq!(tail.bytes_left, 3);
+    }
+
+    #[test]
+    fn split_chunk_in_the_middle() {
+        let (head, tail) = chunk_to_split().split_at(1);
+
+        assert_eq!(head.bytes, Bytes::from_static(&[1]));
+        assert_eq!(head.part_index, 2);
+        assert_eq!(head.chunk_index, 1);
+        assert_eq!(head.blob_offset, 10);
+        assert_eq!(head.range_offset, 5);
+        assert_eq!(head.bytes_left, 6);
+
+        assert_eq!(tail.bytes, Bytes::from_static(&[2, 3, 4]));
+        assert_eq!(tail.part_index, 2);
+        assert_eq!(tail.chunk_index, 1);
+        assert_eq!(tail.blob_offset, 11);
+        assert_eq!(tail.range_offset, 6);
+        assert_eq!(tail.bytes_left, 3);
+    }
+
+    #[test]
+    fn split_chunk_at_len() {
+        let (head, tail) = chunk_to_split().split_at(4);
+
+        assert_eq!(head.bytes, Bytes::from_static(&[1, 2, 3, 4]));
+        assert_eq!(head.blob_offset, 10);
+        assert_eq!(head.range_offset, 5);
+        assert_eq!(head.bytes_left, 3);
+
+        assert!(tail.is_empty());
+        assert_eq!(tail.blob_offset, 14);
+        assert_eq!(tail.range_offset, 9);
+        assert_eq!(tail.bytes_left, 3);
+    }
+
+    #[test]
+    #[should_panic]
+    fn split_chunk_beyond_len() {
+        let _ = chunk_to_split().split_at(5);
+    }
+
+    fn chunk_to_split() -> Chunk {
+        Chunk {
+            part_index: 2,
+            chunk_index: 1,
+            blob_offset: 10,
+            range_offset: 5,
+            bytes: Bytes::from_static(&[1, 2, 3, 4]),
+            bytes_left: 3,
+        }
+    }
+
+    #[tokio::test]
+    async fn into_vec_with_absurd_exact_hint() {
+        let stream = stream_with_small_payload(BytesHint::new_exact(1 << 50));
+
+        let result = stream.into_vec().await.unwrap();
+
+        assert_eq!(result, vec![1, 2, 3, 4, 5]);
+    }
+
+    #[tokio::test]
+    async fn into_vec_with_absurd_lower_bound() {
+        let stream = stream_with_small_payload(BytesHint::new(1 << 50, None));
+
+        let result = stream.into_vec().await.unwrap();
+
+        assert_eq!(result, vec![1, 2, 3, 4, 5]);
+    }
+
+    #[tokio::test]
+    async fn into_vec_with_exact_hint_and_unordered_chunks() {
+        for n_parts in 1..10 {
+            let (stream, expected) = create_chunk_stream(n_parts, 3, true, Some(5));
+
+            let result = stream.into_vec().await.unwrap();
+
+            assert_eq!(result, expected, "n_parts={}", n_parts);
+        }
+    }
+
+    #[tokio::test]
+    async fn into_vec_with_an_exact_hint_too_small() {
+        let stream = stream_with_small_payload(BytesHint::new_exact(4));
+
+        assert!(stream.into_vec().await.is_err());
+    }
+
+    fn stream_with_small_payload(bytes_hint: BytesHint) -> ChunkStream {
+        let (stream, sender) = ChunkStream::new(bytes_hint);
+        sender
+            .unbounded_send(Ok(Chunk {
+                part_index: 0,
+                chunk_index: 0,
+                blob_offset: 0,
+                range_offset: 0,
+                bytes: Bytes::from_static(&[1, 2, 3]),
+                bytes_left: 2,
+            }))
+            .unwrap();
+        sender
+            .unbounded_send(Ok(Chunk {
+                part_index: 0,
+                chunk_index: 1,
+                blob_offset: 3,
+                range_offset: 3,
+                bytes: Bytes::from_static(&[4, 5]),
+                bytes_left: 0,
+            }))
+            .unwrap();
+        stream
+    }
+
+    #[tokio::test]
+    async fn empty_stream_stays_closed() {
+        let mut stream = ChunkStream::empty();
+
+        assert!(stream.next().await.is_none());
+        assert!(stream.next().await.is_none());
+    }
+
     async fn check_stream(mut result_stream: ChunkStream, data: &[u8]) -> Result<(), CondowError> {
         let mut bytes_left = data.len();
         let mut first_blob_offset = 0;
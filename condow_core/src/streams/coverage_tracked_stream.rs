@@ -0,0 +1,343 @@
+use std::{
+    collections::BTreeMap,
+    task::{Context, Poll},
+};
+
+use futures::{Stream, StreamExt};
+
+use crate::InclusiveRange;
+
+use super::{BytesHint, ChunkStream, ChunkStreamItem};
+
+/// Tells which bytes of the downloaded range were received by a [CoverageTrackedStream]
+///
+/// All ranges are relative to the downloaded range like the `range_offset`
+/// of a [Chunk](super::Chunk).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoverageReport {
+    /// The length of the downloaded range if it is known
+    pub expected_len: Option<u64>,
+    /// The ranges covered by the received chunks in ascending order
+    ///
+    /// Adjacent and overlapping chunks are merged.
+    pub covered: Vec<InclusiveRange>,
+    /// The ranges of the downloaded range not covered by any chunk in ascending order
+    ///
+    /// Empty if the length of the downloaded range is not known.
+    pub gaps: Vec<InclusiveRange>,
+    /// The number of bytes which were received more than once
+    pub overlapping_bytes: u64,
+}
+
+impl CoverageReport {
+    /// Returns `true` if the received chunks covered each byte of the
+    /// downloaded range exactly once and nothing beyond
+    pub fn is_exact(&self) -> bool {
+        let expected_len = if let Some(expected_len) = self.expected_len {
+            expected_len
+        } else {
+            return false;
+        };
+
+        if self.overlapping_bytes > 0 {
+            return false;
+        }
+
+        match self.covered.as_slice() {
+            [] => expected_len == 0,
+            [covered] => covered.start() == 0 && covered.len() == expected_len,
+            _ => false,
+        }
+    }
+}
+
+/// A [ChunkStream] which tracks the ranges of the chunks passed through
+///
+/// Once the stream ended a [CoverageReport] tells whether the chunks
+/// covered the downloaded range. This is useful if the chunks are
+/// written to their offsets, e.g. in a file, without being ordered.
+///
+/// Created with [ChunkStream::into_coverage_tracked].
+pub struct CoverageTrackedStream {
+    chunk_stream: ChunkStream,
+    expected_len: Option<u64>,
+    /// Disjoint and non adjacent ranges as start -> end exclusive
+    covered: BTreeMap<u64, u64>,
+    overlapping_bytes: u64,
+    is_finished: bool,
+}
+
+impl CoverageTrackedStream {
+    pub(crate) fn new(chunk_stream: ChunkStream) -> Self {
+        Self {
+            expected_len: chunk_stream.bytes_hint().upper_bound(),
+            chunk_stream,
+            covered: BTreeMap::new(),
+            overlapping_bytes: 0,
+            is_finished: false,
+        }
+    }
+
+    /// Hint on the remaining bytes on this stream.
+    pub fn bytes_hint(&self) -> BytesHint {
+        self.chunk_stream.bytes_hint()
+    }
+
+    /// Returns the ranges which were filled with zeros since their parts failed
+    ///
+    /// See [ChunkStream::missing_ranges]. These ranges count as covered.
+    pub fn missing_ranges(&self) -> Vec<InclusiveRange> {
+        self.chunk_stream.missing_ranges()
+    }
+
+    /// Returns the [CoverageReport] once the stream ended or failed
+    ///
+    /// `None` while chunks are still expected.
+    pub fn coverage_report(&self) -> Option<CoverageReport> {
+        if !self.is_finished {
+            return None;
+        }
+
+        let covered: Vec<_> = self
+            .covered
+            .iter()
+            .map(|(&start, &end_excl)| InclusiveRange(start, end_excl - 1))
+            .collect();
+
+        let mut gaps = Vec::new();
+        if let Some(expected_len) = self.expected_len {
+            let mut next_start = 0;
+            for (&start, &end_excl) in self.covered.range(..expected_len) {
+                if start > next_start {
+                    gaps.push(InclusiveRange(next_start, start - 1));
+                }
+                next_start = end_excl;
+            }
+            if next_start < expected_len {
+                gaps.push(InclusiveRange(next_start, expected_len - 1));
+            }
+        }
+
+        Some(CoverageReport {
+            expected_len: self.expected_len,
+            covered,
+            gaps,
+            overlapping_bytes: self.overlapping_bytes,
+        })
+    }
+
+    /// Adds the range `[start, end_excl)` to the covered ranges
+    fn track(&mut self, start: u64, end_excl: u64) {
+        if start == end_excl {
+            return;
+        }
+
+        // The covered ranges are disjoint so all touching ranges
+        // are found walking backwards until one ends before `start`
+        let touching: Vec<(u64, u64)> = self
+            .covered
+            .range(..=end_excl)
+            .rev()
+            .take_while(|(_, &covered_end_excl)| covered_end_excl >= start)
+            .map(|(&covered_start, &covered_end_excl)| (covered_start, covered_end_excl))
+            .collect();
+
+        let mut merged_start = start;
+        let mut merged_end_excl = end_excl;
+        for (covered_start, covered_end_excl) in touching {
+            self.overlapping_bytes += covered_end_excl
+                .min(end_excl)
+                .saturating_sub(covered_start.max(start));
+            merged_start = merged_start.min(covered_start);
+            merged_end_excl = merged_end_excl.max(covered_end_excl);
+            self.covered.remove(&covered_start);
+        }
+
+        self.covered.insert(merged_start, merged_end_excl);
+    }
+}
+
+impl Stream for CoverageTrackedStream {
+    type Item = ChunkStreamItem;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        if self.is_finished {
+            return Poll::Ready(None);
+        }
+
+        match self.chunk_stream.poll_next_unpin(cx) {
+            Poll::Ready(Some(Ok(chunk))) => {
+                let start = chunk.range_offset;
+                self.track(start, start + chunk.len() as u64);
+                Poll::Ready(Some(Ok(chunk)))
+            }
+            Poll::Ready(Some(Err(err))) => {
+                self.is_finished = true;
+                Poll::Ready(Some(Err(err)))
+            }
+            Poll::Ready(None) => {
+                self.is_finished = true;
+                Poll::Ready(None)
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+
+    use crate::{
+        streams::{BytesHint, Chunk, ChunkStream},
+        test_utils::{create_chunk_stream, create_chunk_stream_with_err},
+        InclusiveRange,
+    };
+
+    use super::CoverageTrackedStream;
+
+    fn chunk(range_offset: u64, len: usize) -> Chunk {
+        Chunk {
+            part_index: 0,
+            chunk_index: 0,
+            blob_offset: range_offset,
+            range_offset,
+            bytes: vec![0; len].into(),
+            bytes_left: 0,
+        }
+    }
+
+    async fn track(bytes_hint: BytesHint, chunks: Vec<Chunk>) -> CoverageTrackedStream {
+        let (stream, tx) = ChunkStream::new(bytes_hint);
+        for chunk in chunks {
+            tx.unbounded_send(Ok(chunk)).unwrap();
+        }
+        drop(tx);
+
+        let mut stream = stream.into_coverage_tracked();
+        while stream.try_next().await.unwrap().is_some() {}
+        stream
+    }
+
+    #[test]
+    fn coverage_tracked_stream_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        assert_send_sync::<CoverageTrackedStream>();
+    }
+
+    #[tokio::test]
+    async fn a_complete_download_is_exact() {
+        for n_parts in 1..10 {
+            for n_chunks in 1..5 {
+                let (stream, expected) = create_chunk_stream(n_parts, n_chunks, false, Some(10));
+                let mut stream = stream.into_coverage_tracked();
+
+                assert!(stream.coverage_report().is_none());
+                while stream.try_next().await.unwrap().is_some() {}
+
+                let report = stream.coverage_report().unwrap();
+                assert!(
+                    report.is_exact(),
+                    "n_parts={}, n_chunks={}",
+                    n_parts,
+                    n_chunks
+                );
+                assert_eq!(
+                    report.covered,
+                    vec![InclusiveRange(0, expected.len() as u64 - 1)]
+                );
+                assert!(report.gaps.is_empty());
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn gaps_are_reported() {
+        let stream = track(
+            BytesHint::new_exact(20),
+            vec![chunk(15, 3), chunk(2, 3), chunk(5, 5)],
+        )
+        .await;
+
+        let report = stream.coverage_report().unwrap();
+        assert!(!report.is_exact());
+        assert_eq!(
+            report.covered,
+            vec![InclusiveRange(2, 9), InclusiveRange(15, 17)]
+        );
+        assert_eq!(
+            report.gaps,
+            vec![
+                InclusiveRange(0, 1),
+                InclusiveRange(10, 14),
+                InclusiveRange(18, 19)
+            ]
+        );
+        assert_eq!(report.overlapping_bytes, 0);
+    }
+
+    #[tokio::test]
+    async fn overlaps_are_reported() {
+        let stream = track(
+            BytesHint::new_exact(10),
+            vec![chunk(0, 5), chunk(8, 2), chunk(3, 6)],
+        )
+        .await;
+
+        let report = stream.coverage_report().unwrap();
+        assert!(!report.is_exact());
+        assert_eq!(report.covered, vec![InclusiveRange(0, 9)]);
+        assert!(report.gaps.is_empty());
+        assert_eq!(report.overlapping_bytes, 3);
+    }
+
+    #[tokio::test]
+    async fn bytes_beyond_the_range_are_not_exact() {
+        let stream = track(BytesHint::new_exact(5), vec![chunk(0, 7)]).await;
+
+        let report = stream.coverage_report().unwrap();
+        assert!(!report.is_exact());
+        assert!(report.gaps.is_empty());
+    }
+
+    #[tokio::test]
+    async fn an_unknown_length_is_not_exact() {
+        let stream = track(BytesHint::new_no_hint(), vec![chunk(0, 5)]).await;
+
+        let report = stream.coverage_report().unwrap();
+        assert!(!report.is_exact());
+        assert_eq!(report.expected_len, None);
+        assert!(report.gaps.is_empty());
+    }
+
+    #[tokio::test]
+    async fn an_empty_stream_is_exact() {
+        let mut stream = ChunkStream::empty().into_coverage_tracked();
+        while stream.try_next().await.unwrap().is_some() {}
+
+        assert!(stream.coverage_report().unwrap().is_exact());
+    }
+
+    #[tokio::test]
+    async fn a_failed_stream_reports_the_gaps() {
+        let (stream, expected) = create_chunk_stream_with_err(5, 3, true, Some(10), 4);
+        let mut stream = stream.into_coverage_tracked();
+
+        assert!((&mut stream)
+            .try_for_each(|_| async { Ok(()) })
+            .await
+            .is_err());
+
+        let report = stream.coverage_report().unwrap();
+        assert!(!report.is_exact());
+        assert!(!report.gaps.is_empty());
+        assert!(report
+            .covered
+            .iter()
+            .all(|r| r.end_incl() < expected.len() as u64));
+    }
+}
@@ -1,19 +1,108 @@
 //! Stream implememtations used by Condow
-use std::fmt;
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+};
 
-use crate::errors::IoError;
+use crate::{errors::IoError, InclusiveRange};
 use bytes::Bytes;
 use futures::stream::BoxStream;
 
+mod async_read_bytes;
 mod chunk_stream;
+mod coverage_tracked_stream;
+mod ordered_bytes_stream;
+mod ordering_strategy;
 mod part_stream;
+mod retry_report;
 
+pub use async_read_bytes::*;
 pub use chunk_stream::*;
+pub use coverage_tracked_stream::*;
+pub use ordered_bytes_stream::*;
+pub use ordering_strategy::*;
 pub use part_stream::*;
+pub use retry_report::{PartError, PartRetries, RetryReport};
+pub(crate) use retry_report::{PartRetryRecorder, RetryRecords};
 
 /// A stream of [Bytes] (chunks) where there can be an error for each chunk of bytes
+///
+/// A [BytesStream] is `Send` but not `Sync`. Use a [ChunkStream], a [PartStream]
+/// or an [OrderedBytesStream] if a `Sync` stream is required.
 pub type BytesStream = BoxStream<'static, Result<Bytes, IoError>>;
 
+/// Ranges of a download which were filled with zeros since their parts failed
+///
+/// Shared by a download and its stream.
+/// See [OnPartFailure::Skip](crate::config::OnPartFailure::Skip).
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MissingRanges(Arc<Mutex<Vec<InclusiveRange>>>);
+
+impl MissingRanges {
+    pub fn push(&self, range: InclusiveRange) {
+        self.0.lock().unwrap().push(range);
+    }
+
+    /// The missing ranges in ascending order
+    pub fn get(&self) -> Vec<InclusiveRange> {
+        let mut ranges = self.0.lock().unwrap().clone();
+        ranges.sort_by_key(|range| range.start());
+        ranges
+    }
+}
+
+/// Describes how a download was performed
+///
+/// This is decided when the download is planned.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum DownloadMode {
+    /// All parts were downloaded one after the other by a single worker
+    #[default]
+    Sequential,
+    /// The parts were downloaded concurrently
+    Concurrent {
+        /// The number of workers downloading parts
+        workers: usize,
+    },
+}
+
+impl DownloadMode {
+    /// Returns the mode for downloading with `workers` workers
+    pub fn from_workers(workers: usize) -> Self {
+        if workers > 1 {
+            DownloadMode::Concurrent { workers }
+        } else {
+            DownloadMode::Sequential
+        }
+    }
+
+    /// Returns `true` if the download was concurrent
+    pub fn is_concurrent(&self) -> bool {
+        matches!(self, DownloadMode::Concurrent { .. })
+    }
+}
+
+/// A [Chunk] of a download of multiple ranges
+///
+/// See [Condow::download_ranges](crate::Condow::download_ranges).
+#[derive(Debug, Clone)]
+pub struct MultiRangeItem {
+    /// Index of the requested range the chunk belongs to
+    pub range_index: usize,
+    /// The chunk of the range. Its offsets refer to the range
+    /// with `range_index`.
+    pub chunk: Chunk,
+}
+
+impl fmt::Display for DownloadMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DownloadMode::Sequential => write!(f, "sequential"),
+            DownloadMode::Concurrent { workers } => write!(f, "concurrent({})", workers),
+        }
+    }
+}
+
 /// Returns the bounds on the remaining bytes of the stream.
 ///
 /// Specifically, `bytes_hint()` returns a tuple where the first element is
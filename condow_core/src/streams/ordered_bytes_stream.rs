@@ -0,0 +1,260 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+
+use crate::errors::CondowError;
+
+use super::{BytesHint, ChunkStream};
+
+/// A stream of [Bytes] in the same order as within the downloaded BLOB/range
+///
+/// This is a lighter alternative to a [PartStream](super::PartStream).
+/// The chunks of the part which is due next are passed through as soon as they
+/// are received. Only the chunks of parts which are ahead are buffered.
+///
+/// As long as the parts are downloaded at a similar speed this
+/// buffers roughly one part per concurrently downloading task.
+/// If the part which is due next stalls, the chunks of all parts
+/// downloaded in the meantime are buffered.
+///
+/// An [OrderedBytesStream] is `Send` and `Sync`.
+pub struct OrderedBytesStream {
+    bytes_hint: BytesHint,
+    chunk_stream: ChunkStream,
+    is_closed: bool,
+    next_part_idx: u64,
+    ready: VecDeque<Bytes>,
+    pending_parts: HashMap<u64, PendingPart>,
+}
+
+#[derive(Default)]
+struct PendingPart {
+    chunks: VecDeque<Bytes>,
+    is_complete: bool,
+}
+
+impl OrderedBytesStream {
+    /// Create a new [OrderedBytesStream] from a [ChunkStream]
+    ///
+    /// Fails if the [ChunkStream] was already iterated.
+    pub fn from_chunk_stream(chunk_stream: ChunkStream) -> Result<Self, CondowError> {
+        if !chunk_stream.is_fresh() {
+            return Err(CondowError::new_other(
+                "chunk stream already iterated".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            bytes_hint: chunk_stream.bytes_hint(),
+            chunk_stream,
+            is_closed: false,
+            next_part_idx: 0,
+            ready: VecDeque::new(),
+            pending_parts: HashMap::new(),
+        })
+    }
+
+    /// Continues ordering the chunks of a [PartStream](super::PartStream)
+    ///
+    /// `collected_parts` are the chunks of the parts which were already received
+    /// but not yet handed out as `(part_index, chunks, is_complete)`.
+    pub(super) fn resume<I>(
+        chunk_stream: ChunkStream,
+        bytes_hint: BytesHint,
+        is_closed: bool,
+        next_part_idx: u64,
+        collected_parts: I,
+    ) -> Self
+    where
+        I: IntoIterator<Item = (u64, Vec<Bytes>, bool)>,
+    {
+        let pending_parts = collected_parts
+            .into_iter()
+            .map(|(part_index, chunks, is_complete)| {
+                let part = PendingPart {
+                    chunks: chunks.into(),
+                    is_complete,
+                };
+                (part_index, part)
+            })
+            .collect();
+
+        let mut stream = Self {
+            bytes_hint,
+            chunk_stream,
+            is_closed,
+            next_part_idx,
+            ready: VecDeque::new(),
+            pending_parts,
+        };
+        stream.release_pending_parts();
+        stream
+    }
+
+    /// Hint on the remaining bytes on this stream.
+    pub fn bytes_hint(&self) -> BytesHint {
+        self.bytes_hint
+    }
+
+    /// Moves the buffered chunks of the parts which are due into `ready`
+    fn release_pending_parts(&mut self) {
+        // The remaining chunks of a part which is due are passed through directly
+        while let Some(part) = self.pending_parts.remove(&self.next_part_idx) {
+            self.ready.extend(part.chunks);
+            if part.is_complete {
+                self.next_part_idx += 1;
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+impl Stream for OrderedBytesStream {
+    type Item = Result<Bytes, CondowError>;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(bytes) = self.ready.pop_front() {
+                self.bytes_hint.reduce_by(bytes.len() as u64);
+                return Poll::Ready(Some(Ok(bytes)));
+            }
+
+            if self.is_closed {
+                return Poll::Ready(None);
+            }
+
+            match self.chunk_stream.poll_next_unpin(cx) {
+                Poll::Ready(Some(Ok(chunk))) => {
+                    let is_last = chunk.is_last();
+                    if chunk.part_index == self.next_part_idx {
+                        self.ready.push_back(chunk.bytes);
+                        if is_last {
+                            self.next_part_idx += 1;
+                        }
+                    } else {
+                        let part = self.pending_parts.entry(chunk.part_index).or_default();
+                        part.chunks.push_back(chunk.bytes);
+                        part.is_complete = is_last;
+                    }
+                    self.release_pending_parts();
+                }
+                Poll::Ready(Some(Err(err))) => {
+                    self.is_closed = true;
+                    self.pending_parts.clear();
+                    return Poll::Ready(Some(Err(err)));
+                }
+                Poll::Ready(None) => {
+                    self.is_closed = true;
+                    if !self.pending_parts.is_empty() {
+                        self.pending_parts.clear();
+                        return Poll::Ready(Some(Err(CondowError::new_other(
+                            "stream ended before all parts were received",
+                        ))));
+                    }
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+
+    use crate::{
+        streams::{BytesHint, ChunkStream},
+        test_utils::{create_chunk_stream, create_chunk_stream_with_err},
+    };
+
+    use super::OrderedBytesStream;
+
+    #[test]
+    fn ordered_bytes_stream_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        assert_send_sync::<OrderedBytesStream>();
+    }
+
+    #[tokio::test]
+    async fn check_ordered() {
+        for n_parts in 1..20 {
+            for n_chunks in 1..10 {
+                let (stream, expected) = create_chunk_stream(n_parts, n_chunks, true, Some(10));
+                let stream = OrderedBytesStream::from_chunk_stream(stream).unwrap();
+
+                let received = stream
+                    .try_fold(Vec::new(), |mut acc, bytes| async move {
+                        acc.extend_from_slice(&bytes);
+                        Ok(acc)
+                    })
+                    .await
+                    .unwrap();
+
+                assert_eq!(
+                    received, expected,
+                    "n_parts={}, n_chunks={}",
+                    n_parts, n_chunks
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn bytes_hint_is_reduced() {
+        let (stream, expected) = create_chunk_stream(3, 2, true, None);
+        let mut stream = OrderedBytesStream::from_chunk_stream(stream).unwrap();
+
+        assert_eq!(
+            stream.bytes_hint(),
+            BytesHint::new_exact(expected.len() as u64)
+        );
+        while stream.try_next().await.unwrap().is_some() {}
+        assert_eq!(stream.bytes_hint(), BytesHint::new_exact(0));
+    }
+
+    #[tokio::test]
+    async fn fails_on_error() {
+        let (stream, _expected) = create_chunk_stream_with_err(5, 3, true, None, 4);
+        let stream = OrderedBytesStream::from_chunk_stream(stream).unwrap();
+
+        let result: Result<Vec<_>, _> = stream.try_collect().await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn fails_on_an_iterated_chunk_stream() {
+        let (mut stream, _expected) = create_chunk_stream(2, 2, true, None);
+        let _ = stream.try_next().await;
+
+        assert!(OrderedBytesStream::from_chunk_stream(stream).is_err());
+    }
+
+    #[tokio::test]
+    async fn fails_on_missing_parts() {
+        let (stream, tx) = ChunkStream::new(BytesHint::new_no_hint());
+        let _ = tx.unbounded_send(Ok(crate::streams::Chunk {
+            part_index: 1,
+            chunk_index: 0,
+            blob_offset: 1,
+            range_offset: 1,
+            bytes: vec![1].into(),
+            bytes_left: 0,
+        }));
+        drop(tx);
+        let stream = OrderedBytesStream::from_chunk_stream(stream).unwrap();
+
+        let result: Result<Vec<_>, _> = stream.try_collect().await;
+
+        assert!(result.is_err());
+    }
+}
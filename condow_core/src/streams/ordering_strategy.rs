@@ -0,0 +1,82 @@
+use futures::Stream;
+
+use crate::errors::CondowError;
+
+use super::{ChunkStream, OrderedBytesStream, PartStream};
+
+/// Decides in which order the downloaded bytes are handed to the consumer
+///
+/// A strategy turns the [ChunkStream] of a download into the stream
+/// returned by [Condow::download_with](crate::Condow::download_with).
+/// The chunks of a [ChunkStream] arrive in the order they were received
+/// from the parts downloaded concurrently.
+///
+/// Implement this trait to buffer or reorder the chunks in a custom way.
+///
+/// The built in strategies are [Unordered], [Ordered] and [PartAligned].
+pub trait OrderingStrategy {
+    /// The stream returned for a download
+    type Stream: Stream + Send + 'static;
+
+    /// Creates the stream for a download from its [ChunkStream]
+    ///
+    /// The [ChunkStream] has not been iterated yet.
+    fn order(chunk_stream: ChunkStream) -> Result<Self::Stream, CondowError>;
+}
+
+/// Hands out the chunks in the order they are received
+///
+/// Nothing is buffered and every chunk is available as soon as possible.
+/// The consumer has to put the chunks in place by their offsets.
+///
+/// Same as [Condow::download_chunks](crate::Condow::download_chunks).
+#[derive(Debug, Clone, Copy)]
+pub struct Unordered;
+
+impl OrderingStrategy for Unordered {
+    type Stream = ChunkStream;
+
+    fn order(chunk_stream: ChunkStream) -> Result<Self::Stream, CondowError> {
+        Ok(chunk_stream)
+    }
+}
+
+/// Hands out the [Bytes](bytes::Bytes) in the same order as within the BLOB/range
+///
+/// The chunks of the part which is due next are passed on as soon as they
+/// are received. Only the chunks of parts which are ahead are buffered.
+/// This is roughly one part per concurrently downloading task but can
+/// be more if the part which is due next stalls. The latency of
+/// a chunk depends on the parts before it.
+///
+/// See [OrderedBytesStream] and
+/// [Condow::download_chunks_ordered](crate::Condow::download_chunks_ordered).
+#[derive(Debug, Clone, Copy)]
+pub struct Ordered;
+
+impl OrderingStrategy for Ordered {
+    type Stream = OrderedBytesStream;
+
+    fn order(chunk_stream: ChunkStream) -> Result<Self::Stream, CondowError> {
+        OrderedBytesStream::from_chunk_stream(chunk_stream)
+    }
+}
+
+/// Hands out complete [Part](super::Part)s in the same order as within the BLOB/range
+///
+/// A part is only handed out once all of its chunks were received.
+/// Therefore at least one complete part is buffered and the first bytes
+/// of a part arrive no earlier than its last ones. In exchange each
+/// item covers a whole part.
+///
+/// Same as [Condow::download](crate::Condow::download).
+#[derive(Debug, Clone, Copy)]
+pub struct PartAligned;
+
+impl OrderingStrategy for PartAligned {
+    type Stream = PartStream<ChunkStream>;
+
+    fn order(chunk_stream: ChunkStream) -> Result<Self::Stream, CondowError> {
+        PartStream::from_chunk_stream(chunk_stream)
+    }
+}
@@ -5,12 +5,18 @@ use std::{
 };
 
 use bytes::Bytes;
-use futures::{ready, stream, Stream, StreamExt, TryStreamExt};
+use futures::{channel::mpsc, ready, stream, AsyncRead, SinkExt, Stream, StreamExt, TryStreamExt};
 use pin_project_lite::pin_project;
 
-use crate::errors::CondowError;
+use crate::{
+    config::MaxPreallocationBytes, errors::CondowError, helpers::u64_to_usize,
+    reader::BytesAsyncReader, InclusiveRange,
+};
 
-use super::{BytesHint, ChunkStream, ChunkStreamItem};
+use super::{
+    AsyncReadBytes, BytesHint, ChunkStream, ChunkStreamItem, DownloadMode, MissingRanges,
+    OrderedBytesStream, RetryRecords, RetryReport,
+};
 
 /// The type of the elements returned by a [PartStream]
 pub type PartStreamItem = Result<Part, CondowError>;
@@ -54,13 +60,21 @@ pin_project! {
     ///
     /// All parts and their chunks are ordered as they would
     /// have appeared in a sequential download of a range/BLOB
+    ///
+    /// A [PartStream] is `Send` and `Sync` if the underlying stream is.
+    /// This is the case for a `PartStream<ChunkStream>`.
     pub struct PartStream<St> {
         bytes_hint: BytesHint,
         #[pin]
         stream: St,
         is_closed: bool,
         next_part_idx: u64,
-        collected_parts: HashMap<u64, PartEntry>
+        collected_parts: HashMap<u64, PartEntry>,
+        download_mode: DownloadMode,
+        n_parts: Option<u64>,
+        max_preallocation_bytes: usize,
+        missing_ranges: MissingRanges,
+        retry_records: RetryRecords,
     }
 }
 
@@ -81,6 +95,11 @@ where
             is_closed: false,
             next_part_idx: 0,
             collected_parts: HashMap::default(),
+            download_mode: DownloadMode::default(),
+            n_parts: None,
+            max_preallocation_bytes: MaxPreallocationBytes::default().into_inner(),
+            missing_ranges: MissingRanges::default(),
+            retry_records: RetryRecords::default(),
         }
     }
 
@@ -89,6 +108,36 @@ where
         self.bytes_hint
     }
 
+    /// Returns how the parts of this stream are downloaded
+    pub fn download_mode(&self) -> DownloadMode {
+        self.download_mode
+    }
+
+    /// Returns the total number of parts of the download
+    ///
+    /// `None` if the number of parts is not known.
+    pub fn n_parts(&self) -> Option<u64> {
+        self.n_parts
+    }
+
+    /// Returns the ranges which were filled with zeros since their parts failed
+    ///
+    /// Only contains ranges if failed parts are skipped
+    /// (see [OnPartFailure::Skip](crate::config::OnPartFailure::Skip)).
+    /// The ranges are relative to the BLOB. The result is only
+    /// final after the stream ended.
+    pub fn missing_ranges(&self) -> Vec<InclusiveRange> {
+        self.missing_ranges.get()
+    }
+
+    /// Returns the retries made for each part of the download
+    ///
+    /// Useful to tell whether failures cluster on particular ranges
+    /// of a BLOB. The result is only final after the stream ended.
+    pub fn retry_report(&self) -> RetryReport {
+        self.retry_records.report()
+    }
+
     /// Writes all bytes left on the stream into the provided buffer
     ///
     /// Fails if the buffer is too small or there was an error on the stream.
@@ -127,42 +176,195 @@ where
 
     /// Creates a `Vec<u8>` filled with the rest of the bytes from the stream.
     ///
+    /// At most [Config::max_preallocation_bytes](crate::config::Config::max_preallocation_bytes)
+    /// are allocated upfront. If the [BytesHint] exceeds this limit the `Vec`
+    /// grows as bytes are received.
+    ///
     /// Fails if there is an error on the stream
     pub async fn into_vec(mut self) -> Result<Vec<u8>, CondowError> {
         if let Some(total_bytes) = self.bytes_hint.exact() {
-            if total_bytes > usize::MAX as u64 {
-                return Err(CondowError::new_other(
-                    "usize overflow while casting from u64",
-                ));
+            let total_bytes = u64_to_usize(total_bytes)?;
+
+            if total_bytes <= self.max_preallocation_bytes {
+                // The parts are ordered so the bytes can simply be appended
+                // without initializing the buffer first
+                let mut buffer = Vec::with_capacity(total_bytes);
+                while let Some(next) = self.next().await {
+                    let part = next?;
+
+                    for chunk in part.chunks {
+                        let end_excl = buffer.len() + chunk.len();
+                        if end_excl > total_bytes {
+                            return Err(CondowError::new_other(format!(
+                                "write attempt beyond buffer end (buffer len = {}). \
+                                attempted to write at index {}",
+                                total_bytes, end_excl
+                            )));
+                        }
+                        buffer.extend_from_slice(&chunk);
+                    }
+                }
+                return Ok(buffer);
             }
+        }
 
-            let mut buffer = vec![0; total_bytes as usize];
-            let _ = self.write_buffer(buffer.as_mut()).await?;
-            Ok(buffer)
-        } else {
-            let mut buffer = Vec::with_capacity(self.bytes_hint.lower_bound() as usize);
+        let capacity =
+            u64_to_usize(self.bytes_hint.lower_bound())?.min(self.max_preallocation_bytes);
+        let mut buffer = Vec::with_capacity(capacity);
 
-            while let Some(next) = self.next().await {
-                let part = next?;
+        while let Some(next) = self.next().await {
+            let part = next?;
 
-                for chunk in part.chunks {
-                    buffer.extend(chunk);
-                }
+            for chunk in part.chunks {
+                buffer.extend(chunk);
             }
-
-            Ok(buffer)
         }
+
+        Ok(buffer)
     }
 
+    /// Turns this stream into a stream of the [Bytes] of the parts in order
+    ///
+    /// See [PartStream::into_byte_stream].
     pub fn bytes_stream(
         self,
     ) -> impl Stream<Item = Result<Bytes, CondowError>> + Send + Sync + 'static {
         self.map_ok(|part| stream::iter(part.chunks.into_iter().map(Ok)))
             .try_flatten()
     }
+
+    /// Turns this stream into a stream of [Bytes] in the same order
+    /// as within the downloaded BLOB/range
+    ///
+    /// [CondowError] implements [std::error::Error] and is `Send` and `Sync`.
+    /// So the returned stream can be used as the body of a response with
+    /// `hyper` or `axum` directly. An error ends the response.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// async fn handler(condow: Condow<S3ClientWrapper<S3Client>>, location: url::Url) -> Response {
+    ///     match condow.download(&location, ..).await {
+    ///         Ok(parts) => Response::new(axum::body::Body::from_stream(parts.into_byte_stream())),
+    ///         Err(err) => (StatusCode::BAD_GATEWAY, err.to_string()).into_response(),
+    ///     }
+    /// }
+    /// ```
+    pub fn into_byte_stream(
+        self,
+    ) -> impl Stream<Item = Result<Bytes, CondowError>> + Send + Sync + 'static {
+        self.bytes_stream()
+    }
+
+    /// Splits the ordered bytes of this stream into two readers.
+    ///
+    /// Both readers receive all bytes. Only a few chunks are buffered
+    /// for the slower reader. If its buffer is full the faster reader has to wait.
+    /// Therefore both readers have to be consumed concurrently. If one
+    /// of them is dropped the other one still receives all bytes.
+    ///
+    /// An error is delivered to both readers.
+    ///
+    /// Must be called within a `tokio` runtime.
+    pub fn tee(
+        self,
+    ) -> (
+        impl AsyncRead + Send + Unpin + 'static,
+        impl AsyncRead + Send + Unpin + 'static,
+    ) {
+        let (tx_left, rx_left) = mpsc::channel(TEE_BUFFER_SIZE);
+        let (tx_right, rx_right) = mpsc::channel(TEE_BUFFER_SIZE);
+
+        let mut bytes_stream = Box::pin(self.bytes_stream());
+        crate::helpers::spawn(async move {
+            let mut tx_left = Some(tx_left);
+            let mut tx_right = Some(tx_right);
+            while let Some(next) = bytes_stream.next().await {
+                match next {
+                    Ok(bytes) => {
+                        tee_send(&mut tx_left, Ok(bytes.clone())).await;
+                        tee_send(&mut tx_right, Ok(bytes)).await;
+                        if tx_left.is_none() && tx_right.is_none() {
+                            return;
+                        }
+                    }
+                    Err(err) => {
+                        let err_left = CondowError::new(err.msg(), err.kind());
+                        tee_send(&mut tx_left, Err(err_left)).await;
+                        tee_send(&mut tx_right, Err(err)).await;
+                        return;
+                    }
+                }
+            }
+        });
+
+        (
+            BytesAsyncReader::new(rx_left),
+            BytesAsyncReader::new(rx_right),
+        )
+    }
+}
+
+/// Number of items buffered for each reader created by [PartStream::tee]
+const TEE_BUFFER_SIZE: usize = 2;
+
+/// Sends to a reader created by [PartStream::tee] and
+/// forgets about the reader once it was dropped.
+async fn tee_send(
+    sender: &mut Option<mpsc::Sender<Result<Bytes, CondowError>>>,
+    item: Result<Bytes, CondowError>,
+) {
+    if let Some(tx) = sender {
+        if tx.send(item).await.is_err() {
+            *sender = None;
+        }
+    }
 }
 
 impl PartStream<ChunkStream> {
+    /// Turns this stream into a stream of [Bytes] in ascending order of their
+    /// offsets within the downloaded BLOB/range
+    ///
+    /// Unlike [PartStream::into_byte_stream] parts are not reassembled before
+    /// their bytes are handed out. The chunks of the part which is due next
+    /// are passed on as soon as they are received. Chunks of parts which are
+    /// ahead are kept in a reorder buffer keyed by the index of their part.
+    /// Parts already collected by this stream are taken over.
+    ///
+    /// As long as the parts are downloaded at a similar speed the reorder buffer
+    /// holds at most one part per concurrently downloading task
+    /// (see [Config::max_concurrency](crate::config::Config::max_concurrency)).
+    /// In the worst case, if the part which is due next stalls, the chunks of
+    /// all parts downloaded in the meantime are buffered.
+    ///
+    /// Chunks are only taken from the download when the returned stream is polled.
+    /// So a slow consumer makes the download wait once its buffers are full
+    /// instead of growing the reorder buffer.
+    ///
+    /// See [OrderedBytesStream].
+    pub fn into_ordered_bytes_stream(self) -> OrderedBytesStream {
+        let collected_parts = self
+            .collected_parts
+            .into_values()
+            .map(|entry| (entry.part_index, entry.chunks, entry.is_complete));
+
+        OrderedBytesStream::resume(
+            self.stream,
+            self.bytes_hint,
+            self.is_closed,
+            self.next_part_idx,
+            collected_parts,
+        )
+    }
+
+    /// Turns this stream into a [tokio::io::AsyncRead]
+    /// which reads the bytes in the same order as within the downloaded BLOB/range
+    ///
+    /// See [AsyncReadBytes].
+    pub fn into_async_read(self) -> AsyncReadBytes {
+        AsyncReadBytes::new(self)
+    }
+
     /// Create a new [PartStream] from the given [ChunkStream]
     ///
     /// Will fail if the [ChunkStream] was already iterated.
@@ -173,7 +375,18 @@ impl PartStream<ChunkStream> {
             ));
         }
         let bytes_hint = chunk_stream.bytes_hint();
-        Ok(Self::new(chunk_stream, bytes_hint))
+        let download_mode = chunk_stream.download_mode();
+        let n_parts = chunk_stream.n_parts();
+        let max_preallocation_bytes = chunk_stream.max_preallocation_bytes();
+        let missing_ranges = chunk_stream.missing_ranges_handle();
+        let retry_records = chunk_stream.retry_records_handle();
+        let mut me = Self::new(chunk_stream, bytes_hint);
+        me.download_mode = download_mode;
+        me.n_parts = n_parts;
+        me.max_preallocation_bytes = max_preallocation_bytes;
+        me.missing_ranges = missing_ranges;
+        me.retry_records = retry_records;
+        Ok(me)
     }
 }
 
@@ -284,9 +497,114 @@ impl TryFrom<ChunkStream> for PartStream<ChunkStream> {
 
 #[cfg(test)]
 mod tests {
-    use futures::StreamExt;
+    use bytes::Bytes;
+    use futures::{StreamExt, TryStream, TryStreamExt};
+
+    use crate::{
+        streams::{ChunkStream, PartStream},
+        test_utils::{create_chunk_stream_with_err, create_part_stream},
+    };
+
+    #[test]
+    fn part_stream_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        assert_send_sync::<PartStream<ChunkStream>>();
+    }
+
+    /// The bounds of `hyper::Body::wrap_stream` and `axum::body::Body::from_stream`
+    fn assert_body_stream<S>(_stream: &S)
+    where
+        S: TryStream + Send + 'static,
+        S::Ok: Into<Bytes>,
+        S::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+    }
+
+    #[tokio::test]
+    async fn into_byte_stream_streams_the_ordered_bytes() {
+        for parts in 1..10 {
+            for chunks in 1..5 {
+                let (stream, expected) = create_part_stream(parts, chunks, true, Some(10));
 
-    use crate::test_utils::create_part_stream;
+                let byte_stream = stream.into_byte_stream();
+                assert_body_stream(&byte_stream);
+
+                let collected = byte_stream
+                    .try_fold(Vec::new(), |mut acc, bytes| async move {
+                        acc.extend_from_slice(&bytes);
+                        Ok(acc)
+                    })
+                    .await
+                    .unwrap();
+
+                assert_eq!(collected, expected, "parts={}, chunks={}", parts, chunks);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn into_byte_stream_ends_with_an_error() {
+        let (chunk_stream, _expected) = create_chunk_stream_with_err(3, 2, true, Some(10), 3);
+        let stream = PartStream::from_chunk_stream(chunk_stream).unwrap();
+
+        let result = stream.into_byte_stream().try_collect::<Vec<Bytes>>().await;
+
+        let err: Box<dyn std::error::Error + Send + Sync> = result.unwrap_err().into();
+        assert!(!err.to_string().is_empty());
+    }
+
+    #[tokio::test]
+    async fn into_ordered_bytes_stream_streams_the_ordered_bytes() {
+        for parts in 1..10 {
+            for chunks in 1..5 {
+                let (stream, expected) = create_part_stream(parts, chunks, true, Some(10));
+
+                let ordered = stream.into_ordered_bytes_stream();
+                assert_body_stream(&ordered);
+
+                let collected = ordered.try_collect::<Vec<Bytes>>().await.unwrap().concat();
+
+                assert_eq!(collected, expected, "parts: {parts}, chunks: {chunks}");
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn into_ordered_bytes_stream_takes_over_collected_parts() {
+        for parts in 2..10 {
+            for chunks in 1..5 {
+                let (mut stream, expected) = create_part_stream(parts, chunks, true, Some(10));
+
+                let first = stream.next().await.unwrap().unwrap();
+                let rest = stream
+                    .into_ordered_bytes_stream()
+                    .try_collect::<Vec<Bytes>>()
+                    .await
+                    .unwrap();
+
+                let collected = first.chunks.into_iter().chain(rest).collect::<Vec<_>>();
+                assert_eq!(
+                    collected.concat(),
+                    expected,
+                    "parts: {parts}, chunks: {chunks}"
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn into_ordered_bytes_stream_ends_with_an_error() {
+        let (chunk_stream, _expected) = create_chunk_stream_with_err(3, 2, true, Some(10), 3);
+        let stream = PartStream::from_chunk_stream(chunk_stream).unwrap();
+
+        let result = stream
+            .into_ordered_bytes_stream()
+            .try_collect::<Vec<Bytes>>()
+            .await;
+
+        assert!(result.is_err());
+    }
 
     #[tokio::test]
     async fn check_iter_one_part_one_chunk() {
@@ -305,6 +623,20 @@ mod tests {
         assert_eq!(collected, expected);
     }
 
+    #[tokio::test]
+    #[cfg(target_pointer_width = "32")]
+    async fn into_vec_fails_for_5_gib_on_32_bit() {
+        use crate::{config::Gibi, errors::CondowErrorKind, streams::BytesHint};
+
+        let (chunk_stream, _sender) = ChunkStream::new(BytesHint::new_exact(Gibi(5).value()));
+        let stream = PartStream::from_chunk_stream(chunk_stream).unwrap();
+
+        let err = stream.into_vec().await.unwrap_err();
+
+        assert_eq!(err.kind(), CondowErrorKind::Other);
+        assert_eq!(err.msg(), "usize overflow while casting from u64");
+    }
+
     #[tokio::test]
     async fn check_iter_one_part_two_chunks() {
         let (mut stream, expected) = create_part_stream(1, 2, true, Some(10));
@@ -361,7 +693,12 @@ mod tests {
     }
 
     mod into_vec {
-        use crate::test_utils::create_part_stream;
+        use bytes::Bytes;
+
+        use crate::{
+            streams::{BytesHint, Chunk, ChunkStream, PartStream},
+            test_utils::create_part_stream,
+        };
 
         #[tokio::test]
         async fn with_exact_hint() {
@@ -388,5 +725,89 @@ mod tests {
                 }
             }
         }
+
+        #[tokio::test]
+        async fn with_absurd_hint() {
+            let (chunk_stream, sender) = ChunkStream::new(BytesHint::new_exact(1 << 50));
+            sender
+                .unbounded_send(Ok(Chunk {
+                    part_index: 0,
+                    chunk_index: 0,
+                    blob_offset: 0,
+                    range_offset: 0,
+                    bytes: Bytes::from_static(&[1, 2, 3]),
+                    bytes_left: 0,
+                }))
+                .unwrap();
+            drop(sender);
+
+            let stream = PartStream::from_chunk_stream(chunk_stream).unwrap();
+            let result = stream.into_vec().await.unwrap();
+
+            assert_eq!(result, vec![1, 2, 3]);
+        }
+    }
+
+    mod tee {
+        use futures::AsyncReadExt;
+
+        use crate::{
+            streams::PartStream,
+            test_utils::{create_chunk_stream_with_err, create_part_stream},
+        };
+
+        #[tokio::test]
+        async fn both_readers_receive_all_bytes() {
+            for parts in 1..10 {
+                for chunks in 1..10 {
+                    let (stream, expected) = create_part_stream(parts, chunks, true, Some(10));
+
+                    let (mut left, mut right) = stream.tee();
+
+                    let mut buf_left = Vec::new();
+                    let mut buf_right = Vec::new();
+                    let (res_left, res_right) = futures::join!(
+                        left.read_to_end(&mut buf_left),
+                        right.read_to_end(&mut buf_right)
+                    );
+                    res_left.unwrap();
+                    res_right.unwrap();
+
+                    assert_eq!(buf_left, expected);
+                    assert_eq!(buf_right, expected);
+                }
+            }
+        }
+
+        #[tokio::test]
+        async fn dropping_one_reader_does_not_block_the_other() {
+            let (stream, expected) = create_part_stream(10, 10, true, Some(10));
+
+            let (left, mut right) = stream.tee();
+            drop(left);
+
+            let mut buf_right = Vec::new();
+            right.read_to_end(&mut buf_right).await.unwrap();
+
+            assert_eq!(buf_right, expected);
+        }
+
+        #[tokio::test]
+        async fn both_readers_receive_the_error() {
+            let (chunk_stream, _) = create_chunk_stream_with_err(3, 3, true, Some(10), 4);
+            let stream = PartStream::from_chunk_stream(chunk_stream).unwrap();
+
+            let (mut left, mut right) = stream.tee();
+
+            let mut buf_left = Vec::new();
+            let mut buf_right = Vec::new();
+            let (res_left, res_right) = futures::join!(
+                left.read_to_end(&mut buf_left),
+                right.read_to_end(&mut buf_right)
+            );
+
+            assert!(res_left.is_err());
+            assert!(res_right.is_err());
+        }
     }
 }
@@ -0,0 +1,223 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::{
+    errors::{CondowError, CondowErrorKind, IoError},
+    reporter::Reporter,
+    InclusiveRange,
+};
+
+/// The retries made for the parts of a download
+///
+/// Only contains parts which were retried, resumed or failed.
+/// See [ChunkStream::retry_report](super::ChunkStream::retry_report)
+/// and [PartStream::retry_report](super::PartStream::retry_report).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RetryReport {
+    /// The retried parts by their `part_index`
+    ///
+    /// If requests cover multiple parts (see
+    /// [Config::request_coalescing](crate::config::Config::request_coalescing))
+    /// this is the index of the request.
+    pub parts: BTreeMap<u64, PartRetries>,
+}
+
+impl RetryReport {
+    /// Returns `true` if no part was retried or failed
+    pub fn is_empty(&self) -> bool {
+        self.parts.is_empty()
+    }
+
+    /// The number of attempts made in addition to the first one of each part
+    pub fn n_retries(&self) -> usize {
+        self.parts.values().map(|part| part.attempts - 1).sum()
+    }
+
+    /// The parts which failed permanently
+    pub fn failed_parts(&self) -> impl Iterator<Item = (u64, &PartRetries)> {
+        self.parts
+            .iter()
+            .filter(|(_, part)| part.failed)
+            .map(|(&part_index, part)| (part_index, part))
+    }
+}
+
+/// The retries of a single part
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartRetries {
+    /// The range of the part within the BLOB
+    pub range: InclusiveRange,
+    /// The number of requests made for the part including the first one
+    ///
+    /// Resuming a broken stream counts as an attempt.
+    pub attempts: usize,
+    /// The errors seen in the order they occurred
+    ///
+    /// The last one made the part fail if `failed` is `true`.
+    pub errors: Vec<PartError>,
+    /// `true` if the part failed permanently
+    pub failed: bool,
+}
+
+/// An error seen while downloading a part
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartError {
+    /// The kind of the error
+    pub kind: CondowErrorKind,
+    /// The message of the error
+    pub msg: String,
+}
+
+impl fmt::Display for PartError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}: {}", self.kind, self.msg)
+    }
+}
+
+/// The retries of a download
+///
+/// Shared by a download and its stream.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RetryRecords(Arc<Mutex<BTreeMap<u64, PartRetries>>>);
+
+impl RetryRecords {
+    /// Record an error after which another attempt is made
+    pub fn retry(&self, part_index: u64, range: InclusiveRange, error: PartError) {
+        let mut parts = self.0.lock().unwrap();
+        let part = Self::part(&mut parts, part_index, range);
+        part.attempts += 1;
+        part.errors.push(error);
+    }
+
+    /// Record the error which made a part fail
+    pub fn failure(&self, part_index: u64, range: InclusiveRange, error: PartError) {
+        let mut parts = self.0.lock().unwrap();
+        let part = Self::part(&mut parts, part_index, range);
+        part.failed = true;
+        part.errors.push(error);
+    }
+
+    pub fn report(&self) -> RetryReport {
+        RetryReport {
+            parts: self.0.lock().unwrap().clone(),
+        }
+    }
+
+    fn part(
+        parts: &mut BTreeMap<u64, PartRetries>,
+        part_index: u64,
+        range: InclusiveRange,
+    ) -> &mut PartRetries {
+        parts.entry(part_index).or_insert_with(|| PartRetries {
+            range,
+            attempts: 1,
+            errors: Vec::new(),
+            failed: false,
+        })
+    }
+}
+
+impl From<&CondowError> for PartError {
+    fn from(err: &CondowError) -> Self {
+        Self {
+            kind: err.kind(),
+            msg: err.to_string(),
+        }
+    }
+}
+
+impl From<&IoError> for PartError {
+    fn from(err: &IoError) -> Self {
+        Self {
+            kind: CondowErrorKind::Io,
+            msg: err.0.clone(),
+        }
+    }
+}
+
+/// A [Reporter] recording the retries of the requests for a single part
+#[derive(Clone)]
+pub(crate) struct PartRetryRecorder {
+    pub part_index: u64,
+    pub range: InclusiveRange,
+    pub records: RetryRecords,
+}
+
+impl Reporter for PartRetryRecorder {
+    fn retry_attempt(&self, _location: &dyn fmt::Display, error: &CondowError, _next_in: Duration) {
+        self.records
+            .retry(self.part_index, self.range, PartError::from(error));
+    }
+
+    fn stream_resume_attempt(
+        &self,
+        _location: &dyn fmt::Display,
+        error: &IoError,
+        _orig_range: InclusiveRange,
+        _remaining_range: InclusiveRange,
+    ) {
+        self.records
+            .retry(self.part_index, self.range, PartError::from(error));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{errors::CondowErrorKind, InclusiveRange};
+
+    use super::{PartError, RetryRecords};
+
+    fn error(msg: &str) -> PartError {
+        PartError {
+            kind: CondowErrorKind::Remote,
+            msg: msg.to_string(),
+        }
+    }
+
+    #[test]
+    fn retries_and_failures_are_recorded_by_part() {
+        let records = RetryRecords::default();
+
+        records.retry(3, InclusiveRange(30, 39), error("a"));
+        records.retry(1, InclusiveRange(10, 19), error("b"));
+        records.retry(3, InclusiveRange(30, 39), error("c"));
+        records.failure(3, InclusiveRange(30, 39), error("d"));
+
+        let report = records.report();
+        assert_eq!(report.parts.keys().copied().collect::<Vec<_>>(), vec![1, 3]);
+        assert_eq!(report.n_retries(), 3);
+
+        let part = &report.parts[&3];
+        assert_eq!(part.range, InclusiveRange(30, 39));
+        assert_eq!(part.attempts, 3);
+        assert_eq!(part.errors, vec![error("a"), error("c"), error("d")]);
+        assert!(part.failed);
+
+        let part = &report.parts[&1];
+        assert_eq!(part.attempts, 2);
+        assert!(!part.failed);
+
+        assert_eq!(
+            report
+                .failed_parts()
+                .map(|(idx, _)| idx)
+                .collect::<Vec<_>>(),
+            vec![3]
+        );
+    }
+
+    #[test]
+    fn a_failure_without_retries_is_a_single_attempt() {
+        let records = RetryRecords::default();
+
+        records.failure(0, InclusiveRange(0, 9), error("a"));
+
+        let report = records.report();
+        assert_eq!(report.parts[&0].attempts, 1);
+        assert_eq!(report.n_retries(), 0);
+    }
+}
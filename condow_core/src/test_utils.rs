@@ -1,5 +1,9 @@
 use std::{
-    sync::{Arc, Mutex},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
     time::Duration,
 };
 
@@ -12,13 +16,30 @@ use rand::{prelude::SliceRandom, rngs::OsRng, Rng};
 use tokio::time;
 
 use crate::{
-    condow_client::{CondowClient, DownloadSpec, NoLocation},
-    errors::CondowError,
+    condow_client::{CondowClient, DownloadSpec, InMemoryClient, NoLocation},
+    config::Config,
+    errors::{CondowError, IoError},
     reader::RandomAccessReader,
     streams::{BytesHint, BytesStream, Chunk, ChunkStream, ChunkStreamItem, PartStream},
-    DownloadRange, Downloads,
+    Condow, DownloadRange, Downloads,
 };
 
+/// Creates a [Condow] for a BLOB of the 100 bytes `0..100`
+pub fn condow(part_size: u64, chunk_size: usize) -> Condow<InMemoryClient> {
+    let client = InMemoryClient::new((0u8..100).collect()).chunk_size(chunk_size);
+    let config = Config::default()
+        .part_size_bytes(part_size)
+        .buffers_full_delay_ms(0);
+    Condow::new(client, config).unwrap()
+}
+
+/// A path within the temp directory which is unique for this process
+pub fn temp_path(prefix: &str, name: &str) -> PathBuf {
+    let mut path = std::env::temp_dir();
+    path.push(format!("{}_{}_{}", prefix, std::process::id(), name));
+    path
+}
+
 #[derive(Clone)]
 pub struct TestCondowClient {
     pub data: Arc<Vec<u8>>,
@@ -372,6 +393,7 @@ async fn check_chunk_stream_variable_chunk_size() {
 pub struct TestDownloader {
     blob: Arc<Mutex<Vec<u8>>>,
     pattern: Arc<Mutex<Vec<Option<usize>>>>,
+    n_downloads: Arc<AtomicUsize>,
 }
 
 impl TestDownloader {
@@ -386,6 +408,7 @@ impl TestDownloader {
         Self {
             blob: Arc::new(Mutex::new(blob)),
             pattern: Arc::new(Mutex::new(vec![Some(2), Some(5), Some(3), Some(7)])),
+            n_downloads: Default::default(),
         }
     }
 
@@ -394,6 +417,7 @@ impl TestDownloader {
         Self {
             blob: Arc::new(Mutex::new(blob)),
             pattern: Arc::new(Mutex::new(vec![Some(5), Some(3), Some(7)])),
+            n_downloads: Default::default(),
         }
     }
 
@@ -404,12 +428,17 @@ impl TestDownloader {
     pub fn blob(&self) -> Vec<u8> {
         self.blob.lock().unwrap().clone()
     }
+
+    /// Number of downloads requested so far
+    pub fn n_downloads(&self) -> usize {
+        self.n_downloads.load(Ordering::SeqCst)
+    }
 }
 
 impl Downloads for TestDownloader {
     fn download<'a, R: Into<DownloadRange> + Send + Sync + 'static>(
         &'a self,
-        location: url::Url,
+        location: &'a url::Url,
         range: R,
     ) -> BoxFuture<'a, Result<crate::streams::PartStream<crate::streams::ChunkStream>, CondowError>>
     {
@@ -420,9 +449,10 @@ impl Downloads for TestDownloader {
 
     fn download_chunks<'a, R: Into<crate::DownloadRange> + Send + Sync + 'static>(
         &'a self,
-        _location: url::Url,
+        _location: &'a url::Url,
         range: R,
     ) -> BoxFuture<'a, Result<crate::streams::ChunkStream, CondowError>> {
+        self.n_downloads.fetch_add(1, Ordering::SeqCst);
         Box::pin(make_a_stream(
             self.blob.as_ref(),
             range.into(),
@@ -430,16 +460,44 @@ impl Downloads for TestDownloader {
         ))
     }
 
-    fn get_size<'a>(&'a self, _location: url::Url) -> BoxFuture<'a, Result<u64, CondowError>> {
+    fn get_size<'a>(&'a self, _location: &'a url::Url) -> BoxFuture<'a, Result<u64, CondowError>> {
         let len = self.blob.lock().unwrap().len();
         futures::future::ok(len as u64).boxed()
     }
 
-    fn reader_with_length(&self, location: url::Url, length: u64) -> RandomAccessReader<Self>
+    fn download_from_sequential<'a>(
+        &'a self,
+        _location: &'a url::Url,
+        start: u64,
+    ) -> BoxFuture<'a, Result<(BytesStream, BytesHint), CondowError>> {
+        self.n_downloads.fetch_add(1, Ordering::SeqCst);
+        if start >= self.blob.lock().unwrap().len() as u64 {
+            let stream: BytesStream = futures::stream::empty().boxed();
+            return futures::future::ok((stream, BytesHint::new_exact(0))).boxed();
+        }
+        make_a_stream(
+            self.blob.as_ref(),
+            (start..).into(),
+            self.pattern.lock().unwrap().clone(),
+        )
+        .map_ok(|chunk_stream| {
+            let bytes_hint = chunk_stream.bytes_hint();
+            let stream = chunk_stream
+                .map(|item| {
+                    item.map(|chunk| chunk.bytes)
+                        .map_err(|err| IoError(err.to_string()))
+                })
+                .boxed();
+            (stream as BytesStream, bytes_hint)
+        })
+        .boxed()
+    }
+
+    fn reader_with_length(&self, location: &url::Url, length: u64) -> RandomAccessReader<Self>
     where
         Self: Sized,
     {
-        RandomAccessReader::new_with_length(self.clone(), location, length)
+        RandomAccessReader::new_with_length(self.clone(), location.clone(), length)
     }
 }
 
@@ -514,7 +572,7 @@ async fn check_test_downloader() {
         let downloader = TestDownloader::new(n as usize);
 
         let parts = downloader
-            .download(url::Url::parse("noscheme://").expect("a valid URL"), ..)
+            .download(&url::Url::parse("noscheme://").expect("a valid URL"), ..)
             .await
             .unwrap();
 
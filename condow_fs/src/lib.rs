@@ -15,7 +15,7 @@
 //!
 //! let location = url::Url::from_file_path("my_file").expect("a valid path");
 //!
-//! let stream = condow.download(location, 23..46).await.unwrap();
+//! let stream = condow.download(&location, 23..46).await.unwrap();
 //! let downloaded_bytes: Vec<u8> = stream.into_vec().await.unwrap();
 //! # };
 //! # ()
@@ -23,14 +23,17 @@
 
 use std::io::SeekFrom;
 use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Error as AnyError;
-use bytes::Bytes;
+use bytes::BytesMut;
 use condow_core::config::Config;
 use futures::future::BoxFuture;
 use futures::StreamExt;
 use tokio::fs;
-use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 
 use condow_core::{
     condow_client::{CondowClient, DownloadSpec},
@@ -40,20 +43,109 @@ use condow_core::{
 
 pub use condow_core::*;
 
-#[derive(Clone)]
-pub struct FsClient;
+/// Creates a buffer for reading the given number of bytes from a file
+///
+/// See [FsClient::with_buffer_factory]
+pub type BufferFactory = Arc<dyn Fn(usize) -> BytesMut + Send + Sync>;
+
+/// A [CondowClient] for local files
+///
+/// The client can simulate a remote backend via
+/// [FsClient::with_simulated_latency] and [FsClient::with_max_open].
+/// These are test aids e.g. for benchmarking code using condow
+/// without a real backend.
+///
+/// The buffers files are read into can be provided via
+/// [FsClient::with_buffer_factory].
+#[derive(Clone, Default)]
+pub struct FsClient {
+    simulated_latency: Option<Duration>,
+    open_files: Option<Arc<Semaphore>>,
+    buffer_factory: Option<BufferFactory>,
+}
 
 impl FsClient {
+    /// Create a new [FsClient] which does not simulate anything
+    pub fn new() -> Self {
+        Self::default()
+    }
+
     /// Create a concurrent downloader from this adapter and the given [Config]
     pub fn condow(config: Config) -> Result<Condow<Self>, AnyError> {
-        Condow::new(FsClient, config)
+        Condow::new(FsClient::new(), config)
+    }
+
+    /// Create a concurrent downloader from this configured adapter and the given [Config]
+    pub fn into_condow(self, config: Config) -> Result<Condow<Self>, AnyError> {
+        Condow::new(self, config)
+    }
+
+    /// Delay every request by `latency` before the file is opened
+    ///
+    /// This is a test aid to simulate the latency of a remote backend.
+    /// With [FsClient::with_max_open] a request already counts as an open
+    /// file while it is delayed.
+    pub fn with_simulated_latency(mut self, latency: Duration) -> Self {
+        self.simulated_latency = Some(latency);
+        self
+    }
+
+    /// Allow at most `max_open` files to be open at the same time
+    ///
+    /// Further requests wait until a file was read completely.
+    /// This is a test aid to simulate a backend limiting the number
+    /// of concurrent requests.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_open` is 0.
+    pub fn with_max_open(mut self, max_open: usize) -> Self {
+        assert!(max_open > 0, "max_open must be greater than 0");
+        self.open_files = Some(Arc::new(Semaphore::new(max_open)));
+        self
+    }
+
+    /// Read files into buffers created by `buffer_factory`
+    ///
+    /// The factory receives the number of bytes to be read, e.g. to take
+    /// a buffer from a pool. The buffer is cleared before it is filled and
+    /// grows if its capacity is too small.
+    ///
+    /// The default is [BytesMut::with_capacity].
+    pub fn with_buffer_factory(mut self, buffer_factory: BufferFactory) -> Self {
+        self.buffer_factory = Some(buffer_factory);
+        self
+    }
+
+    /// Waits until a file may be opened and simulates the latency.
+    ///
+    /// The returned permit must be held while the file is open.
+    fn simulate_backend(
+        &self,
+    ) -> impl std::future::Future<Output = Option<OwnedSemaphorePermit>> + Send + 'static {
+        let simulated_latency = self.simulated_latency;
+        let open_files = self.open_files.clone();
+        async move {
+            let permit = match open_files {
+                Some(open_files) => open_files.acquire_owned().await.ok(),
+                None => None,
+            };
+
+            if let Some(latency) = simulated_latency {
+                tokio::time::sleep(latency).await;
+            }
+
+            permit
+        }
     }
 }
 
 impl CondowClient for FsClient {
     fn get_size(&self, location: url::Url) -> BoxFuture<'static, Result<u64, CondowError>> {
+        let simulate_backend = self.simulate_backend();
         // TODO: use location.to_file_path
         let f = async move {
+            let _permit = simulate_backend.await;
             let file = fs::File::open(Path::new(location.path()).to_path_buf()).await?;
             let len = file.metadata().await?.len();
 
@@ -68,30 +160,29 @@ impl CondowClient for FsClient {
         location: url::Url,
         spec: DownloadSpec,
     ) -> BoxFuture<'static, Result<(BytesStream, BytesHint), CondowError>> {
+        let simulate_backend = self.simulate_backend();
+        let buffer_factory = self.buffer_factory.clone();
         let path = Path::new(location.path()).to_path_buf();
         let f = async move {
-            let bytes = match spec {
-                DownloadSpec::Complete => fs::read(path).await?,
+            let permit = simulate_backend.await;
+            let mut file = fs::File::open(path).await?;
+            let buffer = match spec {
+                DownloadSpec::Complete => {
+                    let n_bytes_to_read = file.metadata().await?.len();
+                    read_into_buffer(file, n_bytes_to_read, buffer_factory.as_ref()).await?
+                }
                 DownloadSpec::Range(range) => {
-                    let mut file = fs::File::open(path).await?;
                     file.seek(SeekFrom::Start(range.start())).await?;
 
                     let n_bytes_to_read = range.len();
+                    let buffer =
+                        read_into_buffer(file, n_bytes_to_read, buffer_factory.as_ref()).await?;
 
-                    if n_bytes_to_read > usize::MAX as u64 {
-                        return Err(CondowError::new_other(
-                            "usize overflow while casting from u64",
-                        ));
-                    }
-
-                    let mut buffer = vec![0; n_bytes_to_read as usize];
-
-                    let n_bytes_read = file.read_exact(&mut buffer).await?;
-
-                    if n_bytes_read as u64 != n_bytes_to_read {
+                    if buffer.len() as u64 != n_bytes_to_read {
                         return Err(CondowError::new_io(format!(
                             "not enough bytes read (expected {} got {})",
-                            n_bytes_to_read, n_bytes_read
+                            n_bytes_to_read,
+                            buffer.len()
                         )));
                     }
 
@@ -99,7 +190,9 @@ impl CondowClient for FsClient {
                 }
             };
 
-            let bytes = Bytes::from(bytes);
+            drop(permit);
+
+            let bytes = buffer.freeze();
 
             let bytes_hint = BytesHint::new_exact(bytes.len() as u64);
 
@@ -111,3 +204,30 @@ impl CondowClient for FsClient {
         Box::pin(f)
     }
 }
+
+/// Reads at most `n_bytes_to_read` from `reader` into a new buffer
+async fn read_into_buffer<R: AsyncRead + Unpin>(
+    reader: R,
+    n_bytes_to_read: u64,
+    buffer_factory: Option<&BufferFactory>,
+) -> Result<BytesMut, CondowError> {
+    if n_bytes_to_read > usize::MAX as u64 {
+        return Err(CondowError::new_other(
+            "usize overflow while casting from u64",
+        ));
+    }
+    let capacity = n_bytes_to_read as usize;
+
+    let mut buffer = if let Some(buffer_factory) = buffer_factory {
+        let mut buffer = buffer_factory(capacity);
+        buffer.clear();
+        buffer
+    } else {
+        BytesMut::with_capacity(capacity)
+    };
+
+    let mut reader = reader.take(n_bytes_to_read);
+    while reader.read_buf(&mut buffer).await? > 0 {}
+
+    Ok(buffer)
+}
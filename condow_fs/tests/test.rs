@@ -16,7 +16,7 @@ async fn download_full() {
     let condow = create_condow_condow();
 
     let data = condow
-        .download(get_test_file_path(), ..)
+        .download(&get_test_file_path(), ..)
         .await
         .unwrap()
         .into_vec()
@@ -31,7 +31,7 @@ async fn download_to() {
     let condow = create_condow_condow();
 
     let data = condow
-        .download(get_test_file_path(), ..5)
+        .download(&get_test_file_path(), ..5)
         .await
         .unwrap()
         .into_vec()
@@ -46,7 +46,7 @@ async fn download_to_end() {
     let condow = create_condow_condow();
 
     let data = condow
-        .download(get_test_file_path(), ..=26)
+        .download(&get_test_file_path(), ..=26)
         .await
         .unwrap()
         .into_vec()
@@ -61,7 +61,7 @@ async fn download_from() {
     let condow = create_condow_condow();
 
     let data = condow
-        .download(get_test_file_path(), 10..)
+        .download(&get_test_file_path(), 10..)
         .await
         .unwrap()
         .into_vec()
@@ -76,7 +76,7 @@ async fn download_from_to() {
     let condow = create_condow_condow();
 
     let data = condow
-        .download(get_test_file_path(), 1..11)
+        .download(&get_test_file_path(), 1..11)
         .await
         .unwrap()
         .into_vec()
@@ -85,3 +85,125 @@ async fn download_from_to() {
 
     assert_eq!(&data[..], b"bcdefghijk");
 }
+
+#[tokio::test]
+async fn download_with_simulated_latency() {
+    let condow = FsClient::new()
+        .with_simulated_latency(std::time::Duration::from_millis(50))
+        .into_condow(Default::default())
+        .unwrap();
+
+    let started = std::time::Instant::now();
+    let data = condow
+        .download(&get_test_file_path(), 1..11)
+        .await
+        .unwrap()
+        .into_vec()
+        .await
+        .unwrap();
+
+    assert!(started.elapsed() >= std::time::Duration::from_millis(50));
+    assert_eq!(&data[..], b"bcdefghijk");
+}
+
+#[tokio::test]
+async fn download_with_max_open() {
+    let latency = std::time::Duration::from_millis(20);
+    // 26 bytes are 9 parts
+    let config = condow_fs::config::Config::default()
+        .part_size_bytes(3)
+        .max_concurrency(8);
+    let condow = FsClient::new()
+        .with_simulated_latency(latency)
+        .with_max_open(2)
+        .into_condow(config)
+        .unwrap();
+
+    let started = std::time::Instant::now();
+    let data = condow
+        .download(&get_test_file_path(), ..)
+        .await
+        .unwrap()
+        .into_vec()
+        .await
+        .unwrap();
+
+    // With at most 2 open files the 9 parts take at least 5 rounds
+    assert!(
+        started.elapsed() >= latency * 5,
+        "elapsed: {:?}",
+        started.elapsed()
+    );
+    assert_eq!(&data[..], b"abcdefghijklmnopqrstuvwxyz");
+}
+
+#[tokio::test]
+async fn download_with_buffer_factory() {
+    use std::sync::{Arc, Mutex};
+
+    let requested = Arc::new(Mutex::new(Vec::new()));
+    let requested_in_factory = Arc::clone(&requested);
+    let config = condow_fs::config::Config::default()
+        .part_size_bytes(10)
+        .max_concurrency(1);
+    let condow = FsClient::new()
+        .with_buffer_factory(Arc::new(move |n_bytes| {
+            requested_in_factory.lock().unwrap().push(n_bytes);
+            bytes::BytesMut::with_capacity(n_bytes)
+        }))
+        .into_condow(config)
+        .unwrap();
+
+    let data = condow
+        .download(&get_test_file_path(), ..)
+        .await
+        .unwrap()
+        .into_vec()
+        .await
+        .unwrap();
+
+    assert_eq!(&data[..], b"abcdefghijklmnopqrstuvwxyz");
+    assert_eq!(*requested.lock().unwrap(), vec![10, 10, 6]);
+}
+
+#[tokio::test]
+async fn buffers_from_the_factory_are_cleared() {
+    let condow = FsClient::new()
+        .with_buffer_factory(std::sync::Arc::new(|_n_bytes| {
+            bytes::BytesMut::from(&b"garbage"[..])
+        }))
+        .into_condow(Default::default())
+        .unwrap();
+
+    let data = condow
+        .download(&get_test_file_path(), 1..11)
+        .await
+        .unwrap()
+        .into_vec()
+        .await
+        .unwrap();
+
+    assert_eq!(&data[..], b"bcdefghijk");
+}
+
+#[tokio::test]
+#[cfg(target_pointer_width = "32")]
+async fn a_range_exceeding_usize_fails_on_32_bit() {
+    use condow_fs::{
+        condow_client::{CondowClient, DownloadSpec},
+        config::Gibi,
+        errors::CondowErrorKind,
+        InclusiveRange,
+    };
+
+    let range = InclusiveRange(0, Gibi(5).value() - 1);
+
+    let err = FsClient::new()
+        .download(get_test_file_path(), DownloadSpec::Range(range))
+        .await
+        .err()
+        .unwrap();
+
+    assert_eq!(err.kind(), CondowErrorKind::Other);
+    assert_eq!(err.msg(), "usize overflow while casting from u64");
+}
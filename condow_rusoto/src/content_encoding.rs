@@ -0,0 +1,213 @@
+//! Decoding of objects stored with a `Content-Encoding`
+//!
+//! Requires the feature `gzip`.
+
+use std::io;
+
+use async_compression::futures::bufread::GzipDecoder;
+use bytes::BytesMut;
+use futures::{stream::TryStreamExt, AsyncRead, AsyncReadExt};
+
+use condow_core::{
+    config::Kibi,
+    errors::{CondowError, IoError},
+    streams::BytesStream,
+};
+
+/// Size of the chunks of bytes yielded by [S3ClientWrapper::download_decoded](crate::S3ClientWrapper::download_decoded)
+/// for an encoded object
+const DECODED_CHUNK_SIZE: usize = Kibi(64).value() as usize;
+
+/// The `Content-Encoding` of an object as far as it can be decoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ContentEncoding {
+    Identity,
+    Gzip,
+}
+
+impl ContentEncoding {
+    /// Fails on encodings which can not be decoded
+    pub(crate) fn from_header(content_encoding: Option<&str>) -> Result<Self, CondowError> {
+        let content_encoding = match content_encoding.map(str::trim) {
+            None | Some("") => return Ok(ContentEncoding::Identity),
+            Some(content_encoding) => content_encoding,
+        };
+
+        if content_encoding.eq_ignore_ascii_case("identity") {
+            Ok(ContentEncoding::Identity)
+        } else if content_encoding.eq_ignore_ascii_case("gzip")
+            || content_encoding.eq_ignore_ascii_case("x-gzip")
+        {
+            Ok(ContentEncoding::Gzip)
+        } else {
+            Err(CondowError::new_other(format!(
+                "objects with content encoding '{}' can not be decoded",
+                content_encoding
+            )))
+        }
+    }
+
+    pub(crate) fn decode(self, stream: BytesStream) -> BytesStream {
+        match self {
+            ContentEncoding::Identity => stream,
+            ContentEncoding::Gzip => {
+                let reader = stream.map_err(io::Error::other).into_async_read();
+                let mut decoder = GzipDecoder::new(reader);
+                // Objects might consist of concatenated gzip members
+                decoder.multiple_members(true);
+                reader_to_stream(decoder)
+            }
+        }
+    }
+}
+
+/// Streams the bytes read from `reader`
+///
+/// All reads go into the same buffer and each chunk is split off of it.
+/// The capacity left after a short read is used by the next read
+/// and the buffer is reclaimed once the chunks split off were dropped.
+fn reader_to_stream<R: AsyncRead + Send + Unpin + 'static>(reader: R) -> BytesStream {
+    let stream = futures::stream::try_unfold(
+        (reader, BytesMut::new()),
+        |(mut reader, mut buffer)| async move {
+            buffer.resize(DECODED_CHUNK_SIZE, 0);
+            let n_bytes = reader.read(&mut buffer).await?;
+            if n_bytes == 0 {
+                return Ok(None);
+            }
+            buffer.truncate(n_bytes);
+            let chunk = buffer.split().freeze();
+            Ok::<_, io::Error>(Some((chunk, (reader, buffer))))
+        },
+    );
+
+    Box::pin(stream.map_err(IoError::from))
+}
+
+#[cfg(test)]
+mod tests {
+    use async_compression::futures::bufread::GzipEncoder;
+    use bytes::Bytes;
+    use condow_core::{errors::CondowErrorKind, streams::BytesStream};
+    use futures::{AsyncReadExt, TryStreamExt};
+
+    use super::{ContentEncoding, DECODED_CHUNK_SIZE};
+
+    fn decode(content_encoding: ContentEncoding, encoded: Vec<u8>) -> Result<Vec<u8>, String> {
+        let stream: BytesStream = Box::pin(futures::stream::iter(
+            encoded
+                .chunks(3)
+                .map(|chunk| Ok(Bytes::copy_from_slice(chunk)))
+                .collect::<Vec<_>>(),
+        ));
+
+        futures::executor::block_on(async {
+            let chunks = content_encoding
+                .decode(stream)
+                .try_collect::<Vec<_>>()
+                .await
+                .map_err(|err| err.to_string())?;
+            Ok(chunks.concat())
+        })
+    }
+
+    fn gzip(bytes: &[u8]) -> Vec<u8> {
+        futures::executor::block_on(async {
+            let mut encoded = Vec::new();
+            GzipEncoder::new(bytes)
+                .read_to_end(&mut encoded)
+                .await
+                .unwrap();
+            encoded
+        })
+    }
+
+    #[test]
+    fn content_encodings_which_can_be_decoded() {
+        assert_eq!(
+            ContentEncoding::from_header(None).unwrap(),
+            ContentEncoding::Identity
+        );
+        assert_eq!(
+            ContentEncoding::from_header(Some("identity")).unwrap(),
+            ContentEncoding::Identity
+        );
+        assert_eq!(
+            ContentEncoding::from_header(Some("gzip")).unwrap(),
+            ContentEncoding::Gzip
+        );
+        assert_eq!(
+            ContentEncoding::from_header(Some(" GZIP ")).unwrap(),
+            ContentEncoding::Gzip
+        );
+        assert_eq!(
+            ContentEncoding::from_header(Some("x-gzip")).unwrap(),
+            ContentEncoding::Gzip
+        );
+    }
+
+    #[test]
+    fn an_unknown_content_encoding_is_an_error() {
+        let err = ContentEncoding::from_header(Some("br")).unwrap_err();
+
+        assert_eq!(err.kind(), CondowErrorKind::Other);
+        assert_eq!(
+            err.msg(),
+            "objects with content encoding 'br' can not be decoded"
+        );
+    }
+
+    #[test]
+    fn identity_is_not_decoded() {
+        let bytes = (0u8..100).collect::<Vec<_>>();
+
+        assert_eq!(
+            decode(ContentEncoding::Identity, bytes.clone()).unwrap(),
+            bytes
+        );
+    }
+
+    #[test]
+    fn gzip_is_decoded() {
+        let bytes = (0..100_000u32).map(|n| (n % 251) as u8).collect::<Vec<_>>();
+
+        assert_eq!(decode(ContentEncoding::Gzip, gzip(&bytes)).unwrap(), bytes);
+    }
+
+    #[test]
+    fn concatenated_gzip_members_are_decoded() {
+        let mut encoded = gzip(b"hello ");
+        encoded.extend(gzip(b"world"));
+
+        assert_eq!(
+            decode(ContentEncoding::Gzip, encoded).unwrap(),
+            b"hello world"
+        );
+    }
+
+    #[test]
+    fn corrupt_gzip_is_an_error() {
+        let mut encoded = gzip(b"hello world");
+        encoded.truncate(encoded.len() / 2);
+
+        assert!(decode(ContentEncoding::Gzip, encoded).is_err());
+    }
+
+    #[test]
+    fn large_objects_are_decoded_in_chunks() {
+        let bytes = (0..1_000_000u32)
+            .map(|n| (n % 251) as u8)
+            .collect::<Vec<_>>();
+        let stream: BytesStream =
+            Box::pin(futures::stream::iter(vec![Ok(Bytes::from(gzip(&bytes)))]));
+
+        let chunks = futures::executor::block_on(
+            ContentEncoding::Gzip.decode(stream).try_collect::<Vec<_>>(),
+        )
+        .unwrap();
+
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|chunk| chunk.len() <= DECODED_CHUNK_SIZE));
+        assert_eq!(chunks.concat(), bytes);
+    }
+}
@@ -18,7 +18,7 @@
 //!
 //! let location = url::Url::parse("s3://my_bucket/my_object").expect("a valid s3 URL");
 //!
-//! let stream = condow.download(location, 23..46).await.unwrap();
+//! let stream = condow.download(&location, 23..46).await.unwrap();
 //! let downloaded_bytes: Vec<u8> = stream.into_vec().await.unwrap();
 //! # };
 //! # ()
@@ -26,25 +26,34 @@
 use std::{
     fmt,
     ops::{Deref, DerefMut},
+    sync::Arc,
 };
 
 use anyhow::Error as AnyError;
-use futures::{future::BoxFuture, stream::TryStreamExt};
+use futures::{future::BoxFuture, stream::TryStreamExt, FutureExt, TryFutureExt};
+use percent_encoding::percent_decode_str;
 use rusoto_core::{request::BufferedHttpResponse, RusotoError};
-use rusoto_s3::{GetObjectError, GetObjectRequest, HeadObjectError, HeadObjectRequest, S3};
+use rusoto_s3::{
+    AbortMultipartUploadRequest, CompleteMultipartUploadRequest, CompletedMultipartUpload,
+    CompletedPart, CreateMultipartUploadRequest, GetObjectError, GetObjectOutput, GetObjectRequest,
+    HeadObjectError, HeadObjectRequest, ListBucketsError, UploadPartRequest, S3,
+};
 
 pub use rusoto_core::Region;
 pub use rusoto_s3::S3Client;
 
 use condow_core::{
     condow_client::*,
-    config::Config,
+    config::{Config, Mebi},
     errors::{CondowError, IoError},
-    streams::{BytesHint, BytesStream},
+    streams::{BytesHint, BytesStream, OrderedBytesStream},
 };
 
 pub use condow_core::*;
 
+#[cfg(feature = "gzip")]
+mod content_encoding;
+
 /// S3 bucket name
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Bucket(String);
@@ -162,10 +171,82 @@ impl fmt::Display for S3Location {
     }
 }
 
+/// Extracts the [Bucket] and the [ObjectKey] from a location
+///
+/// The default expects URLs of the form `s3://bucket/key`
+/// with a percent-encoded key.
+///
+/// # Examples
+///
+/// ```rust
+/// use condow_rusoto::{errors::CondowError, Bucket, LocationParser};
+///
+/// // URLs of the form `https://host/bucket/key`
+/// let parser = LocationParser::new(|location: &url::Url| {
+///     let (bucket, key) = location
+///         .path()
+///         .trim_start_matches('/')
+///         .split_once('/')
+///         .ok_or_else(|| CondowError::new_other(format!("no bucket in '{}'", location)))?;
+///     Ok(Bucket::new(bucket).object(key).into_inner())
+/// });
+///
+/// let location = url::Url::parse("https://example.com/my_bucket/my_object").unwrap();
+/// let (bucket, key) = parser.parse(&location).unwrap();
+///
+/// assert_eq!(&*bucket, "my_bucket");
+/// assert_eq!(&*key, "my_object");
+/// ```
+#[derive(Clone)]
+pub struct LocationParser(
+    Arc<dyn Fn(&url::Url) -> Result<(Bucket, ObjectKey), CondowError> + Send + Sync>,
+);
+
+impl LocationParser {
+    /// Create a [LocationParser] from the given function
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(&url::Url) -> Result<(Bucket, ObjectKey), CondowError> + Send + Sync + 'static,
+    {
+        Self(Arc::new(f))
+    }
+
+    /// Extract the [Bucket] and the [ObjectKey] from the given location
+    pub fn parse(&self, location: &url::Url) -> Result<(Bucket, ObjectKey), CondowError> {
+        (self.0)(location)
+    }
+}
+
+impl Default for LocationParser {
+    fn default() -> Self {
+        Self::new(bucket_and_key)
+    }
+}
+
+impl<F> From<F> for LocationParser
+where
+    F: Fn(&url::Url) -> Result<(Bucket, ObjectKey), CondowError> + Send + Sync + 'static,
+{
+    fn from(f: F) -> Self {
+        Self::new(f)
+    }
+}
+
 /// Just a wrapper around a clietn
 /// to implement the trait [CondowClient](condow_client::CondowClient) on.
+///
+/// The query parameter `versionId` of a location selects the version of
+/// the object to download. Other query parameters are not passed on to S3
+/// since the requests of `rusoto` can not carry arbitrary parameters.
+///
+/// Errors returned by `rusoto` are attached as the source of a [CondowError].
+/// Use [CondowError::downcast_ref] to get e.g. the `RusotoError<GetObjectError>` back.
 #[derive(Clone)]
-pub struct S3ClientWrapper<C>(C);
+pub struct S3ClientWrapper<C> {
+    client: C,
+    location_parser: LocationParser,
+    reject_encoded_ranges: bool,
+}
 
 impl S3ClientWrapper<S3Client> {
     /// Create a new wrapper wrapping the default [S3Client](rusoto_s3::S3Client)
@@ -174,29 +255,203 @@ impl S3ClientWrapper<S3Client> {
         let client = S3Client::new(region);
         Self::from_client(client)
     }
+
+    /// Create a builder for a wrapper wrapping the default [S3Client](rusoto_s3::S3Client)
+    /// for the given [Region](rusoto_core::Region) with adjusted endpoint resolution.
+    pub fn builder(region: Region) -> S3ClientBuilder {
+        S3ClientBuilder::new(region)
+    }
+}
+
+/// Builds an [S3ClientWrapper] wrapping the default [S3Client](rusoto_s3::S3Client)
+///
+/// FIPS and dualstack (IPv4 and IPv6) endpoints are selected by
+/// replacing the [Region] with a [Region::Custom] pointing to
+/// the respective endpoint. The name of the region is kept for signing requests.
+///
+/// A [Region::Custom] is never changed since its endpoint was chosen explicitly.
+/// Requesting FIPS or dualstack endpoints for a [Region::Custom] is an error.
+pub struct S3ClientBuilder {
+    region: Region,
+    use_fips: bool,
+    use_dualstack: bool,
+}
+
+impl S3ClientBuilder {
+    pub fn new(region: Region) -> Self {
+        Self {
+            region,
+            use_fips: false,
+            use_dualstack: false,
+        }
+    }
+
+    /// Use the FIPS endpoint of S3 (`s3-fips.<region>.amazonaws.com`)
+    pub fn use_fips(mut self, use_fips: bool) -> Self {
+        self.use_fips = use_fips;
+        self
+    }
+
+    /// Use the dualstack endpoint of S3 (`s3.dualstack.<region>.amazonaws.com`)
+    pub fn use_dualstack(mut self, use_dualstack: bool) -> Self {
+        self.use_dualstack = use_dualstack;
+        self
+    }
+
+    /// The [Region] the client will be created with
+    ///
+    /// Fails if FIPS or dualstack endpoints were requested for a [Region::Custom].
+    pub fn region(&self) -> Result<Region, AnyError> {
+        resolve_region(&self.region, self.use_fips, self.use_dualstack)
+    }
+
+    /// Create the [S3ClientWrapper]
+    ///
+    /// Fails if FIPS or dualstack endpoints were requested for a [Region::Custom].
+    pub fn build(self) -> Result<S3ClientWrapper<S3Client>, AnyError> {
+        Ok(S3ClientWrapper::new(self.region()?))
+    }
+}
+
+/// Returns a [Region::Custom] with the FIPS and/or dualstack endpoint of S3
+/// for the given region.
+///
+/// Fails for a [Region::Custom] if FIPS or dualstack endpoints are requested
+/// since its endpoint can not be changed.
+fn resolve_region(
+    region: &Region,
+    use_fips: bool,
+    use_dualstack: bool,
+) -> Result<Region, AnyError> {
+    if !use_fips && !use_dualstack {
+        return Ok(region.clone());
+    }
+
+    if let Region::Custom { name, endpoint } = region {
+        anyhow::bail!(
+            "FIPS or dualstack endpoints can not be used with the custom region '{}' ({})",
+            name,
+            endpoint
+        );
+    }
+
+    let name = region.name();
+    let service = if use_fips { "s3-fips" } else { "s3" };
+    let dualstack = if use_dualstack { ".dualstack" } else { "" };
+    let domain = if name.starts_with("cn-") {
+        "amazonaws.com.cn"
+    } else {
+        "amazonaws.com"
+    };
+
+    Ok(Region::Custom {
+        name: name.to_string(),
+        endpoint: format!("https://{}{}.{}.{}", service, dualstack, name, domain),
+    })
 }
 
 impl<C: S3 + Clone + Send + Sync + 'static> S3ClientWrapper<C> {
     /// Create a new wrapper wrapping given an implementor of [S3](rusoto_s3::S3).
     pub fn from_client(client: C) -> Self {
-        Self(client)
+        Self {
+            client,
+            location_parser: LocationParser::default(),
+            reject_encoded_ranges: false,
+        }
+    }
+
+    /// Set how the bucket and the object key are extracted from a location
+    ///
+    /// The default expects URLs of the form `s3://bucket/key`.
+    pub fn with_location_parser<P: Into<LocationParser>>(mut self, location_parser: P) -> Self {
+        self.location_parser = location_parser.into();
+        self
+    }
+
+    /// Fail requests for a range of an object stored with a `Content-Encoding`
+    ///
+    /// The bytes of such a range are a slice of the encoded object which can
+    /// not be decoded on their own. If enabled, downloading (parts of) an
+    /// encoded object with [Condow] fails instead of returning the encoded bytes.
+    /// `identity` is not an encoding.
+    ///
+    /// The default is `false`.
+    pub fn reject_encoded_ranges(mut self, reject_encoded_ranges: bool) -> Self {
+        self.reject_encoded_ranges = reject_encoded_ranges;
+        self
     }
 
     /// Create a concurrent downloader from this adapter and the given [Config]
     pub fn condow(self, config: Config) -> Result<Condow<Self>, AnyError> {
         Condow::new(self, config)
     }
+
+    /// Create a concurrent downloader from this adapter and the given [Config]
+    /// after checking that S3 accepts the credentials
+    ///
+    /// Makes a request to list the buckets. It fails if the credentials can not
+    /// be loaded, are rejected by S3 or S3 can not be reached in the configured region.
+    /// If listing the buckets is not permitted the credentials are still valid
+    /// and the check succeeds.
+    ///
+    /// Use [S3ClientWrapper::condow] to not make any request upfront.
+    pub async fn condow_checked(self, config: Config) -> Result<Condow<Self>, CondowError> {
+        match self.client.list_buckets().await {
+            Ok(_) => {}
+            Err(RusotoError::Unknown(response)) if is_permission_denied(&response) => {}
+            Err(err) => return Err(list_buckets_err_to_check_err(err)),
+        }
+
+        Condow::try_new(self, config)
+    }
+
+    /// Download the complete object and decode it according to its `Content-Encoding`
+    ///
+    /// Requires the feature `gzip`.
+    ///
+    /// An object stored with `Content-Encoding: gzip` is decompressed while it
+    /// is streamed. An object without a `Content-Encoding` (or `identity`) is
+    /// returned as it is. Any other encoding fails with a [CondowError].
+    ///
+    /// The object is downloaded with a single request and without retries.
+    /// Since a range of an encoded object can not be decoded on its own
+    /// there is no ranged variant. Use [S3ClientWrapper::reject_encoded_ranges]
+    /// to make downloads of ranges of encoded objects with [Condow] fail.
+    #[cfg(feature = "gzip")]
+    pub async fn download_decoded(&self, location: &url::Url) -> Result<BytesStream, CondowError> {
+        let get_object_request = get_object_request(&self.location_parser, location, None)?;
+
+        let response = self
+            .client
+            .get_object(get_object_request)
+            .await
+            .map_err(get_obj_err_to_download_err)?;
+
+        let content_encoding =
+            content_encoding::ContentEncoding::from_header(response.content_encoding.as_deref())?;
+
+        let stream = if let Some(stream) = response.body {
+            stream
+        } else {
+            return Err(CondowError::new_other("response had no body"));
+        };
+
+        let stream: BytesStream = Box::pin(stream.map_err(|err| IoError(err.to_string())));
+
+        Ok(content_encoding.decode(stream))
+    }
 }
 
 impl<C: S3 + Clone + Send + Sync + 'static> CondowClient for S3ClientWrapper<C> {
     fn get_size(&self, location: url::Url) -> BoxFuture<'static, Result<u64, CondowError>> {
-        let client = self.0.clone();
-        let bucket = location.host_str().expect("a valid S3 URL").to_string();
-        let object_key = location.path().to_string();
+        let client = self.client.clone();
+        let location_parser = self.location_parser.clone();
         let f = async move {
+            let (bucket, object_key) = location_parser.parse(&location)?;
             let head_object_request = HeadObjectRequest {
-                bucket: bucket,
-                key: object_key,
+                bucket: bucket.into_inner(),
+                key: object_key.into_inner(),
+                version_id: version_id(&location),
                 ..Default::default()
             };
 
@@ -205,11 +460,7 @@ impl<C: S3 + Clone + Send + Sync + 'static> CondowClient for S3ClientWrapper<C>
                 .await
                 .map_err(head_obj_err_to_get_size_err)?;
 
-            if let Some(size) = response.content_length {
-                Ok(size as u64)
-            } else {
-                Err(CondowError::new_other("response had no content length"))
-            }
+            content_length_to_size(response.content_length)
         };
 
         Box::pin(f)
@@ -220,62 +471,497 @@ impl<C: S3 + Clone + Send + Sync + 'static> CondowClient for S3ClientWrapper<C>
         location: url::Url,
         spec: DownloadSpec,
     ) -> BoxFuture<'static, Result<(BytesStream, BytesHint), CondowError>> {
-        let client = self.0.clone();
-        let bucket = location.host_str().expect("a valid S3 URL").to_string();
-        let object_key = location.path().to_string();
+        self.download_with_content_range(location, spec)
+            .map_ok(|(stream, bytes_hint, _content_range)| (stream, bytes_hint))
+            .boxed()
+    }
+
+    fn download_with_content_range(
+        &self,
+        location: url::Url,
+        spec: DownloadSpec,
+    ) -> BoxFuture<'static, Result<ContentRangeResponse, CondowError>> {
+        let client = self.client.clone();
+        let location_parser = self.location_parser.clone();
+        let reject_encoded_ranges = self.reject_encoded_ranges;
         let f = async move {
-            let get_object_request = GetObjectRequest {
-                bucket: bucket,
-                key: object_key,
-                range: spec.http_range_value(),
-                ..Default::default()
-            };
+            let range = spec.http_range_value();
+            let get_object_request =
+                get_object_request(&location_parser, &location, range.clone())?;
 
             let response = client
                 .get_object(get_object_request)
                 .await
                 .map_err(get_obj_err_to_download_err)?;
 
-            let bytes_hint = response
-                .content_length
-                .map(|s| BytesHint::new_exact(s as u64))
-                .unwrap_or_else(BytesHint::new_no_hint);
+            if reject_encoded_ranges {
+                check_range_not_encoded(range.as_deref(), &response)?;
+            }
 
-            let stream = if let Some(stream) = response.body {
-                stream
-            } else {
-                return Err(CondowError::new_other("response had no body"));
+            get_object_response(response, range.as_deref())
+        };
+
+        Box::pin(f)
+    }
+
+    fn download_from(
+        &self,
+        location: url::Url,
+        start: u64,
+    ) -> BoxFuture<'static, Result<(BytesStream, BytesHint), CondowError>> {
+        let client = self.client.clone();
+        let location_parser = self.location_parser.clone();
+        let reject_encoded_ranges = self.reject_encoded_ranges;
+        let f = async move {
+            let range = format!("bytes={}-", start);
+            let get_object_request =
+                get_object_request(&location_parser, &location, Some(range.clone()))?;
+
+            let response = match client.get_object(get_object_request).await {
+                Ok(response) => response,
+                // S3 answers with "416 Range Not Satisfiable" if `start`
+                // is not within the object
+                Err(RusotoError::Unknown(response)) if response.status.as_u16() == 416 => {
+                    let stream: BytesStream = Box::pin(futures::stream::empty());
+                    return Ok((stream, BytesHint::new_exact(0)));
+                }
+                Err(err) => return Err(get_obj_err_to_download_err(err)),
             };
 
-            let stream: BytesStream = Box::pin(stream.map_err(|err| IoError(err.to_string())));
+            if reject_encoded_ranges {
+                check_range_not_encoded(Some(&range), &response)?;
+            }
 
-            Ok((stream, bytes_hint))
+            get_object_response(response, Some(&range))
+                .map(|(stream, bytes_hint, _content_range)| (stream, bytes_hint))
         };
 
         Box::pin(f)
     }
 }
 
-fn get_obj_err_to_download_err(err: RusotoError<GetObjectError>) -> CondowError {
-    match err {
-        RusotoError::Service(err) => match err {
-            GetObjectError::NoSuchKey(s) => CondowError::new_not_found(s),
-            GetObjectError::InvalidObjectState(s) => {
-                CondowError::new_other(format!("invalid object state (get object request): {}", s))
+/// Creates the request to get the object at `location`
+///
+/// `range` is the value of the `Range` header if any.
+fn get_object_request(
+    location_parser: &LocationParser,
+    location: &url::Url,
+    range: Option<String>,
+) -> Result<GetObjectRequest, CondowError> {
+    let (bucket, object_key) = location_parser.parse(location)?;
+    Ok(GetObjectRequest {
+        bucket: bucket.into_inner(),
+        key: object_key.into_inner(),
+        range,
+        version_id: version_id(location),
+        ..Default::default()
+    })
+}
+
+/// Turns the response of a get object request into a [ContentRangeResponse]
+///
+/// Fails if `range` was requested but not honored.
+fn get_object_response(
+    response: GetObjectOutput,
+    range: Option<&str>,
+) -> Result<ContentRangeResponse, CondowError> {
+    check_range_honored(range, &response)?;
+    let content_range = response
+        .content_range
+        .as_deref()
+        .and_then(parse_content_range);
+
+    let bytes_hint = match response.content_length {
+        Some(content_length) => BytesHint::new_exact(content_length_to_size(Some(content_length))?),
+        None => BytesHint::new_no_hint(),
+    };
+
+    let stream = if let Some(stream) = response.body {
+        stream
+    } else {
+        return Err(CondowError::new_other("response had no body"));
+    };
+
+    let stream: BytesStream = Box::pin(stream.map_err(|err| IoError(err.to_string())));
+
+    Ok((stream, bytes_hint, content_range))
+}
+
+/// Minimum size of the parts uploaded by [copy]
+///
+/// S3 requires all parts but the last one to have at least 5 MiB.
+const COPY_MIN_PART_SIZE_BYTES: u64 = Mebi(8).value();
+
+/// The maximum number of parts of a multipart upload to S3
+const COPY_MAX_PARTS: u64 = 10_000;
+
+/// Returns the size of the parts uploaded by [copy] for a BLOB of `size` bytes
+///
+/// Parts have at least 8 MiB. Larger BLOBs get larger parts so that
+/// they fit into the 10,000 parts of a multipart upload.
+fn copy_part_size(size: u64) -> Result<usize, CondowError> {
+    let part_size = size.div_ceil(COPY_MAX_PARTS).max(COPY_MIN_PART_SIZE_BYTES);
+    usize::try_from(part_size).map_err(|_| {
+        CondowError::new_other(format!(
+            "part size of {} bytes for copying {} bytes does not fit into memory",
+            part_size, size
+        ))
+    })
+}
+
+/// Copy a BLOB downloaded with `src_condow` to the S3 object `dst`
+///
+/// The BLOB is downloaded concurrently and streamed into a multipart upload
+/// while it is downloaded. The parts are uploaded one after the other with
+/// 8 MiB each or more if the BLOB would need more than the 10,000 parts S3
+/// allows. So besides the buffers of the download only one part is
+/// held in memory at a time.
+///
+/// Uploading a part is not retried. If the copy fails, even if only one part
+/// could not be uploaded, the multipart upload is aborted and the copy has to
+/// be started again. [CondowError::is_retryable] tells whether this might succeed.
+///
+/// The source can be any location `src_condow` can download from, e.g. another
+/// bucket or a local file.
+///
+/// Returns the number of bytes copied.
+pub async fn copy<C, D>(
+    src_condow: &Condow<C>,
+    src: &url::Url,
+    dst_client: &S3ClientWrapper<D>,
+    dst: &url::Url,
+) -> Result<u64, CondowError>
+where
+    C: CondowClient,
+    D: S3 + Clone + Send + Sync + 'static,
+{
+    let (bucket, object_key) = dst_client.location_parser.parse(dst)?;
+    let mut bytes_stream = src_condow.download_chunks_ordered(src, ..).await?;
+    let size = bytes_stream.bytes_hint().upper_bound().ok_or_else(|| {
+        CondowError::new_other("the size of the BLOB to copy is unknown. This is a bug.")
+    })?;
+    let part_size = copy_part_size(size)?;
+
+    let create_request = CreateMultipartUploadRequest {
+        bucket: bucket.to_string(),
+        key: object_key.to_string(),
+        ..Default::default()
+    };
+    let upload_id = dst_client
+        .client
+        .create_multipart_upload(create_request)
+        .await
+        .map_err(|err| upload_err_to_condow_err("create multipart upload", err))?
+        .upload_id
+        .ok_or_else(|| CondowError::new_other("response had no upload id"))?;
+
+    let upload = MultipartUpload {
+        client: &dst_client.client,
+        bucket: bucket.into_inner(),
+        key: object_key.into_inner(),
+        upload_id,
+    };
+
+    match upload.upload_parts(&mut bytes_stream, part_size).await {
+        Ok((n_bytes, parts)) => {
+            upload.complete(parts).await?;
+            Ok(n_bytes)
+        }
+        Err(err) => {
+            let _ = upload.abort().await;
+            Err(err)
+        }
+    }
+}
+
+/// A started multipart upload of [copy]
+struct MultipartUpload<'a, C> {
+    client: &'a C,
+    bucket: String,
+    key: String,
+    upload_id: String,
+}
+
+impl<C: S3> MultipartUpload<'_, C> {
+    /// Upload all bytes of the stream
+    ///
+    /// Returns the number of bytes uploaded and the uploaded parts.
+    async fn upload_parts(
+        &self,
+        bytes_stream: &mut OrderedBytesStream,
+        part_size: usize,
+    ) -> Result<(u64, Vec<CompletedPart>), CondowError> {
+        let mut splitter = PartSplitter::new(part_size);
+        let mut parts = Vec::new();
+        let mut n_bytes = 0;
+
+        while let Some(bytes) = bytes_stream.try_next().await? {
+            n_bytes += bytes.len() as u64;
+            for part in splitter.push(&bytes) {
+                parts.push(self.upload_part(parts.len() + 1, part).await?);
+            }
+        }
+
+        // An empty BLOB is uploaded as a single empty part
+        let last_part = splitter.finish();
+        if !last_part.is_empty() || parts.is_empty() {
+            parts.push(self.upload_part(parts.len() + 1, last_part).await?);
+        }
+
+        Ok((n_bytes, parts))
+    }
+
+    async fn upload_part(
+        &self,
+        part_number: usize,
+        part: Vec<u8>,
+    ) -> Result<CompletedPart, CondowError> {
+        let request = UploadPartRequest {
+            bucket: self.bucket.clone(),
+            key: self.key.clone(),
+            upload_id: self.upload_id.clone(),
+            part_number: part_number as i64,
+            content_length: Some(part.len() as i64),
+            body: Some(part.into()),
+            ..Default::default()
+        };
+
+        let response = self
+            .client
+            .upload_part(request)
+            .await
+            .map_err(|err| upload_err_to_condow_err("upload part", err))?;
+
+        Ok(CompletedPart {
+            e_tag: response.e_tag,
+            part_number: Some(part_number as i64),
+        })
+    }
+
+    async fn complete(&self, parts: Vec<CompletedPart>) -> Result<(), CondowError> {
+        let request = CompleteMultipartUploadRequest {
+            bucket: self.bucket.clone(),
+            key: self.key.clone(),
+            upload_id: self.upload_id.clone(),
+            multipart_upload: Some(CompletedMultipartUpload { parts: Some(parts) }),
+            ..Default::default()
+        };
+
+        self.client
+            .complete_multipart_upload(request)
+            .await
+            .map_err(|err| upload_err_to_condow_err("complete multipart upload", err))?;
+
+        Ok(())
+    }
+
+    async fn abort(&self) -> Result<(), CondowError> {
+        let request = AbortMultipartUploadRequest {
+            bucket: self.bucket.clone(),
+            key: self.key.clone(),
+            upload_id: self.upload_id.clone(),
+            ..Default::default()
+        };
+
+        self.client
+            .abort_multipart_upload(request)
+            .await
+            .map_err(|err| upload_err_to_condow_err("abort multipart upload", err))?;
+
+        Ok(())
+    }
+}
+
+/// Collects bytes into parts of a fixed size
+struct PartSplitter {
+    part_size: usize,
+    buffer: Vec<u8>,
+}
+
+impl PartSplitter {
+    fn new(part_size: usize) -> Self {
+        Self {
+            part_size,
+            buffer: Vec::with_capacity(part_size),
+        }
+    }
+
+    /// Add bytes and return all parts which were completed
+    fn push(&mut self, mut bytes: &[u8]) -> Vec<Vec<u8>> {
+        let mut parts = Vec::new();
+        while !bytes.is_empty() {
+            let n_bytes = (self.part_size - self.buffer.len()).min(bytes.len());
+            self.buffer.extend_from_slice(&bytes[..n_bytes]);
+            bytes = &bytes[n_bytes..];
+
+            if self.buffer.len() == self.part_size {
+                let part = Vec::with_capacity(self.part_size);
+                parts.push(std::mem::replace(&mut self.buffer, part));
+            }
+        }
+        parts
+    }
+
+    /// Returns the remaining bytes which do not fill a complete part
+    fn finish(self) -> Vec<u8> {
+        self.buffer
+    }
+}
+
+/// Returns the size of an object or of a requested range from a content length
+///
+/// A content length of 0 is an empty object. A missing content length is an error
+/// since the size of the object is unknown.
+fn content_length_to_size(content_length: Option<i64>) -> Result<u64, CondowError> {
+    match content_length {
+        Some(size) if size >= 0 => Ok(size as u64),
+        Some(size) => Err(CondowError::new_other(format!(
+            "response had a negative content length ({})",
+            size
+        ))),
+        None => Err(CondowError::new_other("response had no content length")),
+    }
+}
+
+/// Fails if a range was requested but the server sent the complete object
+///
+/// A server ignoring the `Range` header answers with a 200 instead of a 206
+/// and therefore without a `Content-Range` header.
+fn check_range_honored(range: Option<&str>, response: &GetObjectOutput) -> Result<(), CondowError> {
+    let range = if let Some(range) = range {
+        range
+    } else {
+        return Ok(());
+    };
+
+    if response.accept_ranges.as_deref() == Some("none") {
+        return Err(CondowError::new_other(
+            "the server does not support range requests (Accept-Ranges: none)",
+        ));
+    }
+
+    if response.content_range.is_none() {
+        return Err(CondowError::new_other(format!(
+            "the server ignored the range '{}' and did not respond with a Content-Range \
+            - it probably does not support range requests",
+            range
+        )));
+    }
+
+    Ok(())
+}
+
+/// Fails if a range of an object stored with a `Content-Encoding` was requested
+///
+/// See [S3ClientWrapper::reject_encoded_ranges].
+fn check_range_not_encoded(
+    range: Option<&str>,
+    response: &GetObjectOutput,
+) -> Result<(), CondowError> {
+    let range = if let Some(range) = range {
+        range
+    } else {
+        return Ok(());
+    };
+
+    match response.content_encoding.as_deref().map(str::trim) {
+        None | Some("") => Ok(()),
+        Some(encoding) if encoding.eq_ignore_ascii_case("identity") => Ok(()),
+        Some(encoding) => Err(CondowError::new_other(format!(
+            "the range '{}' of an object with content encoding '{}' can not be decoded \
+            - download the complete object instead",
+            range, encoding
+        ))),
+    }
+}
+
+/// Parses the range of a `Content-Range` header like `bytes 0-99/100`
+///
+/// Returns `None` if the value is not a valid byte range.
+fn parse_content_range(value: &str) -> Option<InclusiveRange> {
+    let range = value.trim().strip_prefix("bytes ")?;
+    let (range, _total) = range.split_once('/')?;
+    let (start, end_incl) = range.split_once('-')?;
+    let start = start.trim().parse().ok()?;
+    let end_incl = end_incl.trim().parse().ok()?;
+
+    if start > end_incl {
+        return None;
+    }
+
+    Some(InclusiveRange(start, end_incl))
+}
+
+/// Extracts the bucket and the percent-decoded object key from an `s3://` location
+///
+/// Fails if the key is not properly percent-encoded or does not decode to UTF-8.
+fn bucket_and_key(location: &url::Url) -> Result<(Bucket, ObjectKey), CondowError> {
+    let bucket = location
+        .host_str()
+        .ok_or_else(|| CondowError::new_other(format!("no bucket in S3 URL '{}'", location)))?;
+
+    let object_key = decode_object_key(location.path()).map_err(|msg| {
+        CondowError::new_other(format!(
+            "invalid object key '{}' in S3 URL '{}': {}",
+            location.path(),
+            location,
+            msg
+        ))
+    })?;
+
+    Ok((Bucket::new(bucket), ObjectKey::new(object_key)))
+}
+
+/// Returns the decoded value of the query parameter `versionId`
+fn version_id(location: &url::Url) -> Option<String> {
+    location
+        .query_pairs()
+        .find(|(name, _)| name == "versionId")
+        .map(|(_, value)| value.into_owned())
+}
+
+/// Percent-decodes an object key
+///
+/// Unlike [percent_decode_str] a `%` which is not followed by two hex digits is an error.
+fn decode_object_key(encoded: &str) -> Result<String, String> {
+    let bytes = encoded.as_bytes();
+    for (idx, _) in encoded.match_indices('%') {
+        match bytes.get(idx + 1..idx + 3) {
+            None => return Err(format!("truncated percent-encoding at position {}", idx)),
+            Some(hex) if !hex.iter().all(u8::is_ascii_hexdigit) => {
+                return Err(format!("invalid percent-encoding at position {}", idx))
             }
+            Some(_) => {}
+        }
+    }
+
+    percent_decode_str(encoded)
+        .decode_utf8()
+        .map(|key| key.into_owned())
+        .map_err(|_| "key is not valid UTF-8".to_string())
+}
+
+fn get_obj_err_to_download_err(err: RusotoError<GetObjectError>) -> CondowError {
+    let condow_err = match &err {
+        RusotoError::Service(service_err) => match service_err {
+            GetObjectError::NoSuchKey(s) => CondowError::new_not_found(s.as_str()),
+            GetObjectError::InvalidObjectState(s) => CondowError::new_object_not_ready(format!(
+                "object not restored from its storage class (get object request): {}",
+                s
+            )),
         },
         RusotoError::Validation(cause) => {
             CondowError::new_other(format!("validation error (get object request): {}", cause))
         }
-        RusotoError::Credentials(err) => {
-            CondowError::new_other(format!("credentials error (get object request): {}", err))
-                .with_source(err)
-        }
-        RusotoError::HttpDispatch(dispatch_error) => CondowError::new_other(format!(
+        RusotoError::Credentials(cred_err) => CondowError::new_other(format!(
+            "credentials error (get object request): {}",
+            cred_err
+        )),
+        // Connection resets and GOAWAYs end up here. They are worth a retry.
+        RusotoError::HttpDispatch(dispatch_error) => CondowError::new_io(format!(
             "http dispatch error (get object request): {}",
             dispatch_error
-        ))
-        .with_source(dispatch_error),
+        )),
         RusotoError::ParseError(cause) => {
             CondowError::new_other(format!("parse error (get object request): {}", cause))
         }
@@ -283,26 +969,28 @@ fn get_obj_err_to_download_err(err: RusotoError<GetObjectError>) -> CondowError
         RusotoError::Blocking => {
             CondowError::new_other("failed to run blocking future within rusoto")
         }
-    }
+    };
+
+    condow_err.with_source(err)
 }
 
 fn head_obj_err_to_get_size_err(err: RusotoError<HeadObjectError>) -> CondowError {
-    match err {
-        RusotoError::Service(err) => match err {
-            HeadObjectError::NoSuchKey(s) => CondowError::new_not_found(s),
+    let condow_err = match &err {
+        RusotoError::Service(service_err) => match service_err {
+            HeadObjectError::NoSuchKey(s) => CondowError::new_not_found(s.as_str()),
         },
         RusotoError::Validation(cause) => {
             CondowError::new_other(format!("validation error (head object request): {}", cause))
         }
-        RusotoError::Credentials(err) => {
-            CondowError::new_other(format!("credentials error (head object request): {}", err))
-                .with_source(err)
-        }
-        RusotoError::HttpDispatch(dispatch_error) => CondowError::new_other(format!(
+        RusotoError::Credentials(cred_err) => CondowError::new_other(format!(
+            "credentials error (head object request): {}",
+            cred_err
+        )),
+        // Connection resets and GOAWAYs end up here. They are worth a retry.
+        RusotoError::HttpDispatch(dispatch_error) => CondowError::new_io(format!(
             "http dispatch error (head object request): {}",
             dispatch_error
-        ))
-        .with_source(dispatch_error),
+        )),
         RusotoError::ParseError(cause) => {
             CondowError::new_other(format!("parse error (head object request): {}", cause))
         }
@@ -310,10 +998,78 @@ fn head_obj_err_to_get_size_err(err: RusotoError<HeadObjectError>) -> CondowErro
         RusotoError::Blocking => {
             CondowError::new_other("failed to run blocking future within rusoto")
         }
-    }
+    };
+
+    condow_err.with_source(err)
+}
+
+fn list_buckets_err_to_check_err(err: RusotoError<ListBucketsError>) -> CondowError {
+    let condow_err = match &err {
+        RusotoError::Service(service_err) => CondowError::new_other(format!(
+            "service error (list buckets request): {}",
+            service_err
+        )),
+        RusotoError::Validation(cause) => CondowError::new_other(format!(
+            "validation error (list buckets request): {}",
+            cause
+        )),
+        RusotoError::Credentials(cred_err) => CondowError::new_other(format!(
+            "credentials error (list buckets request): {}",
+            cred_err
+        )),
+        RusotoError::HttpDispatch(dispatch_error) => CondowError::new_io(format!(
+            "http dispatch error (list buckets request): {}",
+            dispatch_error
+        )),
+        RusotoError::ParseError(cause) => {
+            CondowError::new_other(format!("parse error (list buckets request): {}", cause))
+        }
+        RusotoError::Unknown(response) => response_to_condow_err(response),
+        RusotoError::Blocking => {
+            CondowError::new_other("failed to run blocking future within rusoto")
+        }
+    };
+
+    condow_err.with_source(err)
+}
+
+fn upload_err_to_condow_err<E>(request: &str, err: RusotoError<E>) -> CondowError
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    let condow_err = match &err {
+        RusotoError::Service(service_err) => CondowError::new_other(format!(
+            "service error ({} request): {}",
+            request, service_err
+        )),
+        // Connection resets and GOAWAYs end up here. They are worth a retry.
+        RusotoError::HttpDispatch(dispatch_error) => CondowError::new_io(format!(
+            "http dispatch error ({} request): {}",
+            request, dispatch_error
+        )),
+        RusotoError::Unknown(response) => response_to_condow_err(response),
+        other => CondowError::new_other(format!("{} request failed: {}", request, other)),
+    };
+
+    condow_err.with_source(err)
+}
+
+/// Returns `true` if S3 authenticated the request but denied it for missing permissions
+///
+/// Invalid credentials are also answered with a 403 but with
+/// another code (e.g. `InvalidAccessKeyId` or `SignatureDoesNotMatch`).
+fn is_permission_denied(response: &BufferedHttpResponse) -> bool {
+    response.status.as_u16() == 403 && error_code(response.body.as_ref()) == Some("AccessDenied")
+}
+
+/// Returns the error code of an S3 error response body
+fn error_code(body: &[u8]) -> Option<&str> {
+    std::str::from_utf8(body)
+        .ok()
+        .and_then(|body| xml_element(body, "Code"))
 }
 
-fn response_to_condow_err(response: BufferedHttpResponse) -> CondowError {
+fn response_to_condow_err(response: &BufferedHttpResponse) -> CondowError {
     let message = if let Ok(body_str) = std::str::from_utf8(response.body.as_ref()) {
         body_str
     } else {
@@ -321,6 +1077,15 @@ fn response_to_condow_err(response: BufferedHttpResponse) -> CondowError {
     };
 
     let status = response.status;
+
+    if xml_element(message, "Code") == Some("InvalidObjectState") {
+        let storage_class = xml_element(message, "StorageClass").unwrap_or("<unknown>");
+        return CondowError::new_object_not_ready(format!(
+            "{} - object not restored from storage class {}: {}",
+            status, storage_class, message
+        ));
+    }
+
     let message = format!("{} - {}", status, message);
     match status.as_u16() {
         404 => CondowError::new_not_found(message),
@@ -334,3 +1099,358 @@ fn response_to_condow_err(response: BufferedHttpResponse) -> CondowError {
         }
     }
 }
+
+/// Returns the text of the first element with the given name in an XML document
+fn xml_element<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let open = format!("<{}>", name);
+    let close = format!("</{}>", name);
+
+    let start = xml.find(&open)? + open.len();
+    let len = xml[start..].find(&close)?;
+
+    Some(xml[start..start + len].trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use condow_core::{errors::CondowErrorKind, InclusiveRange};
+
+    use rusoto_core::RusotoError;
+    use rusoto_s3::{GetObjectError, HeadObjectError};
+
+    use super::{
+        bucket_and_key, check_range_not_encoded, content_length_to_size, copy_part_size,
+        error_code, get_obj_err_to_download_err, head_obj_err_to_get_size_err, parse_content_range,
+        resolve_region, version_id, xml_element, GetObjectOutput, LocationParser, PartSplitter,
+        Region,
+    };
+
+    fn parse(url: &str) -> url::Url {
+        url::Url::parse(url).expect("a valid URL")
+    }
+
+    #[test]
+    fn plain_key() {
+        let (bucket, key) = bucket_and_key(&parse("s3://my_bucket/my_object")).unwrap();
+
+        assert_eq!(&*bucket, "my_bucket");
+        assert_eq!(&*key, "/my_object");
+    }
+
+    #[test]
+    fn percent_encoded_key() {
+        let (_, key) = bucket_and_key(&parse("s3://my_bucket/my%20dir/%C3%A4.txt")).unwrap();
+
+        assert_eq!(&*key, "/my dir/ä.txt");
+    }
+
+    #[test]
+    fn default_location_parser_parses_s3_urls() {
+        let (bucket, key) = LocationParser::default()
+            .parse(&parse("s3://my_bucket/my_object"))
+            .unwrap();
+
+        assert_eq!(&*bucket, "my_bucket");
+        assert_eq!(&*key, "/my_object");
+    }
+
+    #[test]
+    fn invalid_percent_encoding() {
+        let err = bucket_and_key(&parse("s3://my_bucket/my%zzobject")).unwrap_err();
+
+        assert_eq!(err.kind(), CondowErrorKind::Other);
+        assert!(err.msg().contains("invalid percent-encoding"), "{}", err);
+    }
+
+    #[test]
+    fn truncated_percent_encoding() {
+        let err = bucket_and_key(&parse("s3://my_bucket/my_object%4")).unwrap_err();
+
+        assert_eq!(err.kind(), CondowErrorKind::Other);
+        assert!(err.msg().contains("truncated percent-encoding"), "{}", err);
+    }
+
+    #[test]
+    fn a_sign_is_not_a_hex_digit() {
+        let err = bucket_and_key(&parse("s3://my_bucket/my%+1object")).unwrap_err();
+
+        assert_eq!(err.kind(), CondowErrorKind::Other);
+        assert!(err.msg().contains("invalid percent-encoding"), "{}", err);
+    }
+
+    #[test]
+    fn key_not_utf8() {
+        let err = bucket_and_key(&parse("s3://my_bucket/%FF%FE")).unwrap_err();
+
+        assert_eq!(err.kind(), CondowErrorKind::Other);
+        assert!(err.msg().contains("not valid UTF-8"), "{}", err);
+    }
+
+    #[test]
+    fn xml_element_of_an_invalid_object_state_error() {
+        let body = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+            <Error><Code>InvalidObjectState</Code>\
+            <Message>The operation is not valid for the object's storage class</Message>\
+            <StorageClass>DEEP_ARCHIVE</StorageClass></Error>";
+
+        assert_eq!(xml_element(body, "Code"), Some("InvalidObjectState"));
+        assert_eq!(xml_element(body, "StorageClass"), Some("DEEP_ARCHIVE"));
+        assert_eq!(xml_element(body, "RequestId"), None);
+    }
+
+    #[test]
+    fn error_codes_of_denied_requests() {
+        let access_denied = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+            <Error><Code>AccessDenied</Code><Message>Access Denied</Message></Error>";
+        let invalid_key = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+            <Error><Code>InvalidAccessKeyId</Code>\
+            <Message>The AWS Access Key Id you provided does not exist in our records.</Message>\
+            </Error>";
+
+        assert_eq!(error_code(access_denied.as_bytes()), Some("AccessDenied"));
+        assert_eq!(
+            error_code(invalid_key.as_bytes()),
+            Some("InvalidAccessKeyId")
+        );
+        assert_eq!(error_code(&[0xff, 0xfe]), None);
+    }
+
+    #[test]
+    fn version_id_from_the_query() {
+        assert_eq!(version_id(&parse("s3://bucket/key")), None);
+        assert_eq!(
+            version_id(&parse(
+                "s3://bucket/key?versionId=3%2FL4kqtJlcpXroDTDmJ%2Brmsd"
+            )),
+            Some("3/L4kqtJlcpXroDTDmJ+rmsd".to_string())
+        );
+        assert_eq!(
+            version_id(&parse("s3://bucket/key?other=1&versionId=abc")),
+            Some("abc".to_string())
+        );
+    }
+
+    #[test]
+    fn the_query_is_not_part_of_the_key() {
+        let (bucket, key) = bucket_and_key(&parse("s3://bucket/a/key?versionId=abc")).unwrap();
+
+        assert_eq!(&*bucket, "bucket");
+        assert_eq!(&*key, "/a/key");
+    }
+
+    fn endpoint(region: Region) -> String {
+        match region {
+            Region::Custom { endpoint, .. } => endpoint,
+            other => panic!("expected a custom region but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn the_region_is_kept_without_options() {
+        assert_eq!(
+            resolve_region(&Region::EuCentral1, false, false).unwrap(),
+            Region::EuCentral1
+        );
+    }
+
+    #[test]
+    fn fips_endpoint() {
+        let region = resolve_region(&Region::UsEast1, true, false).unwrap();
+
+        assert_eq!(region.name(), "us-east-1");
+        assert_eq!(endpoint(region), "https://s3-fips.us-east-1.amazonaws.com");
+    }
+
+    #[test]
+    fn dualstack_endpoint() {
+        let region = resolve_region(&Region::EuCentral1, false, true).unwrap();
+
+        assert_eq!(region.name(), "eu-central-1");
+        assert_eq!(
+            endpoint(region),
+            "https://s3.dualstack.eu-central-1.amazonaws.com"
+        );
+    }
+
+    #[test]
+    fn fips_and_dualstack_endpoint() {
+        let region = resolve_region(&Region::UsGovWest1, true, true).unwrap();
+
+        assert_eq!(
+            endpoint(region),
+            "https://s3-fips.dualstack.us-gov-west-1.amazonaws.com"
+        );
+    }
+
+    #[test]
+    fn dualstack_endpoint_in_china() {
+        let region = resolve_region(&Region::CnNorth1, false, true).unwrap();
+
+        assert_eq!(
+            endpoint(region),
+            "https://s3.dualstack.cn-north-1.amazonaws.com.cn"
+        );
+    }
+
+    #[test]
+    fn a_custom_region_is_never_changed() {
+        let custom = Region::Custom {
+            name: "local".to_string(),
+            endpoint: "http://localhost:9000".to_string(),
+        };
+
+        assert_eq!(resolve_region(&custom, false, false).unwrap(), custom);
+    }
+
+    #[test]
+    fn fips_or_dualstack_with_a_custom_region_is_an_error() {
+        let custom = Region::Custom {
+            name: "local".to_string(),
+            endpoint: "http://localhost:9000".to_string(),
+        };
+
+        assert!(resolve_region(&custom, true, false).is_err());
+        assert!(resolve_region(&custom, false, true).is_err());
+        assert!(resolve_region(&custom, true, true).is_err());
+    }
+
+    #[test]
+    fn the_native_error_is_the_source() {
+        let err = get_obj_err_to_download_err(RusotoError::Service(GetObjectError::NoSuchKey(
+            "no such key".to_string(),
+        )));
+
+        assert_eq!(err.kind(), CondowErrorKind::NotFound);
+        assert!(matches!(
+            err.downcast_ref::<RusotoError<GetObjectError>>(),
+            Some(RusotoError::Service(GetObjectError::NoSuchKey(_)))
+        ));
+        assert!(matches!(
+            err.downcast_ref::<GetObjectError>(),
+            Some(GetObjectError::NoSuchKey(_))
+        ));
+    }
+
+    #[test]
+    fn the_native_error_is_the_source_of_a_size_request() {
+        let err = head_obj_err_to_get_size_err(RusotoError::Validation("invalid".to_string()));
+
+        assert!(matches!(
+            err.downcast_ref::<RusotoError<HeadObjectError>>(),
+            Some(RusotoError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn copy_part_size_fits_into_the_maximum_number_of_parts() {
+        let mib = 1024 * 1024;
+
+        assert_eq!(copy_part_size(0).unwrap(), 8 * mib);
+        assert_eq!(copy_part_size(80_000 * mib as u64).unwrap(), 8 * mib);
+        assert_eq!(
+            copy_part_size(80_000 * mib as u64 + 1).unwrap(),
+            8 * mib + 1
+        );
+
+        let five_tib = 5 * 1024 * 1024 * mib as u64;
+        let part_size = copy_part_size(five_tib).unwrap() as u64;
+        assert!(five_tib.div_ceil(part_size) <= 10_000);
+    }
+
+    #[test]
+    fn part_splitter_collects_full_parts() {
+        let mut splitter = PartSplitter::new(4);
+
+        assert!(splitter.push(&[0, 1, 2]).is_empty());
+        assert_eq!(splitter.push(&[3, 4]), vec![vec![0, 1, 2, 3]]);
+        assert_eq!(
+            splitter.push(&[5, 6, 7, 8, 9, 10, 11, 12]),
+            vec![vec![4, 5, 6, 7], vec![8, 9, 10, 11]]
+        );
+        assert_eq!(splitter.finish(), vec![12]);
+    }
+
+    #[test]
+    fn part_splitter_without_bytes() {
+        let mut splitter = PartSplitter::new(4);
+
+        assert!(splitter.push(&[]).is_empty());
+        assert!(splitter.finish().is_empty());
+    }
+
+    #[test]
+    fn a_content_length_of_0_is_an_empty_object() {
+        assert_eq!(content_length_to_size(Some(0)).unwrap(), 0);
+        assert_eq!(content_length_to_size(Some(42)).unwrap(), 42);
+    }
+
+    #[test]
+    fn a_missing_content_length_is_an_error() {
+        let err = content_length_to_size(None).unwrap_err();
+
+        assert_eq!(err.kind(), CondowErrorKind::Other);
+        assert_eq!(err.msg(), "response had no content length");
+    }
+
+    #[test]
+    fn a_negative_content_length_is_an_error() {
+        assert!(content_length_to_size(Some(-1)).is_err());
+    }
+
+    #[test]
+    fn content_ranges_are_parsed() {
+        assert_eq!(
+            parse_content_range("bytes 0-99/100"),
+            Some(InclusiveRange(0, 99))
+        );
+        assert_eq!(
+            parse_content_range("bytes 10-19/*"),
+            Some(InclusiveRange(10, 19))
+        );
+    }
+
+    #[test]
+    fn invalid_content_ranges_are_not_parsed() {
+        assert_eq!(parse_content_range("bytes */100"), None);
+        assert_eq!(parse_content_range("bytes 20-10/100"), None);
+        assert_eq!(parse_content_range("items 0-9/100"), None);
+        assert_eq!(parse_content_range(""), None);
+    }
+
+    fn with_content_encoding(content_encoding: Option<&str>) -> GetObjectOutput {
+        GetObjectOutput {
+            content_encoding: content_encoding.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn a_range_of_an_encoded_object_is_an_error() {
+        for encoding in ["gzip", "br", " GZIP "] {
+            let response = with_content_encoding(Some(encoding));
+
+            let err = check_range_not_encoded(Some("bytes=0-9"), &response).unwrap_err();
+            assert_eq!(err.kind(), CondowErrorKind::Other, "{}", encoding);
+        }
+    }
+
+    #[test]
+    fn a_range_of_an_object_without_encoding_is_ok() {
+        for encoding in [None, Some(""), Some("identity"), Some("Identity")] {
+            let response = with_content_encoding(encoding);
+
+            assert!(
+                check_range_not_encoded(Some("bytes=0-9"), &response).is_ok(),
+                "{:?}",
+                encoding
+            );
+        }
+    }
+
+    #[test]
+    fn a_complete_encoded_object_is_ok() {
+        let response = with_content_encoding(Some("gzip"));
+
+        assert!(check_range_not_encoded(None, &response).is_ok());
+    }
+}